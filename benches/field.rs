@@ -1,5 +1,5 @@
 use criterion::{ black_box, criterion_group, Criterion };
-use distaff::math::{ field, parallel };
+use distaff::math::{ field, montgomery, parallel };
 
 pub fn add128(c: &mut Criterion) {
     let x = field::rand();
@@ -17,6 +17,22 @@ pub fn mul128(c: &mut Criterion) {
     });
 }
 
+pub fn mul128_montgomery_canonical(c: &mut Criterion) {
+    let x = field::rand();
+    let y = field::rand();
+    c.bench_function("mul128 (Montgomery, canonical form)", |bench| {
+        bench.iter(|| montgomery::mul(black_box(x), black_box(y)))
+    });
+}
+
+pub fn mul128_montgomery(c: &mut Criterion) {
+    let x = montgomery::to_montgomery(field::rand());
+    let y = montgomery::to_montgomery(field::rand());
+    c.bench_function("mul128 (Montgomery form)", |bench| {
+        bench.iter(|| montgomery::mont_mul(black_box(x), black_box(y)))
+    });
+}
+
 pub fn mul_parallel(c: &mut Criterion) {
 
     let n = (1 << 10) as usize;
@@ -56,4 +72,4 @@ pub fn inv128(c: &mut Criterion) {
     });
 }
 
-criterion_group!(group, add128, mul128, mul_parallel, mul_parallel_in_place, exp128, inv128);
\ No newline at end of file
+criterion_group!(group, add128, mul128, mul128_montgomery_canonical, mul128_montgomery, mul_parallel, mul_parallel_in_place, exp128, inv128);
\ No newline at end of file
@@ -0,0 +1,254 @@
+use crate::math::field;
+use crate::programs::ProgramInputs;
+use super::rescue;
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// A Merkle tree in which all nodes are pairs of field elements, and internal nodes are computed
+/// using the same Rescue hash function as the one used by `hash`, `smpath`, and `pmpath`
+/// instructions of Distaff VM. Roots of such trees can be verified by Distaff programs.
+///
+/// Each node is represented by two field elements listed in the order in which they are pushed
+/// onto the stack (or, equivalently, as [value from tape A, value from tape B]).
+pub struct FieldMerkleTree {
+    nodes   : Vec<[u128; 2]>,
+    values  : Vec<[u128; 2]>,
+}
+
+// FIELD MERKLE TREE IMPLEMENTATION
+// ================================================================================================
+impl FieldMerkleTree {
+
+    /// Creates a new Merkle tree from the provided leaves; leaves are used as the lowest level
+    /// of the tree without being hashed.
+    pub fn new(leaves: Vec<[u128; 2]>) -> FieldMerkleTree {
+        assert!(leaves.len().is_power_of_two(), "number of leaves must be a power of 2");
+        assert!(leaves.len() >= 2, "a tree must contain at least 2 leaves");
+
+        let n = leaves.len();
+        let mut nodes = vec![[field::ZERO; 2]; n];
+        for i in (n / 2)..n {
+            nodes[i] = rescue::merge(&leaves[(i - n / 2) * 2], &leaves[(i - n / 2) * 2 + 1]);
+        }
+        for i in (1..(n / 2)).rev() {
+            nodes[i] = rescue::merge(&nodes[i * 2], &nodes[i * 2 + 1]);
+        }
+
+        return FieldMerkleTree { nodes, values: leaves };
+    }
+
+    /// Returns the root of the tree.
+    pub fn root(&self) -> &[u128; 2] {
+        return &self.nodes[1];
+    }
+
+    /// Returns leaf nodes of the tree.
+    pub fn leaves(&self) -> &[[u128; 2]] {
+        return &self.values;
+    }
+
+    /// Returns depth of the tree as expected by `smpath` and `pmpath` instructions; this is the
+    /// number of nodes in an authentication path (including the leaf itself).
+    pub fn depth(&self) -> usize {
+        return self.values.len().trailing_zeros() as usize + 1;
+    }
+
+    /// Computes Merkle authentication path for the leaf at the specified `index`. The path
+    /// starts with the leaf itself, followed by its sibling, followed by siblings of all nodes
+    /// on the way to the root.
+    pub fn prove(&self, index: usize) -> Vec<[u128; 2]> {
+        assert!(index < self.values.len(), "invalid index {}", index);
+
+        let mut path = Vec::with_capacity(self.depth());
+        path.push(self.values[index]);
+        path.push(self.values[index ^ 1]);
+
+        let mut index = (index + self.nodes.len()) >> 1;
+        while index > 1 {
+            path.push(self.nodes[index ^ 1]);
+            index = index >> 1;
+        }
+
+        return path;
+    }
+
+    // PATH VERIFICATION
+    // --------------------------------------------------------------------------------------------
+
+    /// Computes the root to which the authentication `path` for a leaf at the specified `index`
+    /// resolves; this is the same value `smpath` and `pmpath` instructions leave on the stack.
+    pub fn compute_root(index: usize, path: &[[u128; 2]]) -> [u128; 2] {
+        assert!(path.len() >= 2, "authentication path must contain at least 2 nodes");
+        assert!(index < usize::pow(2, (path.len() - 1) as u32), "invalid index {}", index);
+
+        let r = index & 1;
+        let mut v = rescue::merge(&path[r], &path[1 - r]);
+
+        let mut index = index >> 1;
+        for i in 2..path.len() {
+            if index & 1 == 0 {
+                v = rescue::merge(&v, &path[i]);
+            }
+            else {
+                v = rescue::merge(&path[i], &v);
+            }
+            index = index >> 1;
+        }
+
+        return v;
+    }
+
+    /// Checks whether the `path` for the specified `index` resolves to the specified `root`.
+    pub fn verify(root: &[u128; 2], index: usize, path: &[[u128; 2]]) -> bool {
+        return FieldMerkleTree::compute_root(index, path) == *root;
+    }
+
+    // PROGRAM INPUTS
+    // --------------------------------------------------------------------------------------------
+
+    /// Returns contents of secret input tapes A and B consumed by `smpath.n` instruction (with n
+    /// equal to `path.len()`) when it computes the root of the `path` for a leaf at the specified
+    /// `index`. The leaf itself is not included: `smpath` expects it to be at the top of the stack.
+    pub fn smpath_tapes(index: usize, path: &[[u128; 2]]) -> [Vec<u128>; 2] {
+        assert!(path.len() >= 2, "authentication path must contain at least 2 nodes");
+
+        let mut a = Vec::with_capacity((path.len() - 1) * 2);
+        let mut b = Vec::with_capacity((path.len() - 1) * 2);
+        let mut index = index;
+        for i in 1..path.len() {
+            // bits of the index are read from tape B, starting with the least significant bit;
+            // tape A is padded with zeros so that both values can be read with a single READ2
+            a.push(field::ZERO);
+            b.push((index & 1) as u128);
+            index = index >> 1;
+
+            // nodes of the path are split across both tapes
+            a.push(path[i][0]);
+            b.push(path[i][1]);
+        }
+
+        return [a, b];
+    }
+
+    /// Returns contents of secret input tapes A and B consumed by `pmpath.n` instruction (with n
+    /// equal to `path.len()`). The leaf and its index are not included: `pmpath` expects them to
+    /// be on the stack, and binary decomposition of the index is added to the tapes by the VM.
+    pub fn pmpath_tapes(path: &[[u128; 2]]) -> [Vec<u128>; 2] {
+        assert!(path.len() >= 2, "authentication path must contain at least 2 nodes");
+        let a = path[1..].iter().map(|node| node[0]).collect();
+        let b = path[1..].iter().map(|node| node[1]).collect();
+        return [a, b];
+    }
+
+    /// Returns inputs for a program which starts with `smpath.n` instruction: public inputs put
+    /// the leaf at the top of the stack, and secret inputs are the same as for `smpath_tapes()`.
+    pub fn smpath_inputs(index: usize, path: &[[u128; 2]]) -> ProgramInputs {
+        let [a, b] = FieldMerkleTree::smpath_tapes(index, path);
+        return ProgramInputs::new(&[path[0][1], path[0][0]], &a, &b);
+    }
+
+    /// Returns inputs for a program which starts with `pmpath.n` instruction: public inputs put
+    /// the leaf at the top of the stack followed by its index, and secret inputs are the same as
+    /// for `pmpath_tapes()`.
+    pub fn pmpath_inputs(index: usize, path: &[[u128; 2]]) -> ProgramInputs {
+        let [a, b] = FieldMerkleTree::pmpath_tapes(path);
+        return ProgramInputs::new(&[path[0][1], path[0][0], index as u128], &a, &b);
+    }
+
+    /// Returns inputs for a program which starts with `mrupdate.n` instruction: public inputs put
+    /// the new `value` of the leaf at the top of the stack followed by the current leaf and its
+    /// index, and secret inputs are the same as for `pmpath_tapes()`.
+    pub fn mrupdate_inputs(index: usize, path: &[[u128; 2]], value: [u128; 2]) -> ProgramInputs {
+        let [a, b] = FieldMerkleTree::pmpath_tapes(path);
+        let public = [value[1], value[0], path[0][1], path[0][0], index as u128];
+        return ProgramInputs::new(&public, &a, &b);
+    }
+}
+
+// TESTS
+// ================================================================================================
+#[cfg(test)]
+mod tests {
+
+    use crate::{ math::field, tests::run_source };
+    use crate::crypto::rescue;
+    use super::FieldMerkleTree;
+
+    #[test]
+    fn new_tree() {
+        let leaves = build_leaves(4);
+        let tree = FieldMerkleTree::new(leaves.clone());
+
+        let n01 = rescue::merge(&leaves[0], &leaves[1]);
+        let n23 = rescue::merge(&leaves[2], &leaves[3]);
+        assert_eq!(rescue::merge(&n01, &n23), *tree.root());
+        assert_eq!(3, tree.depth());
+    }
+
+    #[test]
+    fn prove_verify() {
+        let tree = FieldMerkleTree::new(build_leaves(16));
+        for index in 0..16 {
+            let path = tree.prove(index);
+            assert_eq!(tree.depth(), path.len());
+            assert_eq!(tree.leaves()[index], path[0]);
+            assert!(FieldMerkleTree::verify(tree.root(), index, &path));
+            assert!(!FieldMerkleTree::verify(tree.root(), index ^ 1, &path));
+        }
+    }
+
+    #[test]
+    fn smpath() {
+        let tree = FieldMerkleTree::new(build_leaves(8));
+        let source = format!("begin smpath.{} end", tree.depth());
+        for &index in [0, 3, 6, 7].iter() {
+            let inputs = FieldMerkleTree::smpath_inputs(index, &tree.prove(index));
+            let stack = run_source(&source, &inputs, 2).unwrap();
+            assert_eq!(*tree.root(), [stack[1], stack[0]]);
+        }
+    }
+
+    #[test]
+    fn pmpath() {
+        let tree = FieldMerkleTree::new(build_leaves(8));
+        let source = format!("begin pmpath.{} end", tree.depth());
+        for &index in [0, 3, 6, 7].iter() {
+            let inputs = FieldMerkleTree::pmpath_inputs(index, &tree.prove(index));
+            let stack = run_source(&source, &inputs, 2).unwrap();
+            assert_eq!(*tree.root(), [stack[1], stack[0]]);
+        }
+    }
+
+    #[test]
+    fn mrupdate() {
+        let leaves = build_leaves(8);
+        let tree = FieldMerkleTree::new(leaves.clone());
+        let source = format!("begin mrupdate.{} end", tree.depth());
+        for &index in [0, 3, 6, 7].iter() {
+            let value = [field::rand(), field::rand()];
+            let inputs = FieldMerkleTree::mrupdate_inputs(index, &tree.prove(index), value);
+
+            let mut new_leaves = leaves.clone();
+            new_leaves[index] = value;
+            let new_tree = FieldMerkleTree::new(new_leaves);
+
+            let stack = run_source(&source, &inputs, 4).unwrap();
+            assert_eq!([stack[1], stack[0]], *new_tree.root());
+            assert_eq!([stack[3], stack[2]], *tree.root());
+        }
+    }
+
+    #[test]
+    fn mrupdate_wrong_index() {
+        let tree = FieldMerkleTree::new(build_leaves(8));
+        let source = format!("begin mrupdate.{} end", tree.depth());
+        let inputs = FieldMerkleTree::mrupdate_inputs(5, &tree.prove(3), [1, 2]);
+        let stack = run_source(&source, &inputs, 4).unwrap();
+        assert_ne!([stack[3], stack[2]], *tree.root());
+    }
+
+    fn build_leaves(n: usize) -> Vec<[u128; 2]> {
+        return (0..n).map(|_| [field::rand(), field::rand()]).collect();
+    }
+}
@@ -1,6 +1,7 @@
 use crate::math::field;
 use crate::utils::{ as_bytes };
-use sha3::Digest;
+use sha3::Digest as _;
+use super::Hasher;
 
 // CONSTANTS
 // ================================================================================================
@@ -114,79 +115,132 @@ const ARK: [u128; 546] = [
     309924869726711895128876537262983061064, 112525655489195767040829402475371392715, 170125078093491169855652980913547448221, 294706318451527567281025752400950837744, 206842437959190858648222146486983588628, 285833121646488198141311208833491008916,
 ];
 
+// HASH FUNCTIONS
+// ================================================================================================
+
 // ------------------------------------------------------------------------------------------------
-/// Poseidon hash function
+/// Poseidon hash function; see `absorb()` for how input bytes are encoded into field elements.
 pub fn poseidon(values: &[u8], result: &mut [u8]) {
-    debug_assert!(values.len() <= 64, "expected 64 or fewer input bytes but received {}", values.len());
     debug_assert!(result.len() == 32, "expected result to be exactly 32 bytes but received {}", result.len());
+    let state = absorb(values, poseidon_permutation);
+    result.copy_from_slice(as_bytes(&state[..2]));
+}
 
-    // copy values into state and set the remaining state elements to 0
-    let mut state = [0u128; 6];
-    let state_bytes: &mut [u8; 64] = unsafe { &mut *(&state as *const _ as *mut [u8; 64]) };
-    state_bytes[..values.len()].copy_from_slice(values);
+// ------------------------------------------------------------------------------------------------
+/// Rescue hash function; see `absorb()` for how input bytes are encoded into field elements.
+pub fn rescue(values: &[u8], result: &mut [u8]) {
+    debug_assert!(result.len() == 32, "expected result to be exactly 32 bytes but received {}", result.len());
+    let state = absorb(values, rescue_permutation);
+    result.copy_from_slice(as_bytes(&state[..2]));
+}
 
-    // execute round function 48 times
-    for i in 0..91 {
+// ------------------------------------------------------------------------------------------------
+/// GMiMC_erf hash function; see `absorb()` for how input bytes are encoded into field elements.
+pub fn gmimc(values: &[u8], result: &mut [u8]) {
+    debug_assert!(result.len() == 32, "expected result to be exactly 32 bytes but received {}", result.len());
+    let state = absorb(values, gmimc_permutation);
+    result.copy_from_slice(as_bytes(&state[..2]));
+}
 
-        add_constants(&mut state, i * 6);
+// ------------------------------------------------------------------------------------------------
+/// Wrapper around blake3 hash function
+pub fn blake3(values: &[u8], result: &mut [u8]) {
+    debug_assert!(result.len() == 32, "expected result to be exactly 32 bytes but received {}", result.len());
+    let hash = blake3::hash(&values);
+    result.copy_from_slice(hash.as_bytes());
+}
 
-        if i < 4 || i >= 87 {
-            // full round
-            apply_sbox(&mut state);
-        }
-        else {
-            // partial round
-            state[5] = field::exp(state[5], ALPHA);
-        }
+/// Wrapper around sha3 hash function
+pub fn sha3(values: &[u8], result: &mut [u8]) {
+    debug_assert!(result.len() == 32, "expected result to be exactly 32 bytes but received {}", result.len());
+    let mut sha256 = sha3::Sha3_256::new();
+    sha256.input(&values);
+    let hash = sha256.result();
+    result.copy_from_slice(hash.as_ref());
+}
 
-        apply_mds(&mut state);
-    }
+// HASHER IMPLEMENTATIONS
+// ================================================================================================
 
-    // return the result
-    result.copy_from_slice(as_bytes(&state[..2]));
+// identifiers of the hash functions defined in this module must remain stable because they
+// are written into serialized proofs
+macro_rules! impl_hasher {
+    ($name:ident, $hash_fn:ident, $id:expr) => {
+        #[derive(Copy, Clone, Debug, Default)]
+        pub struct $name;
+
+        impl Hasher for $name {
+            type Digest = [u8; 32];
+            const ID: u8 = $id;
+
+            fn hash(&self, values: &[u8]) -> [u8; 32] {
+                let mut result = [0u8; 32];
+                $hash_fn(values, &mut result);
+                return result;
+            }
+        }
+    };
+    // algebraic hash functions merge digests as field elements; see `merge_digests()`
+    ($name:ident, $hash_fn:ident, $id:expr, $permutation:ident) => {
+        #[derive(Copy, Clone, Debug, Default)]
+        pub struct $name;
+
+        impl Hasher for $name {
+            type Digest = [u8; 32];
+            const ID: u8 = $id;
+
+            fn hash(&self, values: &[u8]) -> [u8; 32] {
+                let mut result = [0u8; 32];
+                $hash_fn(values, &mut result);
+                return result;
+            }
+
+            fn merge(&self, left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+                let state = merge_digests(left, right, $permutation);
+                let mut result = [0u8; 32];
+                result.copy_from_slice(as_bytes(&state[..2]));
+                return result;
+            }
+        }
+    };
 }
 
-// ------------------------------------------------------------------------------------------------
-/// Rescue hash function
-pub fn rescue(values: &[u8], result: &mut [u8]) {
-    debug_assert!(values.len() <= 64, "expected 64 or fewer input bytes but received {}", values.len());
-    debug_assert!(result.len() == 32, "expected result to be exactly 32 bytes but received {}", result.len());
+impl_hasher!(Blake3,    blake3,     0);
+impl_hasher!(Sha3,      sha3,       1);
+impl_hasher!(Poseidon,  poseidon,   2, poseidon_permutation);
+impl_hasher!(Rescue,    rescue,     3, rescue_permutation);
+impl_hasher!(GMiMC,     gmimc,      4, gmimc_permutation);
 
-    // copy values into state and set the remaining state elements to 0
-    let mut state = [0u128; 6];
-    let state_bytes: &mut [u8; 64] = unsafe { &mut *(&state as *const _ as *mut [u8; 64]) };
-    state_bytes[..values.len()].copy_from_slice(values);
+// PERMUTATIONS
+// ================================================================================================
+fn poseidon_permutation(state: &mut [u128; 6]) {
+    for i in 0..POSEIDON_NUM_ROUNDS {
+        let ark = poseidon_round_constants(i);
+        for j in 0..6 {
+            state[j] = field::add(state[j], ark[j]);
+        }
+        apply_poseidon_round(state, is_poseidon_full_round(i));
+    }
+}
 
+fn rescue_permutation(state: &mut [u128; 6]) {
     // apply round function 10 times
-    add_constants(&mut state, 0);
+    add_constants(state, 0);
     for i in 0..10 {
 
         // step 1
-        apply_inv_sbox(&mut state);
-        apply_mds(&mut state);
-        add_constants(&mut state, (i * 2 + 1) * 6);
+        apply_inv_sbox(state);
+        apply_mds(state);
+        add_constants(state, (i * 2 + 1) * 6);
 
         // step 2
-        apply_sbox(&mut state);
-        apply_mds(&mut state);
-        add_constants(&mut state, (i * 2 + 2) * 6);
+        apply_sbox(state);
+        apply_mds(state);
+        add_constants(state, (i * 2 + 2) * 6);
     }
-
-    // return the result
-    result.copy_from_slice(as_bytes(&state[..2]));
 }
 
-// ------------------------------------------------------------------------------------------------
-/// GMiMC_erf hash function
-pub fn gmimc(values: &[u8], result: &mut [u8]) {
-    debug_assert!(values.len() <= 64, "expected 64 or fewer input bytes but received {}", values.len());
-    debug_assert!(result.len() == 32, "expected result to be exactly 32 bytes but received {}", result.len());
-
-    // copy values into state and set the remaining state elements to 0
-    let mut state = [0u128; 6];
-    let state_bytes: &mut [u8; 64] = unsafe { &mut *(&state as *const _ as *mut [u8; 64]) };
-    state_bytes[..values.len()].copy_from_slice(values);
-
+fn gmimc_permutation(state: &mut [u128; 6]) {
     for i in 0..166 {
         let s0 = state[0];
         let mask = field::exp(field::add(s0, ARK[i]), ALPHA);
@@ -195,30 +249,114 @@ pub fn gmimc(values: &[u8], result: &mut [u8]) {
         }
         state[5] = s0;
     }
+}
 
-    // return the result
-    result.copy_from_slice(as_bytes(&state[..2]));
+// POSEIDON ROUNDS
+// ================================================================================================
+
+/// Number of rounds in Poseidon permutation; the first 4 and the last 4 rounds are full rounds.
+pub(crate) const POSEIDON_NUM_ROUNDS: usize = 91;
+
+/// Returns true if S-BOX is applied to the entire state in the specified round of Poseidon
+/// permutation, and false if it is applied only to the last element of the state.
+pub(crate) fn is_poseidon_full_round(round: usize) -> bool {
+    return round < 4 || round >= POSEIDON_NUM_ROUNDS - 4;
 }
 
-// ------------------------------------------------------------------------------------------------
-/// Wrapper around blake3 hash function
-pub fn blake3(values: &[u8], result: &mut [u8]) {
-    debug_assert!(result.len() == 32, "expected result to be exactly 32 bytes but received {}", result.len());
-    let hash = blake3::hash(&values);
-    result.copy_from_slice(hash.as_bytes());
+/// Returns constants which are added to the state at the start of the specified round of
+/// Poseidon permutation; for rounds past the last round, all constants are zeros.
+pub(crate) fn poseidon_round_constants(round: usize) -> [u128; 6] {
+    let mut result = [field::ZERO; 6];
+    if round < POSEIDON_NUM_ROUNDS {
+        result.copy_from_slice(&ARK[(round * 6)..(round * 6 + 6)]);
+    }
+    return result;
 }
 
-/// Wrapper around sha3 hash function
-pub fn sha3(values: &[u8], result: &mut [u8]) {
-    debug_assert!(result.len() == 32, "expected result to be exactly 32 bytes but received {}", result.len());
-    let mut sha256 = sha3::Sha3_256::new();
-    sha256.input(&values);
-    let hash = sha256.result();
-    result.copy_from_slice(hash.as_ref());
+/// Applies Poseidon permutation to a state which starts with `values` followed by zeros; this is
+/// the same as what `poseidon.n` instruction computes on the stack (with the state in reverse
+/// order), and the same as `Poseidon::merge()` for digests made of the first 4 values.
+pub(crate) fn poseidon_elements(values: &[u128]) -> [u128; 2] {
+    debug_assert!(values.len() <= 4, "expected 4 or fewer values but received {}", values.len());
+    let mut state = [0u128; 6];
+    state[..values.len()].copy_from_slice(values);
+    poseidon_permutation(&mut state);
+    return [state[0], state[1]];
+}
+
+/// Applies S-BOX and MDS steps of a single Poseidon round to the state; round constants are
+/// expected to have been added to the state already.
+pub(crate) fn apply_poseidon_round(state: &mut [u128; 6], full: bool) {
+    if full {
+        apply_sbox(state);
+    }
+    else {
+        state[5] = field::exp(state[5], ALPHA);
+    }
+    apply_mds(state);
 }
 
 // HELPER FUNCTIONS
 // ================================================================================================
+
+/// Number of input bytes encoded into a single field element; 15-byte values are always smaller
+/// than the field modulus, and so the encoding of bytes into field elements is injective.
+const BYTES_PER_ELEMENT: usize = 15;
+
+/// Number of input bytes absorbed into the state between applications of the permutation.
+const BYTES_PER_BLOCK: usize = BYTES_PER_ELEMENT * 4;
+
+/// Absorbs `values` into the first 4 elements of the state 60 bytes at a time, with every 15
+/// bytes encoded into a single field element, and applies the permutation after each 60-byte
+/// block. Before anything is absorbed, input length plus one is written into the capacity portion
+/// of the state; this makes sure that inputs which differ only by trailing zeros hash to
+/// different values, and that hashes of bytes never coincide with merges of digests (for which
+/// this element is always zero).
+fn absorb(values: &[u8], permutation: fn(&mut [u128; 6])) -> [u128; 6] {
+    let mut state = [0u128; 6];
+    state[4] = values.len() as u128 + 1;
+
+    let num_blocks = std::cmp::max(1, (values.len() + BYTES_PER_BLOCK - 1) / BYTES_PER_BLOCK);
+    for i in 0..num_blocks {
+        let block = &values[(i * BYTES_PER_BLOCK)..std::cmp::min(values.len(), (i + 1) * BYTES_PER_BLOCK)];
+        for (j, chunk) in block.chunks(BYTES_PER_ELEMENT).enumerate() {
+            let mut element_bytes = [0u8; 16];
+            element_bytes[..chunk.len()].copy_from_slice(chunk);
+            state[j] = field::add(state[j], u128::from_le_bytes(element_bytes));
+        }
+        permutation(&mut state);
+    }
+
+    return state;
+}
+
+/// Interprets two 32-byte digests as 4 field elements and applies the permutation to them; for
+/// digests produced by algebraic hash functions, this is the same as hashing 4 field elements
+/// with `hash.4` or `poseidon.4` instructions, and so Merkle trees built with these hashers can
+/// be verified by Distaff programs.
+///
+/// Digests received from elsewhere may contain 16-byte values which are not valid field elements;
+/// such values are reduced by the modulus and are marked in the capacity portion of the state, so
+/// that they do not collide with the values they are reduced to.
+fn merge_digests(left: &[u8; 32], right: &[u8; 32], permutation: fn(&mut [u128; 6])) -> [u128; 6] {
+    let mut state = [0u128; 6];
+    for (i, chunk) in left.chunks(16).chain(right.chunks(16)).enumerate() {
+        let mut element_bytes = [0u8; 16];
+        element_bytes.copy_from_slice(chunk);
+        let element = u128::from_le_bytes(element_bytes);
+        if element >= field::MODULUS {
+            state[i] = element - field::MODULUS;
+            state[5] |= 1 << i;
+        }
+        else {
+            state[i] = element;
+        }
+    }
+
+    permutation(&mut state);
+    return state;
+}
+
 fn add_constants(state: &mut[u128; 6], offset: usize) {
     for i in 0..6 {
         state[i] = field::add(state[i], ARK[offset + i]);
@@ -258,6 +396,7 @@ fn apply_mds(state: &mut[u128; 6]) {
 #[cfg(test)]
 mod tests {
 
+    use crate::math::field;
     use crate::utils::{ as_bytes };
 
     #[test]
@@ -267,8 +406,8 @@ mod tests {
         super::poseidon(as_bytes(&value), &mut result);
 
         assert_eq!([
-            224,  9,  85,  92, 75, 117, 136,  23, 142,  67, 249, 199, 39, 177,  97, 129,
-            93, 192, 153, 131, 76, 160,  94, 162, 200, 192, 187,   5, 159, 69,  48, 165], 
+            212,  46, 244,  45,  32,   8,  90,  28, 172, 119,  91,  52, 237,  88, 224,  65,
+            107, 248,  25, 115, 171,  85, 117, 138,  17,  92,  66,   4, 243, 236, 148,  25],
             result);
     }
 
@@ -279,11 +418,96 @@ mod tests {
         super::rescue(as_bytes(&value), &mut result);
 
         assert_eq!([
-            148, 191,  96, 185, 107, 196, 170,  28, 161, 214, 196, 211, 158, 111, 135, 32, 
-            122, 173, 195,  37, 123,  60, 246, 104, 176,  53, 127,  67,  38, 208,  69, 54],
+            170, 121,  22, 202, 158, 154,  87, 100, 144,  82, 196, 100, 128, 154, 119, 129,
+            136,  74, 100,  56, 209, 117,  47, 130, 191,  22, 193, 159, 224, 229, 141,  88],
             result);
     }
 
+    #[test]
+    fn long_inputs() {
+        let value = [1u128, 2, 3, 4, 5, 6, 7, 8, 9];
+        let mut value_padded = value.to_vec();
+        value_padded.push(0);
+
+        let hash_fns: [fn(&[u8], &mut [u8]); 3] = [super::poseidon, super::rescue, super::gmimc];
+        for &hash in hash_fns.iter() {
+            let mut result1 = [0; 32];
+            hash(as_bytes(&value), &mut result1);
+
+            // hashing only the first 64 bytes should give a different result
+            let mut result2 = [0; 32];
+            hash(as_bytes(&value[..4]), &mut result2);
+            assert_ne!(result1, result2);
+
+            // padding the input with zeros should give a different result
+            let mut result3 = [0; 32];
+            hash(as_bytes(&value_padded), &mut result3);
+            assert_ne!(result1, result3);
+        }
+    }
+
+    #[test]
+    fn short_inputs() {
+        let hash_fns: [fn(&[u8], &mut [u8]); 3] = [super::poseidon, super::rescue, super::gmimc];
+        for &hash in hash_fns.iter() {
+            // inputs which differ only by trailing zeros hash to different values
+            let mut result1 = [0; 32];
+            hash(b"a", &mut result1);
+            let mut result2 = [0; 32];
+            hash(b"a\0", &mut result2);
+            assert_ne!(result1, result2);
+
+            let mut result3 = [0; 32];
+            hash(&[], &mut result3);
+            let mut result4 = [0; 32];
+            hash(&[0], &mut result4);
+            assert_ne!(result3, result4);
+
+            // 16-byte values which are equal modulo field modulus hash to different values
+            let value = [5u128];
+            let value_plus_modulus = [5u128 + field::MODULUS];
+            let mut result5 = [0; 32];
+            hash(as_bytes(&value), &mut result5);
+            let mut result6 = [0; 32];
+            hash(as_bytes(&value_plus_modulus), &mut result6);
+            assert_ne!(result5, result6);
+        }
+    }
+
+    #[test]
+    fn hashers() {
+        use crate::crypto::Hasher;
+        use super::{ Blake3, Sha3, Poseidon, Rescue, GMiMC };
+
+        assert_eq!(0, Blake3::ID);
+        assert_eq!(1, Sha3::ID);
+        assert_eq!(2, Poseidon::ID);
+        assert_eq!(3, Rescue::ID);
+        assert_eq!(4, GMiMC::ID);
+
+        // merging two digests should be the same as applying the permutation to their elements,
+        // but should be different from hashing their concatenation
+        let value = [1u128, 2, 3, 4];
+        let mut expected = [0u128; 6];
+        expected[..4].copy_from_slice(&value);
+        super::rescue_permutation(&mut expected);
+
+        let mut left = [0u8; 32];
+        left.copy_from_slice(as_bytes(&value[..2]));
+        let mut right = [0u8; 32];
+        right.copy_from_slice(as_bytes(&value[2..]));
+        assert_eq!(as_bytes(&expected[..2]), Rescue.merge(&left, &right));
+        assert_ne!(Rescue.hash(as_bytes(&value)), Rescue.merge(&left, &right));
+
+        // digests with values which are not valid field elements do not collide with digests
+        // which contain these values reduced by the modulus
+        let mut non_canonical = [0u8; 32];
+        non_canonical.copy_from_slice(as_bytes(&[value[0] + field::MODULUS, value[1]]));
+        assert_ne!(Rescue.merge(&left, &right), Rescue.merge(&non_canonical, &right));
+        assert_ne!(Poseidon.merge(&left, &right), Poseidon.merge(&non_canonical, &right));
+        assert_ne!(GMiMC.merge(&left, &right), GMiMC.merge(&non_canonical, &right));
+    }
+
     #[test]
     fn gmimc() {
         let value = [1u128, 2, 3, 4];
@@ -291,8 +515,8 @@ mod tests {
         super::gmimc(as_bytes(&value), &mut result);
 
         assert_eq!([
-            115, 208,  64, 41, 162,  43, 134, 243, 236,  80, 161, 106, 195, 234, 30, 26,
-             71,  74, 255, 77,  41, 125,  25, 152, 162, 106,  65, 108,  84, 216, 37, 37],
+             16, 208, 101, 189, 178, 164,   2, 194,  65,  58,  65,  68, 114, 161, 244,  27,
+            191, 195,  88,  67,  92,  86, 146, 119, 105,  48, 111, 172,  34, 131, 104,  30],
             result);
     }
 }
@@ -0,0 +1,197 @@
+use crate::math::field;
+use crate::programs::ProgramInputs;
+use super::rescue;
+
+// CONSTANTS
+// ================================================================================================
+
+/// Maximum number of message bits which can be signed with a single key.
+pub const MAX_MESSAGE_BITS: usize = 128;
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// A secret key of a Lamport one-time signature scheme built from the same Rescue hash function
+/// as the one used by `hash` instruction of Distaff VM. Signatures produced by this key can be
+/// verified by Distaff programs using `lamport.n` instruction.
+///
+/// For every bit of a message, the key contains two secret elements (one for bit value 0 and
+/// one for bit value 1); each element is a pair of field elements.
+pub struct SecretKey {
+    elements: Vec<[[u128; 2]; 2]>,
+}
+
+/// A signature of a message produced by a Lamport `SecretKey`. For every bit of the message,
+/// the signature contains the secret element for the value of the bit, followed by the hash of
+/// the other secret element.
+pub struct Signature {
+    elements: Vec<[[u128; 2]; 2]>,
+}
+
+// SECRET KEY IMPLEMENTATION
+// ================================================================================================
+impl SecretKey {
+
+    /// Generates a new random key for signing messages of the specified number of bits.
+    pub fn new(message_bits: usize) -> SecretKey {
+        assert!(message_bits > 0 && message_bits <= MAX_MESSAGE_BITS,
+            "number of message bits must be between 1 and {}, but was {}", MAX_MESSAGE_BITS, message_bits);
+
+        let elements = (0..message_bits).map(|_| [
+            [field::rand(), field::rand()],
+            [field::rand(), field::rand()],
+        ]).collect();
+
+        return SecretKey { elements };
+    }
+
+    /// Returns the number of message bits this key can sign.
+    pub fn message_bits(&self) -> usize {
+        return self.elements.len();
+    }
+
+    /// Returns the public key commitment for this key; this is the value against which
+    /// `lamport.n` instruction verifies signatures.
+    pub fn public_key(&self) -> [u128; 2] {
+        let mut commitment = [field::ZERO; 2];
+        for element in self.elements.iter() {
+            let h0 = rescue::hash(&element[0]);
+            let h1 = rescue::hash(&element[1]);
+            commitment = rescue::merge(&commitment, &rescue::merge(&h0, &h1));
+        }
+        return commitment;
+    }
+
+    /// Signs the `message`; the key should be used to sign only a single message.
+    pub fn sign(&self, message: u128) -> Signature {
+        let n = self.message_bits();
+        assert!(n == MAX_MESSAGE_BITS || message < (1 << n),
+            "message {} cannot be represented with {} bits", message, n);
+
+        let elements = self.elements.iter().enumerate().map(|(i, element)| {
+            let bit = ((message >> i) & 1) as usize;
+            [element[bit], rescue::hash(&element[1 - bit])]
+        }).collect();
+
+        return Signature { elements };
+    }
+}
+
+// SIGNATURE IMPLEMENTATION
+// ================================================================================================
+impl Signature {
+
+    /// Returns the number of message bits covered by this signature.
+    pub fn message_bits(&self) -> usize {
+        return self.elements.len();
+    }
+
+    /// Checks whether this signature is a valid signature of the `message` for the specified
+    /// public key commitment.
+    pub fn verify(&self, public_key: &[u128; 2], message: u128) -> bool {
+        let n = self.message_bits();
+        if n < MAX_MESSAGE_BITS && message >= (1 << n) {
+            return false;
+        }
+
+        let mut commitment = [field::ZERO; 2];
+        for (i, element) in self.elements.iter().enumerate() {
+            let revealed = rescue::hash(&element[0]);
+            let pk_element = if (message >> i) & 1 == 0 {
+                rescue::merge(&revealed, &element[1])
+            }
+            else {
+                rescue::merge(&element[1], &revealed)
+            };
+            commitment = rescue::merge(&commitment, &pk_element);
+        }
+
+        return commitment == *public_key;
+    }
+
+    // PROGRAM INPUTS
+    // --------------------------------------------------------------------------------------------
+
+    /// Returns contents of secret input tapes A and B consumed by `lamport.n` instruction (with
+    /// n equal to the number of message bits) when it verifies this signature of the `message`.
+    /// Bits of the message are read from tape A, starting with the least significant bit.
+    pub fn tapes(&self, message: u128) -> [Vec<u128>; 2] {
+        let mut a = Vec::with_capacity(self.elements.len() * 3);
+        let mut b = Vec::with_capacity(self.elements.len() * 2);
+        for (i, element) in self.elements.iter().enumerate() {
+            a.push((message >> i) & 1);
+            a.push(element[0][0]);
+            b.push(element[0][1]);
+            a.push(element[1][0]);
+            b.push(element[1][1]);
+        }
+        return [a, b];
+    }
+
+    /// Returns inputs for a program which starts with `lamport.n` instruction: public inputs put
+    /// the `message` at the top of the stack followed by the public key commitment, and secret
+    /// inputs are the same as for `tapes()`.
+    pub fn inputs(&self, public_key: &[u128; 2], message: u128) -> ProgramInputs {
+        let [a, b] = self.tapes(message);
+        return ProgramInputs::new(&[message, public_key[1], public_key[0]], &a, &b);
+    }
+}
+
+// TESTS
+// ================================================================================================
+#[cfg(test)]
+mod tests {
+
+    use crate::{ math::field, tests::run_source, ExecutionError, OpCode, ProgramInputs, MAX_OUTPUTS };
+    use super::{ SecretKey, MAX_MESSAGE_BITS };
+
+    #[test]
+    fn sign_verify() {
+        let key = SecretKey::new(16);
+        let public_key = key.public_key();
+        let signature = key.sign(12345);
+
+        assert!(signature.verify(&public_key, 12345));
+        assert!(!signature.verify(&public_key, 12344));
+        assert!(!signature.verify(&public_key, 1 << 16));
+        assert!(!signature.verify(&SecretKey::new(16).public_key(), 12345));
+    }
+
+    #[test]
+    fn lamport() {
+        let key = SecretKey::new(16);
+        let source = "begin lamport.16 end";
+        for &message in [0, 1, 12345, 65535].iter() {
+            let signature = key.sign(message);
+            let stack = run_source(source, &signature.inputs(&key.public_key(), message), MAX_OUTPUTS).unwrap();
+            assert!(stack.iter().all(|&v| v == field::ZERO));
+        }
+
+        let key = SecretKey::new(MAX_MESSAGE_BITS);
+        let message = field::rand();
+        let signature = key.sign(message);
+        run_source("begin lamport.128 end", &signature.inputs(&key.public_key(), message), 0).unwrap();
+    }
+
+    #[test]
+    fn lamport_wrong_message() {
+        let key = SecretKey::new(16);
+        let public_key = key.public_key();
+        let [a, b] = key.sign(12345).tapes(12345);
+        let result = run_source("begin lamport.16 end", &ProgramInputs::new(&[12344, public_key[1], public_key[0]], &a, &b), 0);
+        assert!(matches!(result, Err(ExecutionError::FailedAssertion { op: OpCode::AssertEq, .. })));
+    }
+
+    #[test]
+    fn lamport_forged_signature() {
+        let key = SecretKey::new(16);
+        let signature = key.sign(12345);
+
+        // flip the lowest bit of the message on the tape while keeping the revealed elements
+        let [mut a, b] = signature.tapes(12345);
+        a[0] = 0;
+        let public_key = key.public_key();
+        let result = run_source("begin lamport.16 end", &ProgramInputs::new(&[12344, public_key[1], public_key[0]], &a, &b), 0);
+        assert!(matches!(result, Err(ExecutionError::FailedAssertion { op: OpCode::AssertEq, .. })));
+    }
+}
@@ -1,50 +1,177 @@
-use std::slice;
 use std::collections::{ HashMap, BTreeSet };
+use crossbeam_utils::thread;
 use serde::{ Serialize, Deserialize };
-use crate::crypto::{ HashFunction };
+use crate::crypto::{ Hasher };
+
+// CONSTANTS
+// ================================================================================================
+
+/// Trees with fewer leaves than this are always built in a single thread.
+const MIN_CONCURRENT_LEAVES: usize = 1024;
 
 // TYPES AND INTERFACES
 // ================================================================================================
-pub struct MerkleTree {
-    nodes   : Vec<[u8; 32]>,
-    values  : Vec<[u8; 32]>
+pub struct MerkleTree<H: Hasher> {
+    nodes   : Vec<H::Digest>,
+    values  : Vec<H::Digest>,
+    hasher  : H,
 }
 
+/// Proof for a set of leaves of the same tree; nodes which can be computed from the leaves or
+/// from other paths of the proof are omitted.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct BatchMerkleProof {
-    pub values  : Vec<[u8; 32]>,
-    pub nodes   : Vec<Vec<[u8; 32]>>,
+#[serde(bound = "")]
+pub struct BatchMerkleProof<H: Hasher> {
+    pub values  : Vec<H::Digest>,
+    pub nodes   : Vec<Vec<H::Digest>>,
     pub depth   : u8
 }
 
+/// Describes the reason for which a batch Merkle proof could not be verified.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MerkleError {
+    /// Leaf index is outside of the tree, or is repeated.
+    InvalidIndex(usize),
+    /// Number of leaf values in the proof does not match the number of indexes.
+    ValueCountMismatch { expected: usize, actual: usize },
+    /// Number of paths in the proof does not match the number implied by the indexes.
+    PathCountMismatch { expected: usize, actual: usize },
+    /// Depth of the tree is too large for the tree to be addressable on this platform.
+    InvalidDepth(u8),
+    /// Proof does not contain enough nodes to authenticate the leaf at the specified index.
+    MissingNode(usize),
+    /// Proof contains more nodes than needed to authenticate the leaf at the specified index.
+    UnexpectedNode(usize),
+    /// Root computed from the proof does not match the expected root.
+    RootMismatch,
+}
+
 // MERKLE TREE IMPLEMENTATION
 // ================================================================================================
-impl MerkleTree {
+impl <H: Hasher> MerkleTree<H> {
+
+    /// Creates a new merkle tree from the provide leaves and using the default instance of the
+    /// hash function specified by `H` type parameter. Large trees are built using all available
+    /// threads.
+    pub fn new(leaves: Vec<H::Digest>) -> MerkleTree<H> {
+        return MerkleTree::with_hasher(leaves, H::default());
+    }
+
+    /// Same as `new()`, but nodes of the tree are computed using the provided `hasher` instance;
+    /// the tree keeps the instance, and uses it when leaves are updated.
+    pub fn with_hasher(leaves: Vec<H::Digest>, hasher: H) -> MerkleTree<H> {
+        let num_threads = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+        return MerkleTree::build(leaves, hasher, num_threads);
+    }
+
+    /// Creates a new merkle tree from the provided leaves; internal nodes are computed level by
+    /// level, and each level is split into batches which are distributed across `num_threads`
+    /// threads.
+    pub fn with_threads(leaves: Vec<H::Digest>, num_threads: usize) -> MerkleTree<H> {
+        return MerkleTree::build(leaves, H::default(), num_threads);
+    }
 
-    /// Creates a new merkle tree from the provide leaves and using the provided hash function.
-    pub fn new(leaves: Vec<[u8; 32]>, hash: HashFunction) -> MerkleTree {
+    /// Re-creates a merkle tree from the provided leaves and internal nodes, as returned by
+    /// `leaves()` and `nodes()` methods. The nodes are not re-hashed, and so it is up to the
+    /// caller to make sure that they were computed from the provided leaves by the default
+    /// instance of the hash function.
+    pub fn from_nodes(leaves: Vec<H::Digest>, nodes: Vec<H::Digest>) -> MerkleTree<H> {
         assert!(leaves.len().is_power_of_two(), "number of leaves must be a power of 2");
         assert!(leaves.len() >= 2, "a tree must contain at least 2 leaves");
+        assert!(nodes.len() == leaves.len(),
+            "expected {} internal nodes, but received {}", leaves.len(), nodes.len());
+
+        return MerkleTree {
+            values  : leaves,
+            nodes   : nodes,
+            hasher  : H::default(),
+        };
+    }
+
+    fn build(leaves: Vec<H::Digest>, hasher: H, num_threads: usize) -> MerkleTree<H> {
+        assert!(leaves.len().is_power_of_two(), "number of leaves must be a power of 2");
+        assert!(leaves.len() >= 2, "a tree must contain at least 2 leaves");
+        assert!(num_threads > 0, "number of threads must be greater than 0");
+
+        let nodes = if num_threads == 1 || leaves.len() < MIN_CONCURRENT_LEAVES {
+            build_merkle_nodes(&hasher, &leaves)
+        }
+        else {
+            build_merkle_nodes_concurrent(&hasher, &leaves, num_threads)
+        };
 
-        let nodes = build_merkle_nodes(&leaves, hash);
         return MerkleTree {
             values  : leaves,
-            nodes   : nodes
+            nodes   : nodes,
+            hasher  : hasher,
         };
     }
 
     /// Returns the root of the tree
-    pub fn root(&self) -> &[u8; 32] {
+    pub fn root(&self) -> &H::Digest {
         return &self.nodes[1];
     }
 
+    /// Returns the instance of the hash function used to compute nodes of the tree.
+    pub fn hasher(&self) -> &H {
+        return &self.hasher;
+    }
+
     /// Returns leaf nodes of the tree
-    pub fn leaves(&self) -> &[[u8; 32]] {
+    pub fn leaves(&self) -> &[H::Digest] {
         return &self.values;
     }
 
+    /// Returns internal nodes of the tree; the root is at position 1, children of node i are at
+    /// positions 2i and 2i + 1, and position 0 is unused.
+    pub fn nodes(&self) -> &[H::Digest] {
+        return &self.nodes;
+    }
+
+    /// Replaces the leaf at the specified `index` with `value`, and recomputes only the nodes on
+    /// the path from this leaf to the root.
+    pub fn update_leaf(&mut self, index: usize, value: H::Digest) {
+        assert!(index < self.values.len(), "invalid index {}", index);
+        self.values[index] = value;
+
+        let index = index & !1;
+        let mut node_index = (index + self.values.len()) >> 1;
+        self.nodes[node_index] = self.hasher.merge(&self.values[index], &self.values[index + 1]);
+        while node_index > 1 {
+            node_index = node_index >> 1;
+            self.nodes[node_index] = self.hasher.merge(&self.nodes[node_index * 2], &self.nodes[node_index * 2 + 1]);
+        }
+    }
+
+    /// Replaces leaves at the specified indexes with the provided values; each node which is
+    /// affected by more than one update is recomputed only once.
+    pub fn update_leaves(&mut self, updates: &[(usize, H::Digest)]) {
+        let n = self.values.len();
+        let mut indexes = BTreeSet::new();
+        for &(index, value) in updates {
+            assert!(index < n, "invalid index {}", index);
+            self.values[index] = value;
+            indexes.insert((index + n) >> 1);
+        }
+        if indexes.is_empty() { return; }
+
+        // update parents of the leaves
+        for &i in indexes.iter() {
+            let j = (i - n / 2) * 2;
+            self.nodes[i] = self.hasher.merge(&self.values[j], &self.values[j + 1]);
+        }
+
+        // move up level by level until we get to the root
+        while !indexes.contains(&1) {
+            indexes = indexes.into_iter().map(|i| i >> 1).collect();
+            for &i in indexes.iter() {
+                self.nodes[i] = self.hasher.merge(&self.nodes[i * 2], &self.nodes[i * 2 + 1]);
+            }
+        }
+    }
+
     /// Computes merkle path the given leaf index.
-    pub fn prove(&self, index: usize) -> Vec<[u8; 32]> {
+    pub fn prove(&self, index: usize) -> Vec<H::Digest> {
         assert!(index < self.values.len(), "invalid index {}", index);
 
         let mut proof = Vec::new();
@@ -61,246 +188,373 @@ impl MerkleTree {
     }
 
     /// Computes merkle paths for the provided indexes and compresses the paths into a single proof.
-    pub fn prove_batch(&self, indexes: &[usize]) -> BatchMerkleProof {
+    pub fn prove_batch(&self, indexes: &[usize]) -> BatchMerkleProof<H> {
         let n = self.values.len();
+        let depth = n.trailing_zeros() as u8;
+        return build_batch_proof::<H, _>(indexes, depth, |i| {
+            if i >= n { self.values[i - n] } else { self.nodes[i] }
+        });
+    }
 
-        let index_map = map_indexes(indexes, n);
-        let indexes = normalize_indexes(indexes);
-        let mut values = vec![[0u8; 32]; index_map.len()];
-        let mut nodes: Vec<Vec<[u8; 32]>> = Vec::with_capacity(indexes.len());
+    /// Checks whether the path for the specified index is valid; nodes on the path are computed
+    /// with the provided `hasher` instance.
+    pub fn verify(hasher: &H, root: &H::Digest, index: usize, proof: &[H::Digest]) -> bool {
+        let r = index & 1;
+        let mut v = hasher.merge(&proof[r], &proof[1 - r]);
 
-        // populate the proof with leaf node values
-        let mut next_indexes: Vec<usize> = Vec::new();
-        for index in indexes {
-            let v1 = self.values[index];
-            let v2 = self.values[index + 1];
-
-            // only values for indexes that were explicitly requested are included in values array
-            let input_index1 = index_map.get(&index);
-            let input_index2 = index_map.get(&(index + 1));
-            if input_index1.is_some() {
-                if input_index2.is_some() {
-                    values[*input_index1.unwrap()] = v1;
-                    values[*input_index2.unwrap()] = v2;
-                    nodes.push(Vec::new());
-                }
-                else {
-                    values[*input_index1.unwrap()] = v1;
-                    nodes.push(vec![v2]);
-                }
+        let mut index = (index + usize::pow(2, (proof.len() - 1) as u32)) >> 1;
+        for i in 2..proof.len() {
+            if index & 1 == 0 {
+                v = hasher.merge(&v, &proof[i]);
             }
             else {
-                values[*input_index2.unwrap()] = v2;
-                nodes.push(vec![v1]);
+                v = hasher.merge(&proof[i], &v);
             }
-
-            next_indexes.push((index + n) >> 1);
+            index = index >> 1;
         }
 
-        // add required internal nodes to the proof, skipping redundancies
-        let depth = self.values.len().trailing_zeros() as u8;
-        for _ in 1..depth {
-            let indexes = next_indexes.clone();
-            next_indexes.truncate(0);
-
-            let mut i = 0;
-            while i < indexes.len() {
-                let sibling_index = indexes[i] ^ 1;
-                if i + 1 < indexes.len() && indexes[i + 1] == sibling_index {
-                    i += 1;
-                }
-                else {
-                    nodes[i].push(self.nodes[sibling_index]);
-                }
-
-                // add parent index to the set of next indexes
-                next_indexes.push(sibling_index >> 1);
+        return v == *root;
+    }
 
-                i += 1;
-            }
+    /// Checks whether the batch proof contains merkle paths for the of the specified indexes;
+    /// if it does not, the returned error describes the reason.
+    pub fn verify_batch(hasher: &H, root: &H::Digest, indexes: &[usize], proof: &BatchMerkleProof<H>) -> Result<(), MerkleError> {
+        if proof.compute_root(hasher, indexes)? != *root {
+            return Err(MerkleError::RootMismatch);
         }
-
-        return BatchMerkleProof { values, nodes, depth };
+        return Ok(());
     }
+}
 
-    /// Checks whether the path for the specified index is valid.
-    pub fn verify(root: &[u8; 32], index: usize, proof: &[[u8; 32]], hash: HashFunction) -> bool {
-        let mut buf = [0u8; 64];
-        let mut v = [0u8; 32];
+// BATCH MERKLE PROOF IMPLEMENTATION
+// ================================================================================================
+impl <H: Hasher> BatchMerkleProof<H> {
 
-        let r = index & 1;
-        &buf[0..32].copy_from_slice(&proof[r]);
-        &buf[32..64].copy_from_slice(&proof[1 - r]);
-        hash(&buf, &mut v);
+    /// Computes the root of the tree from the values and nodes of this proof, where values are
+    /// leaves at the specified indexes.
+    pub fn compute_root(&self, hasher: &H, indexes: &[usize]) -> Result<H::Digest, MerkleError> {
+        let nodes = self.get_nodes(hasher, indexes)?;
+        return Ok(nodes[&1]);
+    }
 
-        let mut index = (index + usize::pow(2, (proof.len() - 1) as u32)) >> 1;
-        for i in 2..proof.len() {
-            if index & 1 == 0 {
-                &buf[0..32].copy_from_slice(&v);
-                &buf[32..64].copy_from_slice(&proof[i]);
-            }
-            else {
-                &buf[0..32].copy_from_slice(&proof[i]);
-                &buf[32..64].copy_from_slice(&v);
+    /// Merges this proof with the `other` proof for the same tree into a single proof. The
+    /// merged proof is for `indexes` followed by those of `other_indexes` which are not in
+    /// `indexes`; these are returned together with the proof. An error is returned if either
+    /// of the proofs is invalid, or if the proofs resolve to different roots.
+    pub fn merge(&self, hasher: &H, indexes: &[usize], other: &BatchMerkleProof<H>, other_indexes: &[usize])
+        -> Result<(BatchMerkleProof<H>, Vec<usize>), MerkleError>
+    {
+        let mut nodes = self.get_nodes(hasher, indexes)?;
+        let other_nodes = other.get_nodes(hasher, other_indexes)?;
+        if self.depth != other.depth || nodes[&1] != other_nodes[&1] {
+            return Err(MerkleError::RootMismatch);
+        }
+        nodes.extend(other_nodes);
+
+        let mut merged_indexes = indexes.to_vec();
+        for &index in other_indexes {
+            if !indexes.contains(&index) {
+                merged_indexes.push(index);
             }
-            hash(&buf, &mut v);
-            index = index >> 1;
         }
 
-        return v == *root;
+        let proof = build_batch_proof::<H, _>(&merged_indexes, self.depth, |i| nodes[&i]);
+        return Ok((proof, merged_indexes));
     }
 
-    /// Checks whether the batch proof contains merkle paths for the of the specified indexes.
-    pub fn verify_batch(root: &[u8; 32], indexes: &[usize], proof: &BatchMerkleProof, hash: HashFunction) -> bool {
-        let mut buf = [0u8; 64];
-        let mut v: HashMap<usize, [u8; 32]> = HashMap::new();
+    /// Computes all nodes of the tree which can be derived from this proof, and returns them
+    /// together with the nodes contained in the proof. Nodes are indexed in the same way as in
+    /// `MerkleTree`, and leaf i is at position 2^depth + i.
+    fn get_nodes(&self, hasher: &H, indexes: &[usize]) -> Result<HashMap<usize, H::Digest>, MerkleError> {
+        // depth comes from the proof, and so it must be checked before it is used as a shift
+        if self.depth as u32 >= usize::BITS {
+            return Err(MerkleError::InvalidDepth(self.depth));
+        }
+        let offset = usize::pow(2, self.depth as u32);
+        let index_map = try_map_indexes(indexes, offset)?;
+        if self.values.len() != indexes.len() {
+            return Err(MerkleError::ValueCountMismatch { expected: indexes.len(), actual: self.values.len() });
+        }
 
-        // replace odd indexes, offset, and sort in ascending order
-        let offset = usize::pow(2, proof.depth as u32);
-        let index_map = map_indexes(indexes, offset - 1);
         let indexes = normalize_indexes(indexes);
-        if indexes.len() != proof.nodes.len() { return false; }
+        if self.nodes.len() != indexes.len() {
+            return Err(MerkleError::PathCountMismatch { expected: indexes.len(), actual: self.nodes.len() });
+        }
+
+        // errors refer to leaf indexes which were requested by the caller; as paths merge at
+        // upper levels, each node keeps the first requested leaf below it, and each path keeps
+        // the leaf of the node which last took a sibling from it
+        let requested: Vec<usize> = indexes.iter()
+            .map(|&index| if index_map.contains_key(&index) { index } else { index + 1 })
+            .collect();
+        let mut path_leaves = requested.clone();
+        let mut next_leaves = requested.clone();
 
         // for each index use values to compute parent nodes
+        let mut v: HashMap<usize, H::Digest> = HashMap::new();
         let mut next_indexes: Vec<usize> = Vec::new();
         let mut proof_pointers: Vec<usize> = Vec::with_capacity(indexes.len());
-        for (i, index) in indexes.into_iter().enumerate() {
-            // copy values of leaf sibling leaf nodes into the buffer
-            match index_map.get(&index) {
-                Some(&index1) => {
-                    if proof.values.len() <= index1 { return false }
-                    &buf[0..32].copy_from_slice(&proof.values[index1]);
-                    match index_map.get(&(index + 1)) {
-                        Some(&index2) => {
-                            if proof.values.len() <= index2 { return false }
-                            &buf[32..64].copy_from_slice(&proof.values[index2]);
-                            proof_pointers.push(0);
-                        },
-                        None => {
-                            if proof.nodes[i].len() < 1 { return false }
-                            &buf[32..64].copy_from_slice(&proof.nodes[i][0]);
-                            proof_pointers.push(1);
-                        }
-                    }
+        for (i, &index) in indexes.iter().enumerate() {
+            // determine values of sibling leaf nodes; if only one of them is in the values
+            // array, the other one must be the first node of the path
+            let (left, right, pointer) = match (index_map.get(&index), index_map.get(&(index + 1))) {
+                (Some(&index1), Some(&index2)) => (self.values[index1], self.values[index2], 0),
+                (Some(&index1), None) => match self.nodes[i].first() {
+                    Some(&node) => (self.values[index1], node, 1),
+                    None => return Err(MerkleError::MissingNode(requested[i])),
                 },
-                None => {
-                    if proof.nodes[i].len() < 1 { return false }
-                    &buf[0..32].copy_from_slice(&proof.nodes[i][0]);
-                    match index_map.get(&(index + 1)) {
-                        Some(&index2) => {
-                            if proof.values.len() <= index2 { return false }
-                            &buf[32..64].copy_from_slice(&proof.values[index2]);
-                        },
-                        None => return false
-                    }
-                    proof_pointers.push(1);
-                }
-            }
+                (None, Some(&index2)) => match self.nodes[i].first() {
+                    Some(&node) => (node, self.values[index2], 1),
+                    None => return Err(MerkleError::MissingNode(requested[i])),
+                },
+                (None, None) => unreachable!("normalized index must be requested"),
+            };
+            proof_pointers.push(pointer);
 
             // hash sibling nodes into their parent
-            let mut parent = [0u8; 32];
-            hash(&buf, &mut parent);
-
-            let parent_index = offset + index >> 1;
-            v.insert(parent_index, parent);
+            v.insert(offset + index, left);
+            v.insert(offset + index + 1, right);
+            let parent_index = (offset + index) >> 1;
+            v.insert(parent_index, hasher.merge(&left, &right));
             next_indexes.push(parent_index);
         }
 
         // iteratively move up, until we get to the root
-        for _ in 1..proof.depth {
+        for _ in 1..self.depth {
             let indexes = next_indexes.clone();
             next_indexes.truncate(0);
+            let leaves = next_leaves.clone();
+            next_leaves.truncate(0);
 
             let mut i = 0;
             while i < indexes.len() {
                 let node_index = indexes[i];
                 let sibling_index = node_index ^ 1;
+                let leaf = leaves[i];
 
                 // determine the sibling
-                let sibling: &[u8; 32];
-                if i + 1 < indexes.len() && indexes[i + 1] == sibling_index {
-                    sibling = match v.get(&sibling_index) {
-                        Some(sibling) => sibling,
-                        None => return false
-                    };
+                let sibling = if i + 1 < indexes.len() && indexes[i + 1] == sibling_index {
                     i += 1;
+                    v[&sibling_index]
                 }
                 else {
                     let pointer = proof_pointers[i];
-                    if proof.nodes[i].len() <= pointer { return false }
-                    sibling = &proof.nodes[i][pointer];
+                    let sibling = match self.nodes[i].get(pointer) {
+                        Some(&node) => node,
+                        None => return Err(MerkleError::MissingNode(leaf)),
+                    };
                     proof_pointers[i] += 1;
-                }
-
-                // get the node from the map of hashed nodes
-                let node = match v.get(&node_index) {
-                    Some(node) => node,
-                    None => return false
+                    path_leaves[i] = leaf;
+                    v.insert(sibling_index, sibling);
+                    sibling
                 };
 
                 // compute parent node from node and sibling
-                if node_index & 1 != 0 {
-                    &buf[0..32].copy_from_slice(sibling);
-                    &buf[32..64].copy_from_slice(node);
+                let node = v[&node_index];
+                let parent = if node_index & 1 != 0 {
+                    hasher.merge(&sibling, &node)
                 }
                 else {
-                    &buf[0..32].copy_from_slice(node);
-                    &buf[32..64].copy_from_slice(sibling);
-                }
-                let mut parent = [0u8; 32];
-                hash(&buf, &mut parent);
+                    hasher.merge(&node, &sibling)
+                };
 
                 // add the parent node to the next set of nodes
                 let parent_index = node_index >> 1;
                 v.insert(parent_index, parent);
                 next_indexes.push(parent_index);
+                next_leaves.push(leaf);
 
                 i += 1;
             }
         }
-     
-        return *root == *v.get(&1).unwrap();
+
+        // make sure all nodes of the proof have been used
+        for (i, path) in self.nodes.iter().enumerate() {
+            if proof_pointers[i] != path.len() {
+                return Err(MerkleError::UnexpectedNode(path_leaves[i]));
+            }
+        }
+
+        return Ok(v);
     }
 }
 
+// MERKLE ERROR
+// ================================================================================================
+
+impl std::fmt::Display for MerkleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MerkleError::InvalidIndex(index) =>
+                write!(f, "leaf index {} is out of bounds or repeated", index),
+            MerkleError::ValueCountMismatch { expected, actual } =>
+                write!(f, "expected {} leaf values, but proof contains {}", expected, actual),
+            MerkleError::PathCountMismatch { expected, actual } =>
+                write!(f, "expected {} paths, but proof contains {}", expected, actual),
+            MerkleError::InvalidDepth(depth) =>
+                write!(f, "tree depth {} is not supported", depth),
+            MerkleError::MissingNode(index) =>
+                write!(f, "proof is missing a node for leaf {}", index),
+            MerkleError::UnexpectedNode(index) =>
+                write!(f, "proof contains extra nodes for leaf {}", index),
+            MerkleError::RootMismatch =>
+                write!(f, "proof does not resolve to the expected root"),
+        }
+    }
+}
+
+impl std::error::Error for MerkleError {}
+
 // HELPER FUNCTIONS
 // ================================================================================================
 
-pub fn build_merkle_nodes(leaves: &[[u8; 32]], hash: HashFunction) -> Vec<[u8; 32]> {
+pub fn build_merkle_nodes<H: Hasher>(hasher: &H, leaves: &[H::Digest]) -> Vec<H::Digest> {
     let n = leaves.len() / 2;
 
-    // create un-initialized array to hold all intermediate nodes
-    let mut nodes: Vec<[u8; 32]> = Vec::with_capacity(2 * n);
-    unsafe { nodes.set_len(2 * n); }
-    nodes[0] = [0u8; 32];
-
-    // re-interpret leaves as an array of two leaves fused together
-    let two_leaves = unsafe { slice::from_raw_parts(leaves.as_ptr() as *const [u8; 64], n) };
+    // create an array to hold all intermediate nodes
+    let mut nodes = vec![H::Digest::default(); 2 * n];
 
     // build first row of internal nodes (parents of leaves)
     for (i, j) in (0..n).zip(n..nodes.len()) {
-        hash(&two_leaves[i], &mut nodes[j]);
+        nodes[j] = hasher.merge(&leaves[i * 2], &leaves[i * 2 + 1]);
     }
 
-    // re-interpret nodes as an array of two nodes fused together
-    let two_nodes = unsafe { slice::from_raw_parts(nodes.as_ptr() as *const [u8; 64], n) };
-
     // calculate all other tree nodes
     for i in (1..n).rev() {
-        hash(&two_nodes[i], &mut nodes[i]);
+        nodes[i] = hasher.merge(&nodes[i * 2], &nodes[i * 2 + 1]);
     }
 
     return nodes;
 }
 
-fn map_indexes(indexes: &[usize], max_valid: usize) -> HashMap<usize, usize> {
+/// Builds internal nodes of a merkle tree in the same way as `build_merkle_nodes()`, but each
+/// level of the tree is split into batches which are hashed in `num_threads` threads. Levels
+/// with fewer nodes than there are threads are hashed in a single thread.
+pub fn build_merkle_nodes_concurrent<H: Hasher>(hasher: &H, leaves: &[H::Digest], num_threads: usize) -> Vec<H::Digest> {
+    let n = leaves.len() / 2;
+
+    // create an array to hold all intermediate nodes
+    let mut nodes = vec![H::Digest::default(); 2 * n];
+
+    // build first row of internal nodes (parents of leaves)
+    let batch_size = (n + num_threads - 1) / num_threads;
+    thread::scope(|s| {
+        for (i, batch) in nodes[n..].chunks_mut(batch_size).enumerate() {
+            let leaves = &leaves[(i * batch_size * 2)..];
+            s.spawn(move |_| {
+                for (j, node) in batch.iter_mut().enumerate() {
+                    *node = hasher.merge(&leaves[j * 2], &leaves[j * 2 + 1]);
+                }
+            });
+        }
+    }).unwrap();
+
+    // calculate all other tree nodes one level at a time; nodes at level [k, 2k) are computed
+    // from their children at level [2k, 4k)
+    let mut k = n / 2;
+    while k >= num_threads && k > 1 {
+        let (parents, children) = nodes.split_at_mut(2 * k);
+        let batch_size = k / num_threads;
+        thread::scope(|s| {
+            for (i, batch) in parents[k..].chunks_mut(batch_size).enumerate() {
+                let children = &children[(i * batch_size * 2)..];
+                s.spawn(move |_| {
+                    for (j, node) in batch.iter_mut().enumerate() {
+                        *node = hasher.merge(&children[j * 2], &children[j * 2 + 1]);
+                    }
+                });
+            }
+        }).unwrap();
+        k = k / 2;
+    }
+
+    // the remaining levels are small enough to be computed in the current thread
+    for i in (1..(k * 2)).rev() {
+        nodes[i] = hasher.merge(&nodes[i * 2], &nodes[i * 2 + 1]);
+    }
+
+    return nodes;
+}
+
+/// Builds a batch proof for the specified indexes in a tree of the specified depth; `get_node`
+/// returns the node at the specified position, where nodes are indexed in the same way as in
+/// `MerkleTree`, and leaf i is at position 2^depth + i.
+fn build_batch_proof<H, F>(indexes: &[usize], depth: u8, get_node: F) -> BatchMerkleProof<H>
+    where H: Hasher, F: Fn(usize) -> H::Digest
+{
+    let n = usize::pow(2, depth as u32);
+
+    let index_map = match try_map_indexes(indexes, n) {
+        Ok(index_map) => index_map,
+        Err(err) => panic!("{}", err),
+    };
+    let indexes = normalize_indexes(indexes);
+    let mut values = vec![H::Digest::default(); index_map.len()];
+    let mut nodes: Vec<Vec<H::Digest>> = Vec::with_capacity(indexes.len());
+
+    // populate the proof with leaf node values
+    let mut next_indexes: Vec<usize> = Vec::new();
+    for index in indexes {
+        let v1 = get_node(n + index);
+        let v2 = get_node(n + index + 1);
+
+        // only values for indexes that were explicitly requested are included in values array
+        let input_index1 = index_map.get(&index);
+        let input_index2 = index_map.get(&(index + 1));
+        if input_index1.is_some() {
+            if input_index2.is_some() {
+                values[*input_index1.unwrap()] = v1;
+                values[*input_index2.unwrap()] = v2;
+                nodes.push(Vec::new());
+            }
+            else {
+                values[*input_index1.unwrap()] = v1;
+                nodes.push(vec![v2]);
+            }
+        }
+        else {
+            values[*input_index2.unwrap()] = v2;
+            nodes.push(vec![v1]);
+        }
+
+        next_indexes.push((index + n) >> 1);
+    }
+
+    // add required internal nodes to the proof, skipping redundancies
+    for _ in 1..depth {
+        let indexes = next_indexes.clone();
+        next_indexes.truncate(0);
+
+        let mut i = 0;
+        while i < indexes.len() {
+            let sibling_index = indexes[i] ^ 1;
+            if i + 1 < indexes.len() && indexes[i + 1] == sibling_index {
+                i += 1;
+            }
+            else {
+                nodes[i].push(get_node(sibling_index));
+            }
+
+            // add parent index to the set of next indexes
+            next_indexes.push(sibling_index >> 1);
+
+            i += 1;
+        }
+    }
+
+    return BatchMerkleProof { values, nodes, depth };
+}
+
+/// Maps each index to its position in the `indexes` list; returns an error if any of the indexes
+/// is repeated or is not smaller than `num_leaves`.
+fn try_map_indexes(indexes: &[usize], num_leaves: usize) -> Result<HashMap<usize, usize>, MerkleError> {
     let mut map = HashMap::new();
     for (i, index) in indexes.iter().cloned().enumerate() {
-        map.insert(index, i);
-        assert!(index <= max_valid, "invalid index {}", index);
+        if index >= num_leaves || map.insert(index, i).is_some() {
+            return Err(MerkleError::InvalidIndex(index));
+        }
     }
-    assert!(indexes.len() == map.len(), "repeating indexes detected");
-    return map;
+    return Ok(map);
 }
 
 fn normalize_indexes(indexes: &[usize]) -> Vec<usize> {
@@ -316,7 +570,10 @@ fn normalize_indexes(indexes: &[usize]) -> Vec<usize> {
 #[cfg(test)]
 mod tests {
 
-    use crate::crypto::hash;
+    use crate::crypto::{ Hasher, hash::{ self, Poseidon } };
+    use crate::tests::KeyedHasher;
+    use super::MerkleError::*;
+    type MerkleTree = super::MerkleTree<Poseidon>;
 
     static LEAVES4: [[u8; 32]; 4] = [
         [166, 168,  47, 140, 153, 86, 156,  86, 226, 229, 149,  76,  70, 132, 209, 109, 166, 193, 113, 197,  42, 116, 170, 144,  74, 104,  29, 110, 220, 49, 224, 123],
@@ -339,7 +596,7 @@ mod tests {
     #[test]
     fn new_tree() {
         let leaves = LEAVES4.to_vec();
-        let tree = super::MerkleTree::new(leaves, hash::poseidon);
+        let tree = MerkleTree::new(leaves);
         let root = hash_2x1(
             &hash_2x1(&LEAVES4[0], &LEAVES4[1]),
             &hash_2x1(&LEAVES4[2], &LEAVES4[3])
@@ -347,7 +604,7 @@ mod tests {
         assert_eq!(&root, tree.root());
 
         let leaves = LEAVES8.to_vec();
-        let tree = super::MerkleTree::new(leaves, hash::poseidon);
+        let tree = MerkleTree::new(leaves);
         let root = hash_2x1(
             &hash_2x1(
                 &hash_2x1(&LEAVES8[0], &LEAVES8[1]),
@@ -365,7 +622,7 @@ mod tests {
     fn prove() {
         // depth 4
         let leaves = LEAVES4.to_vec();
-        let tree = super::MerkleTree::new(leaves, hash::poseidon);
+        let tree = MerkleTree::new(leaves);
 
         let proof = vec![
             LEAVES4[1],
@@ -383,7 +640,7 @@ mod tests {
 
         // depth 5
         let leaves = LEAVES8.to_vec();
-        let tree = super::MerkleTree::new(leaves, hash::poseidon);
+        let tree = MerkleTree::new(leaves);
 
         let proof = vec![
             LEAVES8[1],
@@ -406,27 +663,27 @@ mod tests {
     fn verify() {
         // depth 4
         let leaves = LEAVES4.to_vec();
-        let tree = super::MerkleTree::new(leaves, hash::poseidon);
+        let tree = MerkleTree::new(leaves);
         let proof = tree.prove(1);
-        assert_eq!(true, super::MerkleTree::verify(tree.root(), 1, &proof, hash::poseidon));
+        assert_eq!(true, MerkleTree::verify(tree.hasher(), tree.root(), 1, &proof));
 
         let proof = tree.prove(2);
-        assert_eq!(true, super::MerkleTree::verify(tree.root(), 2, &proof, hash::poseidon));
+        assert_eq!(true, MerkleTree::verify(tree.hasher(), tree.root(), 2, &proof));
 
         // depth 5
         let leaves = LEAVES8.to_vec();
-        let tree = super::MerkleTree::new(leaves, hash::poseidon);
+        let tree = MerkleTree::new(leaves);
         let proof = tree.prove(1);
-        assert_eq!(true, super::MerkleTree::verify(tree.root(), 1, &proof, hash::poseidon));
+        assert_eq!(true, MerkleTree::verify(tree.hasher(), tree.root(), 1, &proof));
 
         let proof = tree.prove(6);
-        assert_eq!(true, super::MerkleTree::verify(tree.root(), 6, &proof, hash::poseidon));
+        assert_eq!(true, MerkleTree::verify(tree.hasher(), tree.root(), 6, &proof));
     }
 
     #[test]
     fn prove_batch() {
         let leaves = LEAVES8.to_vec();
-        let tree = super::MerkleTree::new(leaves, hash::poseidon);
+        let tree = MerkleTree::new(leaves);
         
         // 1 index
         let proof = tree.prove_batch(&[1]);
@@ -495,37 +752,159 @@ mod tests {
     #[test]
     fn verify_batch() {
         let leaves = LEAVES8.to_vec();
-        let tree = super::MerkleTree::new(leaves, hash::poseidon);
+        let tree = MerkleTree::new(leaves);
 
         let proof = tree.prove_batch(&[1]);
-        assert_eq!(true, super::MerkleTree::verify_batch(tree.root(), &[1], &proof, hash::poseidon));
-        assert_eq!(false, super::MerkleTree::verify_batch(tree.root(), &[2], &proof, hash::poseidon));
+        assert_eq!(Ok(()), MerkleTree::verify_batch(tree.hasher(), tree.root(), &[1], &proof));
+        assert_eq!(Err(RootMismatch), MerkleTree::verify_batch(tree.hasher(), tree.root(), &[2], &proof));
 
         let proof = tree.prove_batch(&[1, 2]);
-        assert_eq!(true, super::MerkleTree::verify_batch(tree.root(), &[1, 2], &proof, hash::poseidon));
-        assert_eq!(false, super::MerkleTree::verify_batch(tree.root(), &[1], &proof, hash::poseidon));
-        assert_eq!(false, super::MerkleTree::verify_batch(tree.root(), &[1, 3], &proof, hash::poseidon));
-        assert_eq!(false, super::MerkleTree::verify_batch(tree.root(), &[1, 2, 3], &proof, hash::poseidon));
+        assert_eq!(Ok(()), MerkleTree::verify_batch(tree.hasher(), tree.root(), &[1, 2], &proof));
+        assert_eq!(Err(ValueCountMismatch { expected: 1, actual: 2 }),
+            MerkleTree::verify_batch(tree.hasher(), tree.root(), &[1], &proof));
+        assert_eq!(Err(RootMismatch), MerkleTree::verify_batch(tree.hasher(), tree.root(), &[1, 3], &proof));
+        assert_eq!(Err(ValueCountMismatch { expected: 3, actual: 2 }),
+            MerkleTree::verify_batch(tree.hasher(), tree.root(), &[1, 2, 3], &proof));
+        assert_eq!(Err(InvalidIndex(8)), MerkleTree::verify_batch(tree.hasher(), tree.root(), &[1, 8], &proof));
+        assert_eq!(Err(InvalidIndex(1)), MerkleTree::verify_batch(tree.hasher(), tree.root(), &[1, 1], &proof));
 
         let proof = tree.prove_batch(&[1, 6]);
-        assert_eq!(true, super::MerkleTree::verify_batch(tree.root(), &[1, 6], &proof, hash::poseidon));
+        assert_eq!(Ok(()), MerkleTree::verify_batch(tree.hasher(), tree.root(), &[1, 6], &proof));
 
         let proof = tree.prove_batch(&[1, 3, 6]);
-        assert_eq!(true, super::MerkleTree::verify_batch(tree.root(), &[1, 3, 6], &proof, hash::poseidon));
+        assert_eq!(Ok(()), MerkleTree::verify_batch(tree.hasher(), tree.root(), &[1, 3, 6], &proof));
 
         let proof = tree.prove_batch(&[0, 1, 2, 3, 4, 5, 6, 7]);
-        assert_eq!(true, super::MerkleTree::verify_batch(tree.root(), &[0, 1, 2, 3, 4, 5, 6, 7], &proof, hash::poseidon));
+        assert_eq!(Ok(()), MerkleTree::verify_batch(tree.hasher(), tree.root(), &[0, 1, 2, 3, 4, 5, 6, 7], &proof));
+
+        // missing and extra nodes are attributed to the leaf of the affected path
+        let mut proof = tree.prove_batch(&[1, 6]);
+        proof.nodes[1].pop();
+        assert_eq!(Err(MissingNode(6)), MerkleTree::verify_batch(tree.hasher(), tree.root(), &[1, 6], &proof));
+
+        let mut proof = tree.prove_batch(&[1, 6]);
+        proof.nodes[0].push(LEAVES8[0]);
+        assert_eq!(Err(UnexpectedNode(1)), MerkleTree::verify_batch(tree.hasher(), tree.root(), &[1, 6], &proof));
+
+        // once paths of leaves 1 and 3 merge, the second path holds a sibling for leaf 9
+        let tree = MerkleTree::new(build_leaves(16));
+        let mut proof = tree.prove_batch(&[1, 3, 9]);
+        proof.nodes[1].pop();
+        assert_eq!(Err(MissingNode(9)), MerkleTree::verify_batch(tree.hasher(), tree.root(), &[1, 3, 9], &proof));
+
+        let mut proof = tree.prove_batch(&[1, 3, 9]);
+        proof.nodes[1].push(LEAVES8[0]);
+        assert_eq!(Err(UnexpectedNode(9)), MerkleTree::verify_batch(tree.hasher(), tree.root(), &[1, 3, 9], &proof));
+
+        // depth is supplied by the prover, and so it cannot be trusted to fit into usize
+        let mut proof = tree.prove_batch(&[1, 3, 9]);
+        proof.depth = 64;
+        assert_eq!(Err(InvalidDepth(64)), MerkleTree::verify_batch(tree.hasher(), tree.root(), &[1, 3, 9], &proof));
+    }
+
+    #[test]
+    fn merge_batch() {
+        let tree = MerkleTree::new(build_leaves(64));
+        let proof1 = tree.prove_batch(&[3, 17, 40]);
+        let proof2 = tree.prove_batch(&[2, 40, 63]);
+
+        let (merged, indexes) = proof1.merge(&Poseidon, &[3, 17, 40], &proof2, &[2, 40, 63]).unwrap();
+        assert_eq!(vec![3, 17, 40, 2, 63], indexes);
+        assert_eq!(tree.prove_batch(&indexes).nodes, merged.nodes);
+        assert_eq!(Ok(()), MerkleTree::verify_batch(tree.hasher(), tree.root(), &indexes, &merged));
+
+        // proofs against different roots cannot be merged
+        let tree2 = MerkleTree::new(build_leaves(32).into_iter().chain(build_leaves(32)).collect());
+        let proof3 = tree2.prove_batch(&[5]);
+        assert_eq!(Err(RootMismatch), proof1.merge(&Poseidon, &[3, 17, 40], &proof3, &[5]).map(|_| ()));
+    }
+
+    #[test]
+    fn build_concurrent() {
+        let leaves = build_leaves(1 << 12);
+        let expected = super::build_merkle_nodes(&Poseidon, &leaves);
+        for &num_threads in [2, 3, 4, 8].iter() {
+            assert_eq!(expected, super::build_merkle_nodes_concurrent(&Poseidon, &leaves, num_threads));
+        }
+
+        let tree = MerkleTree::with_threads(leaves, 4);
+        assert_eq!(expected, tree.nodes());
+    }
+
+    #[test]
+    fn update_leaf() {
+        let mut leaves = LEAVES8.to_vec();
+        let mut tree = MerkleTree::new(leaves.clone());
+
+        tree.update_leaf(5, LEAVES4[0]);
+        leaves[5] = LEAVES4[0];
+        assert_eq!(MerkleTree::new(leaves.clone()).nodes(), tree.nodes());
+        assert_eq!(true, MerkleTree::verify(tree.hasher(), tree.root(), 5, &tree.prove(5)));
+
+        tree.update_leaf(0, LEAVES4[1]);
+        leaves[0] = LEAVES4[1];
+        assert_eq!(MerkleTree::new(leaves).nodes(), tree.nodes());
+    }
+
+    #[test]
+    fn update_leaves() {
+        let mut leaves = build_leaves(64);
+        let mut tree = MerkleTree::new(leaves.clone());
+
+        let updates = [(3, LEAVES4[0]), (2, LEAVES4[1]), (40, LEAVES4[2]), (63, LEAVES4[3])];
+        tree.update_leaves(&updates);
+        for &(index, value) in updates.iter() {
+            leaves[index] = value;
+        }
+        assert_eq!(MerkleTree::new(leaves).nodes(), tree.nodes());
+
+        // an empty update leaves the tree unchanged
+        let root = *tree.root();
+        tree.update_leaves(&[]);
+        assert_eq!(&root, tree.root());
+    }
+
+    #[test]
+    fn from_nodes() {
+        let tree = MerkleTree::new(LEAVES8.to_vec());
+        let tree2 = MerkleTree::from_nodes(tree.leaves().to_vec(), tree.nodes().to_vec());
+        assert_eq!(tree.root(), tree2.root());
+        assert_eq!(tree.prove(3), tree2.prove(3));
+    }
+
+    #[test]
+    fn keyed_hasher() {
+        let mut leaves = build_leaves(16);
+        let tree1 = super::MerkleTree::with_hasher(leaves.clone(), KeyedHasher::new([1; 32]));
+        let tree2 = super::MerkleTree::with_hasher(leaves.clone(), KeyedHasher::new([2; 32]));
+        assert_ne!(tree1.root(), tree2.root());
+
+        // proofs can be verified only with the key used to build the tree
+        let proof = tree1.prove_batch(&[1, 9]);
+        assert_eq!(Ok(()), super::MerkleTree::verify_batch(tree1.hasher(), tree1.root(), &[1, 9], &proof));
+        assert_eq!(Err(RootMismatch), super::MerkleTree::verify_batch(tree2.hasher(), tree1.root(), &[1, 9], &proof));
+
+        // updated nodes are computed with the key of the tree
+        let mut tree = tree1;
+        tree.update_leaf(3, LEAVES4[0]);
+        leaves[3] = LEAVES4[0];
+        let expected = super::MerkleTree::with_hasher(leaves, KeyedHasher::new([1; 32]));
+        assert_eq!(expected.nodes(), tree.nodes());
     }
 
     // HELPER FUNCTIONS
     // --------------------------------------------------------------------------------------------
-    fn hash_2x1(v1: &[u8; 32], v2: &[u8; 32]) -> [u8; 32] {
-        let mut buf = [0u8; 64];
-        buf[..32].copy_from_slice(v1);
-        buf[32..].copy_from_slice(v2);
+    fn build_leaves(n: usize) -> Vec<[u8; 32]> {
+        let mut leaves = Vec::with_capacity(n);
+        for i in 0..n {
+            let mut leaf = [0u8; 32];
+            hash::poseidon(&(i as u64).to_le_bytes(), &mut leaf);
+            leaves.push(leaf);
+        }
+        return leaves;
+    }
 
-        let mut result = [0u8; 32];
-        hash::poseidon(&buf, &mut result);
-        return result;
+    fn hash_2x1(v1: &[u8; 32], v2: &[u8; 32]) -> [u8; 32] {
+        return Poseidon.merge(v1, v2);
     }
 }
\ No newline at end of file
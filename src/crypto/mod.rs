@@ -1,6 +1,65 @@
+use std::fmt::Debug;
+use serde::{ Serialize, de::DeserializeOwned };
+
 pub mod hash;
+pub mod rescue;
+pub mod poseidon;
+pub mod lamport;
 
 mod merkle;
-pub use merkle::{ MerkleTree, BatchMerkleProof, build_merkle_nodes };
+pub use merkle::{
+    MerkleTree, BatchMerkleProof, MerkleError,
+    build_merkle_nodes, build_merkle_nodes_concurrent,
+};
+
+mod field_merkle;
+pub use field_merkle::{ FieldMerkleTree };
+
+mod sparse_merkle;
+pub use sparse_merkle::{ SparseMerkleTree, MAX_SPARSE_TREE_DEPTH };
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Defines a hash function which can be used to build Merkle trees and to generate proofs.
+/// 
+/// Hash functions are called on an instance of the hasher, and so the instance can carry state,
+/// e.g. a key or a domain separation tag. Stateless hash functions (such as the ones defined in
+/// the `hash` module) are unit structs; wherever an instance is not supplied by the caller, the
+/// `Default` instance is used.
+/// 
+/// `ID` is a stable identifier of the hash function; it is written into serialized proofs
+/// and must be unique among all hash functions which are used together. The state of the hasher
+/// is not serialized.
+pub trait Hasher: Clone + Debug + Default + Send + Sync + 'static {
+    /// Output of the hash function.
+    type Digest: Digest;
+
+    /// Stable identifier of the hash function.
+    const ID: u8;
+
+    /// Hashes an arbitrary sequence of bytes into a digest.
+    fn hash(&self, values: &[u8]) -> Self::Digest;
+
+    /// Merges two digests into a single digest; this is used to compute internal nodes of
+    /// Merkle trees. By default, bytes of both digests are concatenated and hashed together.
+    fn merge(&self, left: &Self::Digest, right: &Self::Digest) -> Self::Digest {
+        let mut buf = [0u8; 64];
+        buf[..32].copy_from_slice(&left.as_bytes());
+        buf[32..].copy_from_slice(&right.as_bytes());
+        return self.hash(&buf);
+    }
+}
+
+/// Defines output of a hash function.
+pub trait Digest: Copy + Clone + Eq + Debug + Default + Serialize + DeserializeOwned + Send + Sync {
+    /// Returns a byte representation of the digest; this is also used to derive
+    /// pseudo-random values (e.g. query positions) from the digest.
+    fn as_bytes(&self) -> [u8; 32];
+}
 
-pub type HashFunction = fn(&[u8], &mut [u8]);
\ No newline at end of file
+impl Digest for [u8; 32] {
+    fn as_bytes(&self) -> [u8; 32] {
+        return *self;
+    }
+}
@@ -0,0 +1,92 @@
+use crate::{
+    HASH_STATE_RATE,
+    HASH_DIGEST_SIZE,
+};
+use super::hash;
+
+// CONSTANTS
+// ================================================================================================
+
+/// Maximum number of field elements which can be hashed in a single invocation.
+pub const RATE: usize = HASH_STATE_RATE;
+
+/// Number of field elements in a hash digest.
+pub const DIGEST_SIZE: usize = HASH_DIGEST_SIZE;
+
+// PUBLIC FUNCTIONS
+// ================================================================================================
+
+/// Computes the same hash as `poseidon.n` assembly instruction (n = `values.len()`) computes on
+/// the stack. For 4 values, this is the same value as the one computed by `Poseidon::merge()`
+/// for digests made of these values, and so Distaff programs can verify commitments (e.g. Merkle
+/// roots) built with `crypto::hash::Poseidon` hasher.
+///
+/// Both `values` and the returned digest are listed in the order in which they are pushed onto
+/// the stack; that is, the last element is the one at the top of the stack.
+///
+/// Panics if `values` is empty or contains more than 4 elements.
+pub fn hash(values: &[u128]) -> [u128; DIGEST_SIZE] {
+    assert!(values.len() > 0 && values.len() <= RATE,
+        "expected between 1 and {} values, but received {}", RATE, values.len());
+
+    return hash::poseidon_elements(values);
+}
+
+/// Computes the hash of two digests; this is the same as calling `hash()` with the elements of
+/// `a` followed by the elements of `b`. That is, this is the value `poseidon.4` instruction
+/// computes when digest `a` is pushed onto the stack before digest `b`.
+pub fn merge(a: &[u128; DIGEST_SIZE], b: &[u128; DIGEST_SIZE]) -> [u128; DIGEST_SIZE] {
+    return hash(&[a[0], a[1], b[0], b[1]]);
+}
+
+// TESTS
+// ================================================================================================
+#[cfg(test)]
+mod tests {
+
+    use crate::{ math::field, tests::run_source, ProgramInputs };
+    use crate::crypto::{ Hasher, hash::Poseidon };
+    use crate::utils::as_bytes;
+    
+    #[test]
+    fn hash_matches_vm() {
+        for n in 1..=super::RATE {
+            let values = field::rand_vector(n);
+            assert_eq!(super::hash(&values).to_vec(), execute_hash(&values));
+        }
+
+        // hash of a single zero
+        assert_eq!(super::hash(&[0]).to_vec(), execute_hash(&[0]));
+    }
+
+    #[test]
+    fn merge_matches_hasher() {
+        let a = [field::rand(), field::rand()];
+        let b = [field::rand(), field::rand()];
+        assert_eq!(super::merge(&a, &b).to_vec(), execute_hash(&[a[0], a[1], b[0], b[1]]));
+        assert!(super::merge(&a, &b) != super::merge(&b, &a));
+
+        // merging digests of Poseidon hasher gives the same result
+        let mut left = [0u8; 32];
+        left.copy_from_slice(as_bytes(&a));
+        let mut right = [0u8; 32];
+        right.copy_from_slice(as_bytes(&b));
+        let expected = Poseidon.merge(&left, &right);
+        assert_eq!(&expected[..], as_bytes(&super::merge(&a, &b)));
+    }
+
+    #[test]
+    #[should_panic(expected = "expected between 1 and 4 values, but received 5")]
+    fn hash_too_many_values() {
+        super::hash(&[1, 2, 3, 4, 5]);
+    }
+
+    /// Pushes `values` onto the stack, executes `poseidon.n`, and returns the top two stack items
+    /// in the order in which they would have been pushed onto the stack.
+    fn execute_hash(values: &[u128]) -> Vec<u128> {
+        let pushes = values.iter().map(|v| format!("push.{}", v)).collect::<Vec<_>>().join(" ");
+        let source = format!("begin {} poseidon.{} end", pushes, values.len());
+        let outputs = run_source(&source, &ProgramInputs::none(), 2).unwrap();
+        return vec![outputs[1], outputs[0]];
+    }
+}
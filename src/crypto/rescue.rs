@@ -0,0 +1,98 @@
+use crate::utils::hasher;
+use crate::{
+    HASH_STATE_RATE,
+    HASH_DIGEST_SIZE,
+};
+
+// CONSTANTS
+// ================================================================================================
+
+/// Maximum number of field elements which can be hashed in a single invocation.
+pub const RATE: usize = HASH_STATE_RATE;
+
+/// Number of field elements in a hash digest.
+pub const DIGEST_SIZE: usize = HASH_DIGEST_SIZE;
+
+// PUBLIC FUNCTIONS
+// ================================================================================================
+
+/// Computes the same hash as `hash.n` assembly instruction (n = `values.len()`) computes on the
+/// stack. The hash is a modified version of [Rescue](https://eprint.iacr.org/2019/426) described
+/// [here](../../../docs/assembly.md#Rescue-hash-function).
+///
+/// Both `values` and the returned digest are listed in the order in which they are pushed onto
+/// the stack; that is, the last element is the one at the top of the stack. For example, after
+/// executing `push.1 push.2 push.3 push.4 hash.4`, the stack will contain the digest returned by
+/// `hash(&[1, 2, 3, 4])` with its last element at the top.
+///
+/// Panics if `values` is empty or contains more than 4 elements.
+pub fn hash(values: &[u128]) -> [u128; DIGEST_SIZE] {
+    assert!(values.len() > 0 && values.len() <= RATE,
+        "expected between 1 and {} values, but received {}", RATE, values.len());
+
+    let digest = hasher::digest(values);
+    return [digest[0], digest[1]];
+}
+
+/// Computes the hash of two digests; this is the same as calling `hash()` with the elements of
+/// `a` followed by the elements of `b`. That is, this is the value `hash.4` instruction computes
+/// when digest `a` is pushed onto the stack before digest `b`.
+pub fn merge(a: &[u128; DIGEST_SIZE], b: &[u128; DIGEST_SIZE]) -> [u128; DIGEST_SIZE] {
+    return hash(&[a[0], a[1], b[0], b[1]]);
+}
+
+// TESTS
+// ================================================================================================
+#[cfg(test)]
+mod tests {
+
+    use crate::{ math::field, tests::run_source, ProgramInputs };
+
+    #[test]
+    fn hash() {
+        // test vectors; any change to these values means the hash function has changed
+        assert_eq!([
+            279286848567113119869118759719823148634,
+            74099529548440269876339428048800829206,
+        ], super::hash(&[1, 2, 3, 4]));
+
+        assert_eq!([
+            325770709507943575793020394214788590230,
+            307758163315608979237983299782144502219,
+        ], super::hash(&[1]));
+    }
+
+    #[test]
+    fn hash_matches_vm() {
+        for n in 1..=super::RATE {
+            let values = field::rand_vector(n);
+            assert_eq!(super::hash(&values).to_vec(), execute_hash(&values));
+        }
+
+        // hash of a single zero
+        assert_eq!(super::hash(&[0]).to_vec(), execute_hash(&[0]));
+    }
+
+    #[test]
+    fn merge_matches_vm() {
+        let a = [field::rand(), field::rand()];
+        let b = [field::rand(), field::rand()];
+        assert_eq!(super::merge(&a, &b).to_vec(), execute_hash(&[a[0], a[1], b[0], b[1]]));
+        assert!(super::merge(&a, &b) != super::merge(&b, &a));
+    }
+
+    #[test]
+    #[should_panic(expected = "expected between 1 and 4 values, but received 5")]
+    fn hash_too_many_values() {
+        super::hash(&[1, 2, 3, 4, 5]);
+    }
+
+    /// Pushes `values` onto the stack, executes `hash.n`, and returns the top two stack items
+    /// in the order in which they would have been pushed onto the stack.
+    fn execute_hash(values: &[u128]) -> Vec<u128> {
+        let pushes = values.iter().map(|v| format!("push.{}", v)).collect::<Vec<_>>().join(" ");
+        let source = format!("begin {} hash.{} end", pushes, values.len());
+        let outputs = run_source(&source, &ProgramInputs::none(), 2).unwrap();
+        return vec![outputs[1], outputs[0]];
+    }
+}
@@ -0,0 +1,334 @@
+use std::collections::HashMap;
+use crate::math::field;
+use crate::programs::ProgramInputs;
+use super::{ rescue, FieldMerkleTree };
+
+// CONSTANTS
+// ================================================================================================
+
+/// Maximum depth of a sparse Merkle tree; for this depth, keys are smaller than 2^127, and thus,
+/// binary decomposition of every key computed by `pmpath` instruction is unique in the field.
+pub const MAX_SPARSE_TREE_DEPTH: usize = 128;
+
+/// Value of a leaf which has not been set.
+const EMPTY_LEAF: [u128; 2] = [field::ZERO, field::ZERO];
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// A sparse Merkle tree which maps keys to values; both are field elements, and values are
+/// represented by pairs of field elements in the same way as nodes of `FieldMerkleTree`. A key
+/// is the index of its leaf, and leaves which have not been set contain [0, 0]. Internal nodes
+/// are computed using the same Rescue hash function as the one used by `pmpath` instruction,
+/// and so reads and updates of the tree can be verified by `smt.get`, `smt.absent` and `smt.set`
+/// instructions of Distaff VM.
+///
+/// Only nodes which differ from roots of empty subtrees are stored.
+pub struct SparseMerkleTree {
+    depth   : usize,
+    nodes   : HashMap<(usize, u128), [u128; 2]>,
+    empty   : Vec<[u128; 2]>,
+}
+
+// SPARSE MERKLE TREE IMPLEMENTATION
+// ================================================================================================
+impl SparseMerkleTree {
+
+    /// Creates a new empty tree of the specified `depth`. Depth has the same meaning as for
+    /// `FieldMerkleTree`: a tree of depth `n` has 2^(n - 1) leaves, and so keys must be smaller
+    /// than 2^(n - 1).
+    pub fn new(depth: usize) -> SparseMerkleTree {
+        assert!(depth >= 2 && depth <= MAX_SPARSE_TREE_DEPTH,
+            "tree depth must be between 2 and {}, but was {}", MAX_SPARSE_TREE_DEPTH, depth);
+
+        // empty[i] is the root of an empty subtree with 2^i leaves
+        let mut empty = Vec::with_capacity(depth);
+        empty.push(EMPTY_LEAF);
+        for i in 1..depth {
+            empty.push(rescue::merge(&empty[i - 1], &empty[i - 1]));
+        }
+
+        return SparseMerkleTree { depth, nodes: HashMap::new(), empty };
+    }
+
+    /// Returns depth of the tree as expected by `smt.get`, `smt.absent` and `smt.set`
+    /// instructions; this is the number of nodes in an authentication path (including the leaf).
+    pub fn depth(&self) -> usize {
+        return self.depth;
+    }
+
+    /// Returns the root of the tree.
+    pub fn root(&self) -> [u128; 2] {
+        return self.get_node(self.depth - 1, 0);
+    }
+
+    /// Returns the value stored under the specified `key`; if the key has not been set, [0, 0]
+    /// is returned.
+    pub fn get(&self, key: u128) -> [u128; 2] {
+        self.check_key(key);
+        return self.get_node(0, key);
+    }
+
+    /// Sets the value stored under the specified `key` and returns the previous value. Setting
+    /// a value to [0, 0] removes the key from the tree.
+    pub fn insert(&mut self, key: u128, value: [u128; 2]) -> [u128; 2] {
+        let old_value = self.get(key);
+
+        let mut node = value;
+        let mut index = key;
+        for level in 0..self.depth {
+            if node == self.empty[level] {
+                self.nodes.remove(&(level, index));
+            }
+            else {
+                self.nodes.insert((level, index), node);
+            }
+
+            if level + 1 < self.depth {
+                let sibling = self.get_node(level, index ^ 1);
+                node = if index & 1 == 0 {
+                    rescue::merge(&node, &sibling)
+                } else {
+                    rescue::merge(&sibling, &node)
+                };
+                index = index >> 1;
+            }
+        }
+
+        return old_value;
+    }
+
+    /// Computes Merkle authentication path for the specified `key`. The path starts with the
+    /// value stored under the key ([0, 0] if the key has not been set), followed by siblings of
+    /// all nodes on the way to the root.
+    pub fn prove(&self, key: u128) -> Vec<[u128; 2]> {
+        self.check_key(key);
+
+        let mut path = Vec::with_capacity(self.depth);
+        path.push(self.get_node(0, key));
+
+        let mut index = key;
+        for level in 0..(self.depth - 1) {
+            path.push(self.get_node(level, index ^ 1));
+            index = index >> 1;
+        }
+
+        return path;
+    }
+
+    // PATH VERIFICATION
+    // --------------------------------------------------------------------------------------------
+
+    /// Computes the root to which the authentication `path` for the specified `key` resolves.
+    pub fn compute_root(key: u128, path: &[[u128; 2]]) -> [u128; 2] {
+        assert!(path.len() >= 2, "authentication path must contain at least 2 nodes");
+        assert!(path.len() > 128 || key >> (path.len() - 1) == 0, "invalid key {}", key);
+
+        let mut v = path[0];
+        let mut key = key;
+        for i in 1..path.len() {
+            if key & 1 == 0 {
+                v = rescue::merge(&v, &path[i]);
+            }
+            else {
+                v = rescue::merge(&path[i], &v);
+            }
+            key = key >> 1;
+        }
+
+        return v;
+    }
+
+    /// Checks whether the `path` for the specified `key` resolves to the specified `root`.
+    pub fn verify(root: &[u128; 2], key: u128, path: &[[u128; 2]]) -> bool {
+        return SparseMerkleTree::compute_root(key, path) == *root;
+    }
+
+    // PROGRAM INPUTS
+    // --------------------------------------------------------------------------------------------
+
+    /// Returns contents of secret input tapes A and B consumed by `smt.get.n` and `smt.absent.n`
+    /// instructions (with n equal to the depth of the tree) for the specified `key`.
+    pub fn get_tapes(&self, key: u128) -> [Vec<u128>; 2] {
+        return FieldMerkleTree::pmpath_tapes(&self.prove(key));
+    }
+
+    /// Returns contents of secret input tapes A and B consumed by `smt.set.n` instruction (with
+    /// n equal to the depth of the tree) for the specified `key`. The instruction is built on
+    /// top of `mrupdate` instruction, and so the authentication path is read only once.
+    pub fn set_tapes(&self, key: u128) -> [Vec<u128>; 2] {
+        return self.get_tapes(key);
+    }
+
+    /// Returns inputs for a program which starts with `smt.get.n` instruction: public inputs put
+    /// the value stored under the `key` at the top of the stack followed by the key and the root
+    /// of the tree, and secret inputs are the same as for `get_tapes()`.
+    pub fn get_inputs(&self, key: u128) -> ProgramInputs {
+        let value = self.get(key);
+        let root = self.root();
+        let [a, b] = self.get_tapes(key);
+        return ProgramInputs::new(&[value[1], value[0], key, root[1], root[0]], &a, &b);
+    }
+
+    /// Returns inputs for a program which starts with `smt.set.n` instruction: public inputs put
+    /// the new `value` at the top of the stack followed by the current value stored under the
+    /// `key`, the key itself, and the current root of the tree; secret inputs are the same as
+    /// for `set_tapes()`.
+    pub fn set_inputs(&self, key: u128, value: [u128; 2]) -> ProgramInputs {
+        let old_value = self.get(key);
+        let root = self.root();
+        let [a, b] = self.set_tapes(key);
+        let public = [value[1], value[0], old_value[1], old_value[0], key, root[1], root[0]];
+        return ProgramInputs::new(&public, &a, &b);
+    }
+
+    // HELPER METHODS
+    // --------------------------------------------------------------------------------------------
+
+    fn get_node(&self, level: usize, index: u128) -> [u128; 2] {
+        return match self.nodes.get(&(level, index)) {
+            Some(node) => *node,
+            None => self.empty[level],
+        };
+    }
+
+    fn check_key(&self, key: u128) {
+        assert!(key >> (self.depth - 1) == 0,
+            "key {} is too large for a tree of depth {}", key, self.depth);
+    }
+}
+
+// TESTS
+// ================================================================================================
+#[cfg(test)]
+mod tests {
+
+    use crate::{ math::field, tests::run_source, ExecutionError, OpCode, ProgramInputs };
+    use crate::crypto::{ rescue, FieldMerkleTree };
+    use super::SparseMerkleTree;
+
+    #[test]
+    fn empty_tree() {
+        let tree = SparseMerkleTree::new(3);
+        let empty = FieldMerkleTree::new(vec![[0, 0]; 4]);
+        assert_eq!(*empty.root(), tree.root());
+        assert_eq!([0, 0], tree.get(2));
+    }
+
+    #[test]
+    fn insert() {
+        let mut tree = SparseMerkleTree::new(3);
+        let mut leaves = vec![[0, 0]; 4];
+
+        let v1 = [field::rand(), field::rand()];
+        let v2 = [field::rand(), field::rand()];
+        assert_eq!([0, 0], tree.insert(1, v1));
+        assert_eq!([0, 0], tree.insert(2, v2));
+        leaves[1] = v1;
+        leaves[2] = v2;
+
+        let n01 = rescue::merge(&leaves[0], &leaves[1]);
+        let n23 = rescue::merge(&leaves[2], &leaves[3]);
+        assert_eq!(rescue::merge(&n01, &n23), tree.root());
+        assert_eq!(*FieldMerkleTree::new(leaves).root(), tree.root());
+        assert_eq!(v2, tree.get(2));
+
+        // removing values restores the empty root
+        assert_eq!(v1, tree.insert(1, [0, 0]));
+        assert_eq!(v2, tree.insert(2, [0, 0]));
+        assert_eq!(SparseMerkleTree::new(3).root(), tree.root());
+        assert!(tree.nodes.is_empty());
+    }
+
+    #[test]
+    fn prove_verify() {
+        let mut tree = SparseMerkleTree::new(64);
+        let keys = [0, 7, 1 << 40, (1 << 63) - 1];
+        for &key in keys.iter() {
+            tree.insert(key, [field::rand(), field::rand()]);
+        }
+
+        for &key in keys.iter().chain([5, 1 << 62].iter()) {
+            let mut path = tree.prove(key);
+            assert_eq!(tree.depth(), path.len());
+            assert_eq!(tree.get(key), path[0]);
+            assert!(SparseMerkleTree::verify(&tree.root(), key, &path));
+
+            path[0] = [field::rand(), field::rand()];
+            assert!(!SparseMerkleTree::verify(&tree.root(), key, &path));
+        }
+    }
+
+    #[test]
+    fn smt_get() {
+        let tree = build_tree(32, &[3, 1 << 20, 1 << 30]);
+        let source = format!("begin smt.get.{} end", tree.depth());
+        for &key in [3, 1 << 20, 1 << 30].iter() {
+            run_source(&source, &tree.get_inputs(key), 0).unwrap();
+        }
+    }
+
+    #[test]
+    fn smt_get_wrong_value() {
+        let tree = build_tree(32, &[3, 1 << 20]);
+        let source = format!("begin smt.get.{} end", tree.depth());
+        let [a, b] = tree.get_tapes(3);
+        let root = tree.root();
+        let result = run_source(&source, &ProgramInputs::new(&[1, 2, 3, root[1], root[0]], &a, &b), 0);
+        assert!(matches!(result, Err(ExecutionError::FailedAssertion { op: OpCode::AssertEq, .. })));
+    }
+
+    #[test]
+    fn smt_absent() {
+        let tree = build_tree(32, &[3, 1 << 20]);
+        let source = format!("begin smt.absent.{} end", tree.depth());
+        let root = tree.root();
+        for &key in [2, 1 << 21].iter() {
+            let [a, b] = tree.get_tapes(key);
+            run_source(&source, &ProgramInputs::new(&[key, root[1], root[0]], &a, &b), 0).unwrap();
+        }
+    }
+
+    #[test]
+    fn smt_absent_present_key() {
+        let tree = build_tree(32, &[3, 1 << 20]);
+        let source = format!("begin smt.absent.{} end", tree.depth());
+        let root = tree.root();
+        let [a, b] = tree.get_tapes(3);
+        let result = run_source(&source, &ProgramInputs::new(&[3, root[1], root[0]], &a, &b), 0);
+        assert!(matches!(result, Err(ExecutionError::FailedAssertion { op: OpCode::AssertEq, .. })));
+    }
+
+    #[test]
+    fn smt_set() {
+        let mut tree = build_tree(32, &[3, 1 << 20]);
+        let source = format!("begin smt.set.{} end", tree.depth());
+
+        // update an existing value, then insert a new one
+        for &key in [3, 1 << 21].iter() {
+            let value = [field::rand(), field::rand()];
+            let inputs = tree.set_inputs(key, value);
+            tree.insert(key, value);
+            let stack = run_source(&source, &inputs, 2).unwrap();
+            assert_eq!(tree.root(), [stack[1], stack[0]]);
+        }
+    }
+
+    #[test]
+    fn smt_set_wrong_old_value() {
+        let tree = build_tree(32, &[3, 1 << 20]);
+        let source = format!("begin smt.set.{} end", tree.depth());
+        let [a, b] = tree.set_tapes(3);
+        let root = tree.root();
+        let result = run_source(&source, &ProgramInputs::new(&[5, 6, 1, 2, 3, root[1], root[0]], &a, &b), 0);
+        assert!(matches!(result, Err(ExecutionError::FailedAssertion { op: OpCode::AssertEq, .. })));
+    }
+
+    fn build_tree(depth: usize, keys: &[u128]) -> SparseMerkleTree {
+        let mut tree = SparseMerkleTree::new(depth);
+        for &key in keys.iter() {
+            tree.insert(key, [field::rand(), field::rand()]);
+        }
+        return tree;
+    }
+}
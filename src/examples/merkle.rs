@@ -1,4 +1,4 @@
-use distaff::{ Program, ProgramInputs, assembly, math::field, utils::hasher };
+use distaff::{ Program, ProgramInputs, assembly, math::field, crypto::FieldMerkleTree };
 use super::{ Example, utils::parse_args };
 
 pub fn get_example(args: &[String]) -> Example  {
@@ -11,7 +11,7 @@ pub fn get_example(args: &[String]) -> Example  {
     let (auth_path, leaf_index) = generate_authentication_path(depth);
 
     // compute root of the Merkle tree to which the path resolves
-    let mut expected_result = compute_merkle_root(&auth_path, leaf_index);
+    let mut expected_result = FieldMerkleTree::compute_root(leaf_index, &auth_path).to_vec();
     println!("Expected tree root: {:?}", expected_result);
     
     // generate the program to verify Merkle path of given length
@@ -60,42 +60,28 @@ fn generate_merkle_program(n: usize, index: usize) -> Program {
 
 /// Converts Merkle authentication path for a node at the specified `index` into 
 /// a set of inputs which can be consumed by the program created by the function above.
-fn generate_program_inputs(path: &[Vec<u128>; 2], index: usize) -> ProgramInputs {
-
-    let mut a = Vec::new();
-    let mut b = Vec::new();
-    let n = path[0].len();
-    let mut index = index + usize::pow(2, (n - 1) as u32);
+fn generate_program_inputs(path: &[[u128; 2]], index: usize) -> ProgramInputs {
 
     // push the leaf node onto secret input tapes A and B
-    a.push(path[0][0]);
-    b.push(path[1][0]);
+    let mut a = vec![path[0][0]];
+    let mut b = vec![path[0][1]];
 
     // populate the tapes with inputs for smpath operation
-    for i in 1..n {
-        // push next bit of the position index onto tapes A and B; we use both tapes
-        // here so that we can use READ2 instruction when reading inputs from the tapes
-        a.push(field::ZERO);
-        b.push((index & 1) as u128);
-        index = index >> 1;
-
-        // push the next node onto tapes A and B
-        a.push(path[0][i]);
-        b.push(path[1][i]);
-    }
+    let [smpath_a, smpath_b] = FieldMerkleTree::smpath_tapes(index, path);
+    a.extend_from_slice(&smpath_a);
+    b.extend_from_slice(&smpath_b);
 
     // populate the tapes with inputs for pmpath operation
-    for i in 1..n {
-        a.push(path[0][i]);
-        b.push(path[1][i]);
-    }
+    let [pmpath_a, pmpath_b] = FieldMerkleTree::pmpath_tapes(path);
+    a.extend_from_slice(&pmpath_a);
+    b.extend_from_slice(&pmpath_b);
 
     return ProgramInputs::new(&[], &a, &b);
 }
 
 /// Pseudo-randomly generates a Merkle authentication path for an imaginary Merkle tree
 /// of depth equal to `n`
-fn generate_authentication_path(n: usize) -> ([Vec<u128>; 2], usize) {
+fn generate_authentication_path(n: usize) -> (Vec<[u128; 2]>, usize) {
     let mut s1 = [0u8; 32];
     s1[0] = 1; s1[1] = 2; s1[2] = 3;
     let mut s2 = [0u8; 32];
@@ -104,43 +90,9 @@ fn generate_authentication_path(n: usize) -> ([Vec<u128>; 2], usize) {
     let leaves = u128::pow(2, (n - 1) as u32);
     let leaf_index = (field::prng(s1) % leaves) as usize;
 
-    return ([field::prng_vector(s1, n), field::prng_vector(s2, n)], leaf_index);
+    let path = field::prng_vector(s1, n).into_iter()
+        .zip(field::prng_vector(s2, n).into_iter())
+        .map(|(v0, v1)| [v0, v1])
+        .collect();
+    return (path, leaf_index);
 }
-
-/// Computes tree root to which a given authentication path resolves assuming the
-/// path is for a leaf node at position specified by `index` parameter.
-fn compute_merkle_root(path: &[Vec<u128>; 2], index: usize) -> Vec<u128> {
-
-    let mut buf = [field::ZERO; 4];
-    let mut v: Vec<u128>;
-    let n = path[0].len();
-
-    let r = index & 1;
-    buf[0] = path[0][r];
-    buf[1] = path[1][r];
-    buf[2] = path[0][1 - r];
-    buf[3] = path[1][1 - r];
-
-    v = hasher::digest(&buf);
-
-    let mut index = (index + usize::pow(2, (n - 1) as u32)) >> 1;
-    for i in 2..n {
-        if index & 1 == 0 {
-            buf[0] = v[0];
-            buf[1] = v[1];
-            buf[2] = path[0][i];
-            buf[3] = path[1][i];
-        }
-        else {
-            buf[0] = path[0][i];
-            buf[1] = path[1][i];
-            buf[2] = v[0];
-            buf[3] = v[1];
-        }
-        
-        v = hasher::digest(&buf);
-        index = index >> 1;
-    }
-
-    return v.to_vec();
-}
\ No newline at end of file
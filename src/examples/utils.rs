@@ -28,5 +28,14 @@ pub fn parse_args(args: &[String]) -> (usize, ProofOptions) {
         grind_factor = args[4].parse().unwrap();
     }
 
-    return (n, ProofOptions::new(ext_factor, num_queries, grind_factor, default_options.hash_fn()));
+    // proofs are zero-knowledge only if explicitly requested
+    let zero_knowledge = args.len() > 5 && args[5] == "zk";
+
+    return (n, ProofOptions::new(
+        ext_factor,
+        num_queries,
+        grind_factor,
+        zero_knowledge,
+        default_options.folding_factor(),
+        default_options.max_remainder_degree()));
 }
\ No newline at end of file
@@ -0,0 +1,56 @@
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Describes the reason for which a FRI proof could not be verified.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FriError {
+
+    // PARAMETERS
+    // --------------------------------------------------------------------------------------------
+
+    /// Domain size is not a power of 2, or is larger than the field supports.
+    InvalidDomainSize(usize),
+    /// Maximum degree is not smaller than the domain size.
+    DegreeTooHigh { max_degree: usize, domain_size: usize },
+
+    // PROOF
+    // --------------------------------------------------------------------------------------------
+
+    /// Number of layers does not match the number implied by the degree and the options.
+    LayerCountMismatch { expected: usize, actual: usize },
+    /// Values at the specified layer are malformed, or are inconsistent with the values of the
+    /// previous layer.
+    LayerInconsistent { layer: usize },
+    /// Values at the specified layer are inconsistent with the commitment to the layer.
+    LayerMerkleMismatch { layer: usize },
+    /// Remainder values are inconsistent with the values of the last layer.
+    RemainderInconsistent,
+    /// Remainder values do not describe a polynomial of the expected degree.
+    RemainderDegree,
+}
+
+// COMMON TRAIT IMPLEMENTATIONS
+// ================================================================================================
+
+impl std::fmt::Display for FriError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FriError::InvalidDomainSize(size) =>
+                write!(f, "domain of {} elements is not supported", size),
+            FriError::DegreeTooHigh { max_degree, domain_size } =>
+                write!(f, "degree {} is not smaller than domain size {}", max_degree, domain_size),
+            FriError::LayerCountMismatch { expected, actual } =>
+                write!(f, "expected {} FRI layers, but proof contains {}", expected, actual),
+            FriError::LayerInconsistent { layer } =>
+                write!(f, "evaluations did not match column value at depth {}", layer),
+            FriError::LayerMerkleMismatch { layer } =>
+                write!(f, "verification of Merkle proof failed at layer {}", layer),
+            FriError::RemainderInconsistent =>
+                write!(f, "remainder values are inconsistent with values of the last column"),
+            FriError::RemainderDegree =>
+                write!(f, "remainder is not a valid polynomial of the expected degree"),
+        }
+    }
+}
+
+impl std::error::Error for FriError {}
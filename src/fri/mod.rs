@@ -0,0 +1,292 @@
+use serde::{ Serialize, Deserialize };
+use crate::crypto::{ Hasher };
+use crate::math::{ FieldElement };
+
+// RE-EXPORTS
+// ================================================================================================
+mod options;
+pub use options::{ FriOptions };
+
+mod utils;
+pub use utils::{ hash_remainder, get_remainder_size };
+
+mod transcript;
+pub use transcript::{ FriTranscript };
+
+mod errors;
+pub use errors::{ FriError };
+
+mod prover;
+pub use prover::{ prove, reduce, build_proof };
+
+mod verifier;
+pub use verifier::{ verify, verify_layers, verify_layers_with_plan };
+
+const MAX_FOLDING_FACTOR: usize = 16;
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Proof that a committed vector of evaluations describes a polynomial of bounded degree; the
+/// proof contains queried rows of all FRI layers and all values of the remainder. Evaluations
+/// can be elements of any field implementing `FieldElement`; STARK proofs use the 128-bit field.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct FriProof<H: Hasher, E: FieldElement = u128> {
+    pub layers      : Vec<FriLayer<H, E>>,
+    pub rem_values  : Vec<E>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct FriLayer<H: Hasher, E: FieldElement = u128> {
+    pub root    : H::Digest,
+    pub values  : Vec<Vec<E>>,
+    pub nodes   : Vec<Vec<H::Digest>>,
+    pub depth   : u8,
+}
+
+// TESTS
+// ================================================================================================
+#[cfg(test)]
+mod tests {
+    use crate::math::{ field, fft, polynom, FieldElement, BaseElement, QuadExtension };
+    use crate::crypto::{ hash::{ Blake3, Sha3 } };
+    use super::{ FriProof, FriOptions, FriTranscript, FriError, utils };
+
+    const CONTEXT: &[u8] = b"distaff-fri-tests";
+
+    #[test]
+    fn prove_verify() {
+        let degree: usize = 63;
+        let domain_size: usize = 512;
+        let evaluations = build_random_poly_evaluations(domain_size, degree);
+
+        for &folding_factor in [2, 4, 8, 16].iter() {
+            for &max_remainder_degree in [0, 7, 63].iter() {
+                let options = FriOptions::new(folding_factor, max_remainder_degree, 32);
+                let proof = super::prove(&Blake3, &evaluations, degree, &options, CONTEXT);
+                let num_layers = utils::get_num_layers(degree, folding_factor, max_remainder_degree);
+                assert_eq!(num_layers, proof.layers.len());
+
+                let result = super::verify(&Blake3, &proof, domain_size, degree, &options, CONTEXT);
+                assert_eq!(Ok(()), result);
+            }
+        }
+    }
+
+    #[test]
+    fn prove_verify_field64() {
+        check_prove_verify::<BaseElement>();
+        check_prove_verify::<QuadExtension>();
+    }
+
+    #[test]
+    fn prove_verify_poly() {
+        // evaluate a polynomial built from its roots over the domain
+        let domain_size: usize = 256;
+        let root = field::get_root_of_unity(domain_size);
+        let domain = field::get_power_series(root, domain_size);
+        let mut poly = vec![field::ONE];
+        for &r in field::rand_vector(31).iter() {
+            poly = polynom::mul(&poly, &[field::neg(r), field::ONE]);
+        }
+        let evaluations = domain.iter().map(|&x| polynom::eval(&poly, x)).collect::<Vec<u128>>();
+
+        let options = FriOptions::new(4, 7, 16);
+        let proof = super::prove(&Sha3, &evaluations, 31, &options, CONTEXT);
+        assert_eq!(Ok(()), super::verify(&Sha3, &proof, domain_size, 31, &options, CONTEXT));
+
+        // the degree is absorbed into the transcript, so the proof must be built for the lower
+        // degree to reach the remainder check
+        let proof = super::prove(&Sha3, &evaluations, 27, &options, CONTEXT);
+        assert_eq!(Err(FriError::RemainderDegree), super::verify(&Sha3, &proof, domain_size, 27, &options, CONTEXT));
+    }
+
+    #[test]
+    fn prove_verify_remainder_only() {
+        let degree: usize = 15;
+        let domain_size: usize = 64;
+        let evaluations = build_random_poly_evaluations(domain_size, degree);
+
+        let options = FriOptions::new(4, 15, 16);
+        let proof = super::prove(&Blake3, &evaluations, degree, &options, CONTEXT);
+        assert_eq!(0, proof.layers.len());
+        assert_eq!(evaluations, proof.rem_values);
+        assert_eq!(Ok(()), super::verify(&Blake3, &proof, domain_size, degree, &options, CONTEXT));
+        assert_eq!(Err(FriError::RemainderDegree), super::verify(&Blake3, &proof, domain_size, degree - 1, &options, CONTEXT));
+    }
+
+    #[test]
+    fn verify_fail_tampered() {
+        let degree: usize = 63;
+        let domain_size: usize = 512;
+        let evaluations = build_random_poly_evaluations(domain_size, degree);
+        let options = FriOptions::new(4, 15, 32);
+        let proof = super::prove(&Blake3, &evaluations, degree, &options, CONTEXT);
+
+        // evaluations of a polynomial of higher degree
+        let evaluations2 = build_random_poly_evaluations(domain_size, degree * 2);
+        let proof2 = super::prove(&Blake3, &evaluations2, degree, &options, CONTEXT);
+        assert!(super::verify(&Blake3, &proof2, domain_size, degree, &options, CONTEXT).is_err());
+
+        // modified remainder changes query positions
+        let mut proof3 = proof.clone();
+        proof3.rem_values[0] = field::add(proof3.rem_values[0], field::ONE);
+        assert!(super::verify(&Blake3, &proof3, domain_size, degree, &options, CONTEXT).is_err());
+
+        // modified layer values
+        let mut proof4 = proof.clone();
+        proof4.layers[0].values[0][0] = field::add(proof4.layers[0].values[0][0], field::ONE);
+        assert_eq!(Err(FriError::LayerMerkleMismatch { layer: 0 }), super::verify(&Blake3, &proof4, domain_size, degree, &options, CONTEXT));
+
+        // malformed layer
+        let mut proof5 = proof.clone();
+        proof5.layers[0].values.pop();
+        assert_eq!(Err(FriError::LayerInconsistent { layer: 0 }), super::verify(&Blake3, &proof5, domain_size, degree, &options, CONTEXT));
+
+        // layer committing to a domain of the wrong size
+        let mut proof6 = proof.clone();
+        proof6.layers[0].depth = 64;
+        assert_eq!(Err(FriError::LayerInconsistent { layer: 0 }), super::verify(&Blake3, &proof6, domain_size, degree, &options, CONTEXT));
+
+        // different options
+        let options2 = FriOptions::new(4, 63, 32);
+        assert_eq!(Err(FriError::LayerCountMismatch { expected: 0, actual: 1 }), super::verify(&Blake3, &proof, domain_size, degree, &options2, CONTEXT));
+    }
+
+    #[test]
+    fn verify_fail_transcript() {
+        let degree: usize = 63;
+        let domain_size: usize = 512;
+        let evaluations = build_random_poly_evaluations(domain_size, degree);
+        let options = FriOptions::new(4, 7, 32);
+        let proof = super::prove(&Blake3, &evaluations, degree, &options, CONTEXT);
+
+        // proof is bound to the context
+        assert!(super::verify(&Blake3, &proof, domain_size, degree, &options, b"another-context").is_err());
+
+        // proof is bound to the parameters; a proof with more queries is not a valid proof with
+        // fewer queries, even though it opens every layer at a superset of positions
+        let options2 = FriOptions::new(4, 7, 16);
+        assert!(super::verify(&Blake3, &proof, domain_size, degree, &options2, CONTEXT).is_err());
+
+        // folding challenges depend on roots of all preceding layers
+        let mut transcript1 = FriTranscript::new(&Blake3, CONTEXT, domain_size, degree, &options);
+        let mut transcript2 = transcript1.clone();
+        let _: u128 = transcript2.commit_layer(&proof.layers[1].root);
+        let c1: u128 = transcript1.commit_layer(&proof.layers[0].root);
+        let c2: u128 = transcript2.commit_layer(&proof.layers[0].root);
+        assert_ne!(c1, c2);
+
+        // invalid parameters are reported as errors
+        assert_eq!(Err(FriError::InvalidDomainSize(500)), super::verify(&Blake3, &proof, 500, degree, &options, CONTEXT));
+        assert_eq!(Err(FriError::InvalidDomainSize(0)), super::verify(&Blake3, &proof, 0, degree, &options, CONTEXT));
+        assert_eq!(Err(FriError::DegreeTooHigh { max_degree: 512, domain_size }),
+            super::verify(&Blake3, &proof, domain_size, 512, &options, CONTEXT));
+    }
+
+    #[test]
+    fn verify_layers_fail() {
+        let degree: usize = 63;
+        let domain_size: usize = 512;
+        let root = field::get_root_of_unity(domain_size);
+        let domain = field::get_power_series(root, domain_size);
+        let options = FriOptions::new(4, 15, 50);
+
+        // degree too low 1
+        let evaluations = build_random_poly_evaluations(domain_size, degree);
+        let (proof, positions, challenges) = build_proof(&evaluations, &domain, degree, &options);
+
+        let sampled_evaluations = positions.iter().map(|&i| evaluations[i]).collect::<Vec<u128>>();
+        let result = super::verify_layers(&Blake3, &proof, &sampled_evaluations, &positions, &challenges, domain_size, field::ONE, degree / 2, &options);
+        assert_eq!(Err(FriError::RemainderDegree), result);
+
+        // degree too low 2
+        let evaluations = build_random_poly_evaluations(domain_size, degree + 1);
+        let (proof, positions, challenges) = build_proof(&evaluations, &domain, degree, &options);
+
+        let sampled_evaluations = positions.iter().map(|&i| evaluations[i]).collect::<Vec<u128>>();
+        let result = super::verify_layers(&Blake3, &proof, &sampled_evaluations, &positions, &challenges, domain_size, field::ONE, degree, &options);
+        assert_eq!(Err(FriError::RemainderDegree), result);
+
+        // invalid evaluations
+        let sampled_evaluations = sampled_evaluations[1..].to_vec();
+        let result = super::verify_layers(&Blake3, &proof, &sampled_evaluations, &positions, &challenges, domain_size, field::ONE, degree, &options);
+        assert_eq!(Err(FriError::LayerInconsistent { layer: 0 }), result);
+
+        // proof built with different FRI options
+        let options2 = FriOptions::new(4, 63, 50);
+        let evaluations = build_random_poly_evaluations(domain_size, degree);
+        let (proof, positions, challenges) = build_proof(&evaluations, &domain, degree, &options2);
+
+        let sampled_evaluations = positions.iter().map(|&i| evaluations[i]).collect::<Vec<u128>>();
+        let result = super::verify_layers(&Blake3, &proof, &sampled_evaluations, &positions, &challenges, domain_size, field::ONE, degree, &options);
+        assert_eq!(Err(FriError::LayerCountMismatch { expected: 1, actual: 0 }), result);
+    }
+
+    #[test]
+    fn verify_layers_coset() {
+        let degree: usize = 63;
+        let domain_size: usize = 512;
+        let root = field::get_root_of_unity(domain_size);
+        let offset = field::GENERATOR;
+        let domain = fft::get_coset_domain(root, offset, domain_size);
+        let options = FriOptions::new(4, 7, 50);
+
+        // evaluate a random polynomial over the coset
+        let mut evaluations = field::rand_vector(degree + 1);
+        evaluations.resize(domain_size, 0);
+        polynom::eval_fft_coset(&mut evaluations, offset, &fft::get_twiddles(root, domain_size), true);
+
+        let (proof, positions, challenges) = build_proof(&evaluations, &domain, degree, &options);
+        let sampled_evaluations = positions.iter().map(|&i| evaluations[i]).collect::<Vec<u128>>();
+        let result = super::verify_layers(&Blake3, &proof, &sampled_evaluations, &positions, &challenges, domain_size, offset, degree, &options);
+        assert_eq!(Ok(()), result);
+
+        // the same proof should not verify against the unshifted domain
+        let result = super::verify_layers(&Blake3, &proof, &sampled_evaluations, &positions, &challenges, domain_size, field::ONE, degree, &options);
+        assert!(result.is_err());
+    }
+
+    fn check_prove_verify<E: FieldElement>() {
+        let degree: usize = 63;
+        let domain_size: usize = 512;
+        let options = FriOptions::new(4, 7, 32);
+
+        let mut evaluations = E::rand_vector(degree + 1);
+        evaluations.resize(domain_size, E::ZERO);
+        polynom::eval_fft(&mut evaluations, true);
+
+        let proof = super::prove::<_, E>(&Blake3, &evaluations, degree, &options, CONTEXT);
+        let proof_bytes = bincode::serialize(&proof).unwrap();
+        let proof: FriProof<Blake3, E> = bincode::deserialize(&proof_bytes).unwrap();
+        assert_eq!(Ok(()), super::verify(&Blake3, &proof, domain_size, degree, &options, CONTEXT));
+
+        // evaluations of a polynomial of higher degree
+        let mut evaluations = E::rand_vector(degree * 2);
+        evaluations.resize(domain_size, E::ZERO);
+        polynom::eval_fft(&mut evaluations, true);
+        let proof = super::prove::<_, E>(&Blake3, &evaluations, degree, &options, CONTEXT);
+        assert!(super::verify(&Blake3, &proof, domain_size, degree, &options, CONTEXT).is_err());
+    }
+
+    fn build_proof(evaluations: &[u128], domain: &[u128], degree: usize, options: &FriOptions) -> (super::FriProof<Blake3>, Vec<usize>, Vec<u128>) {
+        let mut transcript = FriTranscript::new(&Blake3, CONTEXT, domain.len(), degree, options);
+        let (fri_trees, fri_values, fri_remainder) = super::reduce(evaluations, domain, degree, options, &mut transcript);
+        let seed = transcript.commit_remainder(&fri_remainder);
+        let positions = utils::compute_query_positions(&seed, domain.len(), options.num_queries());
+        let proof = super::build_proof(fri_trees, fri_values, fri_remainder, &positions, options);
+
+        // challenges are obtained by the verifier from the proof
+        let (challenges, _) = FriTranscript::new(&Blake3, CONTEXT, domain.len(), degree, options).replay(&proof);
+        return (proof, positions, challenges);
+    }
+
+    fn build_random_poly_evaluations(domain_size: usize, degree: usize) -> Vec<u128> {
+        let mut evaluations = field::rand_vector(degree + 1);
+        evaluations.resize(domain_size, 0);
+        polynom::eval_fft(&mut evaluations, true);
+        return evaluations;
+    }
+}
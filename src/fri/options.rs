@@ -0,0 +1,58 @@
+// CONSTANTS
+// ================================================================================================
+const DEFAULT_FOLDING_FACTOR      : usize = 4;
+const DEFAULT_MAX_REMAINDER_DEGREE: usize = 63;
+const DEFAULT_NUM_QUERIES         : usize = 50;
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FriOptions {
+    folding_factor      : usize,
+    max_remainder_degree: usize,
+    num_queries         : usize,
+}
+
+// FRI OPTIONS IMPLEMENTATION
+// ================================================================================================
+impl FriOptions {
+
+    /// Returns a new set of FRI options. `folding_factor` specifies by how much the degree of
+    /// the polynomial is reduced at every FRI layer, `max_remainder_degree` specifies the degree
+    /// at which reduction stops, and `num_queries` specifies how many positions of the
+    /// evaluation domain are queried when proofs are generated by `fri::prove()`.
+    pub fn new(folding_factor: usize, max_remainder_degree: usize, num_queries: usize) -> FriOptions {
+        assert!(folding_factor == 2 || folding_factor == 4 || folding_factor == 8 || folding_factor == 16,
+            "folding_factor must be 2, 4, 8, or 16");
+        assert!(max_remainder_degree <= 255, "max_remainder_degree cannot be greater than 255");
+
+        assert!(num_queries > 0, "num_queries must be greater than 0");
+        assert!(num_queries <= 128, "num_queries cannot be greater than 128");
+
+        return FriOptions {
+            folding_factor      : folding_factor,
+            max_remainder_degree: max_remainder_degree,
+            num_queries         : num_queries,
+        };
+    }
+
+    pub fn folding_factor(&self) -> usize {
+        return self.folding_factor;
+    }
+
+    pub fn max_remainder_degree(&self) -> usize {
+        return self.max_remainder_degree;
+    }
+
+    pub fn num_queries(&self) -> usize {
+        return self.num_queries;
+    }
+}
+
+impl Default for FriOptions {
+
+    fn default() -> FriOptions {
+        return FriOptions::new(DEFAULT_FOLDING_FACTOR, DEFAULT_MAX_REMAINDER_DEGREE, DEFAULT_NUM_QUERIES);
+    }
+}
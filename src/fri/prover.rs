@@ -0,0 +1,124 @@
+use crate::math::{ FieldElement };
+use crate::crypto::{ Hasher, MerkleTree };
+
+use super::{ FriProof, FriLayer, FriOptions, FriTranscript, utils };
+
+// PROVER FUNCTIONS
+// ================================================================================================
+
+/// Generates a proof that `evaluations` are evaluations of a polynomial of degree at most
+/// `max_degree` over the domain generated by the root of unity of order `evaluations.len()`;
+/// folding challenges and query positions are derived from a transcript seeded with the
+/// `context` and the parameters of the proof. All hashing is done with the provided `hasher`.
+pub fn prove<H: Hasher, E: FieldElement>(hasher: &H, evaluations: &[E], max_degree: usize, options: &FriOptions, context: &[u8]) -> FriProof<H, E> {
+    let domain_size = evaluations.len();
+    assert!(domain_size.is_power_of_two(), "number of evaluations must be a power of 2");
+    assert!(max_degree < domain_size, "max_degree must be smaller than the number of evaluations");
+
+    // every layer must contain at least 2 rows to be put into a Merkle tree
+    let num_layers = utils::get_num_layers(max_degree, options.folding_factor(), options.max_remainder_degree());
+    assert!(domain_size >= 2 * usize::pow(options.folding_factor(), num_layers as u32),
+        "{} evaluations are not enough to build {} FRI layers", domain_size, num_layers);
+
+    let root = E::get_root_of_unity(domain_size);
+    let domain = E::get_power_series(root, domain_size);
+    let mut transcript = FriTranscript::new(hasher, context, domain_size, max_degree, options);
+    let (trees, values, remainder) = reduce(evaluations, &domain, max_degree, options, &mut transcript);
+
+    // derive query positions from the transcript of all layer roots and the remainder
+    let seed = transcript.commit_remainder(&remainder);
+    let positions = utils::compute_query_positions(&seed, domain_size, options.num_queries());
+
+    return build_proof(trees, values, remainder, &positions, options);
+}
+
+/// Reduces evaluations of a polynomial of `max_degree` by the folding factor specified in the
+/// `options` until the degree of the remaining polynomial is at most the maximum remainder degree.
+/// The `domain` can be either a multiplicative subgroup or its coset (in which case the first
+/// element of the domain is the coset offset). Roots of all layers are committed to the
+/// `transcript`, and folding challenges are drawn from it; the remainder is not committed, so
+/// the caller can derive query positions from it afterwards. Layers are hashed with the hasher
+/// of the transcript. Returns Merkle trees and evaluation
+/// matrices for all layers, as well as the remainder values.
+pub fn reduce<H: Hasher, E: FieldElement>(evaluations: &[E], domain: &[E], max_degree: usize, options: &FriOptions, transcript: &mut FriTranscript<H>)
+    -> (Vec<MerkleTree<H>>, Vec<Vec<Vec<E>>>, Vec<E>)
+{
+    let folding_factor = options.folding_factor();
+    let num_layers = utils::get_num_layers(max_degree, folding_factor, options.max_remainder_degree());
+
+    let mut tree_results: Vec<MerkleTree<H>> = Vec::with_capacity(num_layers);
+    let mut value_results: Vec<Vec<Vec<E>>> = Vec::with_capacity(num_layers);
+
+    // reduce the degree by folding_factor at each iteration until the remaining polynomial
+    // is small enough
+    let inv_offset = E::inv(domain[0]);
+    let mut column = evaluations.to_vec();
+    for depth in 0..num_layers {
+
+        // transpose evaluations into a matrix with folding_factor columns and put its rows
+        // into a Merkle tree
+        let p_values = utils::transpose(&column, folding_factor);
+        let hashed_values = utils::hash_values(transcript.hasher(), &p_values);
+        let p_tree = MerkleTree::with_hasher(hashed_values, transcript.hasher().clone());
+
+        // row i of the matrix contains evaluations at x_i * r^j where x_i = (offset * g^i)^stride;
+        // since domain[k] = offset * g^k, 1 / x_i = domain[-i * stride] / offset^(stride + 1)
+        let stride = usize::pow(folding_factor, depth as u32);
+        let inv_offset_factor = E::exp(inv_offset, (stride + 1) as u128);
+        let inv_xs = (0..p_values.len())
+            .map(|i| domain[(domain.len() - (i * stride) % domain.len()) % domain.len()])
+            .map(|x| E::mul(x, inv_offset_factor))
+            .collect::<Vec<E>>();
+
+        // draw a pseudo-random x coordinate and evaluate each row polynomial at that x
+        let special_x = transcript.commit_layer(p_tree.root());
+        column = utils::fold_rows(&p_values, &inv_xs, special_x, folding_factor);
+
+        // add the tree and the values of this layer to the result
+        tree_results.push(p_tree);
+        value_results.push(p_values);
+    }
+
+    return (tree_results, value_results, column);
+}
+
+/// Builds a proof for the layers computed by `reduce()` by opening all layers at the specified
+/// `positions` of the evaluation domain.
+pub fn build_proof<H: Hasher, E: FieldElement>(
+    trees       : Vec<MerkleTree<H>>,
+    values      : Vec<Vec<Vec<E>>>,
+    remainder   : Vec<E>,
+    positions   : &[usize],
+    options     : &FriOptions) -> FriProof<H, E>
+{
+    let mut positions = positions.to_vec();
+    let folding_factor = options.folding_factor();
+    let mut domain_size = remainder.len() * usize::pow(folding_factor, trees.len() as u32);
+
+    // for all trees, record tree root, authentication paths to row evaluations, and values
+    // for row evaluations
+    let mut layers = Vec::with_capacity(trees.len());
+    for i in 0..trees.len() {
+        
+        positions = utils::get_augmented_positions(&positions, domain_size, folding_factor);
+
+        let tree = &trees[i];
+        let proof = tree.prove_batch(&positions);
+        
+        let mut queried_values: Vec<Vec<E>> = Vec::with_capacity(positions.len());
+        for &position in positions.iter() {
+            queried_values.push(values[i][position].clone());
+        }
+
+        layers.push(FriLayer {
+            root    : *tree.root(),
+            values  : queried_values,
+            nodes   : proof.nodes,
+            depth   : proof.depth
+        });
+        domain_size = domain_size / folding_factor;
+    }
+
+    // use the remaining polynomial values directly as proof
+    return FriProof { layers, rem_values: remainder };
+}
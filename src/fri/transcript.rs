@@ -0,0 +1,68 @@
+use crate::math::{ FieldElement };
+use crate::crypto::{ Hasher, Digest };
+use super::{ FriProof, FriOptions, utils };
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Fiat-Shamir transcript of a FRI proof. The transcript is seeded with a caller-supplied
+/// context and with the parameters of the proof, and every commitment made by the prover is
+/// chained into it; so, the folding challenge of each layer depends on the roots of all layers
+/// up to and including this one, and the query seed depends on all commitments of the proof.
+#[derive(Clone)]
+pub struct FriTranscript<H: Hasher> {
+    hasher  : H,
+    state   : H::Digest,
+}
+
+// FRI TRANSCRIPT IMPLEMENTATION
+// ================================================================================================
+impl <H: Hasher> FriTranscript<H> {
+
+    /// Returns a new transcript for a proof that evaluations over a domain of `domain_size`
+    /// elements describe a polynomial of degree at most `max_degree`. The `context` separates
+    /// proofs of different protocols (and of different statements of the same protocol) from
+    /// each other; e.g. a STARK proof uses commitments to the execution trace as the context.
+    /// The `hasher` is used both by the transcript and to commit to FRI layers.
+    pub fn new(hasher: &H, context: &[u8], domain_size: usize, max_degree: usize, options: &FriOptions) -> FriTranscript<H> {
+        let mut buf = Vec::with_capacity(context.len() + 48);
+        buf.extend_from_slice(&(context.len() as u64).to_le_bytes());
+        buf.extend_from_slice(context);
+        for &value in [domain_size, max_degree, options.folding_factor(), options.max_remainder_degree(), options.num_queries()].iter() {
+            buf.extend_from_slice(&(value as u64).to_le_bytes());
+        }
+        return FriTranscript { state: hasher.hash(&buf), hasher: hasher.clone() };
+    }
+
+    /// Returns the instance of the hash function used by this transcript.
+    pub fn hasher(&self) -> &H {
+        return &self.hasher;
+    }
+
+    /// Absorbs the root of the next FRI layer and returns the folding challenge for this layer.
+    pub fn commit_layer<E: FieldElement>(&mut self, root: &H::Digest) -> E {
+        self.absorb(&root.as_bytes());
+        return E::prng(self.state.as_bytes());
+    }
+
+    /// Absorbs the commitment to the remainder and returns a seed for query positions.
+    pub fn commit_remainder<E: FieldElement>(&mut self, remainder: &[E]) -> [u8; 32] {
+        self.absorb(&utils::hash_remainder(&self.hasher, remainder).as_bytes());
+        return self.state.as_bytes();
+    }
+
+    /// Replays the commitments of the `proof` in the same order in which the prover made them,
+    /// and returns folding challenges for all layers together with the seed for query positions.
+    pub fn replay<E: FieldElement>(&mut self, proof: &FriProof<H, E>) -> (Vec<E>, [u8; 32]) {
+        let challenges = proof.layers.iter().map(|layer| self.commit_layer(&layer.root)).collect();
+        let seed = self.commit_remainder(&proof.rem_values);
+        return (challenges, seed);
+    }
+
+    fn absorb(&mut self, bytes: &[u8]) {
+        let mut buf = Vec::with_capacity(32 + bytes.len());
+        buf.extend_from_slice(&self.state.as_bytes());
+        buf.extend_from_slice(bytes);
+        self.state = self.hasher.hash(&buf);
+    }
+}
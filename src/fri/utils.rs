@@ -0,0 +1,118 @@
+use rand::prelude::*;
+use rand::distributions::Uniform;
+use crate::math::{ FieldElement, fft, polynom };
+use crate::crypto::{ Hasher };
+use crate::utils::{ as_bytes };
+use super::MAX_FOLDING_FACTOR;
+
+/// Returns the number of FRI layers needed to reduce a polynomial of `max_degree` to a polynomial
+/// of degree at most `max_remainder_degree` when the degree is reduced by `folding_factor` at
+/// every layer.
+pub fn get_num_layers(max_degree: usize, folding_factor: usize, max_remainder_degree: usize) -> usize {
+    let mut max_degree_plus_1 = max_degree + 1;
+    let mut result = 0;
+    while max_degree_plus_1 > max_remainder_degree + 1 {
+        max_degree_plus_1 = get_folded_degree_bound(max_degree_plus_1, folding_factor);
+        result += 1;
+    }
+    return result;
+}
+
+/// Returns the number of remainder values in a proof for a polynomial of degree at most
+/// `max_degree` evaluated over a domain of `domain_size` elements.
+pub fn get_remainder_size(domain_size: usize, max_degree: usize, folding_factor: usize, max_remainder_degree: usize) -> usize {
+    let num_layers = get_num_layers(max_degree, folding_factor, max_remainder_degree);
+    return domain_size / folding_factor.pow(num_layers as u32);
+}
+
+/// Returns the number of coefficients in a polynomial obtained by folding a polynomial
+/// with `degree_plus_1` coefficients by `folding_factor`.
+pub fn get_folded_degree_bound(degree_plus_1: usize, folding_factor: usize) -> usize {
+    return (degree_plus_1 + folding_factor - 1) / folding_factor;
+}
+
+pub fn get_augmented_positions(positions: &[usize], column_length: usize, folding_factor: usize) -> Vec<usize> {
+    let row_length = column_length / folding_factor;
+    let mut result = Vec::new();
+    for i in 0..positions.len() {
+        let ap = positions[i] % row_length;
+        if !result.contains(&ap) {
+            result.push(ap);
+        }
+    }    
+    return result;
+}
+
+/// Transposes a vector of evaluations into a matrix with `folding_factor` columns such that
+/// row i contains evaluations at i, i + n, i + 2n etc., where n is the number of rows.
+pub fn transpose<E: FieldElement>(vector: &[E], folding_factor: usize) -> Vec<Vec<E>> {
+    assert!(vector.len() % folding_factor == 0, "vector length must be divisible by {}", folding_factor);
+    let row_count = vector.len() / folding_factor;
+
+    let mut result = Vec::with_capacity(row_count);
+    for i in 0..row_count {
+        let row = (0..folding_factor).map(|j| vector[i + j * row_count]).collect();
+        result.push(row);
+    }
+
+    return result;
+}
+
+/// Folds every row of the evaluation matrix into a single value. Values in row i are evaluations
+/// of some polynomial P_i at x_i * r^j for all j, where r is a root of unity of the same order
+/// as the number of values in a row; the folded value is P_i(special_x). `inv_xs` must contain
+/// 1 / x_i for every row.
+pub fn fold_rows<E: FieldElement>(rows: &[Vec<E>], inv_xs: &[E], special_x: E, folding_factor: usize) -> Vec<E> {
+    debug_assert!(rows.len() == inv_xs.len(), "number of rows must be equal to number of x coordinates");
+    assert!(folding_factor <= MAX_FOLDING_FACTOR, "folding factor cannot be greater than {}", MAX_FOLDING_FACTOR);
+
+    // since P_i(x_i * y) = Q_i(y), where Q_i is the polynomial interpolated from row values over
+    // the roots of unity, P_i(special_x) = Q_i(special_x / x_i)
+    let root = E::get_root_of_unity(folding_factor);
+    let inv_twiddles = fft::get_inv_twiddles(root, folding_factor);
+    let inv_length = E::inv(E::from_u64(folding_factor as u64));
+
+    let mut result = Vec::with_capacity(rows.len());
+    let mut poly = [E::ZERO; MAX_FOLDING_FACTOR];
+    for (row, &inv_x) in rows.iter().zip(inv_xs) {
+        let poly = &mut poly[..folding_factor];
+        poly.copy_from_slice(row);
+        fft::fft_in_place(poly, &inv_twiddles, 1, 1, 0, 1);
+        fft::permute(poly);
+
+        let value = polynom::eval(poly, E::mul(special_x, inv_x));
+        result.push(E::mul(value, inv_length));
+    }
+
+    return result;
+}
+
+pub fn hash_values<H: Hasher, E: FieldElement>(hasher: &H, values: &[Vec<E>]) -> Vec<H::Digest> {
+    let mut result = Vec::with_capacity(values.len());
+    for i in 0..values.len() {
+        result.push(hasher.hash(as_bytes(&values[i])));
+    }
+    return result;
+}
+
+/// Returns a commitment to the values of the FRI remainder.
+pub fn hash_remainder<H: Hasher, E: FieldElement>(hasher: &H, remainder: &[E]) -> H::Digest {
+    return hasher.hash(as_bytes(remainder));
+}
+
+/// Returns `num_queries` distinct pseudo-random positions in a domain of `domain_size` elements;
+/// if the domain is smaller than `num_queries`, all positions of the domain are returned.
+pub fn compute_query_positions(seed: &[u8; 32], domain_size: usize, num_queries: usize) -> Vec<usize> {
+    let num_queries = std::cmp::min(num_queries, domain_size);
+    let range = Uniform::from(0..domain_size);
+    let mut index_iter = StdRng::from_seed(*seed).sample_iter(range);
+
+    let mut result = Vec::with_capacity(num_queries);
+    while result.len() < num_queries {
+        let value = index_iter.next().unwrap();
+        if result.contains(&value) { continue; }
+        result.push(value);
+    }
+
+    return result;
+}
@@ -0,0 +1,295 @@
+use std::mem;
+use crate::math::{ FieldElement, fft, fft::FftPlan, polynom };
+use crate::crypto::{ Hasher, MerkleTree, BatchMerkleProof };
+
+use super::{ FriProof, FriLayer, FriOptions, FriTranscript, FriError, utils };
+
+// VERIFIER
+// ================================================================================================
+
+/// Verifies that the evaluations committed to in the `proof` are evaluations of a polynomial of
+/// degree at most `max_degree` over a domain of `domain_size` elements; folding challenges and
+/// query positions are derived from the proof in the same way as in `fri::prove()`, and so the
+/// `hasher` and the `context` must be the same as the ones used by the prover.
+pub fn verify<H: Hasher, E: FieldElement>(
+    hasher      : &H,
+    proof       : &FriProof<H, E>,
+    domain_size : usize,
+    max_degree  : usize,
+    options     : &FriOptions,
+    context     : &[u8]) -> Result<(), FriError>
+{
+    validate_parameters::<E>(domain_size, max_degree)?;
+
+    let mut transcript = FriTranscript::new(hasher, context, domain_size, max_degree, options);
+    let (challenges, seed) = transcript.replay(proof);
+    let positions = utils::compute_query_positions(&seed, domain_size, options.num_queries());
+
+    return verify_proof(hasher, proof, None, &positions, &challenges, domain_size, E::ONE, max_degree, options, None);
+}
+
+/// Verifies that the `evaluations` at the specified `positions` are consistent with the `proof`
+/// and that the proof describes a polynomial of degree at most `max_degree`. This is used when
+/// query positions are determined outside of FRI (e.g. in a STARK proof); `challenges` must be
+/// the folding challenges obtained by replaying the proof with `FriTranscript::replay()`. The
+/// evaluation domain is the subgroup of `domain_size` elements shifted by `domain_offset`.
+pub fn verify_layers<H: Hasher, E: FieldElement>(
+    hasher      : &H,
+    proof       : &FriProof<H, E>,
+    evaluations : &[E],
+    positions   : &[usize],
+    challenges  : &[E],
+    domain_size : usize,
+    domain_offset: E,
+    max_degree  : usize,
+    options     : &FriOptions) -> Result<(), FriError>
+{
+    validate_parameters::<E>(domain_size, max_degree)?;
+    return verify_proof(hasher, proof, Some(evaluations), positions, challenges, domain_size, domain_offset, max_degree, options, None);
+}
+
+/// Same as `verify_layers()`, but the degree of the remainder is checked by interpolating it with
+/// twiddles from the `remainder_plan`; the plan must support domains of `get_remainder_size()`
+/// elements, and it can be shared by all proofs with the same domain, degree, and options.
+pub fn verify_layers_with_plan<H: Hasher, E: FieldElement>(
+    hasher      : &H,
+    proof       : &FriProof<H, E>,
+    evaluations : &[E],
+    positions   : &[usize],
+    challenges  : &[E],
+    domain_size : usize,
+    domain_offset: E,
+    max_degree  : usize,
+    options     : &FriOptions,
+    remainder_plan: &FftPlan<E>) -> Result<(), FriError>
+{
+    validate_parameters::<E>(domain_size, max_degree)?;
+    return verify_proof(hasher, proof, Some(evaluations), positions, challenges, domain_size, domain_offset, max_degree, options, Some(remainder_plan));
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+fn validate_parameters<E: FieldElement>(domain_size: usize, max_degree: usize) -> Result<(), FriError> {
+    if !domain_size.is_power_of_two() || domain_size.trailing_zeros() > E::TWO_ADICITY {
+        return Err(FriError::InvalidDomainSize(domain_size));
+    }
+    if max_degree >= domain_size {
+        return Err(FriError::DegreeTooHigh { max_degree, domain_size });
+    }
+    return Ok(());
+}
+
+/// When `evaluations` are not provided, values of the first layer are taken as evaluations
+/// at the queried positions.
+fn verify_proof<H: Hasher, E: FieldElement>(
+    hasher      : &H,
+    proof       : &FriProof<H, E>,
+    evaluations : Option<&[E]>,
+    positions   : &[usize],
+    challenges  : &[E],
+    domain_size : usize,
+    domain_offset: E,
+    max_degree  : usize,
+    options     : &FriOptions,
+    remainder_plan: Option<&FftPlan<E>>) -> Result<(), FriError>
+{
+    let folding_factor = options.folding_factor();
+
+    // make sure the proof has the number of layers implied by the proof options
+    let num_layers = utils::get_num_layers(max_degree, folding_factor, options.max_remainder_degree());
+    if proof.layers.len() != num_layers {
+        return Err(FriError::LayerCountMismatch { expected: num_layers, actual: proof.layers.len() });
+    }
+    else if challenges.len() != num_layers {
+        return Err(FriError::LayerCountMismatch { expected: num_layers, actual: challenges.len() });
+    }
+
+    let domain_root = E::get_root_of_unity(domain_size);
+
+    // 1 ----- verify the recursive components of the FRI proof -----------------------------------
+    let mut domain_root = domain_root;
+    let mut domain_offset = domain_offset;
+    let mut domain_size = domain_size;
+    let mut max_degree_plus_1 = max_degree + 1;
+    let mut positions = positions.to_vec();
+    let mut evaluations = evaluations.map(|e| e.to_vec());
+
+    for (depth, layer) in proof.layers.iter().enumerate() {
+
+        // every layer commits to a domain `folding_factor` times smaller than the current one
+        if domain_size < folding_factor || layer.depth as u32 != (domain_size / folding_factor).trailing_zeros() {
+            return Err(FriError::LayerInconsistent { layer: depth });
+        }
+
+        let mut augmented_positions = utils::get_augmented_positions(&positions, domain_size, folding_factor);
+        if layer.values.len() != augmented_positions.len() || layer.values.iter().any(|row| row.len() != folding_factor) {
+            return Err(FriError::LayerInconsistent { layer: depth });
+        }
+
+        let column_values = get_column_values(&layer.values, &positions, &augmented_positions, domain_size, folding_factor);
+        if evaluations.as_ref().map_or(false, |e| *e != column_values) {
+            return Err(FriError::LayerInconsistent { layer: depth });
+        }
+
+        // verify Merkle proof for the layer
+        let merkle_proof = build_layer_merkle_proof(hasher, &layer);
+        if MerkleTree::verify_batch(hasher, &layer.root, &augmented_positions, &merkle_proof).is_err() {
+            return Err(FriError::LayerMerkleMismatch { layer: depth });
+        }
+
+        // build 1 / x for each row polynomial, where x = offset * g^i
+        let inv_domain_root = E::inv(domain_root);
+        let inv_domain_offset = E::inv(domain_offset);
+        let inv_xs = augmented_positions.iter()
+            .map(|&i| E::mul(E::exp(inv_domain_root, i as u128), inv_domain_offset))
+            .collect::<Vec<E>>();
+
+        // check that when the row polynomials are evaluated at x, the result is equal to
+        // the corresponding column value
+        evaluations = Some(utils::fold_rows(&layer.values, &inv_xs, challenges[depth], folding_factor));
+
+        // update variables for the next iteration of the loop
+        domain_root = E::exp(domain_root, folding_factor as u128);
+        domain_offset = E::exp(domain_offset, folding_factor as u128);
+        max_degree_plus_1 = utils::get_folded_degree_bound(max_degree_plus_1, folding_factor);
+        domain_size = domain_size / folding_factor;
+        mem::swap(&mut positions, &mut augmented_positions);
+    }
+
+    // 2 ----- verify the remainder of the FRI proof ----------------------------------------------
+    if proof.rem_values.len() != domain_size {
+        return Err(FriError::RemainderInconsistent);
+    }
+
+    if let Some(evaluations) = evaluations {
+        for (&position, evaluation) in positions.iter().zip(evaluations) {
+            if proof.rem_values[position] != evaluation {
+                return Err(FriError::RemainderInconsistent);
+            }
+        }
+    }
+
+    // make sure the remainder values satisfy the degree
+    return match remainder_plan {
+        Some(plan) if domain_size > 1 && domain_size <= plan.max_size() =>
+            verify_remainder_with_plan(&proof.rem_values, max_degree_plus_1, domain_offset, plan),
+        _ => verify_remainder(&proof.rem_values, max_degree_plus_1, domain_root, domain_offset),
+    };
+}
+
+fn verify_remainder<E: FieldElement>(remainder: &[E], max_degree_plus_1: usize, domain_root: E, domain_offset: E) -> Result<(), FriError> {
+    if max_degree_plus_1 > remainder.len() {
+        return Err(FriError::RemainderDegree);
+    }
+
+    // pick a subset of points from the remainder and interpolate them into a polynomial
+    let domain = fft::get_coset_domain(domain_root, domain_offset, remainder.len());
+    let mut xs = Vec::with_capacity(max_degree_plus_1);
+    let mut ys = Vec::with_capacity(max_degree_plus_1);
+    for i in 0..max_degree_plus_1 {
+        xs.push(domain[i]);
+        ys.push(remainder[i]);
+    }
+    let poly = polynom::interpolate(&xs, &ys);
+
+    // check that polynomial evaluates correctly for all other points in the remainder
+    for i in max_degree_plus_1..remainder.len() {
+        if polynom::eval(&poly, domain[i]) != remainder[i] {
+            return Err(FriError::RemainderDegree);
+        }
+    }
+
+    return Ok(());
+}
+
+/// Checks the degree of the remainder by interpolating it into a polynomial with an FFT and
+/// making sure that all coefficients above the degree bound are zeros.
+fn verify_remainder_with_plan<E: FieldElement>(remainder: &[E], max_degree_plus_1: usize, domain_offset: E, fft_plan: &FftPlan<E>) -> Result<(), FriError> {
+    if max_degree_plus_1 > remainder.len() {
+        return Err(FriError::RemainderDegree);
+    }
+
+    let mut poly = remainder.to_vec();
+    fft_plan.interpolate(&mut poly, domain_offset);
+    if poly[max_degree_plus_1..].iter().any(|&c| c != E::ZERO) {
+        return Err(FriError::RemainderDegree);
+    }
+
+    return Ok(());
+}
+
+fn get_column_values<E: FieldElement>(values: &[Vec<E>], positions: &[usize], augmented_positions: &[usize], column_length: usize, folding_factor: usize) -> Vec<E> {
+    let row_length = column_length / folding_factor;
+
+    let mut result = Vec::new();
+    for position in positions {
+        let idx = augmented_positions.iter().position(|&v| v == position % row_length).unwrap();
+        let value = values[idx][position / row_length];
+        result.push(value);
+    }
+
+    return result;
+}
+
+fn build_layer_merkle_proof<H: Hasher, E: FieldElement>(hasher: &H, layer: &FriLayer<H, E>) -> BatchMerkleProof<H> {
+    return BatchMerkleProof {
+        values  : utils::hash_values(hasher, &layer.values),
+        nodes   : layer.nodes.clone(),
+        depth   : layer.depth
+    };
+}
+
+// TESTS
+// ================================================================================================
+#[cfg(test)]
+mod tests {
+    
+    use crate::math::{ field, polynom, fft::FftPlan };
+    use crate::fri::FriError;
+
+    #[test]
+    fn verify_remainder() {
+        let degree_plus_1: usize = 32;
+        let root = field::get_root_of_unity(degree_plus_1 * 2);
+
+        let mut remainder = field::rand_vector(degree_plus_1);
+        remainder.resize(degree_plus_1 * 2, 0);
+        polynom::eval_fft(&mut remainder, true);
+
+        // check against exact degree
+        let result = super::verify_remainder(&remainder, degree_plus_1, root, field::ONE);
+        assert_eq!(Ok(()), result);
+
+        // check against higher degree
+        let result = super::verify_remainder(&remainder, degree_plus_1 + 1, root, field::ONE);
+        assert_eq!(Ok(()), result);
+
+        // check against lower degree
+        let degree_plus_1 = degree_plus_1 - 1;
+        let result = super::verify_remainder(&remainder, degree_plus_1, root, field::ONE);
+        assert_eq!(Err(FriError::RemainderDegree), result);
+    }
+
+    #[test]
+    fn verify_remainder_with_plan() {
+        let degree_plus_1: usize = 32;
+        let root = field::get_root_of_unity(degree_plus_1 * 4);
+        let offset = field::GENERATOR;
+        let plan = FftPlan::with_threads(degree_plus_1 * 8, 1);
+
+        let mut poly = field::rand_vector(degree_plus_1);
+        poly.resize(degree_plus_1 * 4, 0);
+        let remainder = (0..poly.len())
+            .map(|i| polynom::eval(&poly, field::mul(offset, field::exp(root, i as u128))))
+            .collect::<Vec<u128>>();
+
+        // results must be the same as for interpolation without the plan
+        for &degree_plus_1 in [degree_plus_1, degree_plus_1 + 1, degree_plus_1 - 1, degree_plus_1 * 5].iter() {
+            let expected = super::verify_remainder(&remainder, degree_plus_1, root, offset);
+            let result = super::verify_remainder_with_plan(&remainder, degree_plus_1, offset, &plan);
+            assert_eq!(expected, result);
+        }
+        assert_eq!(Ok(()), super::verify_remainder_with_plan(&remainder, degree_plus_1, offset, &plan));
+    }
+}
@@ -8,17 +8,19 @@ mod tests;
 // RE-EXPORTS
 // ================================================================================================
 pub mod crypto;
+use crypto::Hasher;
 pub mod math;
 pub mod utils;
+pub mod fri;
 
 mod stark;
-pub use stark::{ StarkProof, ProofOptions };
+pub use stark::{ StarkProof, ProofOptions, VerifierPolicy, VerificationError };
 
 mod processor;
-pub use processor::{ OpCode, OpHint };
+pub use processor::{ OpCode, OpHint, ExecutionError };
 
 mod programs;
-pub use programs::{ Program, ProgramInputs, assembly, blocks };
+pub use programs::{ Program, ProgramInputs, Statement, assembly, blocks };
 
 // EXECUTOR
 // ================================================================================================
@@ -27,15 +29,82 @@ pub use programs::{ Program, ProgramInputs, assembly, blocks };
 /// 
 /// * `inputs` specifies the initial stack state and provides secret input tapes;
 /// * `num_outputs` specifies the number of elements from the top of the stack to be returned;
-pub fn execute(program: &Program, inputs: &ProgramInputs, num_outputs: usize, options: &ProofOptions) -> (Vec<u128>, StarkProof)
+/// 
+/// Panics if the program cannot be executed against the provided inputs; `try_execute()` returns
+/// an error instead.
+pub fn execute<H: Hasher>(program: &Program, inputs: &ProgramInputs, num_outputs: usize, options: &ProofOptions<H>) -> (Vec<u128>, StarkProof<H>)
+{
+    return match try_execute(program, inputs, num_outputs, options) {
+        Ok(result) => result,
+        Err(err) => panic!("{}", err),
+    };
+}
+
+/// Same as `execute()`, but returns an error if the program cannot be executed against the
+/// provided inputs (e.g. an assertion fails, or a secret input tape runs out of values).
+pub fn try_execute<H: Hasher>(program: &Program, inputs: &ProgramInputs, num_outputs: usize, options: &ProofOptions<H>) -> Result<(Vec<u128>, StarkProof<H>), ExecutionError>
+{
+    let (mut outputs, proof) = try_execute_batch(program, std::slice::from_ref(inputs), num_outputs, options)?;
+    return Ok((outputs.remove(0), proof));
+}
+
+/// Executes the specified `program` once for each set of `inputs` and returns the results of all
+/// executions together with a single STARK-based proof covering all of them.
+/// 
+/// Execution traces are committed to side by side, and so the proof is considerably smaller
+/// than a set of proofs for individual executions; the proof can be verified using
+/// `verify_batch()` function.
+pub fn execute_batch<H: Hasher>(program: &Program, inputs: &[ProgramInputs], num_outputs: usize, options: &ProofOptions<H>) -> (Vec<Vec<u128>>, StarkProof<H>)
+{
+    return match try_execute_batch(program, inputs, num_outputs, options) {
+        Ok(result) => result,
+        Err(err) => panic!("{}", err),
+    };
+}
+
+/// Same as `execute_batch()`, but returns an error if the program cannot be executed against
+/// any of the provided sets of inputs.
+pub fn try_execute_batch<H: Hasher>(program: &Program, inputs: &[ProgramInputs], num_outputs: usize, options: &ProofOptions<H>) -> Result<(Vec<Vec<u128>>, StarkProof<H>), ExecutionError>
 {
-    assert!(num_outputs <= MAX_OUTPUTS, 
-        "cannot produce more than {} outputs, but requested {}", MAX_OUTPUTS, num_outputs);
+    assert!(inputs.len() > 0, "at least one set of inputs must be provided");
+    check_num_outputs(num_outputs)?;
 
+    let mut traces = Vec::with_capacity(inputs.len());
+    let mut outputs = Vec::with_capacity(inputs.len());
+    for inputs in inputs.iter() {
+        let (trace, trace_outputs) = build_trace(program, inputs, num_outputs, options.extension_factor())?;
+        traces.push(trace);
+        outputs.push(trace_outputs);
+    }
+
+    // generate STARK proof
+    let public_inputs = inputs.iter().map(|i| i.get_public_inputs().to_vec()).collect::<Vec<_>>();
+    let proof = stark::prove(&mut traces, &public_inputs, &outputs, options);
+
+    return Ok((outputs, proof));
+}
+
+/// Executes the specified `program` without generating a proof of execution and returns
+/// `num_outputs` elements from the top of the stack; this is useful for checking the results of
+/// a program before committing to proof generation.
+pub fn run(program: &Program, inputs: &ProgramInputs, num_outputs: usize) -> Result<Vec<u128>, ExecutionError>
+{
+    check_num_outputs(num_outputs)?;
+
+    let (trace, ctx_depth, loop_depth) = processor::execute(program, inputs)?;
+    let last_step = trace[0].len() - 1;
+    let stack_offset = stark::TraceState::compute_decoder_width(ctx_depth, loop_depth);
+    return Ok(trace[stack_offset..(stack_offset + num_outputs)].iter().map(|r| r[last_step]).collect());
+}
+
+/// Executes the program against the specified inputs and returns the resulting execution trace
+/// together with program outputs.
+fn build_trace(program: &Program, inputs: &ProgramInputs, num_outputs: usize, extension_factor: usize) -> Result<(stark::TraceTable, Vec<u128>), ExecutionError>
+{
     // execute the program to create an execution trace
     let now = Instant::now();
-    let (trace, ctx_depth, loop_depth) = processor::execute(program, inputs);
-    let mut trace = stark::TraceTable::new(trace, ctx_depth, loop_depth, options.extension_factor());
+    let (trace, ctx_depth, loop_depth) = processor::execute(program, inputs)?;
+    let trace = stark::TraceTable::new(trace, ctx_depth, loop_depth, extension_factor);
     debug!("Generated execution trace of {} registers and {} steps in {} ms",
         trace.register_count(),
         trace.unextended_length(),
@@ -46,10 +115,9 @@ pub fn execute(program: &Program, inputs: &ProgramInputs, num_outputs: usize, op
     let outputs = last_state.user_stack()[..num_outputs].to_vec();
 
     // make sure number of executed operations was sufficient
-    assert!(last_state.op_counter() as usize >= MIN_TRACE_LENGTH,
-        "a program must consist of at least {} operation, but only {} were executed",
-        MIN_TRACE_LENGTH,
-        last_state.op_counter());
+    if (last_state.op_counter() as usize) < MIN_TRACE_LENGTH {
+        return Err(ExecutionError::TooFewOperations { min: MIN_TRACE_LENGTH, actual: last_state.op_counter() as usize });
+    }
 
     // make sure program hash generated by the VM matches the hash of the program
     let program_hash = utils::as_bytes(last_state.program_hash());
@@ -58,10 +126,15 @@ pub fn execute(program: &Program, inputs: &ProgramInputs, num_outputs: usize, op
         hex::encode(program.hash()),
         hex::encode(program_hash));
 
-    // generate STARK proof
-    let proof = stark::prove(&mut trace, inputs.get_public_inputs(), &outputs, options);
+    return Ok((trace, outputs));
+}
 
-    return (outputs, proof);
+/// Makes sure that no more than `MAX_OUTPUTS` outputs are requested.
+fn check_num_outputs(num_outputs: usize) -> Result<(), ExecutionError> {
+    if num_outputs > MAX_OUTPUTS {
+        return Err(ExecutionError::TooManyOutputs { max: MAX_OUTPUTS, actual: num_outputs });
+    }
+    return Ok(());
 }
 
 // VERIFIER
@@ -69,9 +142,54 @@ pub fn execute(program: &Program, inputs: &ProgramInputs, num_outputs: usize, op
 
 /// Verifies that if a program with the specified `program_hash` is executed with the 
 /// provided `public_inputs` and some secret inputs, the result is equal to the `outputs`.
-pub fn verify(program_hash: &[u8; 32], public_inputs: &[u128], outputs: &[u128], proof: &StarkProof) -> Result<bool, String>
+/// 
+/// The proof is rejected without further checks if it does not satisfy the default
+/// `VerifierPolicy`.
+pub fn verify<H: Hasher>(program_hash: &[u8; 32], public_inputs: &[u128], outputs: &[u128], proof: &StarkProof<H>) -> Result<(), VerificationError>
+{
+    return verify_with_policy(program_hash, public_inputs, outputs, proof, &VerifierPolicy::default());
+}
+
+/// Same as `verify()`, but rejects the proof if it does not satisfy the specified `policy`
+/// (e.g. minimum security level, allowed hash functions, maximum trace length).
+pub fn verify_with_policy<H: Hasher>(program_hash: &[u8; 32], public_inputs: &[u128], outputs: &[u128], proof: &StarkProof<H>, policy: &VerifierPolicy) -> Result<(), VerificationError>
+{
+    return stark::verify(program_hash, &[public_inputs.to_vec()], &[outputs.to_vec()], proof, policy);
+}
+
+/// Verifies a proof generated by `execute_batch()`: for every i, if a program with the specified
+/// `program_hash` is executed with `public_inputs[i]` and some secret inputs, the result is equal
+/// to `outputs[i]`.
+/// 
+/// The proof is rejected without further checks if it does not satisfy the default
+/// `VerifierPolicy`.
+pub fn verify_batch<H: Hasher>(program_hash: &[u8; 32], public_inputs: &[Vec<u128>], outputs: &[Vec<u128>], proof: &StarkProof<H>) -> Result<(), VerificationError>
+{
+    return verify_batch_with_policy(program_hash, public_inputs, outputs, proof, &VerifierPolicy::default());
+}
+
+/// Same as `verify_batch()`, but rejects the proof if it does not satisfy the specified `policy`.
+pub fn verify_batch_with_policy<H: Hasher>(program_hash: &[u8; 32], public_inputs: &[Vec<u128>], outputs: &[Vec<u128>], proof: &StarkProof<H>, policy: &VerifierPolicy) -> Result<(), VerificationError>
+{
+    return stark::verify(program_hash, public_inputs, outputs, proof, policy);
+}
+
+/// Verifies many independent proofs; every statement consists of a program hash, public inputs,
+/// outputs, and a proof of program execution, and is verified in the same way as by `verify()`.
+/// Returns verification results in the same order as the statements.
+/// 
+/// Proofs are verified in parallel using all available threads, and values which depend only
+/// on the dimensions of the proof domain are computed once for all proofs with the same domain.
+pub fn verify_many<H: Hasher>(statements: &[(&[u8; 32], &[u128], &[u128], &StarkProof<H>)]) -> Vec<Result<(), VerificationError>>
+{
+    return verify_many_with_policy(statements, &VerifierPolicy::default());
+}
+
+/// Same as `verify_many()`, but rejects proofs which do not satisfy the specified `policy`.
+pub fn verify_many_with_policy<H: Hasher>(statements: &[(&[u8; 32], &[u128], &[u128], &StarkProof<H>)], policy: &VerifierPolicy) -> Vec<Result<(), VerificationError>>
 {
-    return stark::verify(program_hash, public_inputs, outputs, proof);
+    let num_threads = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    return stark::verify_many(statements, policy, num_threads);
 }
 
 // GLOBAL CONSTANTS
@@ -1,33 +1,229 @@
-use std::{ env, io::Write, time::Instant };
-use distaff::{ self, StarkProof };
+use std::{ env, fs, collections::HashMap, io::Write, process, time::Instant };
+use serde::{ Serialize, de::DeserializeOwned };
+use distaff::{
+    self, Program, ProgramInputs, ProofOptions, StarkProof, Statement, VerifierPolicy, assembly,
+    crypto::{ Hasher, hash::{ Blake3, Sha3, Poseidon, Rescue, GMiMC } },
+};
 
 mod examples;
 use examples::{ Example };
 
+// CONSTANTS
+// ================================================================================================
+const USAGE: &str = "\
+Usage: distaff <command> [arguments]
+
+Commands:
+    compile <source.dasm> [-o <program.bin>]
+        compiles Distaff assembly into a binary program and prints its hash
+
+    hash <program>
+        prints hash of the program
+
+    run <program> [--inputs <inputs.json>] [--num-outputs <n>]
+        executes the program without generating a proof and prints the outputs
+
+    prove <program> [--inputs <inputs.json>] [--num-outputs <n>] [--options <options>] [-o <proof.bin>]
+          [--statement <statement.json>]
+        executes the program, writes the proof of execution to a file, and prints the outputs;
+        if a statement file is specified, program hash, public inputs, and outputs are written
+        into it
+
+    verify <proof.bin> --hash <program hash> [--inputs <inputs.json>] [--outputs <v1,v2,...>]
+    verify <proof.bin> --statement <statement.json>
+        verifies that the program with the specified hash, executed with the public inputs from
+        the inputs file, produces the specified outputs
+
+    example <name> [arguments]
+        executes, proves, and verifies one of the built-in example programs: collatz,
+        comparison, conditional, fibonacci, merkle, rangecheck
+
+A <program> can be either a binary program produced by `compile` command, or a source file with
+a .dasm extension.
+
+Inputs and statement files can be either JSON or TOML files (files with .toml extension are read
+as TOML); see docs/formats.md for the description of these formats. For example, an inputs file
+could look like this: {\"public\": [\"1\", \"0\"], \"secret_a\": [\"3\"]}.
+
+Proof <options> are a comma-separated list of key=value pairs; supported keys are: extension,
+queries, grinding, zk (true or false), folding, remainder, and hash (blake3, sha3, poseidon,
+rescue, or gmimc). For example: --options extension=32,queries=48,zk=true";
+
+const DEFAULT_NUM_OUTPUTS: usize = 1;
+const DEFAULT_PROOF_FILE: &str = "proof.bin";
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Command-line arguments split into positional arguments and named options (e.g. `-o file`
+/// or `--inputs file`); every named option must be followed by a value.
+struct Args {
+    positional  : Vec<String>,
+    named       : HashMap<String, String>,
+}
+
+// MAIN
+// ================================================================================================
 fn main() {
 
-    // configure logging
-    env_logger::Builder::new()
-        .format(|buf, record| writeln!(buf, "{}", record.args()))
-        .filter_level(log::LevelFilter::Debug).init();
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.is_empty() {
+        eprintln!("{}", USAGE);
+        process::exit(1);
+    }
 
-    // determine the example to run based on command-line inputs
-    let ex: Example;
-    let args: Vec<String> = env::args().collect();
-    if args.len() < 2 {
-        ex = examples::fibonacci::get_example(&args);
+    // configure logging; examples print execution details by default, while for other commands
+    // these can be enabled by setting DISTAFF_LOG environment variable to debug
+    let log_level = if args[0] == "example" { log::LevelFilter::Debug } else { log::LevelFilter::Warn };
+    let mut logger = env_logger::Builder::new();
+    logger.format(|buf, record| writeln!(buf, "{}", record.args())).filter_level(log_level);
+    if let Ok(filters) = env::var("DISTAFF_LOG") {
+        logger.parse_filters(&filters);
     }
-    else {
-        ex = match args[1].as_str() {
-            "collatz"       => examples::collatz::get_example(&args[1..]),
-            "comparison"    => examples::comparison::get_example(&args[1..]),
-            "conditional"   => examples::conditional::get_example(&args[1..]),
-            "fibonacci"     => examples::fibonacci::get_example(&args[1..]),
-            "merkle"        => examples::merkle::get_example(&args[1..]),
-            "rangecheck"    => examples::range::get_example(&args[1..]),
-            _ => panic!("Could not find example program for '{}'", args[1])
-        }
+    logger.init();
+
+    let result = match args[0].as_str() {
+        "compile"   => Args::parse(&args[1..]).and_then(|args| compile(&args)),
+        "hash"      => Args::parse(&args[1..]).and_then(|args| hash(&args)),
+        "run"       => Args::parse(&args[1..]).and_then(|args| run(&args)),
+        "prove"     => Args::parse(&args[1..]).and_then(|args| prove(&args)),
+        "verify"    => Args::parse(&args[1..]).and_then(|args| verify(&args)),
+        "example"   => example(&args[1..]),
+        "help" | "-h" | "--help" => {
+            println!("{}", USAGE);
+            Ok(())
+        },
+        command => Err(format!("unknown command '{}'\n\n{}", command, USAGE)),
+    };
+
+    if let Err(msg) = result {
+        eprintln!("error: {}", msg);
+        process::exit(1);
+    }
+}
+
+// COMMANDS
+// ================================================================================================
+
+fn compile(args: &Args) -> Result<(), String> {
+    let source_file = args.positional(0, "source file")?;
+    let source = read_string(source_file)?;
+    let program = assembly::compile(&source)
+        .map_err(|err| format!("failed to compile {}: {}", source_file, err))?;
+
+    let program_file = match args.get("o") {
+        Some(file) => file.to_string(),
+        None => replace_extension(source_file, "bin"),
+    };
+    let bytes = bincode::serialize(&program).map_err(|err| err.to_string())?;
+    write_bytes(&program_file, &bytes)?;
+
+    println!("{}", hex::encode(program.hash()));
+    return Ok(());
+}
+
+fn hash(args: &Args) -> Result<(), String> {
+    let program = read_program(args.positional(0, "program")?)?;
+    println!("{}", hex::encode(program.hash()));
+    return Ok(());
+}
+
+fn run(args: &Args) -> Result<(), String> {
+    let program = read_program(args.positional(0, "program")?)?;
+    let inputs = read_inputs(args.get("inputs"))?;
+    let num_outputs = parse_num_outputs(args)?;
+
+    let now = Instant::now();
+    let outputs = distaff::run(&program, &inputs, num_outputs).map_err(|err| err.to_string())?;
+    log::debug!("Executed program in {} ms", now.elapsed().as_millis());
+
+    println!("{}", format_values(&outputs));
+    return Ok(());
+}
+
+fn prove(args: &Args) -> Result<(), String> {
+    let program = read_program(args.positional(0, "program")?)?;
+    let inputs = read_inputs(args.get("inputs"))?;
+    let num_outputs = parse_num_outputs(args)?;
+    let options = parse_options(args.get("options").unwrap_or(""))?;
+    let proof_file = args.get("o").unwrap_or(DEFAULT_PROOF_FILE);
+    let statement_file = args.get("statement");
+
+    let (outputs, proof_bytes) = match options.get("hash").map(|h| h.as_str()).unwrap_or("blake3") {
+        "blake3"    => prove_with::<Blake3>(&program, &inputs, num_outputs, &options)?,
+        "sha3"      => prove_with::<Sha3>(&program, &inputs, num_outputs, &options)?,
+        "poseidon"  => prove_with::<Poseidon>(&program, &inputs, num_outputs, &options)?,
+        "rescue"    => prove_with::<Rescue>(&program, &inputs, num_outputs, &options)?,
+        "gmimc"     => prove_with::<GMiMC>(&program, &inputs, num_outputs, &options)?,
+        hash_fn     => return Err(format!("unknown hash function '{}'", hash_fn)),
+    };
+    write_bytes(proof_file, &proof_bytes)?;
+    log::debug!("Wrote proof of {} KB to {}", proof_bytes.len() / 1024, proof_file);
+
+    if let Some(statement_file) = statement_file {
+        let statement = Statement::new(program.hash(), inputs.get_public_inputs(), &outputs);
+        write_document(statement_file, &statement)?;
     }
+
+    println!("{}", format_values(&outputs));
+    return Ok(());
+}
+
+fn verify(args: &Args) -> Result<(), String> {
+    let proof_bytes = read_bytes(args.positional(0, "proof file")?)?;
+    let statement = match args.get("statement") {
+        Some(file) => {
+            if args.get("hash").is_some() || args.get("inputs").is_some() || args.get("outputs").is_some() {
+                return Err(String::from("a statement file cannot be combined with --hash, --inputs, or --outputs"));
+            }
+            read_document::<Statement>(file)?
+        },
+        None => {
+            let program_hash = parse_hash(args.require("hash")?)?;
+            let inputs = read_inputs(args.get("inputs"))?;
+            let outputs = parse_values(args.get("outputs").unwrap_or(""))?;
+            if outputs.len() > distaff::MAX_OUTPUTS {
+                return Err(format!("expected no more than {} outputs, but received {}",
+                    distaff::MAX_OUTPUTS, outputs.len()));
+            }
+            Statement::new(&program_hash, inputs.get_public_inputs(), &outputs)
+        }
+    };
+
+    // the proof ends with the ID of the hash function used to generate it
+    let result = match proof_bytes.last() {
+        Some(&id) if id == Blake3::ID   => verify_with::<Blake3>(&statement, &proof_bytes),
+        Some(&id) if id == Sha3::ID     => verify_with::<Sha3>(&statement, &proof_bytes),
+        Some(&id) if id == Poseidon::ID => verify_with::<Poseidon>(&statement, &proof_bytes),
+        Some(&id) if id == Rescue::ID   => verify_with::<Rescue>(&statement, &proof_bytes),
+        Some(&id) if id == GMiMC::ID    => verify_with::<GMiMC>(&statement, &proof_bytes),
+        _ => Err(String::from("proof was generated with an unknown hash function")),
+    };
+
+    return match result {
+        Ok(_) => {
+            println!("Execution verified");
+            Ok(())
+        },
+        Err(msg) => Err(format!("failed to verify execution: {}", msg)),
+    };
+}
+
+fn example(args: &[String]) -> Result<(), String> {
+
+    // determine the example to run based on command-line inputs
+    let args = if args.is_empty() { vec![String::from("fibonacci")] } else { args.to_vec() };
+    let args = &args[..];
+    let name = args[0].as_str();
+    let ex: Example = match name {
+        "collatz"       => examples::collatz::get_example(args),
+        "comparison"    => examples::comparison::get_example(args),
+        "conditional"   => examples::conditional::get_example(args),
+        "fibonacci"     => examples::fibonacci::get_example(args),
+        "merkle"        => examples::merkle::get_example(args),
+        "rangecheck"    => examples::range::get_example(args),
+        _ => return Err(format!("could not find example program for '{}'", name))
+    };
     let Example { program, inputs, num_outputs, options, expected_result } = ex;
     println!("--------------------------------");
 
@@ -35,7 +231,7 @@ fn main() {
     let now = Instant::now();
     let (outputs, proof) = distaff::execute(&program, &inputs, num_outputs, &options);
     println!("--------------------------------");
-    println!("Executed program with hash {} in {} ms", 
+    println!("Executed program with hash {} in {} ms",
         hex::encode(program.hash()),
         now.elapsed().as_millis());
     println!("Program output: {:?}", outputs);
@@ -55,4 +251,205 @@ fn main() {
         Ok(_) => println!("Execution verified in {} ms", now.elapsed().as_millis()),
         Err(msg) => println!("Failed to verify execution: {}", msg)
     }
-}
\ No newline at end of file
+    return Ok(());
+}
+
+// PROVING AND VERIFYING
+// ================================================================================================
+
+fn prove_with<H: Hasher>(program: &Program, inputs: &ProgramInputs, num_outputs: usize, options: &HashMap<String, String>)
+    -> Result<(Vec<u128>, Vec<u8>), String>
+{
+    let defaults = ProofOptions::default();
+    let options = ProofOptions::<H>::try_new(
+        parse_option(options, "extension", defaults.extension_factor())?,
+        parse_option(options, "queries", defaults.num_queries())?,
+        parse_option(options, "grinding", defaults.grinding_factor())?,
+        parse_option(options, "zk", defaults.zero_knowledge())?,
+        parse_option(options, "folding", defaults.folding_factor())?,
+        parse_option(options, "remainder", defaults.max_remainder_degree())?)
+        .map_err(|err| format!("invalid proof options: {}", err))?;
+
+    // make sure `verify` command will accept the proof before spending time generating it
+    VerifierPolicy::default().check_options(&options)
+        .map_err(|err| format!("proof options are not accepted by the default verifier policy: {}", err))?;
+
+    let now = Instant::now();
+    let (outputs, proof) = distaff::try_execute(program, inputs, num_outputs, &options)
+        .map_err(|err| err.to_string())?;
+    log::debug!("Executed program and generated proof with {} bits of security in {} ms",
+        options.security_level(true),
+        now.elapsed().as_millis());
+
+    let proof_bytes = bincode::serialize(&proof).map_err(|err| err.to_string())?;
+    return Ok((outputs, proof_bytes));
+}
+
+fn verify_with<H: Hasher>(statement: &Statement, proof_bytes: &[u8]) -> Result<(), String> {
+    let proof = bincode::deserialize::<StarkProof<H>>(proof_bytes)
+        .map_err(|err| format!("invalid proof: {}", err))?;
+    return statement.verify(&proof).map_err(|err| err.to_string());
+}
+
+// ARGUMENT PARSING
+// ================================================================================================
+impl Args {
+
+    fn parse(args: &[String]) -> Result<Args, String> {
+        let mut positional = Vec::new();
+        let mut named = HashMap::new();
+
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if arg.starts_with('-') && arg.len() > 1 {
+                let name = arg.trim_start_matches('-');
+                let value = args.next().ok_or(format!("missing value for option '{}'", arg))?;
+                if named.insert(name.to_string(), value.clone()).is_some() {
+                    return Err(format!("option '{}' specified more than once", arg));
+                }
+            }
+            else {
+                positional.push(arg.clone());
+            }
+        }
+
+        return Ok(Args { positional, named });
+    }
+
+    fn positional(&self, index: usize, name: &str) -> Result<&str, String> {
+        return match self.positional.get(index) {
+            Some(value) => Ok(value),
+            None => Err(format!("{} was not specified", name)),
+        };
+    }
+
+    fn get(&self, name: &str) -> Option<&str> {
+        return self.named.get(name).map(|value| value.as_str());
+    }
+
+    fn require(&self, name: &str) -> Result<&str, String> {
+        return self.get(name).ok_or(format!("option '--{}' was not specified", name));
+    }
+
+    fn parse_or<T: std::str::FromStr>(&self, name: &str, default: T) -> Result<T, String> {
+        return match self.get(name) {
+            Some(value) => value.parse().map_err(|_| format!("invalid value '{}' for option '--{}'", value, name)),
+            None => Ok(default),
+        };
+    }
+}
+
+fn parse_num_outputs(args: &Args) -> Result<usize, String> {
+    let num_outputs = args.parse_or("num-outputs", DEFAULT_NUM_OUTPUTS)?;
+    if num_outputs > distaff::MAX_OUTPUTS {
+        return Err(format!("cannot produce more than {} outputs, but requested {}",
+            distaff::MAX_OUTPUTS, num_outputs));
+    }
+    return Ok(num_outputs);
+}
+
+fn parse_options(options: &str) -> Result<HashMap<String, String>, String> {
+    let mut result = HashMap::new();
+    for option in options.split(',').map(|o| o.trim()).filter(|o| !o.is_empty()) {
+        let mut parts = option.splitn(2, '=');
+        let key = parts.next().unwrap().trim();
+        let value = parts.next().ok_or(format!("invalid proof option '{}': expected key=value", option))?;
+        match key {
+            "extension" | "queries" | "grinding" | "zk" | "folding" | "remainder" | "hash" => (),
+            _ => return Err(format!("unknown proof option '{}'", key)),
+        }
+        result.insert(key.to_string(), value.trim().to_string());
+    }
+    return Ok(result);
+}
+
+fn parse_option<T: std::str::FromStr>(options: &HashMap<String, String>, key: &str, default: T) -> Result<T, String> {
+    return match options.get(key) {
+        Some(value) => value.parse().map_err(|_| format!("invalid value '{}' for proof option '{}'", value, key)),
+        None => Ok(default),
+    };
+}
+
+fn parse_hash(hash: &str) -> Result<[u8; 32], String> {
+    let bytes = hex::decode(hash.trim_start_matches("0x"))
+        .map_err(|err| format!("invalid program hash '{}': {}", hash, err))?;
+    if bytes.len() != 32 {
+        return Err(format!("invalid program hash '{}': expected 32 bytes, but received {}", hash, bytes.len()));
+    }
+    let mut result = [0u8; 32];
+    result.copy_from_slice(&bytes);
+    return Ok(result);
+}
+
+fn parse_values(values: &str) -> Result<Vec<u128>, String> {
+    return values.split(',').map(|v| v.trim()).filter(|v| !v.is_empty())
+        .map(|v| v.parse::<u128>().map_err(|_| format!("invalid field element '{}'", v)))
+        .collect();
+}
+
+fn format_values(values: &[u128]) -> String {
+    return values.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(",");
+}
+
+// FILE HELPERS
+// ================================================================================================
+
+/// Reads a program from a file; files with .dasm extension are compiled from source, and all
+/// other files are expected to contain binary programs produced by `compile` command.
+fn read_program(file: &str) -> Result<Program, String> {
+    if file.ends_with(".dasm") {
+        let source = read_string(file)?;
+        return assembly::compile(&source).map_err(|err| format!("failed to compile {}: {}", file, err));
+    }
+
+    let bytes = read_bytes(file)?;
+    return bincode::deserialize::<Program>(&bytes).map_err(|err| format!("invalid program {}: {}", file, err));
+}
+
+fn read_inputs(file: Option<&str>) -> Result<ProgramInputs, String> {
+    let file = match file {
+        Some(file) => file,
+        None => return Ok(ProgramInputs::none()),
+    };
+
+    return read_document::<ProgramInputs>(file);
+}
+
+/// Reads a value from a TOML file if the file has .toml extension, and from a JSON file otherwise.
+fn read_document<T: DeserializeOwned>(file: &str) -> Result<T, String> {
+    let content = read_string(file)?;
+    let result = if file.ends_with(".toml") {
+        toml::from_str(&content).map_err(|err| err.to_string())
+    }
+    else {
+        serde_json::from_str(&content).map_err(|err| err.to_string())
+    };
+    return result.map_err(|err| format!("invalid file {}: {}", file, err));
+}
+
+/// Writes a value into a TOML file if the file has .toml extension, and into a JSON file otherwise.
+fn write_document<T: Serialize>(file: &str, value: &T) -> Result<(), String> {
+    let content = if file.ends_with(".toml") {
+        toml::to_string(value).map_err(|err| err.to_string())?
+    }
+    else {
+        serde_json::to_string_pretty(value).map_err(|err| err.to_string())?
+    };
+    return write_bytes(file, content.as_bytes());
+}
+
+fn read_string(file: &str) -> Result<String, String> {
+    return fs::read_to_string(file).map_err(|err| format!("failed to read {}: {}", file, err));
+}
+
+fn read_bytes(file: &str) -> Result<Vec<u8>, String> {
+    return fs::read(file).map_err(|err| format!("failed to read {}: {}", file, err));
+}
+
+fn write_bytes(file: &str, bytes: &[u8]) -> Result<(), String> {
+    return fs::write(file, bytes).map_err(|err| format!("failed to write {}: {}", file, err));
+}
+
+fn replace_extension(file: &str, extension: &str) -> String {
+    return std::path::Path::new(file).with_extension(extension).to_string_lossy().into_owned();
+}
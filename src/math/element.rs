@@ -0,0 +1,299 @@
+use std::fmt::Debug;
+use rand::prelude::*;
+use rand::distributions::{ Distribution, Uniform };
+use serde::{ Serialize, de::DeserializeOwned };
+use crate::math::{ field, field64, field64::{ BaseElement, QuadExtension } };
+use crate::utils::{ uninit_vector };
+
+// FIELD ELEMENT TRAIT
+// ================================================================================================
+
+/// Describes arithmetic over a prime field (or an extension of a prime field) which supports
+/// FFTs over domains of size up to 2^TWO_ADICITY. Functions in `fft`, `polynom`, and `quartic`
+/// modules, as well as FRI proofs, are generic over this trait.
+pub trait FieldElement: Copy + PartialEq + Debug + Send + Sync + Serialize + DeserializeOwned + 'static {
+
+    /// Additive identity.
+    const ZERO: Self;
+
+    /// Multiplicative identity.
+    const ONE: Self;
+
+    /// Number of bytes needed to encode an element.
+    const ELEMENT_BYTES: usize;
+
+    /// Largest n such that a root of unity of order 2^n exists in the field.
+    const TWO_ADICITY: u32;
+
+    // BASIC ARITHMETIC
+    // --------------------------------------------------------------------------------------------
+
+    /// Computes a + b; a and b are assumed to be valid field elements.
+    fn add(a: Self, b: Self) -> Self;
+
+    /// Computes a - b; a and b are assumed to be valid field elements.
+    fn sub(a: Self, b: Self) -> Self;
+
+    /// Computes a * b; a and b are assumed to be valid field elements.
+    fn mul(a: Self, b: Self) -> Self;
+
+    /// Computes y such that x * y = 1; returns 0 if x is 0.
+    fn inv(x: Self) -> Self;
+
+    /// Computes 0 - x.
+    fn neg(x: Self) -> Self {
+        return Self::sub(Self::ZERO, x);
+    }
+
+    /// Computes a / b; b is assumed to be non-zero.
+    fn div(a: Self, b: Self) -> Self {
+        return Self::mul(a, Self::inv(b));
+    }
+
+    /// Computes b^p.
+    fn exp(b: Self, p: u128) -> Self {
+        if b == Self::ZERO { return Self::ZERO; }
+        else if p == 0 { return Self::ONE; }
+
+        let mut r = Self::ONE;
+        let mut b = b;
+        let mut p = p;
+        while p > 0 {
+            if p & 1 == 1 {
+                r = Self::mul(r, b);
+            }
+            p = p >> 1;
+            b = Self::mul(b, b);
+        }
+        return r;
+    }
+
+    /// Computes multiplicative inverses of all slice elements using batch inversion method;
+    /// zero elements are mapped to zero.
+    fn inv_many(values: &[Self]) -> Vec<Self> {
+        let mut result = uninit_vector(values.len());
+        let mut last = Self::ONE;
+        for i in 0..values.len() {
+            result[i] = last;
+            if values[i] != Self::ZERO {
+                last = Self::mul(last, values[i]);
+            }
+        }
+
+        last = Self::inv(last);
+        for i in (0..values.len()).rev() {
+            if values[i] == Self::ZERO {
+                result[i] = Self::ZERO;
+            }
+            else {
+                result[i] = Self::mul(last, result[i]);
+                last = Self::mul(last, values[i]);
+            }
+        }
+        return result;
+    }
+
+    // ROOT OF UNITY
+    // --------------------------------------------------------------------------------------------
+
+    /// Returns a root of unity of the specified order; order must be a power of 2 not greater
+    /// than 2^TWO_ADICITY.
+    fn get_root_of_unity(order: usize) -> Self;
+
+    /// Generates a vector with values [1, b, b^2, b^3, b^4, ..., b^(length - 1)].
+    fn get_power_series(b: Self, length: usize) -> Vec<Self> {
+        let mut result = uninit_vector(length);
+        if length == 0 { return result; }
+        result[0] = Self::ONE;
+        for i in 1..result.len() {
+            result[i] = Self::mul(result[i - 1], b);
+        }
+        return result;
+    }
+
+    // CONVERSIONS AND RANDOMNESS
+    // --------------------------------------------------------------------------------------------
+
+    /// Maps an integer into the field.
+    fn from_u64(value: u64) -> Self;
+
+    /// Generates a random field element.
+    fn rand() -> Self;
+
+    /// Generates a pseudo-random field element from a given `seed`.
+    fn prng(seed: [u8; 32]) -> Self;
+
+    /// Generates a vector of random field elements.
+    fn rand_vector(length: usize) -> Vec<Self> {
+        return (0..length).map(|_| Self::rand()).collect();
+    }
+}
+
+// 128-BIT FIELD
+// ================================================================================================
+
+/// The default field with modulus 2^128 - 45 * 2^40 + 1; all operations delegate to the
+/// `field` module.
+impl FieldElement for u128 {
+
+    const ZERO: u128 = field::ZERO;
+    const ONE: u128 = field::ONE;
+    const ELEMENT_BYTES: usize = 16;
+    const TWO_ADICITY: u32 = 40;
+
+    fn add(a: u128, b: u128) -> u128 { return field::add(a, b); }
+    fn sub(a: u128, b: u128) -> u128 { return field::sub(a, b); }
+    fn mul(a: u128, b: u128) -> u128 { return field::mul(a, b); }
+    fn inv(x: u128) -> u128 { return field::inv(x); }
+    fn neg(x: u128) -> u128 { return field::neg(x); }
+    fn exp(b: u128, p: u128) -> u128 { return field::exp(b, p); }
+    fn inv_many(values: &[u128]) -> Vec<u128> { return field::inv_many(values); }
+
+    fn get_root_of_unity(order: usize) -> u128 {
+        return field::get_root_of_unity(order);
+    }
+
+    fn from_u64(value: u64) -> u128 { return value as u128; }
+    fn rand() -> u128 { return field::rand(); }
+    fn prng(seed: [u8; 32]) -> u128 { return field::prng(seed); }
+    fn rand_vector(length: usize) -> Vec<u128> { return field::rand_vector(length); }
+}
+
+// 64-BIT FIELD
+// ================================================================================================
+
+/// Field with modulus 2^64 - 2^32 + 1; all operations delegate to the `field64` module.
+impl FieldElement for BaseElement {
+
+    const ZERO: BaseElement = BaseElement::from_canonical(field64::ZERO);
+    const ONE: BaseElement = BaseElement::from_canonical(field64::ONE);
+    const ELEMENT_BYTES: usize = 8;
+    const TWO_ADICITY: u32 = 32;
+
+    fn add(a: BaseElement, b: BaseElement) -> BaseElement { return base(field64::add(a.as_u64(), b.as_u64())); }
+    fn sub(a: BaseElement, b: BaseElement) -> BaseElement { return base(field64::sub(a.as_u64(), b.as_u64())); }
+    fn mul(a: BaseElement, b: BaseElement) -> BaseElement { return base(field64::mul(a.as_u64(), b.as_u64())); }
+    fn inv(x: BaseElement) -> BaseElement { return base(field64::inv(x.as_u64())); }
+    fn neg(x: BaseElement) -> BaseElement { return base(field64::neg(x.as_u64())); }
+
+    fn get_root_of_unity(order: usize) -> BaseElement {
+        return base(field64::get_root_of_unity(order));
+    }
+
+    fn from_u64(value: u64) -> BaseElement { return BaseElement::new(value); }
+    fn rand() -> BaseElement { return base(field64::rand()); }
+    fn prng(seed: [u8; 32]) -> BaseElement { return base(field64::prng(seed)); }
+}
+
+/// Quadratic extension of the 64-bit field; all operations delegate to the `field64` module.
+impl FieldElement for QuadExtension {
+
+    const ZERO: QuadExtension = QuadExtension::from_canonical(field64::ZERO, field64::ZERO);
+    const ONE: QuadExtension = QuadExtension::from_canonical(field64::ONE, field64::ZERO);
+    const ELEMENT_BYTES: usize = 16;
+    const TWO_ADICITY: u32 = 32;
+
+    fn add(a: QuadExtension, b: QuadExtension) -> QuadExtension { return ext(field64::ext_add(a.as_u64s(), b.as_u64s())); }
+    fn sub(a: QuadExtension, b: QuadExtension) -> QuadExtension { return ext(field64::ext_sub(a.as_u64s(), b.as_u64s())); }
+    fn mul(a: QuadExtension, b: QuadExtension) -> QuadExtension { return ext(field64::ext_mul(a.as_u64s(), b.as_u64s())); }
+    fn inv(x: QuadExtension) -> QuadExtension { return ext(field64::ext_inv(x.as_u64s())); }
+
+    fn get_root_of_unity(order: usize) -> QuadExtension {
+        return QuadExtension::from_base(BaseElement::get_root_of_unity(order));
+    }
+
+    fn from_u64(value: u64) -> QuadExtension { return QuadExtension::from_base(BaseElement::new(value)); }
+    fn rand() -> QuadExtension { return QuadExtension::new(BaseElement::rand(), BaseElement::rand()); }
+
+    fn prng(seed: [u8; 32]) -> QuadExtension {
+        let range = Uniform::from(field64::RANGE);
+        let mut g = StdRng::from_seed(seed);
+        return ext([range.sample(&mut g), range.sample(&mut g)]);
+    }
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Wraps a result of `field64` arithmetic, which is always a valid field element.
+#[inline(always)]
+fn base(value: u64) -> BaseElement {
+    return BaseElement::from_canonical(value);
+}
+
+/// Wraps a result of `field64` extension arithmetic, which is always a valid field element.
+#[inline(always)]
+fn ext(values: [u64; 2]) -> QuadExtension {
+    return QuadExtension::from_canonical(values[0], values[1]);
+}
+
+// TESTS
+// ================================================================================================
+#[cfg(test)]
+mod tests {
+
+    use super::{ FieldElement, BaseElement, QuadExtension };
+
+    #[test]
+    fn exp() {
+        // default implementation should match the one in the field module
+        let b: u128 = FieldElement::rand();
+        let p: u128 = FieldElement::rand();
+        assert_eq!(crate::math::field::exp(b, p), exp_default(b, p));
+
+        let b = BaseElement::new(12345);
+        assert_eq!(BaseElement::ONE, BaseElement::exp(b, 0));
+        assert_eq!(BaseElement::mul(b, b), BaseElement::exp(b, 2));
+    }
+
+    #[test]
+    fn inv_many() {
+        check_inv_many::<u128>();
+        check_inv_many::<BaseElement>();
+        check_inv_many::<QuadExtension>();
+    }
+
+    #[test]
+    fn get_root_of_unity() {
+        check_root_of_unity::<u128>();
+        check_root_of_unity::<BaseElement>();
+        check_root_of_unity::<QuadExtension>();
+    }
+
+    // HELPER FUNCTIONS
+    // --------------------------------------------------------------------------------------------
+    fn exp_default<E: FieldElement>(b: E, p: u128) -> E {
+        if b == E::ZERO { return E::ZERO; }
+        let mut r = E::ONE;
+        for i in (0..128).rev() {
+            r = E::mul(r, r);
+            if (p >> i) & 1 == 1 { r = E::mul(r, b); }
+        }
+        return r;
+    }
+
+    fn check_inv_many<E: FieldElement>() {
+        let mut values = E::rand_vector(64);
+        values[7] = E::ZERO;
+        let inverses = E::inv_many(&values);
+        for (&v, &i) in values.iter().zip(inverses.iter()) {
+            if v == E::ZERO {
+                assert_eq!(E::ZERO, i);
+            }
+            else {
+                assert_eq!(E::ONE, E::mul(v, i));
+                assert_eq!(E::inv(v), i);
+            }
+        }
+    }
+
+    fn check_root_of_unity<E: FieldElement>() {
+        let order = 1usize << E::TWO_ADICITY;
+        let root = E::get_root_of_unity(order);
+        assert_eq!(E::ONE, E::exp(root, order as u128));
+        assert!(E::exp(root, (order / 2) as u128) != E::ONE);
+
+        let root_16 = E::get_root_of_unity(16);
+        assert_eq!(E::exp(root, (order / 16) as u128), root_16);
+    }
+}
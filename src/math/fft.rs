@@ -1,11 +1,42 @@
+use std::{ any::{ Any, TypeId }, collections::HashMap, sync::{ Arc, Mutex } };
 use crossbeam_utils::thread;
-use crate::math::field;
+use crate::math::{ FieldElement };
+use crate::utils::{ uninit_vector };
 
 // CONSTANTS
 // ================================================================================================
 const USIZE_BITS: usize = 0_usize.count_zeros() as usize;
 const MAX_LOOP: usize = 256;
 
+// FFTs smaller than this are always computed in a single thread
+const MIN_CONCURRENT_SIZE: usize = 1024;
+
+// FFTs of this size and larger are computed with the cache-blocked four-step algorithm
+const MIN_BLOCKED_SIZE: usize = 1 << 16;
+
+// side of the square tiles in which the four-step algorithm transposes matrices
+const TRANSPOSE_TILE: usize = 16;
+
+// plans returned by FftPlan::shared(), keyed by the type of field elements
+static SHARED_PLANS: Mutex<Option<HashMap<TypeId, Arc<dyn Any + Send + Sync>>>> = Mutex::new(None);
+
+// FFT PLAN
+// ================================================================================================
+
+/// Precomputed twiddles and inverse twiddles for FFTs over domains of size up to `max_size`.
+/// 
+/// Twiddles are stored in permuted order, and so twiddles for a domain of size n / 2 are the
+/// first n / 4 twiddles for a domain of size n. Thus, a single plan can be used for FFTs over
+/// all power-of-two domains up to `max_size` without regenerating twiddles.
+/// 
+/// FFTs over domains of 2^16 elements or more are computed using the four-step algorithm, which
+/// splits a large FFT into many small FFTs over contiguous blocks of values that fit into cache.
+pub struct FftPlan<E: FieldElement> {
+    twiddles    : Vec<E>,
+    inv_twiddles: Vec<E>,
+    num_threads : usize,
+}
+
 // PUBLIC FUNCTIONS
 // ================================================================================================
 
@@ -13,7 +44,7 @@ const MAX_LOOP: usize = 256;
 /// performed in multiple threads. Number of threads must be a power of 2.
 /// 
 /// Adapted from: https://github.com/0xProject/OpenZKP/tree/master/algebra/primefield/src/fft
-pub fn fft_in_place(values: &mut [u128], twiddles: &[u128], count: usize, stride: usize, offset: usize, num_threads: usize) {
+pub fn fft_in_place<E: FieldElement>(values: &mut [E], twiddles: &[E], count: usize, stride: usize, offset: usize, num_threads: usize) {
     
     let size = values.len() / stride;
     debug_assert!(size.is_power_of_two());
@@ -30,7 +61,7 @@ pub fn fft_in_place(values: &mut [u128], twiddles: &[u128], count: usize, stride
             thread::scope(|s| {
                 // get another mutable reference to values to be used inside the new thread;
                 // this is OK because halves of FFT don't step on each other
-                let values2 = unsafe { &mut *(values as *mut [u128]) };
+                let values2 = unsafe { &mut *(values as *mut [E]) };
                 s.spawn(move |_| {
                     fft_in_place(values2, twiddles, count, 2 * stride, offset, num_threads / 2);
                 });
@@ -55,20 +86,30 @@ pub fn fft_in_place(values: &mut [u128], twiddles: &[u128], count: usize, stride
     }
 }
 
-pub fn get_twiddles(root: u128, size: usize) -> Vec<u128> {
+pub fn get_twiddles<E: FieldElement>(root: E, size: usize) -> Vec<E> {
     assert!(size.is_power_of_two());
-    assert!(field::exp(root, size as u128) == field::ONE);
-    let mut twiddles = field::get_power_series(root, size / 2);
+    assert!(E::exp(root, size as u128) == E::ONE);
+    let mut twiddles = E::get_power_series(root, size / 2);
     permute(&mut twiddles);
     return twiddles;
 }
 
-pub fn get_inv_twiddles(root: u128, size: usize) -> Vec<u128> {
-    let inv_root = field::exp(root, (size - 1) as u128);
+pub fn get_inv_twiddles<E: FieldElement>(root: E, size: usize) -> Vec<E> {
+    let inv_root = E::exp(root, (size - 1) as u128);
     return get_twiddles(inv_root, size);
 }
 
-pub fn permute(v: &mut [u128]) {
+/// Returns the coset of the multiplicative subgroup generated by `root` shifted by `offset`,
+/// i.e. [offset, offset * root, offset * root^2, ..., offset * root^(size - 1)].
+pub fn get_coset_domain<E: FieldElement>(root: E, offset: E, size: usize) -> Vec<E> {
+    let mut domain = E::get_power_series(root, size);
+    for x in domain.iter_mut() {
+        *x = E::mul(*x, offset);
+    }
+    return domain;
+}
+
+pub fn permute<T>(v: &mut [T]) {
     let n = v.len();
     for i in 0..n {
         let j = permute_index(n, i);
@@ -78,8 +119,260 @@ pub fn permute(v: &mut [u128]) {
     }
 }
 
+// FFT PLAN IMPLEMENTATION
+// ================================================================================================
+impl <E: FieldElement> FftPlan<E> {
+
+    /// Returns a plan for FFTs over domains of size up to `max_size`; FFTs are computed in as
+    /// many threads as there are available CPU cores (rounded down to a power of 2).
+    pub fn new(max_size: usize) -> FftPlan<E> {
+        let num_threads = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+        let num_threads = 1 << (usize::BITS - 1 - num_threads.leading_zeros());
+        return FftPlan::with_threads(max_size, num_threads);
+    }
+
+    /// Returns a plan for FFTs over domains of size up to `max_size`; FFTs are computed in
+    /// `num_threads` threads, which must be a power of 2.
+    pub fn with_threads(max_size: usize, num_threads: usize) -> FftPlan<E> {
+        assert!(max_size.is_power_of_two() && max_size > 1, "max size must be a power of 2 greater than 1");
+        assert!(num_threads.is_power_of_two(), "number of threads must be a power of 2");
+        let root = E::get_root_of_unity(max_size);
+        return FftPlan {
+            twiddles    : get_twiddles(root, max_size),
+            inv_twiddles: get_inv_twiddles(root, max_size),
+            num_threads,
+        };
+    }
+
+    /// Returns a plan shared by all callers for FFTs over domains of size up to at least `size`.
+    /// The plan is built on first use, and is rebuilt only when a larger domain is requested;
+    /// so, functions such as `polynom::eval_fft()` do not regenerate twiddles on every call.
+    pub fn shared(size: usize) -> Arc<FftPlan<E>> {
+        let mut plans = SHARED_PLANS.lock().unwrap();
+        let plans = plans.get_or_insert_with(HashMap::new);
+        if let Some(plan) = plans.get(&TypeId::of::<E>()) {
+            let plan = plan.clone().downcast::<FftPlan<E>>().unwrap();
+            if plan.max_size() >= size { return plan; }
+        }
+
+        let plan = Arc::new(FftPlan::new(size));
+        plans.insert(TypeId::of::<E>(), plan.clone());
+        return plan;
+    }
+
+    // PUBLIC ACCESSORS
+    // --------------------------------------------------------------------------------------------
+
+    /// Returns the size of the largest domain supported by this plan.
+    pub fn max_size(&self) -> usize {
+        return self.twiddles.len() * 2;
+    }
+
+    /// Returns the number of threads used by this plan.
+    pub fn num_threads(&self) -> usize {
+        return self.num_threads;
+    }
+
+    /// Returns twiddles for a domain of the specified `size`.
+    pub fn twiddles(&self, size: usize) -> &[E] {
+        self.validate_size(size);
+        return &self.twiddles[..(size / 2)];
+    }
+
+    /// Returns inverse twiddles for a domain of the specified `size`.
+    pub fn inv_twiddles(&self, size: usize) -> &[E] {
+        self.validate_size(size);
+        return &self.inv_twiddles[..(size / 2)];
+    }
+
+    // FFT OPERATIONS
+    // --------------------------------------------------------------------------------------------
+
+    /// Evaluates polynomial `p` in-place over the domain of size p.len() shifted by `offset`;
+    /// evaluations are returned in natural order.
+    pub fn evaluate(&self, p: &mut [E], offset: E) {
+        self.evaluate_with_threads(p, offset, self.num_threads);
+    }
+
+    /// Interpolates a polynomial in-place from its evaluations `v` over the domain of size
+    /// v.len() shifted by `offset`; coefficients are returned in natural order.
+    pub fn interpolate(&self, v: &mut [E], offset: E) {
+        self.interpolate_with_threads(v, offset, self.num_threads);
+    }
+
+    /// Evaluates all `polys` in-place over domains shifted by `offset`; polynomials are
+    /// distributed across threads, and each polynomial is evaluated in a single thread.
+    pub fn evaluate_batch(&self, polys: &mut [Vec<E>], offset: E) {
+        self.run_batch(polys, |p| self.evaluate_with_threads(p, offset, 1));
+    }
+
+    /// Interpolates polynomials in-place from all `evaluations` over domains shifted by
+    /// `offset`; evaluation vectors are distributed across threads, and each polynomial is
+    /// interpolated in a single thread.
+    pub fn interpolate_batch(&self, evaluations: &mut [Vec<E>], offset: E) {
+        self.run_batch(evaluations, |v| self.interpolate_with_threads(v, offset, 1));
+    }
+
+    // HELPER METHODS
+    // --------------------------------------------------------------------------------------------
+
+    fn evaluate_with_threads(&self, p: &mut [E], offset: E, num_threads: usize) {
+        let twiddles = self.twiddles(p.len());
+        if offset != E::ONE {
+            // p(offset * x) is a polynomial with coefficients p_i * offset^i
+            scale_values(p, E::ONE, offset);
+        }
+        let num_threads = get_num_threads(p.len(), num_threads);
+        if p.len() >= MIN_BLOCKED_SIZE {
+            let root = E::get_root_of_unity(p.len());
+            fft_blocked(p, twiddles, root, num_threads);
+        }
+        else {
+            fft_in_place(p, twiddles, 1, 1, 0, num_threads);
+            permute(p);
+        }
+    }
+
+    fn interpolate_with_threads(&self, v: &mut [E], offset: E, num_threads: usize) {
+        let inv_twiddles = self.inv_twiddles(v.len());
+        let num_threads = get_num_threads(v.len(), num_threads);
+        if v.len() >= MIN_BLOCKED_SIZE {
+            let inv_root = E::inv(E::get_root_of_unity(v.len()));
+            fft_blocked(v, inv_twiddles, inv_root, num_threads);
+        }
+        else {
+            fft_in_place(v, inv_twiddles, 1, 1, 0, num_threads);
+            permute(v);
+        }
+        // divide all coefficients by the domain size, and coefficient i also by offset^i
+        let inv_length = E::inv(E::from_u64(v.len() as u64));
+        scale_values(v, inv_length, E::inv(offset));
+    }
+
+    fn run_batch<F>(&self, values: &mut [Vec<E>], op: F)
+        where F: Fn(&mut [E]) + Sync
+    {
+        if self.num_threads == 1 || values.len() < 2 {
+            values.iter_mut().for_each(|v| op(v));
+            return;
+        }
+
+        let batch_size = (values.len() + self.num_threads - 1) / self.num_threads;
+        thread::scope(|s| {
+            for batch in values.chunks_mut(batch_size) {
+                let op = &op;
+                s.spawn(move |_| batch.iter_mut().for_each(|v| op(v)));
+            }
+        }).unwrap();
+    }
+
+    fn validate_size(&self, size: usize) {
+        assert!(size.is_power_of_two() && size > 1, "domain size must be a power of 2 greater than 1");
+        assert!(size <= self.max_size(), "domain size cannot exceed {}", self.max_size());
+    }
+}
+
 // HELPER FUNCTIONS
 // ================================================================================================
+
+/// Returns the number of threads to use for an FFT of the specified size.
+fn get_num_threads(size: usize, num_threads: usize) -> usize {
+    return if size < MIN_CONCURRENT_SIZE { 1 } else { std::cmp::min(num_threads, size / 2) };
+}
+
+/// Computes FFT of `values` using the four-step algorithm: values are treated as a matrix with
+/// n1 rows and n2 >= n1 columns, FFTs of size n1 are computed over columns, the results are
+/// multiplied by powers of `root`, and FFTs of size n2 are computed over rows. Columns are
+/// transposed into rows beforehand, so that every small FFT runs over a contiguous block of
+/// values; small FFTs are distributed across `num_threads` threads.
+/// 
+/// `root` must be the root of unity of order values.len() from which `twiddles` were generated;
+/// unlike `fft_in_place()`, this function returns results in natural order.
+fn fft_blocked<E: FieldElement>(values: &mut [E], twiddles: &[E], root: E, num_threads: usize) {
+    let n = values.len();
+    debug_assert!(n.is_power_of_two() && n >= 4);
+    let n1 = 1 << (n.trailing_zeros() / 2);
+    let n2 = n / n1;
+
+    // transpose values into n2 rows of n1 elements, and compute FFT over every row; then,
+    // multiply element k1 of row j2 by root^(j2 * k1)
+    let mut scratch = uninit_vector(n);
+    transpose(values, &mut scratch, n1, n2, num_threads);
+    split_rows(&mut scratch, n1, num_threads, |first_row, rows| {
+        for (j2, row) in rows.chunks_mut(n1).enumerate() {
+            fft_in_place(row, &twiddles[..(n1 / 2)], 1, 1, 0, 1);
+            permute(row);
+            scale_values(row, E::ONE, E::exp(root, (first_row + j2) as u128));
+        }
+    });
+
+    // transpose the results back into n1 rows of n2 elements, and compute FFT over every row
+    transpose(&scratch, values, n2, n1, num_threads);
+    split_rows(values, n2, num_threads, |_, rows| {
+        for row in rows.chunks_mut(n2) {
+            fft_in_place(row, &twiddles[..(n2 / 2)], 1, 1, 0, 1);
+            permute(row);
+        }
+    });
+
+    // element k2 of row k1 is now the evaluation at root^(k1 + n1 * k2); so, transposing the
+    // matrix puts all evaluations into natural order
+    transpose(values, &mut scratch, n1, n2, num_threads);
+    values.copy_from_slice(&scratch);
+}
+
+/// Writes transpose of `src` matrix with `rows` rows and `cols` columns into `dst`. The matrix
+/// is transposed in square tiles to keep memory access local, and rows of `dst` are distributed
+/// across `num_threads` threads.
+fn transpose<E: FieldElement>(src: &[E], dst: &mut [E], rows: usize, cols: usize, num_threads: usize) {
+    debug_assert_eq!(src.len(), rows * cols);
+    debug_assert_eq!(dst.len(), rows * cols);
+    split_rows(dst, rows, num_threads, |first_col, dst| {
+        let num_cols = dst.len() / rows;
+        for r0 in (0..rows).step_by(TRANSPOSE_TILE) {
+            let r_end = std::cmp::min(r0 + TRANSPOSE_TILE, rows);
+            for c0 in (0..num_cols).step_by(TRANSPOSE_TILE) {
+                let c_end = std::cmp::min(c0 + TRANSPOSE_TILE, num_cols);
+                for c in c0..c_end {
+                    for r in r0..r_end {
+                        dst[c * rows + r] = src[r * cols + first_col + c];
+                    }
+                }
+            }
+        }
+    });
+}
+
+/// Splits `values` into rows of `row_len` elements, distributes the rows evenly across
+/// `num_threads` threads, and calls `op` with the index of the first row in each batch of rows
+/// together with the batch itself.
+fn split_rows<E, F>(values: &mut [E], row_len: usize, num_threads: usize, op: F)
+    where E: FieldElement, F: Fn(usize, &mut [E]) + Sync
+{
+    let num_rows = values.len() / row_len;
+    if num_threads == 1 || num_rows == 1 {
+        op(0, values);
+        return;
+    }
+
+    let batch_rows = (num_rows + num_threads - 1) / num_threads;
+    thread::scope(|s| {
+        for (i, batch) in values.chunks_mut(batch_rows * row_len).enumerate() {
+            let op = &op;
+            s.spawn(move |_| op(i * batch_rows, batch));
+        }
+    }).unwrap();
+}
+
+/// Multiplies value i by init * factor^i.
+fn scale_values<E: FieldElement>(values: &mut [E], init: E, factor: E) {
+    let mut scale = init;
+    for value in values.iter_mut() {
+        *value = E::mul(*value, scale);
+        scale = E::mul(scale, factor);
+    }
+}
+
 fn permute_index(size: usize, index: usize) -> usize {
     debug_assert!(index < size);
     if size == 1 { return 0 }
@@ -89,29 +382,29 @@ fn permute_index(size: usize, index: usize) -> usize {
 }
 
 #[inline(always)]
-fn butterfly(values: &mut [u128], offset: usize, stride: usize) {
+fn butterfly<E: FieldElement>(values: &mut [E], offset: usize, stride: usize) {
     let i = offset;
     let j = offset + stride;
     let temp = values[i];
-    values[i] = field::add(temp, values[j]);
-    values[j] = field::sub(temp, values[j]);
+    values[i] = E::add(temp, values[j]);
+    values[j] = E::sub(temp, values[j]);
 }
 
 #[inline(always)]
-fn butterfly_twiddle(values: &mut [u128], twiddle: u128, offset: usize, stride: usize) {
+fn butterfly_twiddle<E: FieldElement>(values: &mut [E], twiddle: E, offset: usize, stride: usize) {
     let i = offset;
     let j = offset + stride;
     let temp = values[i];
-    values[j] = field::mul(values[j], twiddle);
-    values[i] = field::add(temp, values[j]);
-    values[j] = field::sub(temp, values[j]);
+    values[j] = E::mul(values[j], twiddle);
+    values[i] = E::add(temp, values[j]);
+    values[j] = E::sub(temp, values[j]);
 }
 
 // TESTS
 // ================================================================================================
 #[cfg(test)]
 mod tests {
-    use crate::math::{ field, polynom };
+    use crate::math::{ field, polynom, FieldElement, BaseElement, QuadExtension };
 
     #[test]
     fn fft_in_place() {
@@ -155,4 +448,116 @@ mod tests {
         super::permute(&mut p);
         assert_eq!(expected, p);
     }
+
+    #[test]
+    fn fft_in_place_64() {
+        check_fft::<BaseElement>(1024);
+        check_fft::<QuadExtension>(256);
+    }
+
+    #[test]
+    fn fft_plan() {
+        let plan = super::FftPlan::<u128>::with_threads(2048, 4);
+        assert_eq!(2048, plan.max_size());
+
+        // twiddles for smaller domains are prefixes of twiddles for the largest domain
+        for &n in [2, 16, 1024, 2048].iter() {
+            let g = field::get_root_of_unity(n);
+            assert_eq!(super::get_twiddles(g, n), plan.twiddles(n));
+            assert_eq!(super::get_inv_twiddles(g, n), plan.inv_twiddles(n));
+        }
+
+        // evaluation over a coset should match direct evaluation, and interpolation should
+        // recover the original polynomial; 2048 is large enough to use multiple threads
+        let offset = field::GENERATOR;
+        for &n in [8, 2048].iter() {
+            let p = field::rand_vector(n);
+            let g = field::get_root_of_unity(n);
+            let domain = super::get_coset_domain(g, offset, n);
+            let expected = domain.iter().map(|&x| polynom::eval(&p, x)).collect::<Vec<u128>>();
+
+            let mut values = p.clone();
+            plan.evaluate(&mut values, offset);
+            assert_eq!(expected, values);
+
+            plan.interpolate(&mut values, offset);
+            assert_eq!(p, values);
+        }
+    }
+
+    #[test]
+    fn fft_plan_batch() {
+        let plan = super::FftPlan::<u128>::with_threads(256, 4);
+        let polys = (0..7).map(|_| field::rand_vector(64)).collect::<Vec<_>>();
+
+        let mut batch = polys.clone();
+        plan.evaluate_batch(&mut batch, field::ONE);
+        for (p, evaluations) in polys.iter().zip(batch.iter()) {
+            let mut expected = p.clone();
+            polynom::eval_fft(&mut expected, true);
+            assert_eq!(&expected, evaluations);
+        }
+
+        plan.interpolate_batch(&mut batch, field::ONE);
+        assert_eq!(polys, batch);
+    }
+
+    #[test]
+    fn fft_blocked() {
+        // square and non-square matrices, in one and in multiple threads
+        for &n in [4, 8, 1024, 2048].iter() {
+            for &num_threads in [1, 4].iter() {
+                check_fft_blocked::<u128>(n, num_threads);
+                check_fft_blocked::<BaseElement>(n, num_threads);
+            }
+        }
+    }
+
+    #[test]
+    fn fft_plan_shared() {
+        let plan = super::FftPlan::<u128>::shared(64);
+        assert!(plan.max_size() >= 64);
+        assert!(super::FftPlan::<u128>::shared(32).max_size() >= 64);
+        assert!(super::FftPlan::<BaseElement>::shared(16).max_size() >= 16);
+
+        // domains of this size are evaluated and interpolated by the blocked algorithm
+        let n = super::MIN_BLOCKED_SIZE;
+        let p = field::rand_vector(n);
+        let g = field::get_root_of_unity(n);
+        let mut expected = p.clone();
+        super::fft_in_place(&mut expected, &super::get_twiddles(g, n), 1, 1, 0, 1);
+        super::permute(&mut expected);
+
+        let mut values = p.clone();
+        polynom::eval_fft(&mut values, true);
+        assert_eq!(expected, values);
+
+        polynom::interpolate_fft(&mut values, true);
+        assert_eq!(p, values);
+    }
+
+    fn check_fft_blocked<E: FieldElement>(n: usize, num_threads: usize) {
+        let p = E::rand_vector(n);
+        let g = E::get_root_of_unity(n);
+        for (root, twiddles) in vec![(g, super::get_twiddles(g, n)), (E::inv(g), super::get_inv_twiddles(g, n))] {
+            let mut expected = p.clone();
+            super::fft_in_place(&mut expected, &twiddles, 1, 1, 0, 1);
+            super::permute(&mut expected);
+
+            let mut values = p.clone();
+            super::fft_blocked(&mut values, &twiddles, root, num_threads);
+            assert_eq!(expected, values);
+        }
+    }
+
+    fn check_fft<E: FieldElement>(n: usize) {
+        let mut p = E::rand_vector(n);
+        let g = E::get_root_of_unity(n);
+        let roots = E::get_power_series(g, n);
+        let expected = roots.iter().map(|x| polynom::eval(&p, *x)).collect::<Vec<E>>();
+        let twiddles = super::get_twiddles(g, n);
+        super::fft_in_place(&mut p, &twiddles, 1, 1, 0, 1);
+        super::permute(&mut p);
+        assert_eq!(expected, p);
+    }
 }
\ No newline at end of file
@@ -3,6 +3,7 @@ use std::convert::TryInto;
 use rand::prelude::*;
 use rand::distributions::{ Distribution, Uniform };
 use crate::utils::{ uninit_vector };
+use crate::math::montgomery;
 
 // CONSTANTS
 // ================================================================================================
@@ -13,6 +14,9 @@ pub const M: u128 = 340282366920938463463374557953744961537;
 // 2^40 root of unity
 pub const G: u128 = 23953097886125630542083529559205016746;
 
+// generator of the multiplicative group of the field; G = GENERATOR^((M - 1) / 2^40)
+pub const GENERATOR: u128 = 3;
+
 // public constants
 pub const MODULUS: u128 = M;
 pub const RANGE: Range<u128> = Range { start: 0, end: M };
@@ -20,6 +24,9 @@ pub const RANGE: Range<u128> = Range { start: 0, end: M };
 pub const ZERO: u128 = 0;
 pub const ONE: u128 = 1;
 
+// log2 of the largest order of a root of unity in the field
+pub const TWO_ADICITY: u32 = 40;
+
 // BASIC ARITHMETIC
 // --------------------------------------------------------------------------------------------
 
@@ -35,6 +42,7 @@ pub fn sub(a: u128, b: u128) -> u128 {
 }
 
 /// Computes (a * b) % m; a and b are assumed to be valid field elements.
+#[cfg(not(feature = "montgomery"))]
 pub fn mul(a: u128, b: u128) -> u128 {
 
     let (x0, x1, x2) = mul_128x64(a, (b >> 64) as u64);         // x = a * b_hi
@@ -72,6 +80,12 @@ pub fn mul(a: u128, b: u128) -> u128 {
     return ((z1 as u128) << 64) + (z0 as u128);
 }
 
+/// Computes (a * b) % m; a and b are assumed to be valid field elements.
+#[cfg(feature = "montgomery")]
+pub fn mul(a: u128, b: u128) -> u128 {
+    return montgomery::mul(a, b);
+}
+
 /// Computes a[i] + b[i] * c for all i and saves result into a.
 pub fn mul_acc(a: &mut [u128], b: &[u128], c: u128) {
     for i in 0..a.len() {
@@ -197,25 +211,10 @@ pub fn div(a: u128, b: u128) -> u128 {
     return mul(a, b);
 }
 
-/// Computes (b^p) % m; b and p are assumed to be valid field elements.
+/// Computes (b^p) % m; b and p are assumed to be valid field elements. Intermediate products
+/// are kept in Montgomery form, which is faster than repeated calls to `mul()`.
 pub fn exp(b: u128, p: u128) -> u128 {
-    if b == 0 { return 0; }
-    else if p == 0 { return 1; }
-
-    let mut r = 1;
-    let mut b = b;
-    let mut p = p;
-
-    // TODO: optimize
-    while p > 0 {
-        if p & 1 == 1 {
-            r = mul(r, b);
-        }
-        p = p >> 1;
-        b = mul(b, b);
-    }
-
-    return r;
+    return montgomery::exp(b, p);
 }
 
 /// Computes (0 - x) % m; x is assumed to be a valid field element.
@@ -228,8 +227,8 @@ pub fn neg(x: u128) -> u128 {
 pub fn get_root_of_unity(order: usize) -> u128 {
     assert!(order != 0, "cannot get root of unity for order 0");
     assert!(order.is_power_of_two(), "order must be a power of 2");
-    assert!(order.trailing_zeros() <= 40, "order cannot exceed 2^40");
-    let p = 1u128 << (40 - order.trailing_zeros());
+    assert!(order.trailing_zeros() <= TWO_ADICITY, "order cannot exceed 2^{}", TWO_ADICITY);
+    let p = 1u128 << (TWO_ADICITY - order.trailing_zeros());
     return exp(G, p);
 }
 
@@ -283,6 +282,7 @@ pub fn from_bytes(bytes: &[u8]) -> u128 {
 // HELPER FUNCTIONS
 // ================================================================================================
 
+#[cfg(not(feature = "montgomery"))]
 #[inline(always)]
 fn mul_128x64(a: u128, b: u64) -> (u64, u64, u64) {
     let z_lo = ((a as u64) as u128) * (b as u128);
@@ -291,6 +291,7 @@ fn mul_128x64(a: u128, b: u64) -> (u64, u64, u64) {
     return (z_lo as u64, z_hi as u64, (z_hi >> 64) as u64);
 }
 
+#[cfg(not(feature = "montgomery"))]
 #[inline(always)]
 fn mul_reduce(z0: u64, z1: u64, z2: u64) -> (u64, u64, u64) {
     let (q0, q1, q2) = mul_by_modulus(z2);
@@ -298,6 +299,7 @@ fn mul_reduce(z0: u64, z1: u64, z2: u64) -> (u64, u64, u64) {
     return (z0, z1, z2);
 }
 
+#[cfg(not(feature = "montgomery"))]
 #[inline(always)]
 fn mul_by_modulus(a: u64) -> (u64, u64, u64) {
     let a_lo = (a as u128).wrapping_mul(M);
@@ -305,6 +307,7 @@ fn mul_by_modulus(a: u64) -> (u64, u64, u64) {
     return (a_lo as u64, (a_lo >> 64) as u64, a_hi);
 }
 
+#[cfg(not(feature = "montgomery"))]
 #[inline(always)]
 fn sub_modulus(a_lo: u64, a_hi: u64) -> (u64, u64) {
     let mut z = 0u128.wrapping_sub(M);
@@ -0,0 +1,409 @@
+use std::{ convert::TryFrom, ops::Range };
+use rand::prelude::*;
+use rand::distributions::{ Distribution, Uniform };
+use serde::{ Serialize, Deserialize };
+
+// CONSTANTS
+// ================================================================================================
+
+// Field modulus = 2^64 - 2^32 + 1
+pub const M: u64 = 18446744069414584321;
+
+// 2^32 root of unity
+pub const G: u64 = 1753635133440165772;
+
+// public constants
+pub const MODULUS: u64 = M;
+pub const RANGE: Range<u64> = Range { start: 0, end: M };
+
+pub const ZERO: u64 = 0;
+pub const ONE: u64 = 1;
+
+// non-residue used to define the quadratic extension: x^2 = W
+const W: u64 = 7;
+
+// 2^32 - 1 = 2^64 mod m
+const EPSILON: u64 = 0xFFFFFFFF;
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Element of the field with modulus 2^64 - 2^32 + 1; the inner value is always a valid field
+/// element (i.e. it is smaller than the modulus). Arithmetic on this type is provided by the
+/// `FieldElement` trait.
+#[repr(transparent)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "u64", into = "u64")]
+pub struct BaseElement(u64);
+
+/// Element of the quadratic extension of the 64-bit field; the element is a0 + a1 * x, where
+/// x^2 = 7. Arithmetic on this type is provided by the `FieldElement` trait.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "[u64; 2]", into = "[u64; 2]")]
+pub struct QuadExtension(BaseElement, BaseElement);
+
+// BASIC ARITHMETIC
+// ================================================================================================
+
+/// Computes (a + b) % m; a and b are assumed to be valid field elements.
+pub fn add(a: u64, b: u64) -> u64 {
+    let (z, overflow) = a.overflowing_add(b);
+    // if the sum overflowed 64 bits, z + 2^64 - m = z + EPSILON fits into 64 bits
+    return if overflow || z >= M { z.wrapping_sub(M) } else { z };
+}
+
+/// Computes (a - b) % m; a and b are assumed to be valid field elements.
+pub fn sub(a: u64, b: u64) -> u64 {
+    return if a < b { a.wrapping_sub(b).wrapping_add(M) } else { a - b };
+}
+
+/// Computes (a * b) % m; a and b are assumed to be valid field elements.
+pub fn mul(a: u64, b: u64) -> u64 {
+    return reduce((a as u128) * (b as u128));
+}
+
+/// Computes y such that (x * y) % m = 1; x is assumed to be a valid field element.
+pub fn inv(x: u64) -> u64 {
+    if x == 0 { return 0; }
+    return exp(x, M - 2);
+}
+
+/// Computes (b^p) % m; b is assumed to be a valid field element.
+pub fn exp(b: u64, p: u64) -> u64 {
+    if b == 0 { return 0; }
+    else if p == 0 { return 1; }
+
+    let mut r = 1;
+    let mut b = b;
+    let mut p = p;
+    while p > 0 {
+        if p & 1 == 1 {
+            r = mul(r, b);
+        }
+        p = p >> 1;
+        b = mul(b, b);
+    }
+
+    return r;
+}
+
+/// Computes (0 - x) % m; x is assumed to be a valid field element.
+pub fn neg(x: u64) -> u64 {
+    return sub(ZERO, x);
+}
+
+// ROOT OF UNITY
+// ================================================================================================
+pub fn get_root_of_unity(order: usize) -> u64 {
+    assert!(order != 0, "cannot get root of unity for order 0");
+    assert!(order.is_power_of_two(), "order must be a power of 2");
+    assert!(order.trailing_zeros() <= 32, "order cannot exceed 2^32");
+    let p = 1u64 << (32 - order.trailing_zeros());
+    return exp(G, p);
+}
+
+// RANDOMNESS
+// ================================================================================================
+
+/// Generates a random field element.
+pub fn rand() -> u64 {
+    let range = Uniform::from(RANGE);
+    return thread_rng().sample(range);
+}
+
+/// Generates a pseudo-random field element from a given `seed`.
+pub fn prng(seed: [u8; 32]) -> u64 {
+    let range = Uniform::from(RANGE);
+    return range.sample(&mut StdRng::from_seed(seed));
+}
+
+// QUADRATIC EXTENSION
+// ================================================================================================
+
+/// Computes a + b in the quadratic extension field.
+pub fn ext_add(a: [u64; 2], b: [u64; 2]) -> [u64; 2] {
+    return [add(a[0], b[0]), add(a[1], b[1])];
+}
+
+/// Computes a - b in the quadratic extension field.
+pub fn ext_sub(a: [u64; 2], b: [u64; 2]) -> [u64; 2] {
+    return [sub(a[0], b[0]), sub(a[1], b[1])];
+}
+
+/// Computes a * b in the quadratic extension field as
+/// (a0 + a1 * x) * (b0 + b1 * x) = (a0 * b0 + W * a1 * b1) + (a0 * b1 + a1 * b0) * x.
+pub fn ext_mul(a: [u64; 2], b: [u64; 2]) -> [u64; 2] {
+    let a0b0 = mul(a[0], b[0]);
+    let a1b1 = mul(a[1], b[1]);
+    let a0b1 = mul(a[0], b[1]);
+    let a1b0 = mul(a[1], b[0]);
+    return [add(a0b0, mul(W, a1b1)), add(a0b1, a1b0)];
+}
+
+/// Computes y such that x * y = 1 in the quadratic extension field; this relies on
+/// (a0 + a1 * x) * (a0 - a1 * x) = a0^2 - W * a1^2 being an element of the base field.
+pub fn ext_inv(x: [u64; 2]) -> [u64; 2] {
+    if x == [ZERO, ZERO] { return x; }
+    let norm = sub(mul(x[0], x[0]), mul(W, mul(x[1], x[1])));
+    let norm_inv = inv(norm);
+    return [mul(x[0], norm_inv), neg(mul(x[1], norm_inv))];
+}
+
+// BASE ELEMENT IMPLEMENTATION
+// ================================================================================================
+impl BaseElement {
+
+    /// Returns a field element for the specified `value` reduced by the field modulus.
+    pub fn new(value: u64) -> BaseElement {
+        return BaseElement(if value >= M { value - M } else { value });
+    }
+
+    /// Returns the integer representation of this element.
+    pub fn as_u64(&self) -> u64 {
+        return self.0;
+    }
+
+    /// Returns a field element for the `value` which is known to be smaller than the modulus.
+    pub(crate) const fn from_canonical(value: u64) -> BaseElement {
+        return BaseElement(value);
+    }
+}
+
+impl TryFrom<u64> for BaseElement {
+    type Error = String;
+
+    fn try_from(value: u64) -> Result<BaseElement, String> {
+        if value >= M {
+            return Err(format!("{} is not a valid field element", value));
+        }
+        return Ok(BaseElement(value));
+    }
+}
+
+impl From<BaseElement> for u64 {
+    fn from(element: BaseElement) -> u64 {
+        return element.0;
+    }
+}
+
+// QUADRATIC EXTENSION IMPLEMENTATION
+// ================================================================================================
+impl QuadExtension {
+
+    /// Returns the element a0 + a1 * x.
+    pub fn new(a0: BaseElement, a1: BaseElement) -> QuadExtension {
+        return QuadExtension(a0, a1);
+    }
+
+    /// Returns the element of the extension field equal to the specified base field element.
+    pub fn from_base(a0: BaseElement) -> QuadExtension {
+        return QuadExtension(a0, BaseElement(ZERO));
+    }
+
+    /// Returns coefficients [a0, a1] of this element.
+    pub fn to_base_elements(&self) -> [BaseElement; 2] {
+        return [self.0, self.1];
+    }
+
+    pub(crate) fn as_u64s(&self) -> [u64; 2] {
+        return [self.0.0, self.1.0];
+    }
+
+    /// Returns the element a0 + a1 * x for `a0` and `a1` which are known to be smaller than
+    /// the modulus.
+    pub(crate) const fn from_canonical(a0: u64, a1: u64) -> QuadExtension {
+        return QuadExtension(BaseElement(a0), BaseElement(a1));
+    }
+}
+
+impl TryFrom<[u64; 2]> for QuadExtension {
+    type Error = String;
+
+    fn try_from(values: [u64; 2]) -> Result<QuadExtension, String> {
+        return Ok(QuadExtension(BaseElement::try_from(values[0])?, BaseElement::try_from(values[1])?));
+    }
+}
+
+impl From<QuadExtension> for [u64; 2] {
+    fn from(element: QuadExtension) -> [u64; 2] {
+        return element.as_u64s();
+    }
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Reduces a 128-bit value modulo m using 2^64 = 2^32 - 1 and 2^96 = -1 (mod m).
+#[inline(always)]
+fn reduce(x: u128) -> u64 {
+    let x_lo = x as u64;
+    let x_hi = (x >> 64) as u64;
+    let x_hi_hi = x_hi >> 32;
+    let x_hi_lo = x_hi & EPSILON;
+
+    // t0 = x_lo - x_hi_hi; on underflow add m, which is the same as subtracting EPSILON
+    let (mut t0, borrow) = x_lo.overflowing_sub(x_hi_hi);
+    if borrow { t0 = t0.wrapping_sub(EPSILON); }
+
+    // t1 = x_hi_lo * (2^32 - 1); this cannot overflow 64 bits
+    let t1 = x_hi_lo * EPSILON;
+
+    // z = t0 + t1; on overflow add 2^64 mod m = EPSILON
+    let (mut z, carry) = t0.overflowing_add(t1);
+    if carry { z = z.wrapping_add(EPSILON); }
+
+    return if z >= M { z - M } else { z };
+}
+
+// TESTS
+// ================================================================================================
+#[cfg(test)]
+mod tests {
+
+    use std::convert::TryFrom;
+    use num_bigint::{ BigUint };
+    use super::{ BaseElement, QuadExtension };
+
+    #[test]
+    fn add() {
+        // identity
+        let r = super::rand();
+        assert_eq!(r, super::add(r, 0));
+
+        // test addition within bounds
+        assert_eq!(5, super::add(2, 3));
+
+        // test overflow
+        let t = super::M - 1;
+        assert_eq!(0, super::add(t, 1));
+        assert_eq!(1, super::add(t, 2));
+        assert_eq!(super::M - 2, super::add(t, t));
+
+        // test random values
+        for _ in 0..1000 {
+            let r1 = super::rand();
+            let r2 = super::rand();
+            assert_eq!(to_u64((BigUint::from(r1) + BigUint::from(r2)) % BigUint::from(super::M)), super::add(r1, r2));
+        }
+    }
+
+    #[test]
+    fn sub() {
+        // identity
+        let r = super::rand();
+        assert_eq!(r, super::sub(r, 0));
+
+        // test subtraction within bounds
+        assert_eq!(2, super::sub(5, 3));
+
+        // test underflow
+        assert_eq!(super::M - 2, super::sub(3, 5));
+    }
+
+    #[test]
+    fn mul() {
+        // identity
+        let r = super::rand();
+        assert_eq!(0, super::mul(r, 0));
+        assert_eq!(r, super::mul(r, 1));
+
+        // test multiplication within bounds
+        assert_eq!(15, super::mul(5, 3));
+
+        // test overflow
+        let t = super::M - 1;
+        assert_eq!(1, super::mul(t, t));
+        assert_eq!(super::M - 2, super::mul(t, 2));
+        assert_eq!(super::M - 4, super::mul(t, 4));
+
+        let t = (super::M + 1) / 2;
+        assert_eq!(1, super::mul(t, 2));
+
+        // test random values
+        for _ in 0..1000 {
+            let r1 = super::rand();
+            let r2 = super::rand();
+            let expected = to_u64((BigUint::from(r1) * BigUint::from(r2)) % BigUint::from(super::M));
+            assert_eq!(expected, super::mul(r1, r2), "failed for: {} * {}", r1, r2);
+        }
+    }
+
+    #[test]
+    fn inv() {
+        // identity
+        assert_eq!(1, super::inv(1));
+        assert_eq!(0, super::inv(0));
+
+        // test random values
+        for _ in 0..1000 {
+            let x = super::rand();
+            assert_eq!(1, super::mul(x, super::inv(x)));
+        }
+    }
+
+    #[test]
+    fn get_root_of_unity() {
+        let root_32 = super::get_root_of_unity(usize::pow(2, 32));
+        assert_eq!(super::G, root_32);
+        assert_eq!(1, super::exp(root_32, u64::pow(2, 32)));
+        assert!(super::exp(root_32, u64::pow(2, 31)) != 1);
+
+        let root_31 = super::get_root_of_unity(usize::pow(2, 31));
+        assert_eq!(super::exp(root_32, 2), root_31);
+    }
+
+    #[test]
+    fn ext_mul() {
+        // identity
+        let r = rand_ext();
+        assert_eq!(r, super::ext_mul(r, [1, 0]));
+        assert_eq!([0, 0], super::ext_mul(r, [0, 0]));
+
+        // x * x = W
+        assert_eq!([super::W, 0], super::ext_mul([0, 1], [0, 1]));
+
+        // distributivity
+        let a = rand_ext();
+        let b = rand_ext();
+        let c = rand_ext();
+        assert_eq!(
+            super::ext_mul(a, super::ext_add(b, c)),
+            super::ext_add(super::ext_mul(a, b), super::ext_mul(a, c)));
+    }
+
+    #[test]
+    fn ext_inv() {
+        assert_eq!([0, 0], super::ext_inv([0, 0]));
+        assert_eq!([1, 0], super::ext_inv([1, 0]));
+
+        for _ in 0..100 {
+            let x = rand_ext();
+            assert_eq!([1, 0], super::ext_mul(x, super::ext_inv(x)));
+        }
+    }
+
+    #[test]
+    fn element_serialization() {
+        let x = BaseElement::new(super::M + 5);
+        assert_eq!(5, x.as_u64());
+        assert_eq!(x, bincode::deserialize(&bincode::serialize(&x).unwrap()).unwrap());
+        assert!(bincode::deserialize::<BaseElement>(&bincode::serialize(&super::M).unwrap()).is_err());
+        assert!(BaseElement::try_from(super::M).is_err());
+
+        let y = QuadExtension::new(x, BaseElement::new(7));
+        assert_eq!(y, bincode::deserialize(&bincode::serialize(&y).unwrap()).unwrap());
+        assert!(bincode::deserialize::<QuadExtension>(&bincode::serialize(&[1, super::M]).unwrap()).is_err());
+    }
+
+    fn rand_ext() -> [u64; 2] {
+        return [super::rand(), super::rand()];
+    }
+
+    fn to_u64(value: BigUint) -> u64 {
+        let bytes = value.to_bytes_le();
+        let mut result = [0u8; 8];
+        result[..bytes.len()].copy_from_slice(&bytes);
+        return u64::from_le_bytes(result);
+    }
+}
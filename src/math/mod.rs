@@ -1,5 +1,11 @@
 pub mod field;
+pub mod field64;
+pub mod montgomery;
 pub mod fft;
 pub mod polynom;
 pub mod quartic;
-pub mod parallel;
\ No newline at end of file
+pub mod parallel;
+
+mod element;
+pub use element::{ FieldElement };
+pub use field64::{ BaseElement, QuadExtension };
@@ -0,0 +1,174 @@
+use crate::math::field::{ M, add64_with_carry };
+
+// CONSTANTS
+// ================================================================================================
+
+// R = 2^128; values in Montgomery form are stored as x * R mod m
+const R1: u128 = 49478023249919;                    // R mod m
+const R2: u128 = 2448074784719525122733506561;      // R^2 mod m
+
+// -m^-1 mod 2^64
+const M_PRIME: u64 = 0xffffd2ffffffffff;
+
+const M0: u64 = M as u64;
+const M1: u64 = (M >> 64) as u64;
+
+// PUBLIC FUNCTIONS
+// ================================================================================================
+
+/// Computes (a * b) % m using Montgomery multiplication; a and b are assumed to be valid field
+/// elements in canonical form, and the result is returned in canonical form as well. This is
+/// the implementation of `field::mul()` when the `montgomery` feature is enabled.
+pub fn mul(a: u128, b: u128) -> u128 {
+    // mont_mul(a, b) = a * b / R; multiplying by R^2 and dividing by R again yields a * b
+    return mont_mul(mont_mul(a, b), R2);
+}
+
+/// Computes (b^p) % m; b is assumed to be a valid field element. All intermediate products
+/// are computed in Montgomery form, so only two conversions are needed per exponentiation.
+pub fn exp(b: u128, p: u128) -> u128 {
+    if b == 0 { return 0; }
+    else if p == 0 { return 1; }
+
+    let mut r = R1;
+    let mut b = to_montgomery(b);
+    let mut p = p;
+    while p > 0 {
+        if p & 1 == 1 {
+            r = mont_mul(r, b);
+        }
+        p = p >> 1;
+        b = mont_mul(b, b);
+    }
+
+    return from_montgomery(r);
+}
+
+/// Converts a field element from canonical form into Montgomery form.
+pub fn to_montgomery(x: u128) -> u128 {
+    return mont_mul(x, R2);
+}
+
+/// Converts a field element from Montgomery form into canonical form.
+pub fn from_montgomery(x: u128) -> u128 {
+    return mont_mul(x, 1);
+}
+
+/// Computes (a * b / 2^128) % m using the coarsely integrated operand scanning (CIOS) method
+/// over 64-bit limbs; a and b are assumed to be smaller than m.
+#[inline(always)]
+pub fn mont_mul(a: u128, b: u128) -> u128 {
+    let a0 = a as u64;
+    let a1 = (a >> 64) as u64;
+
+    // first iteration: t = (a * b0 + q * m) / 2^64
+    let (t0, t1, t2) = mul_128x64(a0, a1, b as u64);
+    let (t0, t1, t2) = reduce_step(t0, t1, t2, 0);
+
+    // second iteration: t = (t + a * b1 + q * m) / 2^64
+    let (u0, u1, u2) = mul_128x64(a0, a1, (b >> 64) as u64);
+    let (t0, carry) = add64_with_carry(t0, u0, 0);
+    let (t1, carry) = add64_with_carry(t1, u1, carry);
+    let (t2, t3) = add64_with_carry(t2, u2, carry);
+    let (t0, t1, t2) = reduce_step(t0, t1, t2, t3);
+
+    // the result is smaller than 2m, so at most one subtraction is needed
+    let t = ((t1 as u128) << 64) | (t0 as u128);
+    return if t2 != 0 || t >= M { t.wrapping_sub(M) } else { t };
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Computes a * b for a 128-bit a = (a0, a1) and a 64-bit b, returning a 192-bit result.
+#[inline(always)]
+fn mul_128x64(a0: u64, a1: u64, b: u64) -> (u64, u64, u64) {
+    let z_lo = (a0 as u128) * (b as u128);
+    let z_hi = (a1 as u128) * (b as u128) + (z_lo >> 64);
+    return (z_lo as u64, z_hi as u64, (z_hi >> 64) as u64);
+}
+
+/// Adds q * m to a 256-bit value t such that the lowest limb becomes 0, and returns the value
+/// shifted right by 64 bits.
+#[inline(always)]
+fn reduce_step(t0: u64, t1: u64, t2: u64, t3: u64) -> (u64, u64, u64) {
+    let q = t0.wrapping_mul(M_PRIME);
+    let (q0, q1, q2) = mul_128x64(M0, M1, q);
+    let (_, carry) = add64_with_carry(t0, q0, 0);
+    let (t1, carry) = add64_with_carry(t1, q1, carry);
+    let (t2, carry) = add64_with_carry(t2, q2, carry);
+    let (t3, _) = add64_with_carry(t3, 0, carry);
+    return (t1, t2, t3);
+}
+
+// TESTS
+// ================================================================================================
+#[cfg(test)]
+mod tests {
+
+    use std::convert::TryInto;
+    use num_bigint::{ BigUint };
+    use crate::math::field;
+
+    #[test]
+    fn mul() {
+        // edge cases
+        let m = field::MODULUS;
+        let edge_values = [0, 1, 2, m - 1, m - 2, (m + 1) / 2, 1u128 << 64, (1u128 << 64) - 1];
+        for &a in edge_values.iter() {
+            for &b in edge_values.iter() {
+                assert_eq!(expected_mul(a, b), super::mul(a, b), "failed for: {} * {}", a, b);
+            }
+        }
+
+        // randomized differential test against the default implementation and BigUint
+        let v1 = field::rand_vector(10000);
+        let v2 = field::rand_vector(10000);
+        for (&a, &b) in v1.iter().zip(v2.iter()) {
+            let expected = expected_mul(a, b);
+            assert_eq!(expected, super::mul(a, b), "failed for: {} * {}", a, b);
+            assert_eq!(expected, field::mul(a, b), "failed for: {} * {}", a, b);
+        }
+    }
+
+    #[test]
+    fn exp() {
+        assert_eq!(0, super::exp(0, 5));
+        assert_eq!(1, super::exp(5, 0));
+        assert_eq!(1, super::exp(field::get_root_of_unity(1 << 40), 1 << 40));
+
+        for _ in 0..100 {
+            let b = field::rand();
+            let p = field::rand();
+            let expected = BigUint::from(b).modpow(&BigUint::from(p), &BigUint::from(field::M));
+            assert_eq!(to_u128(expected), super::exp(b, p));
+        }
+    }
+
+    #[test]
+    fn montgomery_form() {
+        let x = field::rand_vector(1000);
+        for &a in x.iter() {
+            let a_mont = super::to_montgomery(a);
+            assert!(a_mont < field::M);
+            assert_eq!(a, super::from_montgomery(a_mont));
+        }
+
+        // products in Montgomery form should match canonical products
+        let a = field::rand();
+        let b = field::rand();
+        let ab_mont = super::mont_mul(super::to_montgomery(a), super::to_montgomery(b));
+        assert_eq!(expected_mul(a, b), super::from_montgomery(ab_mont));
+    }
+
+    fn expected_mul(a: u128, b: u128) -> u128 {
+        return to_u128((BigUint::from(a) * BigUint::from(b)) % BigUint::from(field::M));
+    }
+
+    fn to_u128(value: BigUint) -> u128 {
+        let bytes = value.to_bytes_le();
+        let mut result = [0u8; 16];
+        result[..bytes.len()].copy_from_slice(&bytes);
+        return u128::from_le_bytes(result[..].try_into().unwrap());
+    }
+}
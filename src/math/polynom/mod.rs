@@ -1,17 +1,20 @@
 use std::mem;
-use crate::math::{ field, fft };
+use crate::math::{ fft, FieldElement };
 use crate::utils::{ uninit_vector, filled_vector };
 
+mod polynomial;
+pub use polynomial::{ Polynomial };
+
 // POLYNOMIAL EVALUATION
 // ================================================================================================
 
 /// Evaluates polynomial `p` at coordinate `x`
-pub fn eval(p: &[u128], x: u128) -> u128 {
-    let mut y = field::ZERO;
-    let mut power_of_x = field::ONE;
+pub fn eval<E: FieldElement>(p: &[E], x: E) -> E {
+    let mut y = E::ZERO;
+    let mut power_of_x = E::ONE;
     for i in 0..p.len() {
-        y = field::add(y, field::mul(p[i], power_of_x));
-        power_of_x = field::mul(power_of_x, x);
+        y = E::add(y, E::mul(p[i], power_of_x));
+        power_of_x = E::mul(power_of_x, x);
     }
     return y;
 }
@@ -20,10 +23,13 @@ pub fn eval(p: &[u128], x: u128) -> u128 {
 /// `p` is updated with results of the evaluation.
 /// 
 /// If `unpermute` parameter is set to false, the evaluations will be left in permuted state.
-pub fn eval_fft(p: &mut [u128], unpermute: bool) {
-    let g = field::get_root_of_unity(p.len());
-    let twiddles = fft::get_twiddles(g, p.len());
-    eval_fft_twiddles(p, &twiddles, unpermute);
+/// Twiddles are taken from the plan returned by `fft::FftPlan::shared()`, and the evaluation
+/// is done in as many threads as the plan supports.
+pub fn eval_fft<E: FieldElement>(p: &mut [E], unpermute: bool) {
+    fft::FftPlan::shared(p.len()).evaluate(p, E::ONE);
+    if !unpermute {
+        fft::permute(p);
+    }
 }
 
 /// Evaluates polynomial `p` using FFT algorithm; the evaluation is done in-place, meaning
@@ -31,7 +37,7 @@ pub fn eval_fft(p: &mut [u128], unpermute: bool) {
 /// does not generate twiddles internally. Thus, the twiddles must be supplied as a parameter.
 /// 
 /// If `unpermute` parameter is set to false, the evaluations will be left in permuted state.
-pub fn eval_fft_twiddles(p: &mut [u128], twiddles: &[u128], unpermute: bool) {
+pub fn eval_fft_twiddles<E: FieldElement>(p: &mut [E], twiddles: &[E], unpermute: bool) {
     debug_assert!(p.len() == twiddles.len() * 2, "Invalid number of twiddles");
     // TODO: don't hard-code num_threads
     fft::fft_in_place(p, &twiddles, 1, 1, 0, 1);
@@ -40,33 +46,44 @@ pub fn eval_fft_twiddles(p: &mut [u128], twiddles: &[u128], unpermute: bool) {
     }
 }
 
+/// Evaluates polynomial `p` over the coset of the FFT domain shifted by `offset`, i.e. at points
+/// offset * g^i; the evaluation is done in-place, meaning `p` is updated with results of the
+/// evaluation. Twiddles for the (unshifted) domain must be supplied as a parameter.
+/// 
+/// If `unpermute` parameter is set to false, the evaluations will be left in permuted state.
+pub fn eval_fft_coset<E: FieldElement>(p: &mut [E], offset: E, twiddles: &[E], unpermute: bool) {
+    // p(offset * x) is a polynomial with coefficients p_i * offset^i
+    scale_coefficients(p, offset);
+    eval_fft_twiddles(p, twiddles, unpermute);
+}
+
 // POLYNOMIAL INTERPOLATION
 // ================================================================================================
 
 /// Uses Lagrange interpolation to build a polynomial from X and Y coordinates.
-pub fn interpolate(xs: &[u128], ys: &[u128]) -> Vec<u128> {
+pub fn interpolate<E: FieldElement>(xs: &[E], ys: &[E]) -> Vec<E> {
     debug_assert!(xs.len() == ys.len(), "Number of X and Y coordinates must be the same");
 
     let roots = get_zero_roots(xs);
-    let mut divisor = [field::ZERO, field::ONE];
-    let mut numerators: Vec<Vec<u128>> = Vec::with_capacity(xs.len());
+    let mut divisor = [E::ZERO, E::ONE];
+    let mut numerators: Vec<Vec<E>> = Vec::with_capacity(xs.len());
     for i in 0..xs.len() {
-        divisor[0] = field::neg(xs[i]);
+        divisor[0] = E::neg(xs[i]);
         numerators.push(div(&roots, &divisor));
     }
 
-    let mut denominators: Vec<u128> = Vec::with_capacity(xs.len());
+    let mut denominators: Vec<E> = Vec::with_capacity(xs.len());
     for i in 0..xs.len() {
         denominators.push(eval(&numerators[i], xs[i]));
     }
-    let denominators = field::inv_many(&denominators);
+    let denominators = E::inv_many(&denominators);
 
-    let mut result = vec![field::ZERO; xs.len()];
+    let mut result = vec![E::ZERO; xs.len()];
     for i in 0..xs.len() {
-        let y_slice = field::mul(ys[i], denominators[i]);
+        let y_slice = E::mul(ys[i], denominators[i]);
         for j in 0..xs.len() {
-            if numerators[i][j] != field::ZERO && ys[i] != field::ZERO {
-                result[j] = field::add(result[j], field::mul(numerators[i][j], y_slice));
+            if numerators[i][j] != E::ZERO && ys[i] != E::ZERO {
+                result[j] = E::add(result[j], E::mul(numerators[i][j], y_slice));
             }
         }
     }
@@ -78,10 +95,13 @@ pub fn interpolate(xs: &[u128], ys: &[u128]) -> Vec<u128> {
 /// is done in-place, meaning `v` is updated with polynomial coefficients.
 /// 
 /// If `unpermute` parameter is set to false, the coefficients will be left in permuted state.
-pub fn interpolate_fft(v: &mut [u128], unpermute: bool) {
-    let g = field::get_root_of_unity(v.len());
-    let twiddles = fft::get_inv_twiddles(g, v.len());
-    interpolate_fft_twiddles(v, &twiddles, unpermute);
+/// Inverse twiddles are taken from the plan returned by `fft::FftPlan::shared()`, and the
+/// interpolation is done in as many threads as the plan supports.
+pub fn interpolate_fft<E: FieldElement>(v: &mut [E], unpermute: bool) {
+    fft::FftPlan::shared(v.len()).interpolate(v, E::ONE);
+    if !unpermute {
+        fft::permute(v);
+    }
 }
 
 /// Uses FFT algorithm to interpolate a polynomial from provided values `v`; the interpolation
@@ -90,70 +110,80 @@ pub fn interpolate_fft(v: &mut [u128], unpermute: bool) {
 /// must be supplied as a parameter.
 /// 
 /// If `unpermute` parameter is set to false, the evaluations will be left in permuted state.
-pub fn interpolate_fft_twiddles(v: &mut [u128], inv_twiddles: &[u128], unpermute: bool) {
+pub fn interpolate_fft_twiddles<E: FieldElement>(v: &mut [E], inv_twiddles: &[E], unpermute: bool) {
     // TODO: don't hard-code num_threads
     fft::fft_in_place(v, &inv_twiddles, 1, 1, 0, 1);
-    let inv_length = field::inv(v.len() as u128);
+    let inv_length = E::inv(E::from_u64(v.len() as u64));
     for e in v.iter_mut() {
-        *e = field::mul(*e, inv_length);
+        *e = E::mul(*e, inv_length);
     }
     if unpermute {
         fft::permute(v);
     }
 }
 
+/// Uses FFT algorithm to interpolate a polynomial from values `v` at points offset * g^i; the
+/// interpolation is done in-place, meaning `v` is updated with polynomial coefficients. Inverse
+/// twiddles for the (unshifted) domain must be supplied as a parameter.
+/// 
+/// The coefficients are always returned in natural order.
+pub fn interpolate_fft_coset<E: FieldElement>(v: &mut [E], offset: E, inv_twiddles: &[E]) {
+    interpolate_fft_twiddles(v, inv_twiddles, true);
+    scale_coefficients(v, E::inv(offset));
+}
+
 // POLYNOMIAL MATH OPERATIONS
 // ================================================================================================
 
 /// Adds polynomial `a` to polynomial `b`
-pub fn add(a: &[u128], b: &[u128]) -> Vec<u128> {
+pub fn add<E: FieldElement>(a: &[E], b: &[E]) -> Vec<E> {
     let result_len = std::cmp::max(a.len(), b.len());
     let mut result = Vec::with_capacity(result_len);
     for i in 0..result_len {
-        let c1 = if i < a.len() { a[i] } else { field::ZERO };
-        let c2 = if i < b.len() { b[i] } else { field::ZERO };
-        result.push(field::add(c1, c2));
+        let c1 = if i < a.len() { a[i] } else { E::ZERO };
+        let c2 = if i < b.len() { b[i] } else { E::ZERO };
+        result.push(E::add(c1, c2));
     }
     return result;
 }
 
 /// Subtracts polynomial `b` from polynomial `a`
-pub fn sub(a: &[u128], b: &[u128]) -> Vec<u128> {
+pub fn sub<E: FieldElement>(a: &[E], b: &[E]) -> Vec<E> {
     let result_len = std::cmp::max(a.len(), b.len());
     let mut result = Vec::with_capacity(result_len);
     for i in 0..result_len {
-        let c1 = if i < a.len() { a[i] } else { field::ZERO };
-        let c2 = if i < b.len() { b[i] } else { field::ZERO };
-        result.push(field::sub(c1, c2));
+        let c1 = if i < a.len() { a[i] } else { E::ZERO };
+        let c2 = if i < b.len() { b[i] } else { E::ZERO };
+        result.push(E::sub(c1, c2));
     }
     return result;
 }
 
 /// Multiplies polynomial `a` by polynomial `b`
-pub fn mul(a: &[u128], b: &[u128]) -> Vec<u128> {
+pub fn mul<E: FieldElement>(a: &[E], b: &[E]) -> Vec<E> {
     let result_len = a.len() + b.len() - 1;
-    let mut result = vec![field::ZERO; result_len];
+    let mut result = vec![E::ZERO; result_len];
     for i in 0..a.len() {
         for j in 0..b.len() {
-            let s = field::mul(a[i], b[j]);
-            result[i + j] = field::add(result[i + j], s);
+            let s = E::mul(a[i], b[j]);
+            result[i + j] = E::add(result[i + j], s);
         }
     }
     return result;
 }
 
 /// Multiplies every coefficient of polynomial `p` by constant `k`
-pub fn mul_by_const(p: &[u128], k: u128) -> Vec<u128> {
+pub fn mul_by_const<E: FieldElement>(p: &[E], k: E) -> Vec<E> {
     let mut result = Vec::with_capacity(p.len());
     for i in 0..p.len() {
-        result.push(field::mul(p[i], k));
+        result.push(E::mul(p[i], k));
     }
     return result;
 }
 
 /// Divides polynomial `a` by polynomial `b`; if the polynomials don't divide evenly,
 /// the remainder is ignored.
-pub fn div(a: &[u128], b: &[u128]) -> Vec<u128> {
+pub fn div<E: FieldElement>(a: &[E], b: &[E]) -> Vec<E> {
     
     let mut apos = degree_of(a);
     let mut a = a.to_vec();
@@ -161,15 +191,15 @@ pub fn div(a: &[u128], b: &[u128]) -> Vec<u128> {
     let bpos = degree_of(b);
     assert!(apos >= bpos, "cannot divide by polynomial of higher degree");
     if bpos == 0 {
-        assert!(b[0] != field::ZERO, "cannot divide polynomial by zero");
+        assert!(b[0] != E::ZERO, "cannot divide polynomial by zero");
     }
 
-    let mut result = vec![field::ZERO; apos - bpos + 1];
+    let mut result = vec![E::ZERO; apos - bpos + 1];
     for i in (0..result.len()).rev() {
-        let quot = field::div(a[apos], b[bpos]);
+        let quot = E::div(a[apos], b[bpos]);
         result[i] = quot;
         for j in (0..bpos).rev() {
-            a[i + j] = field::sub(a[i + j], field::mul(b[j], quot));
+            a[i + j] = E::sub(a[i + j], E::mul(b[j], quot));
         }
         apos = apos.wrapping_sub(1);
     }
@@ -179,7 +209,7 @@ pub fn div(a: &[u128], b: &[u128]) -> Vec<u128> {
 
 /// Divides polynomial `a` by binomial (x - `b`) using Synthetic division method;
 /// if the polynomials don't divide evenly, the remainder is ignored.
-pub fn syn_div(a: &[u128], b: u128) -> Vec<u128> {
+pub fn syn_div<E: FieldElement>(a: &[E], b: E) -> Vec<E> {
     let mut result = a.to_vec();
     syn_div_in_place(&mut result, b);
     return result;
@@ -187,10 +217,10 @@ pub fn syn_div(a: &[u128], b: u128) -> Vec<u128> {
 
 /// Divides polynomial `a` by binomial (x - `b`) using Synthetic division method and stores the
 /// result in `a`; if the polynomials don't divide evenly, the remainder is ignored.
-pub fn syn_div_in_place(a: &mut [u128], b: u128) {
-    let mut c = field::ZERO;
+pub fn syn_div_in_place<E: FieldElement>(a: &mut [E], b: E) {
+    let mut c = E::ZERO;
     for i in (0..a.len()).rev() {
-        let temp = field::add(a[i], field::mul(b, c));
+        let temp = E::add(a[i], E::mul(b, c));
         a[i] = c;
         c = temp;
     }
@@ -199,31 +229,31 @@ pub fn syn_div_in_place(a: &mut [u128], b: u128) {
 /// Divides polynomial `a` by polynomial (x^degree - 1) / (x - exceptions[i]) for all i using
 /// Synthetic division method and stores the result in `a`; if the polynomials don't divide evenly,
 /// the remainder is ignored.
-pub fn syn_div_expanded_in_place(a: &mut [u128], degree: usize, exceptions: &[u128]) {
+pub fn syn_div_expanded_in_place<E: FieldElement>(a: &mut [E], degree: usize, exceptions: &[E]) {
 
     // allocate space for the result
-    let mut result = filled_vector(a.len(), a.len() + exceptions.len(), field::ZERO);
+    let mut result = filled_vector(a.len(), a.len() + exceptions.len(), E::ZERO);
 
     // compute a / (x^degree - 1)
     result.copy_from_slice(&a);
     let degree_offset = a.len() - degree;
     for i in (0..degree_offset).rev() {
-        result[i] = field::add(result[i], result[i + degree]);
+        result[i] = E::add(result[i], result[i + degree]);
     }
 
     // multiply result by (x - exceptions[i]) in place
     for &exception in exceptions {
 
         // exception term is negative
-        let exception = field::neg(exception);
+        let exception = E::neg(exception);
 
         // extend length of result since we are raising degree
         unsafe { result.set_len(result.len() + 1); }
 
         let mut next_term = result[0];
-        result[0] = field::ZERO;
+        result[0] = E::ZERO;
         for i in 0..(result.len() - 1) {
-            result[i] = field::add(result[i], field::mul(next_term, exception));
+            result[i] = E::add(result[i], E::mul(next_term, exception));
             mem::swap(&mut next_term, &mut result[i + 1]);
         }
     }
@@ -232,23 +262,23 @@ pub fn syn_div_expanded_in_place(a: &mut [u128], degree: usize, exceptions: &[u1
     a[..(degree_offset + exceptions.len())].copy_from_slice(&result[degree..]);
 
     // fill the rest of the result with 0
-    for i in (degree_offset + exceptions.len())..a.len() { a[i] = field::ZERO; }
+    for i in (degree_offset + exceptions.len())..a.len() { a[i] = E::ZERO; }
 }
 
 // DEGREE INFERENCE
 // ================================================================================================
 
 /// Returns degree of the polynomial `poly`
-pub fn degree_of(poly: &[u128]) -> usize {
+pub fn degree_of<E: FieldElement>(poly: &[E]) -> usize {
     for i in (0..poly.len()).rev() {
-        if poly[i] != field::ZERO { return i; }
+        if poly[i] != E::ZERO { return i; }
     }
     return 0;
 }
 
 /// Returns degree of a polynomial with which evaluates to `evaluations` over the domain of
 /// corresponding roots of unity.
-pub fn infer_degree(evaluations: &[u128]) -> usize {
+pub fn infer_degree<E: FieldElement>(evaluations: &[E]) -> usize {
     assert!(evaluations.len().is_power_of_two(), "number of evaluations must be a power of 2");
     let mut poly = evaluations.to_vec();
     interpolate_fft(&mut poly, true);
@@ -257,18 +287,28 @@ pub fn infer_degree(evaluations: &[u128]) -> usize {
 
 // HELPER FUNCTIONS
 // ================================================================================================
-fn get_zero_roots(xs: &[u128]) -> Vec<u128> {
+
+/// Multiplies coefficient i of polynomial `p` by offset^i.
+fn scale_coefficients<E: FieldElement>(p: &mut [E], offset: E) {
+    let mut factor = E::ONE;
+    for coefficient in p.iter_mut() {
+        *coefficient = E::mul(*coefficient, factor);
+        factor = E::mul(factor, offset);
+    }
+}
+
+fn get_zero_roots<E: FieldElement>(xs: &[E]) -> Vec<E> {
     let mut n = xs.len() + 1;
     let mut result = uninit_vector(n);
     
     n -= 1;
-    result[n] = field::ONE;
+    result[n] = E::ONE;
 
     for i in 0..xs.len() {
         n -= 1;
-        result[n] = field::ZERO;
+        result[n] = E::ZERO;
         for j in n..xs.len() {
-            result[j] = field::sub(result[j], field::mul(result[j + 1], xs[i]));
+            result[j] = E::sub(result[j], E::mul(result[j + 1], xs[i]));
         }
     }
 
@@ -280,7 +320,7 @@ fn get_zero_roots(xs: &[u128]) -> Vec<u128> {
 #[cfg(test)]
 mod tests {
 
-    use crate::math::{ field };
+    use crate::math::{ field, fft, FieldElement, BaseElement, QuadExtension };
     use crate::utils::remove_leading_zeros;
 
     #[test]
@@ -330,6 +370,47 @@ mod tests {
         assert_eq!(y1, y2);
     }
 
+    #[test]
+    fn eval_interpolate_fft_coset() {
+        let n: usize = 64;
+        let poly = field::rand_vector(n / 2);
+        let offset = field::GENERATOR;
+
+        // evaluate polynomial over the coset and compare with simple evaluation
+        let root = field::get_root_of_unity(n);
+        let mut ys = poly.clone();
+        ys.resize(n, 0);
+        super::eval_fft_coset(&mut ys, offset, &fft::get_twiddles(root, n), true);
+        let domain = fft::get_coset_domain(root, offset, n);
+        assert_eq!(domain.iter().map(|&x| super::eval(&poly, x)).collect::<Vec<u128>>(), ys);
+
+        // interpolate evaluations back into the original polynomial
+        super::interpolate_fft_coset(&mut ys, offset, &fft::get_inv_twiddles(root, n));
+        assert_eq!(poly, remove_leading_zeros(&ys));
+    }
+
+    #[test]
+    fn eval_interpolate_fft_64() {
+        let n: usize = 256;
+        let poly = BaseElement::rand_vector(n);
+
+        // evaluate polynomial using FFT and compare with simple evaluation
+        let mut ys = poly.clone();
+        super::eval_fft(&mut ys, true);
+        let roots = BaseElement::get_power_series(BaseElement::get_root_of_unity(n), n);
+        assert_eq!(roots.iter().map(|&x| super::eval(&poly, x)).collect::<Vec<BaseElement>>(), ys);
+
+        // interpolate evaluations back into the original polynomial
+        super::interpolate_fft(&mut ys, true);
+        assert_eq!(poly, ys);
+
+        // Lagrange interpolation in the extension field
+        let xs = QuadExtension::rand_vector(8);
+        let ys = QuadExtension::rand_vector(8);
+        let poly = super::interpolate(&xs, &ys);
+        assert_eq!(ys, xs.iter().map(|&x| super::eval(&poly, x)).collect::<Vec<QuadExtension>>());
+    }
+
     #[test]
     fn add() {
         let poly1: [u128; 3] = [384863712573444386, 7682273369345308472, 13294661765012277990];
@@ -491,12 +572,12 @@ mod tests {
 
     #[test]
     fn degree_of() {
-        assert_eq!(0, super::degree_of(&[]));
-        assert_eq!(0, super::degree_of(&[1]));
-        assert_eq!(1, super::degree_of(&[1, 2]));
-        assert_eq!(1, super::degree_of(&[1, 2, 0]));
-        assert_eq!(2, super::degree_of(&[1, 2, 3]));
-        assert_eq!(2, super::degree_of(&[1, 2, 3, 0]));
+        assert_eq!(0, super::degree_of::<u128>(&[]));
+        assert_eq!(0, super::degree_of::<u128>(&[1]));
+        assert_eq!(1, super::degree_of::<u128>(&[1, 2]));
+        assert_eq!(1, super::degree_of::<u128>(&[1, 2, 0]));
+        assert_eq!(2, super::degree_of::<u128>(&[1, 2, 3]));
+        assert_eq!(2, super::degree_of::<u128>(&[1, 2, 3, 0]));
     }
 
     #[test]
@@ -0,0 +1,475 @@
+use std::ops::{ Add, Sub, Mul, Div, Rem, Neg, AddAssign, SubAssign };
+use crate::math::{ fft, FieldElement };
+use super::{ eval, interpolate, degree_of, syn_div_in_place };
+
+// CONSTANTS
+// ================================================================================================
+
+/// Polynomials with fewer coefficients than this are multiplied using the schoolbook method;
+/// larger polynomials are multiplied by evaluating them over a domain of roots of unity.
+const FFT_MUL_THRESHOLD: usize = 64;
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// A polynomial in coefficient form; coefficient i is the coefficient of x^i. Trailing zero
+/// coefficients are allowed and are retained by arithmetic operations, so the number of
+/// coefficients of a result is not always degree + 1.
+#[derive(Clone, Debug)]
+pub struct Polynomial<E: FieldElement> {
+    coefficients: Vec<E>,
+}
+
+// POLYNOMIAL IMPLEMENTATION
+// ================================================================================================
+impl <E: FieldElement> Polynomial<E> {
+
+    /// Returns a polynomial with the specified coefficients.
+    pub fn new(coefficients: Vec<E>) -> Polynomial<E> {
+        return Polynomial { coefficients };
+    }
+
+    /// Returns a polynomial which evaluates to zero everywhere.
+    pub fn zero() -> Polynomial<E> {
+        return Polynomial { coefficients: Vec::new() };
+    }
+
+    /// Returns polynomial x - `root`.
+    pub fn linear(root: E) -> Polynomial<E> {
+        return Polynomial { coefficients: vec![E::neg(root), E::ONE] };
+    }
+
+    /// Uses Lagrange interpolation to build a polynomial from X and Y coordinates.
+    pub fn interpolate(xs: &[E], ys: &[E]) -> Polynomial<E> {
+        assert!(xs.len() == ys.len(), "number of X and Y coordinates must be the same");
+        return Polynomial { coefficients: interpolate(xs, ys) };
+    }
+
+    // PUBLIC ACCESSORS
+    // --------------------------------------------------------------------------------------------
+
+    /// Returns coefficients of the polynomial, including trailing zeros (if any).
+    pub fn coefficients(&self) -> &[E] {
+        return &self.coefficients;
+    }
+
+    /// Consumes the polynomial and returns its coefficients.
+    pub fn into_coefficients(self) -> Vec<E> {
+        return self.coefficients;
+    }
+
+    /// Returns the number of coefficients of the polynomial, including trailing zeros.
+    pub fn len(&self) -> usize {
+        return self.coefficients.len();
+    }
+
+    /// Returns degree of the polynomial; degree of a zero polynomial is reported as 0.
+    pub fn degree(&self) -> usize {
+        return degree_of(&self.coefficients);
+    }
+
+    /// Returns `true` if all coefficients of the polynomial are zero.
+    pub fn is_zero(&self) -> bool {
+        return self.coefficients.iter().all(|&c| c == E::ZERO);
+    }
+
+    // EVALUATION
+    // --------------------------------------------------------------------------------------------
+
+    /// Evaluates the polynomial at coordinate `x`.
+    pub fn eval(&self, x: E) -> E {
+        return eval(&self.coefficients, x);
+    }
+
+    // ARITHMETIC
+    // --------------------------------------------------------------------------------------------
+
+    /// Returns this polynomial multiplied by x^`power`.
+    pub fn mul_by_x_pow(&self, power: usize) -> Polynomial<E> {
+        let mut coefficients = vec![E::ZERO; self.coefficients.len() + power];
+        coefficients[power..].copy_from_slice(&self.coefficients);
+        return Polynomial { coefficients };
+    }
+
+    /// Divides this polynomial by the `divisor` and returns the quotient and the remainder.
+    /// Division by a polynomial of degree 1 is done using synthetic division.
+    pub fn div_rem(&self, divisor: &Polynomial<E>) -> (Polynomial<E>, Polynomial<E>) {
+        assert!(!divisor.is_zero(), "cannot divide polynomial by zero");
+
+        let a_degree = self.degree();
+        let b_degree = divisor.degree();
+        if self.is_zero() || a_degree < b_degree {
+            return (Polynomial::zero(), self.clone());
+        }
+
+        let b_lead_inv = E::inv(divisor.coefficients[b_degree]);
+
+        // for divisors of the form c1 * x + c0, divide by (x + c0 / c1) and then by c1
+        if b_degree == 1 {
+            let root = E::neg(E::mul(divisor.coefficients[0], b_lead_inv));
+            let mut quotient = self.coefficients[..(a_degree + 1)].to_vec();
+            let remainder = eval(&quotient, root);
+            syn_div_in_place(&mut quotient, root);
+            quotient.truncate(a_degree);
+            for c in quotient.iter_mut() {
+                *c = E::mul(*c, b_lead_inv);
+            }
+            return (Polynomial::new(quotient), Polynomial::new(vec![remainder]));
+        }
+
+        // otherwise, use long division
+        let mut remainder = self.coefficients[..(a_degree + 1)].to_vec();
+        let mut quotient = vec![E::ZERO; a_degree - b_degree + 1];
+        for i in (0..quotient.len()).rev() {
+            let q = E::mul(remainder[i + b_degree], b_lead_inv);
+            quotient[i] = q;
+            for j in 0..(b_degree + 1) {
+                remainder[i + j] = E::sub(remainder[i + j], E::mul(divisor.coefficients[j], q));
+            }
+        }
+        remainder.truncate(b_degree);
+
+        return (Polynomial::new(quotient), Polynomial::new(remainder));
+    }
+}
+
+// EQUALITY
+// ================================================================================================
+
+/// Polynomials are equal if their coefficients are equal; trailing zeros are ignored.
+impl <E: FieldElement> PartialEq for Polynomial<E> {
+    fn eq(&self, other: &Polynomial<E>) -> bool {
+        let n = std::cmp::max(self.len(), other.len());
+        for i in 0..n {
+            let a = if i < self.len() { self.coefficients[i] } else { E::ZERO };
+            let b = if i < other.len() { other.coefficients[i] } else { E::ZERO };
+            if a != b { return false; }
+        }
+        return true;
+    }
+}
+
+impl <E: FieldElement> From<&[E]> for Polynomial<E> {
+    fn from(coefficients: &[E]) -> Polynomial<E> {
+        return Polynomial { coefficients: coefficients.to_vec() };
+    }
+}
+
+// POLYNOMIAL OPERATORS
+// ================================================================================================
+
+impl <E: FieldElement> Add for &Polynomial<E> {
+    type Output = Polynomial<E>;
+
+    fn add(self, rhs: &Polynomial<E>) -> Polynomial<E> {
+        let mut result = self.clone();
+        result += rhs;
+        return result;
+    }
+}
+
+impl <E: FieldElement> Sub for &Polynomial<E> {
+    type Output = Polynomial<E>;
+
+    fn sub(self, rhs: &Polynomial<E>) -> Polynomial<E> {
+        let mut result = self.clone();
+        result -= rhs;
+        return result;
+    }
+}
+
+impl <E: FieldElement> Mul for &Polynomial<E> {
+    type Output = Polynomial<E>;
+
+    fn mul(self, rhs: &Polynomial<E>) -> Polynomial<E> {
+        if self.len() == 0 || rhs.len() == 0 {
+            return Polynomial::zero();
+        }
+
+        if self.len() < FFT_MUL_THRESHOLD || rhs.len() < FFT_MUL_THRESHOLD {
+            return Polynomial::new(super::mul(&self.coefficients, &rhs.coefficients));
+        }
+        return Polynomial::new(mul_fft(&self.coefficients, &rhs.coefficients));
+    }
+}
+
+impl <E: FieldElement> Div for &Polynomial<E> {
+    type Output = Polynomial<E>;
+
+    fn div(self, rhs: &Polynomial<E>) -> Polynomial<E> {
+        return self.div_rem(rhs).0;
+    }
+}
+
+impl <E: FieldElement> Rem for &Polynomial<E> {
+    type Output = Polynomial<E>;
+
+    fn rem(self, rhs: &Polynomial<E>) -> Polynomial<E> {
+        return self.div_rem(rhs).1;
+    }
+}
+
+impl <E: FieldElement> Neg for Polynomial<E> {
+    type Output = Polynomial<E>;
+
+    fn neg(mut self) -> Polynomial<E> {
+        for c in self.coefficients.iter_mut() {
+            *c = E::neg(*c);
+        }
+        return self;
+    }
+}
+
+impl <E: FieldElement> AddAssign<&Polynomial<E>> for Polynomial<E> {
+    fn add_assign(&mut self, rhs: &Polynomial<E>) {
+        if self.len() < rhs.len() {
+            self.coefficients.resize(rhs.len(), E::ZERO);
+        }
+        for (a, &b) in self.coefficients.iter_mut().zip(rhs.coefficients.iter()) {
+            *a = E::add(*a, b);
+        }
+    }
+}
+
+impl <E: FieldElement> SubAssign<&Polynomial<E>> for Polynomial<E> {
+    fn sub_assign(&mut self, rhs: &Polynomial<E>) {
+        if self.len() < rhs.len() {
+            self.coefficients.resize(rhs.len(), E::ZERO);
+        }
+        for (a, &b) in self.coefficients.iter_mut().zip(rhs.coefficients.iter()) {
+            *a = E::sub(*a, b);
+        }
+    }
+}
+
+// operators on owned polynomials delegate to the operators on references
+macro_rules! impl_owned_op {
+    ($trait:ident, $method:ident) => {
+        impl <E: FieldElement> $trait for Polynomial<E> {
+            type Output = Polynomial<E>;
+
+            fn $method(self, rhs: Polynomial<E>) -> Polynomial<E> {
+                return (&self).$method(&rhs);
+            }
+        }
+
+        impl <E: FieldElement> $trait<&Polynomial<E>> for Polynomial<E> {
+            type Output = Polynomial<E>;
+
+            fn $method(self, rhs: &Polynomial<E>) -> Polynomial<E> {
+                return (&self).$method(rhs);
+            }
+        }
+    };
+}
+
+impl_owned_op!(Add, add);
+impl_owned_op!(Sub, sub);
+impl_owned_op!(Mul, mul);
+impl_owned_op!(Div, div);
+impl_owned_op!(Rem, rem);
+
+// SCALAR OPERATORS
+// ================================================================================================
+
+/// Adds a constant to the polynomial.
+impl <E: FieldElement> Add<E> for Polynomial<E> {
+    type Output = Polynomial<E>;
+
+    fn add(mut self, rhs: E) -> Polynomial<E> {
+        if self.coefficients.is_empty() { self.coefficients.push(E::ZERO); }
+        self.coefficients[0] = E::add(self.coefficients[0], rhs);
+        return self;
+    }
+}
+
+/// Subtracts a constant from the polynomial.
+impl <E: FieldElement> Sub<E> for Polynomial<E> {
+    type Output = Polynomial<E>;
+
+    fn sub(mut self, rhs: E) -> Polynomial<E> {
+        if self.coefficients.is_empty() { self.coefficients.push(E::ZERO); }
+        self.coefficients[0] = E::sub(self.coefficients[0], rhs);
+        return self;
+    }
+}
+
+/// Multiplies every coefficient of the polynomial by a constant.
+impl <E: FieldElement> Mul<E> for Polynomial<E> {
+    type Output = Polynomial<E>;
+
+    fn mul(mut self, rhs: E) -> Polynomial<E> {
+        for c in self.coefficients.iter_mut() {
+            *c = E::mul(*c, rhs);
+        }
+        return self;
+    }
+}
+
+impl <E: FieldElement> Add<E> for &Polynomial<E> {
+    type Output = Polynomial<E>;
+
+    fn add(self, rhs: E) -> Polynomial<E> {
+        return self.clone() + rhs;
+    }
+}
+
+impl <E: FieldElement> Sub<E> for &Polynomial<E> {
+    type Output = Polynomial<E>;
+
+    fn sub(self, rhs: E) -> Polynomial<E> {
+        return self.clone() - rhs;
+    }
+}
+
+impl <E: FieldElement> Mul<E> for &Polynomial<E> {
+    type Output = Polynomial<E>;
+
+    fn mul(self, rhs: E) -> Polynomial<E> {
+        return self.clone() * rhs;
+    }
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Multiplies polynomials `a` and `b` by evaluating them over a domain of roots of unity large
+/// enough to hold the product, multiplying evaluations pointwise, and interpolating the result.
+fn mul_fft<E: FieldElement>(a: &[E], b: &[E]) -> Vec<E> {
+    let result_len = a.len() + b.len() - 1;
+    let domain_size = result_len.next_power_of_two();
+    let root = E::get_root_of_unity(domain_size);
+
+    let twiddles = fft::get_twiddles(root, domain_size);
+    let mut a_evaluations = vec![E::ZERO; domain_size];
+    a_evaluations[..a.len()].copy_from_slice(a);
+    super::eval_fft_twiddles(&mut a_evaluations, &twiddles, true);
+
+    let mut b_evaluations = vec![E::ZERO; domain_size];
+    b_evaluations[..b.len()].copy_from_slice(b);
+    super::eval_fft_twiddles(&mut b_evaluations, &twiddles, true);
+
+    for (a, &b) in a_evaluations.iter_mut().zip(b_evaluations.iter()) {
+        *a = E::mul(*a, b);
+    }
+
+    let inv_twiddles = fft::get_inv_twiddles(root, domain_size);
+    super::interpolate_fft_twiddles(&mut a_evaluations, &inv_twiddles, true);
+    a_evaluations.truncate(result_len);
+    return a_evaluations;
+}
+
+// TESTS
+// ================================================================================================
+#[cfg(test)]
+mod tests {
+
+    use crate::math::{ field, FieldElement, BaseElement };
+    use super::{ Polynomial, FFT_MUL_THRESHOLD };
+
+    #[test]
+    fn add_sub() {
+        let a = Polynomial::new(field::rand_vector(8));
+        let b = Polynomial::new(field::rand_vector(5));
+
+        let c = &a + &b;
+        assert_eq!(8, c.len());
+        let x = field::rand();
+        assert_eq!(field::add(a.eval(x), b.eval(x)), c.eval(x));
+
+        assert_eq!(a, &c - &b);
+        assert!((&a - &a).is_zero());
+        assert_eq!(Polynomial::zero(), a.clone() + (-a.clone()));
+
+        // scalar operations change only the constant term
+        let d = a.clone() - field::ONE;
+        assert_eq!(field::sub(a.eval(x), field::ONE), d.eval(x));
+        assert_eq!(a, d + field::ONE);
+    }
+
+    #[test]
+    fn mul() {
+        // small polynomials are multiplied using schoolbook method
+        let a = Polynomial::new(vec![1u128, 2, 3]);
+        let b = Polynomial::new(vec![4u128, 5]);
+        assert_eq!(Polynomial::new(vec![4u128, 13, 22, 15]), &a * &b);
+
+        // large polynomials are multiplied using FFT; results should match schoolbook method
+        check_mul_fft::<u128>(FFT_MUL_THRESHOLD, FFT_MUL_THRESHOLD + 3);
+        check_mul_fft::<u128>(200, 100);
+        check_mul_fft::<BaseElement>(128, 128);
+
+        // scalar multiplication
+        let k = field::rand();
+        let x = field::rand();
+        assert_eq!(field::mul(a.eval(x), k), (&a * k).eval(x));
+    }
+
+    #[test]
+    fn div_rem() {
+        let a = Polynomial::new(field::rand_vector(16));
+
+        // division by a linear polynomial
+        let b = Polynomial::new(vec![field::rand(), field::rand()]);
+        let (q, r) = a.div_rem(&b);
+        assert_eq!(14, q.degree());
+        assert_eq!(a, &(&q * &b) + &r);
+
+        let z = field::rand();
+        let q = (a.clone() - a.eval(z)) / Polynomial::linear(z);
+        assert!(((a.clone() - a.eval(z)) % Polynomial::linear(z)).is_zero());
+        assert_eq!(a.clone() - a.eval(z), q * Polynomial::linear(z));
+
+        // division by a higher-degree polynomial
+        let b = Polynomial::new(field::rand_vector(6));
+        let (q, r) = a.div_rem(&b);
+        assert_eq!(10, q.degree());
+        assert!(r.degree() < 5);
+        assert_eq!(a, &(&q * &b) + &r);
+        assert_eq!(q, &a / &b);
+        assert_eq!(r, &a % &b);
+
+        // dividing by a polynomial of higher degree returns a zero quotient
+        let (q, r) = b.div_rem(&a);
+        assert!(q.is_zero());
+        assert_eq!(b, r);
+
+        // the same should work for other fields
+        let a = Polynomial::new(BaseElement::rand_vector(12));
+        let b = Polynomial::new(BaseElement::rand_vector(4));
+        let (q, r) = a.div_rem(&b);
+        assert_eq!(a, &(&q * &b) + &r);
+    }
+
+    #[test]
+    fn interpolate() {
+        let xs = field::rand_vector(8);
+        let ys = field::rand_vector(8);
+        let p = Polynomial::interpolate(&xs, &ys);
+        assert!(p.degree() < 8);
+        for (&x, &y) in xs.iter().zip(ys.iter()) {
+            assert_eq!(y, p.eval(x));
+        }
+    }
+
+    #[test]
+    fn degree() {
+        assert_eq!(0, Polynomial::<u128>::zero().degree());
+        assert_eq!(2, Polynomial::new(vec![1u128, 2, 3, 0, 0]).degree());
+        assert_eq!(Polynomial::new(vec![1u128, 2, 3, 0, 0]), Polynomial::new(vec![1u128, 2, 3]));
+
+        let a = Polynomial::new(vec![1u128, 2, 3]);
+        let b = a.mul_by_x_pow(4);
+        assert_eq!(6, b.degree());
+        assert_eq!(&a * &Polynomial::new(vec![0u128, 0, 0, 0, 1]), b);
+    }
+
+    fn check_mul_fft<E: FieldElement>(a_len: usize, b_len: usize) {
+        let a = Polynomial::new(E::rand_vector(a_len));
+        let b = Polynomial::new(E::rand_vector(b_len));
+        let expected = super::super::mul(a.coefficients(), b.coefficients());
+        let actual = &a * &b;
+        assert_eq!(a_len + b_len - 1, actual.len());
+        assert_eq!(expected, actual.into_coefficients());
+    }
+}
@@ -6,7 +6,7 @@ use super::{ UserOps };
 fn start_block() {
     let mut decoder = super::Decoder::new(16);
     for _ in 0..15 { decoder.decode_op(UserOps::Noop, 0); }
-    decoder.start_block();
+    decoder.start_block().unwrap();
     for _ in 0..16 { decoder.decode_op(UserOps::Noop, 0); }
     decoder.end_block(0, true);
     for _ in 0..14 { decoder.decode_op(UserOps::Noop, 0); }
@@ -23,7 +23,7 @@ fn start_block() {
 fn start_loop() {
     let mut decoder = super::Decoder::new(16);
     for _ in 0..15 { decoder.decode_op(UserOps::Noop, 0); }
-    decoder.start_loop(34133582271386177291348118006257970896);
+    decoder.start_loop(34133582271386177291348118006257970896).unwrap();
     for _ in 0..15 { decoder.decode_op(UserOps::Noop, 0); }
     decoder.wrap_loop();
     for _ in 0..15 { decoder.decode_op(UserOps::Noop, 0); }
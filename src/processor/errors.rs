@@ -0,0 +1,81 @@
+use super::OpCode;
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Describes the reason for which a program could not be executed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ExecutionError {
+
+    // STACK
+    // --------------------------------------------------------------------------------------------
+
+    /// Operation at the specified step requires more values than there are on the stack.
+    StackUnderflow { step: usize },
+    /// Operation at the specified step would grow the stack beyond `MAX_STACK_DEPTH`.
+    StackOverflow { step: usize },
+    /// ASSERT or ASSERTEQ operation at the specified step failed.
+    FailedAssertion { op: OpCode, step: usize },
+    /// Operation at the specified step attempted to read from an empty secret input tape.
+    EmptyInputTape { tape: char, step: usize },
+    /// Operation at the specified step was executed with an execution hint it does not accept.
+    InvalidHint { op: OpCode, step: usize },
+    /// Operation at the specified step expected a binary value, but received something else.
+    NonBinaryValue { op: OpCode, step: usize },
+    /// Operation at the specified step received a value it cannot be applied to (e.g. INV of 0).
+    InvalidOperand { op: OpCode, step: usize },
+
+    // CONTROL FLOW
+    // --------------------------------------------------------------------------------------------
+
+    /// Condition of a branch or a loop at the specified step was neither 0 nor 1.
+    NonBinaryCondition { value: u128, step: usize },
+    /// Blocks at the specified step are nested deeper than `MAX_CONTEXT_DEPTH`.
+    ContextStackOverflow { step: usize },
+    /// Loops at the specified step are nested deeper than `MAX_LOOP_DEPTH`.
+    LoopStackOverflow { step: usize },
+
+    // PROGRAM
+    // --------------------------------------------------------------------------------------------
+
+    /// Program executed fewer operations than the minimum trace length.
+    TooFewOperations { min: usize, actual: usize },
+    /// More outputs were requested than a program can produce.
+    TooManyOutputs { max: usize, actual: usize },
+}
+
+// COMMON TRAIT IMPLEMENTATIONS
+// ================================================================================================
+
+impl std::fmt::Display for ExecutionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExecutionError::StackUnderflow { step } =>
+                write!(f, "stack underflow at step {}", step),
+            ExecutionError::StackOverflow { step } =>
+                write!(f, "stack overflow at step {}", step),
+            ExecutionError::FailedAssertion { op, step } =>
+                write!(f, "{} failed at step {}", op.to_string().to_uppercase(), step),
+            ExecutionError::EmptyInputTape { tape, step } =>
+                write!(f, "attempt to read from empty tape {} at step {}", tape, step),
+            ExecutionError::InvalidHint { op, step } =>
+                write!(f, "execution hint is not valid for {} operation at step {}", op.to_string().to_uppercase(), step),
+            ExecutionError::NonBinaryValue { op, step } =>
+                write!(f, "{} on a non-binary value at step {}", op.to_string().to_uppercase(), step),
+            ExecutionError::InvalidOperand { op, step } =>
+                write!(f, "invalid operand for {} operation at step {}", op.to_string().to_uppercase(), step),
+            ExecutionError::NonBinaryCondition { value, step } =>
+                write!(f, "cannot branch on a non-binary condition {} at step {}", value, step),
+            ExecutionError::ContextStackOverflow { step } =>
+                write!(f, "context stack overflow at step {}", step),
+            ExecutionError::LoopStackOverflow { step } =>
+                write!(f, "loop stack overflow at step {}", step),
+            ExecutionError::TooFewOperations { min, actual } =>
+                write!(f, "a program must consist of at least {} operation, but only {} were executed", min, actual),
+            ExecutionError::TooManyOutputs { max, actual } =>
+                write!(f, "cannot produce more than {} outputs, but requested {}", max, actual),
+        }
+    }
+}
+
+impl std::error::Error for ExecutionError {}
@@ -16,19 +16,23 @@ pub use stack::{ Stack };
 pub mod opcodes;
 pub use opcodes::{ UserOps as OpCode, OpHint };
 
+mod errors;
+pub use errors::{ ExecutionError };
+
 // PUBLIC FUNCTIONS
 // ================================================================================================
 
-/// Returns register traces resulting from executing the `program` against the specified inputs.
-pub fn execute(program: &Program, inputs: &ProgramInputs) -> (Vec<Vec<u128>>, usize, usize)
+/// Returns register traces resulting from executing the `program` against the specified inputs;
+/// returns an error if the program could not be executed against these inputs.
+pub fn execute(program: &Program, inputs: &ProgramInputs) -> Result<(Vec<Vec<u128>>, usize, usize), ExecutionError>
 {
     // initialize decoder and stack components
     let mut decoder = Decoder::new(MIN_TRACE_LENGTH);
     let mut stack = Stack::new(inputs, MIN_TRACE_LENGTH);
 
     // execute body of the program
-    execute_blocks(program.root().body(), &mut decoder, &mut stack);
-    close_block(&mut decoder, &mut stack, field::ZERO, true);
+    execute_blocks(program.root().body(), &mut decoder, &mut stack)?;
+    close_block(&mut decoder, &mut stack, field::ZERO, true)?;
 
     // fill in remaining steps to make sure the length of the trace is a power of 2
     decoder.finalize_trace();
@@ -42,140 +46,146 @@ pub fn execute(program: &Program, inputs: &ProgramInputs) -> (Vec<Vec<u128>>, us
     let mut register_traces = decoder.into_register_traces();
     register_traces.append(&mut stack.into_register_traces());
 
-    return (register_traces, context_depth, loop_depth);
+    return Ok((register_traces, context_depth, loop_depth));
 }
 
 // HELPER FUNCTIONS
 // ================================================================================================
-fn execute_blocks(blocks: &[ProgramBlock], decoder: &mut Decoder, stack: &mut Stack)
+fn execute_blocks(blocks: &[ProgramBlock], decoder: &mut Decoder, stack: &mut Stack) -> Result<(), ExecutionError>
 {
     // execute first block in the sequence, which mast be a Span block
     match &blocks[0] {
-        ProgramBlock::Span(block) => execute_span(block, decoder, stack, true),
+        ProgramBlock::Span(block) => execute_span(block, decoder, stack, true)?,
         _ => panic!("first block in a sequence must be a Span block"),
     }
 
     // execute all other blocks in the sequence one after another
     for block in blocks.iter().skip(1) {
         match block {
-            ProgramBlock::Span(block) => execute_span(block, decoder, stack, false),
+            ProgramBlock::Span(block) => execute_span(block, decoder, stack, false)?,
             ProgramBlock::Group(block) => {
-                start_block(decoder, stack);
-                execute_blocks(block.body(), decoder, stack);
-                close_block(decoder, stack, field::ZERO, true);
+                start_block(decoder, stack)?;
+                execute_blocks(block.body(), decoder, stack)?;
+                close_block(decoder, stack, field::ZERO, true)?;
             },
             ProgramBlock::Switch(block) => {
-                start_block(decoder, stack);
+                start_block(decoder, stack)?;
                 let condition = stack.get_stack_top();
                 match condition {
                     0 => {
-                        execute_blocks(block.false_branch(), decoder, stack);
-                        close_block(decoder, stack, block.true_branch_hash(), false);
+                        execute_blocks(block.false_branch(), decoder, stack)?;
+                        close_block(decoder, stack, block.true_branch_hash(), false)?;
                     },
                     1 => {
-                        execute_blocks(block.true_branch(), decoder, stack);
-                        close_block(decoder, stack, block.false_branch_hash(), true);
+                        execute_blocks(block.true_branch(), decoder, stack)?;
+                        close_block(decoder, stack, block.false_branch_hash(), true)?;
                     },
-                    _ => panic!("cannot select a branch based on a non-binary condition {}", condition)
+                    _ => return Err(ExecutionError::NonBinaryCondition { value: condition, step: stack.current_step() }),
                 };
             },
             ProgramBlock::Loop(block) => {
                 let condition = stack.get_stack_top();
                 match condition {
                     0 => {
-                        start_block(decoder, stack);
-                        execute_blocks(block.skip(), decoder, stack);
-                        close_block(decoder, stack, block.body_hash(), false);
+                        start_block(decoder, stack)?;
+                        execute_blocks(block.skip(), decoder, stack)?;
+                        close_block(decoder, stack, block.body_hash(), false)?;
                     },
-                    1 => execute_loop(block, decoder, stack),
-                    _ => panic!("cannot enter loop based on a non-binary condition {}", condition)
+                    1 => execute_loop(block, decoder, stack)?,
+                    _ => return Err(ExecutionError::NonBinaryCondition { value: condition, step: stack.current_step() }),
                 }
             },
         }
     }
+
+    return Ok(());
 }
 
 /// Executes all instructions in a Span block.
-fn execute_span(block: &Span, decoder: &mut Decoder, stack: &mut Stack, is_first: bool)
+fn execute_span(block: &Span, decoder: &mut Decoder, stack: &mut Stack, is_first: bool) -> Result<(), ExecutionError>
 {
     // if this is the first Span block in a sequence of blocks, it needs to be
     // pre-padded with a NOOP to make sure the first instruction in the block
     // starts executing on a step which is a multiple of 16
     if !is_first {
         decoder.decode_op(OpCode::Noop, field::ZERO);
-        stack.execute(OpCode::Noop, OpHint::None);
+        stack.execute(OpCode::Noop, OpHint::None)?;
     }
 
     // execute all other instructions in the block
     for i in 0..block.length() {
         let (op_code, op_hint) = block.get_op(i);
         decoder.decode_op(op_code, op_hint.value());
-        stack.execute(op_code, op_hint);
+        stack.execute(op_code, op_hint)?;
     }
+
+    return Ok(());
 }
 
 /// Starts executing a new program block.
-fn start_block(decoder: &mut Decoder, stack: &mut Stack)
+fn start_block(decoder: &mut Decoder, stack: &mut Stack) -> Result<(), ExecutionError>
 {
-    decoder.start_block();
-    stack.execute(OpCode::Noop, OpHint::None);
+    decoder.start_block()?;
+    return stack.execute(OpCode::Noop, OpHint::None);
 }
 
 /// Closes the currently executing program block.
-fn close_block(decoder: &mut Decoder, stack: &mut Stack, sibling_hash: u128, is_true_branch: bool)
+fn close_block(decoder: &mut Decoder, stack: &mut Stack, sibling_hash: u128, is_true_branch: bool) -> Result<(), ExecutionError>
 {
     // a sequence of blocks always ends on a step which is one less than a multiple of 16;
     // all sequences end one operation short of multiple of 16 - so, we need to pad them
     // with a single NOOP ensure proper alignment
     decoder.decode_op(OpCode::Noop, field::ZERO);
-    stack.execute(OpCode::Noop, OpHint::None);
+    stack.execute(OpCode::Noop, OpHint::None)?;
 
     // end the block, this prepares decoder registers for merging block hash into
     // program hash
     decoder.end_block(sibling_hash, is_true_branch);
-    stack.execute(OpCode::Noop, OpHint::None);
+    stack.execute(OpCode::Noop, OpHint::None)?;
 
     // execute NOOPs to merge block hash into the program hash
     for _ in 0..HACC_NUM_ROUNDS {
         decoder.decode_op(OpCode::Noop, field::ZERO);
-        stack.execute(OpCode::Noop, OpHint::None);
+        stack.execute(OpCode::Noop, OpHint::None)?;
     }
+
+    return Ok(());
 }
 
 /// Executes the specified loop.
-fn execute_loop(block: &Loop, decoder: &mut Decoder, stack: &mut Stack)
+fn execute_loop(block: &Loop, decoder: &mut Decoder, stack: &mut Stack) -> Result<(), ExecutionError>
 {
     // mark the beginning of the loop block
-    decoder.start_loop(block.image());
-    stack.execute(OpCode::Noop, OpHint::None);
+    decoder.start_loop(block.image())?;
+    stack.execute(OpCode::Noop, OpHint::None)?;
 
     // execute blocks in loop body until top of the stack becomes 0
     loop {
-        execute_blocks(block.body(), decoder, stack);
+        execute_blocks(block.body(), decoder, stack)?;
 
         let condition = stack.get_stack_top();
         match condition {
             0 => {
                 decoder.break_loop();
-                stack.execute(OpCode::Noop, OpHint::None);
+                stack.execute(OpCode::Noop, OpHint::None)?;
                 break;
             },
             1 => {
                 decoder.wrap_loop();
-                stack.execute(OpCode::Noop, OpHint::None);
+                stack.execute(OpCode::Noop, OpHint::None)?;
             },
-            _ => panic!("cannot exit loop based on a non-binary condition {}", condition)
+            _ => return Err(ExecutionError::NonBinaryCondition { value: condition, step: stack.current_step() }),
         };
     }
 
     // execute the contents of the skip block to make sure the loop was exited correctly
     match &block.skip()[0] {
-        ProgramBlock::Span(block) => execute_span(block, decoder, stack, true),
+        ProgramBlock::Span(block) => execute_span(block, decoder, stack, true)?,
         _ => panic!("invalid skip block content: content must be a Span block"),
     }
 
     // close block
-    close_block(decoder, stack, block.skip_hash(), true);
+    return close_block(decoder, stack, block.skip_hash(), true);
 }
 
 // TESTS
@@ -185,14 +195,14 @@ fn execute_loop(block: &Loop, decoder: &mut Decoder, stack: &mut Stack)
 mod tests {
 
     use crate::{ programs::assembly, stark::TraceState, utils::as_bytes };
-    use super::{ ProgramInputs };
+    use super::{ ProgramInputs, OpCode, ExecutionError };
 
     #[test]
     fn execute_span() {
         let program = assembly::compile("begin add push.5 mul push.7 end").unwrap();
         let inputs = ProgramInputs::from_public(&[1, 2]);
 
-        let (trace, ctx_depth, loop_depth) = super::execute(&program, &inputs);
+        let (trace, ctx_depth, loop_depth) = super::execute(&program, &inputs).unwrap();
         let trace_length = trace[0].len();
 
         assert_eq!(64, trace_length);
@@ -214,7 +224,7 @@ mod tests {
         let program = assembly::compile("begin add block push.5 mul push.7 end end").unwrap();
         let inputs = ProgramInputs::from_public(&[1, 2]);
 
-        let (trace, ctx_depth, loop_depth) = super::execute(&program, &inputs);
+        let (trace, ctx_depth, loop_depth) = super::execute(&program, &inputs).unwrap();
         let trace_length = trace[0].len();
 
         assert_eq!(64, trace_length);
@@ -240,7 +250,7 @@ mod tests {
         
         // execute true branch
         let inputs = ProgramInputs::new(&[5, 3], &[1], &[]);
-        let (trace, ctx_depth, loop_depth) = super::execute(&program, &inputs);
+        let (trace, ctx_depth, loop_depth) = super::execute(&program, &inputs).unwrap();
         let trace_length = trace[0].len();
 
         assert_eq!(128, trace_length);
@@ -260,7 +270,7 @@ mod tests {
 
         // execute false branch
         let inputs = ProgramInputs::new(&[5, 3], &[0], &[]);
-        let (trace, ctx_depth, loop_depth) = super::execute(&program, &inputs);
+        let (trace, ctx_depth, loop_depth) = super::execute(&program, &inputs).unwrap();
         let trace_length = trace[0].len();
 
         assert_eq!(128, trace_length);
@@ -286,7 +296,7 @@ mod tests {
 
         // don't enter the loop
         let inputs = ProgramInputs::new(&[5, 3], &[0], &[]);
-        let (trace, ctx_depth, loop_depth) = super::execute(&program, &inputs);
+        let (trace, ctx_depth, loop_depth) = super::execute(&program, &inputs).unwrap();
         let trace_length = trace[0].len();
 
         assert_eq!(64, trace_length);
@@ -306,7 +316,7 @@ mod tests {
 
         // execute one iteration
         let inputs = ProgramInputs::new(&[5, 3], &[1, 0], &[]);
-        let (trace, ctx_depth, loop_depth) = super::execute(&program, &inputs);
+        let (trace, ctx_depth, loop_depth) = super::execute(&program, &inputs).unwrap();
         let trace_length = trace[0].len();
 
         assert_eq!(128, trace_length);
@@ -326,7 +336,7 @@ mod tests {
 
         // execute five iteration
         let inputs = ProgramInputs::new(&[5, 3], &[1, 1, 1, 1, 1, 0], &[]);
-        let (trace, ctx_depth, loop_depth) = super::execute(&program, &inputs);
+        let (trace, ctx_depth, loop_depth) = super::execute(&program, &inputs).unwrap();
         let trace_length = trace[0].len();
 
         assert_eq!(256, trace_length);
@@ -345,6 +355,21 @@ mod tests {
         assert_eq!([43143988327398919500410556793212890625, 0, 0, 0, 0, 0, 0, 0], state.user_stack());
     }
 
+    #[test]
+    fn execute_errors() {
+        let program = assembly::compile("begin push.1 push.2 assert end").unwrap();
+        let result = super::execute(&program, &ProgramInputs::none());
+        assert!(matches!(result, Err(ExecutionError::FailedAssertion { op: OpCode::Assert, .. })));
+
+        let program = assembly::compile("begin read end").unwrap();
+        let result = super::execute(&program, &ProgramInputs::none());
+        assert!(matches!(result, Err(ExecutionError::EmptyInputTape { tape: 'A', .. })));
+
+        let program = assembly::compile("begin read if.true add else mul end end").unwrap();
+        let result = super::execute(&program, &ProgramInputs::new(&[5, 3], &[2], &[]));
+        assert!(matches!(result, Err(ExecutionError::NonBinaryCondition { value: 2, .. })));
+    }
+
     fn build_trace_state(num_registers: usize, ctx_depth: usize, loop_depth: usize) -> TraceState {
         let decoder_width = TraceState::compute_decoder_width(ctx_depth, loop_depth);
         let stack_depth = num_registers - decoder_width;
@@ -1,3 +1,5 @@
+use serde::{ Serialize, Deserialize };
+
 // FLOW CONTROL OPERATIONS
 // ================================================================================================
 #[repr(u8)]
@@ -42,7 +44,7 @@ impl std::fmt::Display for FlowOps {
 // USER OPERATIONS
 // ================================================================================================
 #[repr(u8)]
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum UserOps {
     
     // low-degree operations
@@ -62,7 +64,7 @@ pub enum UserOps {
     Inv         = 0b0_11_01100,         // no shift
     Neg         = 0b0_11_01101,         // no shift
     Not         = 0b0_11_01110,         // no shift
-    //???       = 0b0_11_01111,
+    PoseF       = 0b0_11_01111,         // no shift
 
     Read        = 0b0_11_10000,         // right shift: 1
     Read2       = 0b0_11_10001,         // right shift: 2
@@ -71,7 +73,7 @@ pub enum UserOps {
     Dup4        = 0b0_11_10100,         // right shift: 4
     Pad2        = 0b0_11_10101,         // right shift: 2
     //???       = 0b0_11_10110,
-    //???       = 0b0_11_10111,
+    PoseP       = 0b0_11_10111,         // no shift
 
     Swap        = 0b0_11_11000,         // no shift
     Swap2       = 0b0_11_11001,         // no shift
@@ -162,19 +164,22 @@ impl std::fmt::Display for UserOps {
             UserOps::Cmp        => write!(f, "cmp"),
             UserOps::BinAcc     => write!(f, "binacc"),
     
-            UserOps::RescR      => write!(f, "rescr")
+            UserOps::RescR      => write!(f, "rescr"),
+            UserOps::PoseF      => write!(f, "posef"),
+            UserOps::PoseP      => write!(f, "posep"),
         };
     }
 }
 
 // OPERATION HINTS
 // ================================================================================================
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
 pub enum OpHint {
     EqStart,
     RcStart(u32),
     CmpStart(u32),
     PmpathStart(u32),
+    MrupdateStart(u32),
     PushValue(u128),
     None,
 }
@@ -195,6 +200,7 @@ impl std::fmt::Display for OpHint {
             OpHint::RcStart(value)   => write!(f, ".{}", value),
             OpHint::CmpStart(value)     => write!(f, ".{}", value),
             OpHint::PmpathStart(value)  => write!(f, ".{}", value),
+            OpHint::MrupdateStart(value)=> write!(f, ".{}", value),
             OpHint::PushValue(value)    => write!(f, "({})", value),
             OpHint::None             => Ok(()),
         };
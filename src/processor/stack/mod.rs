@@ -1,8 +1,8 @@
 use crate::{
     math::field,
-    utils::hasher,
-    ProgramInputs, OpCode, OpHint,
-    HASH_STATE_WIDTH, MIN_STACK_DEPTH, MAX_STACK_DEPTH,
+    utils::{ hasher, poseidon },
+    ProgramInputs, OpCode, OpHint, ExecutionError,
+    HASH_STATE_WIDTH, MIN_STACK_DEPTH, MAX_STACK_DEPTH, BASE_CYCLE_LENGTH,
 };
 
 #[cfg(test)]
@@ -57,14 +57,15 @@ impl Stack {
         };
     }
 
-    /// Executes `opcode` against the current state of the stack.
-    pub fn execute(&mut self, op_code: OpCode, op_hint: OpHint) {
+    /// Executes `opcode` against the current state of the stack; returns an error if the
+    /// operation cannot be applied to the current state of the stack.
+    pub fn execute(&mut self, op_code: OpCode, op_hint: OpHint) -> Result<(), ExecutionError> {
 
         // increment step pointer and make sure there is enough memory allocated to hold the trace
         self.advance_step();
 
         // execute the appropriate action against the current state of the stack
-        match op_code {
+        return match op_code {
 
             OpCode::Begin       => self.op_noop(),
             OpCode::Noop        => self.op_noop(),
@@ -108,7 +109,9 @@ impl Stack {
             OpCode::BinAcc      => self.op_binacc(op_hint),
 
             OpCode::RescR       => self.op_rescr(),
-        }
+            OpCode::PoseF       => self.op_poser(true),
+            OpCode::PoseP       => self.op_poser(false),
+        };
     }
 
     /// Returns trace length of register traces in the decoder.
@@ -117,7 +120,6 @@ impl Stack {
     }
 
     /// Returns value of the current step pointer.
-    #[cfg(test)]
     pub fn current_step(&self) -> usize {
         return self.step;
     }
@@ -148,44 +150,56 @@ impl Stack {
 
     // FLOW CONTROL OPERATIONS
     // --------------------------------------------------------------------------------------------
-    fn op_noop(&mut self) {
+    fn op_noop(&mut self) -> Result<(), ExecutionError> {
         self.copy_state(0);
+
+        return Ok(());
     }
 
-    fn op_assert(&mut self) {
-        assert!(self.depth >= 1, "stack underflow at step {}", self.step);
+    fn op_assert(&mut self) -> Result<(), ExecutionError> {
+        self.check_depth(1)?;
         let value = self.registers[0][self.step - 1];
-        assert!(value == field::ONE, "ASSERT failed at step {}", self.step);
-        self.shift_left(1, 1);
+        if value != field::ONE {
+            return Err(ExecutionError::FailedAssertion { op: OpCode::Assert, step: self.step });
+        }
+        self.shift_left(1, 1)?;
+
+        return Ok(());
     }
 
-    fn op_asserteq(&mut self) {
-        assert!(self.depth >= 2, "stack underflow at step {}", self.step);
+    fn op_asserteq(&mut self) -> Result<(), ExecutionError> {
+        self.check_depth(2)?;
         let x = self.registers[0][self.step - 1];
         let y = self.registers[1][self.step - 1];
-        assert!(x == y, "ASSERTEQ failed at step {}", self.step);
-        self.shift_left(2, 2);
+        if x != y {
+            return Err(ExecutionError::FailedAssertion { op: OpCode::AssertEq, step: self.step });
+        }
+        self.shift_left(2, 2)?;
+
+        return Ok(());
     }
 
     // INPUT OPERATIONS
     // --------------------------------------------------------------------------------------------
-    fn op_push(&mut self, hint: OpHint) {
-        self.shift_right(0, 1);
+    fn op_push(&mut self, hint: OpHint) -> Result<(), ExecutionError> {
+        self.shift_right(0, 1)?;
         let op_value = match hint {
             OpHint::PushValue(value) => value,
-            _ => panic!("invalid value for PUSH operation at step {}", self.step),
+            _ => return Err(ExecutionError::InvalidHint { op: OpCode::Push, step: self.step }),
         };
         self.registers[0][self.step] = op_value;
+
+        return Ok(());
     }
 
-    fn op_read(&mut self, hint: OpHint) {
+    fn op_read(&mut self, hint: OpHint) -> Result<(), ExecutionError> {
         // process execution hint
         match hint {
             OpHint::EqStart => {
                 // if we are about to equality comparison sequence, push inverse of the difference
                 // between top two stack values onto secret tape A, if they are equal; otherwise
                 // push value 1
-                assert!(self.depth >= 2, "stack underflow at step {}", self.step);
+                self.check_depth(2)?;
                 let x = self.registers[0][self.step - 1];
                 let y = self.registers[1][self.step - 1];
                 if x == y {
@@ -195,26 +209,30 @@ impl Stack {
                     self.tape_a.push(field::inv(field::sub(x, y)));
                 }
             },
-            OpHint::None => {
-                assert!(self.tape_a.len() > 0, "attempt to read from empty tape A at step {}", self.step);
-            },
-            _ => panic!("execution hint {:?} is not valid for READ operation", hint)
+            OpHint::None => (),
+            _ => return Err(ExecutionError::InvalidHint { op: OpCode::Read, step: self.step }),
         }
 
-        self.shift_right(0, 1);
-        let value = self.tape_a.pop().unwrap();
+        self.shift_right(0, 1)?;
+        let value = self.pop_tape_a()?;
         self.registers[0][self.step] = value;
+
+        return Ok(());
     }
 
-    fn op_read2(&mut self, hint: OpHint) {
+    fn op_read2(&mut self, hint: OpHint) -> Result<(), ExecutionError> {
         // process execution hint
         match hint {
             OpHint::PmpathStart(n) => {
-                assert!(self.depth >= 3, "stack underflow at step {}", self.step);
+                self.check_depth(3)?;
 
                 let n = (n - 1) as usize;
-                assert!(self.tape_a.len() >= n, "too few items on tape A for pmpath macro");
-                assert!(self.tape_b.len() >= n, "too few items on tape B for pmpath macro");
+                if self.tape_a.len() < n {
+                    return Err(ExecutionError::EmptyInputTape { tape: 'A', step: self.step });
+                }
+                if self.tape_b.len() < n {
+                    return Err(ExecutionError::EmptyInputTape { tape: 'B', step: self.step });
+                }
 
                 let idx = self.registers[2][self.step - 1];
 
@@ -230,78 +248,93 @@ impl Stack {
                     self.tape_a.push(v_a[i]);
                 }
             },
-            OpHint::None => {
-                assert!(self.tape_a.len() > 0, "attempt to read from empty tape A at step {}", self.step);
-                assert!(self.tape_b.len() > 0, "attempt to read from empty tape B at step {}", self.step);
-            },
-            _ => panic!("execution hint {:?} is not valid for READ2 operation", hint)
+            OpHint::None => (),
+            _ => return Err(ExecutionError::InvalidHint { op: OpCode::Read2, step: self.step }),
         }
 
-        self.shift_right(0, 2);
-        let value_a = self.tape_a.pop().unwrap();
-        let value_b = self.tape_b.pop().unwrap();
+        self.shift_right(0, 2)?;
+        let value_a = self.pop_tape_a()?;
+        let value_b = self.pop_tape_b()?;
         self.registers[0][self.step] = value_b;
         self.registers[1][self.step] = value_a;
+
+        return Ok(());
     }
 
     // STACK MANIPULATION OPERATIONS
     // --------------------------------------------------------------------------------------------
-    fn op_dup(&mut self) {
-        assert!(self.depth >= 1, "stack underflow at step {}", self.step);
-        self.shift_right(0, 1);
+    fn op_dup(&mut self) -> Result<(), ExecutionError> {
+        self.check_depth(1)?;
+        self.shift_right(0, 1)?;
         self.registers[0][self.step] = self.registers[0][self.step - 1];
+
+        return Ok(());
     }
 
-    fn op_dup2(&mut self) {
-        assert!(self.depth >= 2, "stack underflow at step {}", self.step);
-        self.shift_right(0, 2);
+    fn op_dup2(&mut self) -> Result<(), ExecutionError> {
+        self.check_depth(2)?;
+        self.shift_right(0, 2)?;
         self.registers[0][self.step] = self.registers[0][self.step - 1];
         self.registers[1][self.step] = self.registers[1][self.step - 1];
+
+        return Ok(());
     }
 
-    fn op_dup4(&mut self) {
-        assert!(self.depth >= 4, "stack underflow at step {}", self.step);
-        self.shift_right(0, 4);
+    fn op_dup4(&mut self) -> Result<(), ExecutionError> {
+        self.check_depth(4)?;
+        self.shift_right(0, 4)?;
         self.registers[0][self.step] = self.registers[0][self.step - 1];
         self.registers[1][self.step] = self.registers[1][self.step - 1];
         self.registers[2][self.step] = self.registers[2][self.step - 1];
         self.registers[3][self.step] = self.registers[3][self.step - 1];
+
+        return Ok(());
     }
 
-    fn op_pad2(&mut self) {
-        self.shift_right(0, 2);
+    fn op_pad2(&mut self) -> Result<(), ExecutionError> {
+        self.shift_right(0, 2)?;
         self.registers[0][self.step] = field::ZERO;
         self.registers[1][self.step] = field::ZERO;
+
+        return Ok(());
     }
 
-    fn op_drop(&mut self) {
-        assert!(self.depth >= 1, "stack underflow at step {}", self.step);
-        self.shift_left(1, 1);
+    fn op_drop(&mut self) -> Result<(), ExecutionError> {
+        self.check_depth(1)?;
+        self.shift_left(1, 1)?;
+
+        return Ok(());
     }
 
-    fn op_drop4(&mut self) {
-        assert!(self.depth >= 4, "stack underflow at step {}", self.step);
-        self.shift_left(4, 4);
+    fn op_drop4(&mut self) -> Result<(), ExecutionError> {
+        self.check_depth(4)?;
+        self.shift_left(4, 4)?;
+
+        return Ok(());
     }
 
-    fn op_swap(&mut self) {
-        assert!(self.depth >= 2, "stack underflow at step {}", self.step);
+    fn op_swap(&mut self) -> Result<(), ExecutionError> {
+        self.check_depth(2)?;
         self.registers[0][self.step] = self.registers[1][self.step - 1];
         self.registers[1][self.step] = self.registers[0][self.step - 1];
         self.copy_state(2);
+
+        return Ok(());
     }
 
-    fn op_swap2(&mut self) {
-        assert!(self.depth >= 4, "stack underflow at step {}", self.step);
+    fn op_swap2(&mut self) -> Result<(), ExecutionError> {
+        self.check_depth(4)?;
         self.registers[0][self.step] = self.registers[2][self.step - 1];
         self.registers[1][self.step] = self.registers[3][self.step - 1];
         self.registers[2][self.step] = self.registers[0][self.step - 1];
         self.registers[3][self.step] = self.registers[1][self.step - 1];
         self.copy_state(4);
+
+        return Ok(());
     }
 
-    fn op_swap4(&mut self) {
-        assert!(self.depth >= 8, "stack underflow at step {}", self.step);
+    fn op_swap4(&mut self) -> Result<(), ExecutionError> {
+        self.check_depth(8)?;
         self.registers[0][self.step] = self.registers[4][self.step - 1];
         self.registers[1][self.step] = self.registers[5][self.step - 1];
         self.registers[2][self.step] = self.registers[6][self.step - 1];
@@ -311,19 +344,23 @@ impl Stack {
         self.registers[6][self.step] = self.registers[2][self.step - 1];
         self.registers[7][self.step] = self.registers[3][self.step - 1];
         self.copy_state(8);
+
+        return Ok(());
     }
 
-    fn op_roll4(&mut self) {
-        assert!(self.depth >= 4, "stack underflow at step {}", self.step);
+    fn op_roll4(&mut self) -> Result<(), ExecutionError> {
+        self.check_depth(4)?;
         self.registers[0][self.step] = self.registers[3][self.step - 1];
         self.registers[1][self.step] = self.registers[0][self.step - 1];
         self.registers[2][self.step] = self.registers[1][self.step - 1];
         self.registers[3][self.step] = self.registers[2][self.step - 1];
         self.copy_state(4);
+
+        return Ok(());
     }
 
-    fn op_roll8(&mut self) {
-        assert!(self.depth >= 8, "stack underflow at step {}", self.step);
+    fn op_roll8(&mut self) -> Result<(), ExecutionError> {
+        self.check_depth(8)?;
         self.registers[0][self.step] = self.registers[7][self.step - 1];
         self.registers[1][self.step] = self.registers[0][self.step - 1];
         self.registers[2][self.step] = self.registers[1][self.step - 1];
@@ -333,12 +370,14 @@ impl Stack {
         self.registers[6][self.step] = self.registers[5][self.step - 1];
         self.registers[7][self.step] = self.registers[6][self.step - 1];
         self.copy_state(8);
+
+        return Ok(());
     }
 
     // SELECTION OPERATIONS
     // --------------------------------------------------------------------------------------------
-    fn op_choose(&mut self) {
-        assert!(self.depth >= 3, "stack underflow at step {}", self.step);
+    fn op_choose(&mut self) -> Result<(), ExecutionError> {
+        self.check_depth(3)?;
         let condition = self.registers[2][self.step - 1];
         if condition == field::ONE {
             self.registers[0][self.step] = self.registers[0][self.step - 1];
@@ -347,13 +386,15 @@ impl Stack {
             self.registers[0][self.step] = self.registers[1][self.step - 1];
         }
         else {
-            assert!(false, "CHOOSE on a non-binary condition at step {}", self.step);
+            return Err(ExecutionError::NonBinaryValue { op: OpCode::Choose, step: self.step });
         }
-        self.shift_left(3, 2);
+        self.shift_left(3, 2)?;
+
+        return Ok(());
     }
 
-    fn op_choose2(&mut self) {
-        assert!(self.depth >= 6, "stack underflow at step {}", self.step);
+    fn op_choose2(&mut self) -> Result<(), ExecutionError> {
+        self.check_depth(6)?;
         let condition = self.registers[4][self.step - 1];
         if condition == field::ONE {
             self.registers[0][self.step] = self.registers[0][self.step - 1];
@@ -364,13 +405,15 @@ impl Stack {
             self.registers[1][self.step] = self.registers[3][self.step - 1];
         }
         else {
-            assert!(false, "CHOOSE2 on a non-binary condition at step {}", self.step);
+            return Err(ExecutionError::NonBinaryValue { op: OpCode::Choose2, step: self.step });
         }
-        self.shift_left(6, 4);
+        self.shift_left(6, 4)?;
+
+        return Ok(());
     }
 
-    fn op_cswap2(&mut self) {
-        assert!(self.depth >= 6, "stack underflow at step {}", self.step);
+    fn op_cswap2(&mut self) -> Result<(), ExecutionError> {
+        self.check_depth(6)?;
         let condition = self.registers[4][self.step - 1];
         if condition == field::ZERO {
             self.registers[0][self.step] = self.registers[0][self.step - 1];
@@ -385,79 +428,101 @@ impl Stack {
             self.registers[3][self.step] = self.registers[1][self.step - 1];
         }
         else {
-            assert!(false, "CSWAP2 on a non-binary condition at step {}", self.step);
+            return Err(ExecutionError::NonBinaryValue { op: OpCode::CSwap2, step: self.step });
         }
-        self.shift_left(6, 2);
+        self.shift_left(6, 2)?;
+
+        return Ok(());
     }
 
     // ARITHMETIC AND BOOLEAN OPERATIONS
     // --------------------------------------------------------------------------------------------
-    fn op_add(&mut self) {
-        assert!(self.depth >= 2, "stack underflow at step {}", self.step);
+    fn op_add(&mut self) -> Result<(), ExecutionError> {
+        self.check_depth(2)?;
         let x = self.registers[0][self.step - 1];
         let y = self.registers[1][self.step - 1];
         self.registers[0][self.step] = field::add(x, y);
-        self.shift_left(2, 1);
+        self.shift_left(2, 1)?;
+
+        return Ok(());
     }
 
-    fn op_mul(&mut self) {
-        assert!(self.depth >= 2, "stack underflow at step {}", self.step);
+    fn op_mul(&mut self) -> Result<(), ExecutionError> {
+        self.check_depth(2)?;
         let x = self.registers[0][self.step - 1];
         let y = self.registers[1][self.step - 1];
         self.registers[0][self.step] = field::mul(x, y);
-        self.shift_left(2, 1);
+        self.shift_left(2, 1)?;
+
+        return Ok(());
     }
 
-    fn op_inv(&mut self) {
-        assert!(self.depth >= 1, "stack underflow at step {}", self.step);
+    fn op_inv(&mut self) -> Result<(), ExecutionError> {
+        self.check_depth(1)?;
         let x = self.registers[0][self.step - 1];
-        assert!(x != field::ZERO, "cannot compute INV of {} at step {}", field::ZERO, self.step);
+        if x == field::ZERO {
+            return Err(ExecutionError::InvalidOperand { op: OpCode::Inv, step: self.step });
+        }
         self.registers[0][self.step] = field::inv(x);
         self.copy_state(1);
+
+        return Ok(());
     }
 
-    fn op_neg(&mut self) {
-        assert!(self.depth >= 1, "stack underflow at step {}", self.step);
+    fn op_neg(&mut self) -> Result<(), ExecutionError> {
+        self.check_depth(1)?;
         let x = self.registers[0][self.step - 1];
         self.registers[0][self.step] = field::neg(x);
         self.copy_state(1);
+
+        return Ok(());
     }
 
-    fn op_not(&mut self) {
-        assert!(self.depth >= 1, "stack underflow at step {}", self.step);
+    fn op_not(&mut self) -> Result<(), ExecutionError> {
+        self.check_depth(1)?;
         let x = self.registers[0][self.step - 1];
-        assert!(is_binary(x), "cannot compute NOT of a non-binary value at step {}", self.step);
+        if !is_binary(x) {
+            return Err(ExecutionError::NonBinaryValue { op: OpCode::Not, step: self.step });
+        }
         self.registers[0][self.step] = field::sub(field::ONE, x);
         self.copy_state(1);
+
+        return Ok(());
     }
 
-    fn op_and(&mut self) {
-        assert!(self.depth >= 2, "stack underflow at step {}", self.step);
+    fn op_and(&mut self) -> Result<(), ExecutionError> {
+        self.check_depth(2)?;
         let x = self.registers[0][self.step - 1];
         let y = self.registers[1][self.step - 1];
-        assert!(is_binary(x), "cannot compute AND for a non-binary value at step {}", self.step);
-        assert!(is_binary(y), "cannot compute AND for a non-binary value at step {}", self.step);
+        if !is_binary(x) || !is_binary(y) {
+            return Err(ExecutionError::NonBinaryValue { op: OpCode::And, step: self.step });
+        }
 
         self.registers[0][self.step] = if x == field::ONE && y == field::ONE { field::ONE } else { field::ZERO };
-        self.shift_left(2, 1);
+        self.shift_left(2, 1)?;
+
+        return Ok(());
     }
 
-    fn op_or(&mut self) {
-        assert!(self.depth >= 2, "stack underflow at step {}", self.step);
+    fn op_or(&mut self) -> Result<(), ExecutionError> {
+        self.check_depth(2)?;
         let x = self.registers[0][self.step - 1];
         let y = self.registers[1][self.step - 1];
-        assert!(is_binary(x), "cannot compute OR for a non-binary value at step {}", self.step);
-        assert!(is_binary(y), "cannot compute OR for a non-binary value at step {}", self.step);
+        if !is_binary(x) || !is_binary(y) {
+            return Err(ExecutionError::NonBinaryValue { op: OpCode::Or, step: self.step });
+        }
 
         self.registers[0][self.step] = if x == field::ONE || y == field::ONE { field::ONE } else { field::ZERO };
-        self.shift_left(2, 1);
+        self.shift_left(2, 1)?;
+
+        return Ok(());
     }
 
 
     // COMPARISON OPERATIONS
     // --------------------------------------------------------------------------------------------
-    fn op_eq(&mut self) {
-        assert!(self.depth >= 3, "stack underflow at step {}", self.step);
+    fn op_eq(&mut self) -> Result<(), ExecutionError> {
+        self.check_depth(3)?;
         let aux = self.registers[0][self.step - 1];
         let x = self.registers[1][self.step - 1];
         let y = self.registers[2][self.step - 1];
@@ -465,19 +530,23 @@ impl Stack {
             self.registers[0][self.step] = field::ONE;
         } else {
             let diff = field::sub(x, y);
-            assert!(aux == field::inv(diff), "invalid AUX value for EQ operation at step {}", self.step);
+            if aux != field::inv(diff) {
+                return Err(ExecutionError::InvalidOperand { op: OpCode::Eq, step: self.step });
+            }
             self.registers[0][self.step] = field::ZERO;
         }
-        self.shift_left(3, 2);
+        self.shift_left(3, 2)?;
+
+        return Ok(());
     }
 
-    fn op_cmp(&mut self, hint: OpHint) {
+    fn op_cmp(&mut self, hint: OpHint) -> Result<(), ExecutionError> {
         // process execution hint
         match hint {
             OpHint::CmpStart(n) => {
                 // if we are about to start comparison sequence, push binary decompositions
                 // of a and b values onto the tapes
-                assert!(self.depth >= 10, "stack underflow at step {}", self.step);
+                self.check_depth(10)?;
                 let a_val = self.registers[8][self.step - 1];
                 let b_val = self.registers[9][self.step - 1];
                 for i in 0..n {
@@ -485,21 +554,19 @@ impl Stack {
                     self.tape_b.push((b_val >> i) & 1);
                 }
             },
-            OpHint::None => {
-                assert!(self.depth >= 8, "stack underflow at step {}", self.step);
-                assert!(self.tape_a.len() > 0, "attempt to read from empty tape A at step {}", self.step);
-                assert!(self.tape_b.len() > 0, "attempt to read from empty tape B at step {}", self.step);
-            },
-            _ => panic!("execution hint {:?} is not valid for CMP operation", hint)
+            OpHint::None => self.check_depth(8)?,
+            _ => return Err(ExecutionError::InvalidHint { op: OpCode::Cmp, step: self.step }),
         }
 
         // get next bits of a and b values from the tapes
-        let a_bit = self.tape_a.pop().unwrap();
-        assert!(a_bit == field::ZERO || a_bit == field::ONE,
-            "expected binary input at step {} but received: {}", self.step, a_bit);
-        let b_bit = self.tape_b.pop().unwrap();
-        assert!(b_bit == field::ZERO || b_bit == field::ONE,
-            "expected binary input at step {} but received: {}", self.step, b_bit);
+        let a_bit = self.pop_tape_a()?;
+        if !is_binary(a_bit) {
+            return Err(ExecutionError::NonBinaryValue { op: OpCode::Cmp, step: self.step });
+        }
+        let b_bit = self.pop_tape_b()?;
+        if !is_binary(b_bit) {
+            return Err(ExecutionError::NonBinaryValue { op: OpCode::Cmp, step: self.step });
+        }
 
         // determine which bit is greater
         let bit_gt = field::mul(a_bit, field::sub(field::ONE, b_bit));
@@ -507,8 +574,9 @@ impl Stack {
 
         // compute current power of 2 for binary decomposition
         let power_of_two = self.registers[0][self.step - 1];
-        assert!(power_of_two.is_power_of_two(),
-            "expected top of the stack at step {} to be a power of 2, but received {}", self.step, power_of_two);
+        if !power_of_two.is_power_of_two() {
+            return Err(ExecutionError::InvalidOperand { op: OpCode::Cmp, step: self.step });
+        }
         let next_power_of_two = if power_of_two == 1 {
             field::div(power_of_two, 2)
         }
@@ -532,38 +600,61 @@ impl Stack {
         self.registers[7][self.step] = field::add(self.registers[7][self.step - 1], field::mul(a_bit, power_of_two));
 
         self.copy_state(8);
+
+        return Ok(());
     }
 
-    fn op_binacc(&mut self, hint: OpHint) {
+    fn op_binacc(&mut self, hint: OpHint) -> Result<(), ExecutionError> {
         // process execution hint
         match hint {
             OpHint::RcStart(n) => {
                 // if we are about to start range check sequence, push binary decompositions
                 // of the value onto tape A
-                assert!(self.depth >= 5, "stack underflow at step {}", self.step);
+                self.check_depth(5)?;
                 let val = self.registers[4][self.step - 1];
                 for i in 0..n {
                     // most significant bit is pushed first
                     self.tape_a.push((val >> (n - i - 1)) & 1);
                 }
             },
-            OpHint::None => {
-                assert!(self.depth >= 4, "stack underflow at step {}", self.step);
-                assert!(self.tape_a.len() > 0, "attempt to read from empty tape A at step {}", self.step);
+            OpHint::MrupdateStart(n) => {
+                // if we are about to start mrupdate macro, interlace binary decomposition of the
+                // leaf index with nodes of the authentication path already present on tape A
+                self.check_depth(9)?;
+
+                let n = (n - 1) as usize;
+                if self.tape_a.len() < n {
+                    return Err(ExecutionError::EmptyInputTape { tape: 'A', step: self.step });
+                }
+                if self.tape_b.len() < n {
+                    return Err(ExecutionError::EmptyInputTape { tape: 'B', step: self.step });
+                }
+
+                let idx = self.registers[8][self.step - 1];
+
+                // unlike pmpath, each bit is read before the node it applies to, and so bits
+                // are pushed after the nodes; least significant bit ends up at the top
+                let v_a = self.tape_a.split_off(self.tape_a.len() - n);
+                for i in 0..n {
+                    self.tape_a.push(v_a[i]);
+                    self.tape_a.push((idx >> (n - i - 1)) & 1);
+                }
             },
-            _ => panic!("execution hint {:?} is not valid for BINACC operation", hint)
+            OpHint::None => self.check_depth(4)?,
+            _ => return Err(ExecutionError::InvalidHint { op: OpCode::BinAcc, step: self.step }),
         }
 
         // get the next bit of the value from tape A
-        let bit = self.tape_a.pop().unwrap();
-        assert!(bit == field::ZERO || bit == field::ONE,
-            "expected binary input at step {} but received: {}", self.step, bit);
+        let bit = self.pop_tape_a()?;
+        if !is_binary(bit) {
+            return Err(ExecutionError::NonBinaryValue { op: OpCode::BinAcc, step: self.step });
+        }
 
         // compute current power of 2 for binary decomposition
         let power_of_two = self.registers[2][self.step - 1];
-        assert!(power_of_two.is_power_of_two(),
-            "expected 3rd value from the top of the stack at step {} to be a power of 2, but received {}",
-            self.step, power_of_two);
+        if !power_of_two.is_power_of_two() {
+            return Err(ExecutionError::InvalidOperand { op: OpCode::BinAcc, step: self.step });
+        }
         let next_power_of_two = field::mul(power_of_two, 2);
 
         let acc = self.registers[3][self.step - 1];
@@ -575,12 +666,14 @@ impl Stack {
         self.registers[3][self.step] = field::add(acc, field::mul(bit, power_of_two));
 
         self.copy_state(4);
+
+        return Ok(());
     }
 
     // CRYPTOGRAPHIC OPERATIONS
     // --------------------------------------------------------------------------------------------
-    fn op_rescr(&mut self) {
-        assert!(self.depth >= HASH_STATE_WIDTH, "stack underflow at step {}", self.step);
+    fn op_rescr(&mut self) -> Result<(), ExecutionError> {
+        self.check_depth(HASH_STATE_WIDTH)?;
         let mut state = [
             self.registers[0][self.step - 1],
             self.registers[1][self.step - 1],
@@ -600,6 +693,39 @@ impl Stack {
         self.registers[5][self.step] = state[5];
 
         self.copy_state(HASH_STATE_WIDTH);
+
+        return Ok(());
+    }
+
+    fn op_poser(&mut self, full: bool) -> Result<(), ExecutionError> {
+        self.check_depth(poseidon::STACK_WIDTH)?;
+        let mut selector = [field::ZERO; poseidon::NUM_CYCLES];
+        let mut state = [field::ZERO; HASH_STATE_WIDTH];
+        for i in 0..poseidon::NUM_CYCLES {
+            selector[i] = self.registers[i][self.step - 1];
+        }
+        for i in 0..HASH_STATE_WIDTH {
+            state[i] = self.registers[poseidon::NUM_CYCLES + i][self.step - 1];
+        }
+
+        // apply the round and add round constants of the next round
+        poseidon::apply_round(&mut state, full);
+        let ark = poseidon::get_round_constants(&selector, self.step - 1);
+        for i in 0..HASH_STATE_WIDTH {
+            self.registers[poseidon::NUM_CYCLES + i][self.step] = field::add(state[i], ark[i]);
+        }
+
+        // on the last step of a cycle, move the cycle selector to the next cycle
+        if (self.step - 1) % BASE_CYCLE_LENGTH == BASE_CYCLE_LENGTH - 1 {
+            selector.rotate_right(1);
+        }
+        for i in 0..poseidon::NUM_CYCLES {
+            self.registers[i][self.step] = selector[i];
+        }
+
+        self.copy_state(poseidon::STACK_WIDTH);
+
+        return Ok(());
     }
 
     // HELPER METHODS
@@ -611,8 +737,8 @@ impl Stack {
         }
     }
 
-    fn shift_left(&mut self, start: usize, pos_count: usize) {
-        assert!(self.depth >= pos_count, "stack underflow at step {}", self.step);
+    fn shift_left(&mut self, start: usize, pos_count: usize) -> Result<(), ExecutionError> {
+        self.check_depth(pos_count)?;
         
         // shift all values by pos_count to the left
         for i in start..self.depth {
@@ -626,12 +752,16 @@ impl Stack {
 
         // stack depth has been reduced by pos_count
         self.depth -= pos_count;
+
+        return Ok(());
     }
 
-    fn shift_right(&mut self, start: usize, pos_count: usize) {
+    fn shift_right(&mut self, start: usize, pos_count: usize) -> Result<(), ExecutionError> {
         
         self.depth += pos_count;
-        assert!(self.depth <= MAX_STACK_DEPTH, "stack overflow at step {}", self.step);
+        if self.depth > MAX_STACK_DEPTH {
+            return Err(ExecutionError::StackOverflow { step: self.step });
+        }
 
         if self.depth > self.max_depth {
             self.max_depth += pos_count;
@@ -643,6 +773,26 @@ impl Stack {
         for i in start..(self.depth - pos_count) {
             self.registers[i + pos_count][self.step] = self.registers[i][self.step - 1];
         }
+
+        return Ok(());
+    }
+
+    /// Returns an error if there are fewer than `depth` values on the stack.
+    fn check_depth(&self, depth: usize) -> Result<(), ExecutionError> {
+        if self.depth < depth {
+            return Err(ExecutionError::StackUnderflow { step: self.step });
+        }
+        return Ok(());
+    }
+
+    /// Removes the next value from secret input tape A.
+    fn pop_tape_a(&mut self) -> Result<u128, ExecutionError> {
+        return self.tape_a.pop().ok_or(ExecutionError::EmptyInputTape { tape: 'A', step: self.step });
+    }
+
+    /// Removes the next value from secret input tape B.
+    fn pop_tape_b(&mut self) -> Result<u128, ExecutionError> {
+        return self.tape_b.pop().ok_or(ExecutionError::EmptyInputTape { tape: 'B', step: self.step });
     }
 
     /// Extends the stack by the specified number of registers.
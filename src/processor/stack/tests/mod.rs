@@ -1,6 +1,6 @@
 use crate::math::{ field };
 use crate::utils::{ hasher };
-use super::{ Stack, super::ProgramInputs, OpHint, OpCode };
+use super::{ Stack, super::ProgramInputs, OpHint, OpCode, ExecutionError };
 use crate::{ HASH_STATE_WIDTH };
 
 mod comparisons;
@@ -14,7 +14,7 @@ const TRACE_LENGTH: usize = 16;
 #[test]
 fn noop() {
     let mut stack = init_stack(&[1, 2, 3, 4], &[], &[], TRACE_LENGTH);
-    stack.execute(OpCode::Noop, OpHint::None);
+    stack.execute(OpCode::Noop, OpHint::None).unwrap();
     assert_eq!(vec![1, 2, 3, 4, 0, 0, 0, 0], get_stack_state(&stack, 1));
 
     assert_eq!(4, stack.depth);
@@ -24,7 +24,7 @@ fn noop() {
 #[test]
 fn assert() {
     let mut stack = init_stack(&[1, 2, 3, 4], &[], &[], TRACE_LENGTH);
-    stack.execute(OpCode::Assert, OpHint::None);
+    stack.execute(OpCode::Assert, OpHint::None).unwrap();
     assert_eq!(vec![2, 3, 4, 0, 0, 0, 0, 0], get_stack_state(&stack, 1));
 
     assert_eq!(3, stack.depth);
@@ -32,16 +32,15 @@ fn assert() {
 }
 
 #[test]
-#[should_panic(expected = "ASSERT failed at step 1")]
 fn assert_fail() {
     let mut stack = init_stack(&[2, 3, 4], &[], &[], TRACE_LENGTH);
-    stack.execute(OpCode::Assert, OpHint::None);
+    assert_eq!(Err(ExecutionError::FailedAssertion { op: OpCode::Assert, step: 1 }), stack.execute(OpCode::Assert, OpHint::None));
 }
 
 #[test]
 fn asserteq() {
     let mut stack = init_stack(&[1, 1, 3, 4], &[], &[], TRACE_LENGTH);
-    stack.execute(OpCode::AssertEq, OpHint::None);
+    stack.execute(OpCode::AssertEq, OpHint::None).unwrap();
     assert_eq!(vec![3, 4, 0, 0, 0, 0, 0, 0], get_stack_state(&stack, 1));
 
     assert_eq!(2, stack.depth);
@@ -49,10 +48,9 @@ fn asserteq() {
 }
 
 #[test]
-#[should_panic(expected = "ASSERTEQ failed at step 1")]
 fn asserteq_fail() {
     let mut stack = init_stack(&[2, 3, 4], &[], &[], TRACE_LENGTH);
-    stack.execute(OpCode::AssertEq, OpHint::None);
+    assert_eq!(Err(ExecutionError::FailedAssertion { op: OpCode::AssertEq, step: 1 }), stack.execute(OpCode::AssertEq, OpHint::None));
 }
 
 // INPUT OPERATIONS
@@ -61,7 +59,7 @@ fn asserteq_fail() {
 #[test]
 fn push() {
     let mut stack = init_stack(&[], &[], &[], TRACE_LENGTH);
-    stack.execute(OpCode::Push, OpHint::PushValue(3));
+    stack.execute(OpCode::Push, OpHint::PushValue(3)).unwrap();
     assert_eq!(vec![3, 0, 0, 0, 0, 0, 0, 0], get_stack_state(&stack, 1));
 
     assert_eq!(1, stack.depth);
@@ -72,13 +70,13 @@ fn push() {
 fn read() {
     let mut stack = init_stack(&[1], &[2, 3], &[], TRACE_LENGTH);
 
-    stack.execute(OpCode::Read, OpHint::None);
+    stack.execute(OpCode::Read, OpHint::None).unwrap();
     assert_eq!(vec![2, 1, 0, 0, 0, 0, 0, 0], get_stack_state(&stack, 1));
 
     assert_eq!(2, stack.depth);
     assert_eq!(2, stack.max_depth);
 
-    stack.execute(OpCode::Read, OpHint::None);
+    stack.execute(OpCode::Read, OpHint::None).unwrap();
     assert_eq!(vec![3, 2, 1, 0, 0, 0, 0, 0], get_stack_state(&stack, 2));
 
     assert_eq!(3, stack.depth);
@@ -89,13 +87,13 @@ fn read() {
 fn read2() {
     let mut stack = init_stack(&[1], &[2, 4], &[3, 5], TRACE_LENGTH);
 
-    stack.execute(OpCode::Read2, OpHint::None);
+    stack.execute(OpCode::Read2, OpHint::None).unwrap();
     assert_eq!(vec![3, 2, 1, 0, 0, 0, 0, 0], get_stack_state(&stack, 1));
 
     assert_eq!(3, stack.depth);
     assert_eq!(3, stack.max_depth);
 
-    stack.execute(OpCode::Read2, OpHint::None);
+    stack.execute(OpCode::Read2, OpHint::None).unwrap();
     assert_eq!(vec![5, 4, 3, 2, 1, 0, 0, 0], get_stack_state(&stack, 2));
 
     assert_eq!(5, stack.depth);
@@ -108,7 +106,7 @@ fn read2() {
 #[test]
 fn dup() {
     let mut stack = init_stack(&[1, 2], &[], &[], TRACE_LENGTH);
-    stack.execute(OpCode::Dup, OpHint::None);
+    stack.execute(OpCode::Dup, OpHint::None).unwrap();
     assert_eq!(vec![1, 1, 2, 0, 0, 0, 0, 0], get_stack_state(&stack, 1));
 
     assert_eq!(3, stack.depth);
@@ -118,7 +116,7 @@ fn dup() {
 #[test]
 fn dup2() {
     let mut stack = init_stack(&[1, 2, 3, 4], &[], &[], TRACE_LENGTH);
-    stack.execute(OpCode::Dup2, OpHint::None);
+    stack.execute(OpCode::Dup2, OpHint::None).unwrap();
     assert_eq!(vec![1, 2, 1, 2, 3, 4, 0, 0], get_stack_state(&stack, 1));
 
     assert_eq!(6, stack.depth);
@@ -128,7 +126,7 @@ fn dup2() {
 #[test]
 fn dup4() {
     let mut stack = init_stack(&[1, 2, 3, 4], &[], &[], TRACE_LENGTH);
-    stack.execute(OpCode::Dup4, OpHint::None);
+    stack.execute(OpCode::Dup4, OpHint::None).unwrap();
     assert_eq!(vec![1, 2, 3, 4, 1, 2, 3, 4], get_stack_state(&stack, 1));
 
     assert_eq!(8, stack.depth);
@@ -138,7 +136,7 @@ fn dup4() {
 #[test]
 fn pad2() {
     let mut stack = init_stack(&[1, 2], &[], &[], TRACE_LENGTH);
-    stack.execute(OpCode::Pad2, OpHint::None);
+    stack.execute(OpCode::Pad2, OpHint::None).unwrap();
     assert_eq!(vec![0, 0, 1, 2, 0, 0, 0, 0], get_stack_state(&stack, 1));
 
     assert_eq!(4, stack.depth);
@@ -148,7 +146,7 @@ fn pad2() {
 #[test]
 fn drop() {
     let mut stack = init_stack(&[1, 2], &[], &[], TRACE_LENGTH);
-    stack.execute(OpCode::Drop, OpHint::None);
+    stack.execute(OpCode::Drop, OpHint::None).unwrap();
     assert_eq!(vec![2, 0, 0, 0, 0, 0, 0, 0], get_stack_state(&stack, 1));
 
     assert_eq!(1, stack.depth);
@@ -158,7 +156,7 @@ fn drop() {
 #[test]
 fn drop4() {
     let mut stack = init_stack(&[1, 2, 3, 4, 5], &[], &[], TRACE_LENGTH);
-    stack.execute(OpCode::Drop4, OpHint::None);
+    stack.execute(OpCode::Drop4, OpHint::None).unwrap();
     assert_eq!(vec![5, 0, 0, 0, 0, 0, 0, 0], get_stack_state(&stack, 1));
 
     assert_eq!(1, stack.depth);
@@ -168,7 +166,7 @@ fn drop4() {
 #[test]
 fn swap() {
     let mut stack = init_stack(&[1, 2, 3, 4], &[], &[], TRACE_LENGTH);
-    stack.execute(OpCode::Swap, OpHint::None);
+    stack.execute(OpCode::Swap, OpHint::None).unwrap();
     assert_eq!(vec![2, 1, 3, 4, 0, 0, 0, 0], get_stack_state(&stack, 1));
 
     assert_eq!(4, stack.depth);
@@ -178,7 +176,7 @@ fn swap() {
 #[test]
 fn swap2() {
     let mut stack = init_stack(&[1, 2, 3, 4], &[], &[], TRACE_LENGTH);
-    stack.execute(OpCode::Swap2, OpHint::None);
+    stack.execute(OpCode::Swap2, OpHint::None).unwrap();
     assert_eq!(vec![3, 4, 1, 2, 0, 0, 0, 0], get_stack_state(&stack, 1));
 
     assert_eq!(4, stack.depth);
@@ -188,7 +186,7 @@ fn swap2() {
 #[test]
 fn swap4() {
     let mut stack = init_stack(&[1, 2, 3, 4, 5, 6, 7, 8], &[], &[], TRACE_LENGTH);
-    stack.execute(OpCode::Swap4, OpHint::None);
+    stack.execute(OpCode::Swap4, OpHint::None).unwrap();
     assert_eq!(vec![5, 6, 7, 8, 1, 2, 3, 4], get_stack_state(&stack, 1));
 
     assert_eq!(8, stack.depth);
@@ -198,7 +196,7 @@ fn swap4() {
 #[test]
 fn roll4() {
     let mut stack = init_stack(&[1, 2, 3, 4], &[], &[], TRACE_LENGTH);
-    stack.execute(OpCode::Roll4, OpHint::None);
+    stack.execute(OpCode::Roll4, OpHint::None).unwrap();
     assert_eq!(vec![4, 1, 2, 3, 0, 0, 0, 0], get_stack_state(&stack, 1));
 
     assert_eq!(4, stack.depth);
@@ -208,7 +206,7 @@ fn roll4() {
 #[test]
 fn roll8() {
     let mut stack = init_stack(&[1, 2, 3, 4, 5, 6, 7, 8], &[], &[], TRACE_LENGTH);
-    stack.execute(OpCode::Roll8, OpHint::None);
+    stack.execute(OpCode::Roll8, OpHint::None).unwrap();
     assert_eq!(vec![8, 1, 2, 3, 4, 5, 6, 7], get_stack_state(&stack, 1));
 
     assert_eq!(8, stack.depth);
@@ -221,7 +219,7 @@ fn roll8() {
 #[test]
 fn add() {
     let mut stack = init_stack(&[1, 2], &[], &[], TRACE_LENGTH);
-    stack.execute(OpCode::Add, OpHint::None);
+    stack.execute(OpCode::Add, OpHint::None).unwrap();
     assert_eq!(vec![3, 0, 0, 0, 0, 0, 0, 0], get_stack_state(&stack, 1));
 
     assert_eq!(1, stack.depth);
@@ -231,7 +229,7 @@ fn add() {
 #[test]
 fn mul() {
     let mut stack = init_stack(&[2, 3], &[], &[], TRACE_LENGTH);
-    stack.execute(OpCode::Mul, OpHint::None);
+    stack.execute(OpCode::Mul, OpHint::None).unwrap();
     assert_eq!(vec![6, 0, 0, 0, 0, 0, 0, 0], get_stack_state(&stack, 1));
 
     assert_eq!(1, stack.depth);
@@ -241,7 +239,7 @@ fn mul() {
 #[test]
 fn inv() {
     let mut stack = init_stack(&[2, 3], &[], &[], TRACE_LENGTH);
-    stack.execute(OpCode::Inv, OpHint::None);
+    stack.execute(OpCode::Inv, OpHint::None).unwrap();
     assert_eq!(vec![field::inv(2), 3, 0, 0, 0, 0, 0, 0], get_stack_state(&stack, 1));
 
     assert_eq!(2, stack.depth);
@@ -249,16 +247,15 @@ fn inv() {
 }
 
 #[test]
-#[should_panic(expected = "cannot compute INV of 0 at step 1")]
 fn inv_zero() {
     let mut stack = init_stack(&[0], &[], &[], TRACE_LENGTH);
-    stack.execute(OpCode::Inv, OpHint::None);
+    assert_eq!(Err(ExecutionError::InvalidOperand { op: OpCode::Inv, step: 1 }), stack.execute(OpCode::Inv, OpHint::None));
 }
 
 #[test]
 fn neg() {
     let mut stack = init_stack(&[2, 3], &[], &[], TRACE_LENGTH);
-    stack.execute(OpCode::Neg, OpHint::None);
+    stack.execute(OpCode::Neg, OpHint::None).unwrap();
     assert_eq!(vec![field::neg(2), 3, 0, 0, 0, 0, 0, 0], get_stack_state(&stack, 1));
 
     assert_eq!(2, stack.depth);
@@ -268,13 +265,13 @@ fn neg() {
 #[test]
 fn not() {
     let mut stack = init_stack(&[1, 2], &[], &[], TRACE_LENGTH);
-    stack.execute(OpCode::Not, OpHint::None);
+    stack.execute(OpCode::Not, OpHint::None).unwrap();
     assert_eq!(vec![0, 2, 0, 0, 0, 0, 0, 0], get_stack_state(&stack, 1));
 
     assert_eq!(2, stack.depth);
     assert_eq!(2, stack.max_depth);
 
-    stack.execute(OpCode::Not, OpHint::None);
+    stack.execute(OpCode::Not, OpHint::None).unwrap();
     assert_eq!(vec![1, 2, 0, 0, 0, 0, 0, 0], get_stack_state(&stack, 2));
 
     assert_eq!(2, stack.depth);
@@ -282,22 +279,21 @@ fn not() {
 }
 
 #[test]
-#[should_panic(expected = "cannot compute NOT of a non-binary value at step 1")]
 fn not_fail() {
     let mut stack = init_stack(&[2, 3], &[], &[], TRACE_LENGTH);
-    stack.execute(OpCode::Not, OpHint::None);
+    assert_eq!(Err(ExecutionError::NonBinaryValue { op: OpCode::Not, step: 1 }), stack.execute(OpCode::Not, OpHint::None));
 }
 
 #[test]
 fn and() {
     let mut stack = init_stack(&[1, 1, 0], &[], &[], TRACE_LENGTH);
-    stack.execute(OpCode::And, OpHint::None);
+    stack.execute(OpCode::And, OpHint::None).unwrap();
     assert_eq!(vec![1, 0, 0, 0, 0, 0, 0, 0], get_stack_state(&stack, 1));
 
     assert_eq!(2, stack.depth);
     assert_eq!(3, stack.max_depth);
 
-    stack.execute(OpCode::And, OpHint::None);
+    stack.execute(OpCode::And, OpHint::None).unwrap();
     assert_eq!(vec![0, 0, 0, 0, 0, 0, 0, 0], get_stack_state(&stack, 2));
 
     assert_eq!(1, stack.depth);
@@ -305,22 +301,21 @@ fn and() {
 }
 
 #[test]
-#[should_panic(expected = "cannot compute AND for a non-binary value at step 1")]
 fn and_fail() {
     let mut stack = init_stack(&[1, 3], &[], &[], TRACE_LENGTH);
-    stack.execute(OpCode::And, OpHint::None);
+    assert_eq!(Err(ExecutionError::NonBinaryValue { op: OpCode::And, step: 1 }), stack.execute(OpCode::And, OpHint::None));
 }
 
 #[test]
 fn or() {
     let mut stack = init_stack(&[0, 0, 1], &[], &[], TRACE_LENGTH);
-    stack.execute(OpCode::Or, OpHint::None);
+    stack.execute(OpCode::Or, OpHint::None).unwrap();
     assert_eq!(vec![0, 1, 0, 0, 0, 0, 0, 0], get_stack_state(&stack, 1));
 
     assert_eq!(2, stack.depth);
     assert_eq!(3, stack.max_depth);
 
-    stack.execute(OpCode::Or, OpHint::None);
+    stack.execute(OpCode::Or, OpHint::None).unwrap();
     assert_eq!(vec![1, 0, 0, 0, 0, 0, 0, 0], get_stack_state(&stack, 2));
 
     assert_eq!(1, stack.depth);
@@ -328,10 +323,9 @@ fn or() {
 }
 
 #[test]
-#[should_panic(expected = "cannot compute OR for a non-binary value at step 1")]
 fn and_or() {
     let mut stack = init_stack(&[1, 3], &[], &[], TRACE_LENGTH);
-    stack.execute(OpCode::Or, OpHint::None);
+    assert_eq!(Err(ExecutionError::NonBinaryValue { op: OpCode::Or, step: 1 }), stack.execute(OpCode::Or, OpHint::None));
 }
 
 // CRYPTOGRAPHIC OPERATIONS
@@ -342,11 +336,11 @@ fn rescr() {
     let mut stack = init_stack(&[0, 0, 1, 2, 3, 4], &[], &[], TRACE_LENGTH);
     let mut expected = vec![0, 0, 1, 2, 3, 4, 0, 0];
 
-    stack.execute(OpCode::RescR, OpHint::None);
+    stack.execute(OpCode::RescR, OpHint::None).unwrap();
     hasher::apply_round(&mut expected[..HASH_STATE_WIDTH], 0);
     assert_eq!(expected, get_stack_state(&stack, 1));
 
-    stack.execute(OpCode::RescR, OpHint::None);
+    stack.execute(OpCode::RescR, OpHint::None).unwrap();
     hasher::apply_round(&mut expected[..HASH_STATE_WIDTH], 1);
     assert_eq!(expected, get_stack_state(&stack, 2));
 
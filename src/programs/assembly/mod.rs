@@ -236,8 +236,12 @@ fn parse_op_token(op: Vec<&str>, op_codes: &mut Vec<OpCode>, op_hints: &mut Hint
         "choose" => parse_choose(op_codes, &op, step),
 
         "hash"   => parse_hash(op_codes, &op, step),
+        "poseidon" => parse_poseidon(op_codes, op_hints, &op, step),
         "smpath" => parse_smpath(op_codes, &op, step),
         "pmpath" => parse_pmpath(op_codes, op_hints, &op, step),
+        "mrupdate" => parse_mrupdate(op_codes, op_hints, &op, step),
+        "smt"    => parse_smt(op_codes, op_hints, &op, step),
+        "lamport" => parse_lamport(op_codes, op_hints, &op, step),
 
         _ => return Err(AssemblyError::invalid_op(&op, step))
     }?;
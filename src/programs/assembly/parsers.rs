@@ -1,4 +1,4 @@
-use crate::{ math::field };
+use crate::{ math::field, utils::poseidon };
 use super::{ AssemblyError, HintMap, OpCode, OpHint };
 
 // CONSTANTS
@@ -439,6 +439,58 @@ pub fn parse_hash(program: &mut Vec<OpCode>, op: &[&str], step: usize) -> Result
     return Ok(true);
 }
 
+/// Appends a sequence of operations to the program to hash top n values of the stack using
+/// Poseidon hash function. The result is the same as the one computed by Poseidon hasher
+/// in `crypto::hash` module for the same values.
+pub fn parse_poseidon(program: &mut Vec<OpCode>, hints: &mut HintMap, op: &[&str], step: usize) -> Result<bool, AssemblyError> {
+    let n = read_param(op, step)?;
+    match n {
+        1 => program.extend_from_slice(&[OpCode::Pad2, OpCode::Pad2, OpCode::Drop]),
+        2 => program.push(OpCode::Pad2),
+        3 => program.extend_from_slice(&[OpCode::Pad2, OpCode::Drop]),
+        4 => (),
+        _ => return Err(AssemblyError::invalid_param_reason(op, step,
+            format!("parameter {} is invalid; allowed values are: [1, 2, 3, 4]", n)))
+    }
+
+    // add round constants of the first round to the 4 values at the top of the stack; the
+    // constants are added in the order [3, 0, 1, 2] because ROLL4 moves the 4th item to the top
+    let ark = poseidon::get_initial_constants();
+    for &i in [3, 0, 1, 2].iter() {
+        append_push_op(program, hints, ark[5 - i]);
+        program.extend_from_slice(&[OpCode::Add, OpCode::Roll4]);
+    }
+
+    // the capacity portion of the state is initialized to zeros, and so round constants can be
+    // pushed onto the stack directly
+    append_push_op(program, hints, ark[1]);
+    append_push_op(program, hints, ark[0]);
+
+    // push cycle selector onto the stack; the selector is set to the first cycle
+    program.extend_from_slice(&[OpCode::Pad2, OpCode::Pad2, OpCode::Pad2, OpCode::Drop]);
+    append_push_op(program, hints, field::ONE);
+
+    // pad with NOOPs to make sure hashing starts on a step which is a multiple of 16
+    let alignment = program.len() % HASH_OP_ALIGNMENT;
+    let pad_length = (HASH_OP_ALIGNMENT - alignment) % HASH_OP_ALIGNMENT;
+    program.resize(program.len() + pad_length, OpCode::Noop);
+
+    // append operations to execute all rounds of Poseidon
+    for i in 0..poseidon::NUM_ROUNDS {
+        if poseidon::is_full_round(i) {
+            program.push(OpCode::PoseF);
+        }
+        else {
+            program.push(OpCode::PoseP);
+        }
+    }
+
+    // drop the cycle selector and truncate the state
+    program.extend_from_slice(&[OpCode::Drop4, OpCode::Drop4, OpCode::Drop, OpCode::Drop]);
+
+    return Ok(true);
+}
+
 /// Appends a sequence of operations to the program to compute the root of Merkle authentication
 /// path for a tree of depth n. Leaf index is expected to be provided via input tapes A and B.
 pub fn parse_smpath(program: &mut Vec<OpCode>, op: &[&str], step: usize) -> Result<bool, AssemblyError> {
@@ -492,6 +544,126 @@ pub fn parse_pmpath(program: &mut Vec<OpCode>, hints: &mut HintMap, op: &[&str],
             format!("parameter {} is invalid; value must be between 2 and 256", n)))
     }
 
+    append_pmpath(program, hints, n);
+    return Ok(true);
+}
+
+/// Appends a sequence of operations to the program to compute roots of Merkle authentication
+/// path for a tree of depth n before and after a leaf is updated. The new leaf value is expected
+/// to be at the top of the stack followed by the old leaf value and leaf index; nodes of the path
+/// are expected to be provided via input tapes A and B and are read only once.
+pub fn parse_mrupdate(program: &mut Vec<OpCode>, hints: &mut HintMap, op: &[&str], step: usize) -> Result<bool, AssemblyError> {
+    let n = read_param(op, step)?;
+    if n < 2 || n > 256 {
+        return Err(AssemblyError::invalid_param_reason(op, step,
+            format!("parameter {} is invalid; value must be between 2 and 256", n)))
+    }
+
+    append_mrupdate(program, hints, n);
+    return Ok(true);
+}
+
+/// Appends a sequence of operations to the program to verify or update a value stored in a
+/// sparse Merkle tree of depth n. Authentication paths are expected to be provided via input
+/// tapes A and B; the following variants are supported:
+/// * smt.get.n: pops value, key, and root from the stack, and verifies that the value is stored
+///   under the key in a tree with the specified root.
+/// * smt.absent.n: pops key and root from the stack, and verifies that nothing is stored under
+///   the key in a tree with the specified root.
+/// * smt.set.n: pops new value, old value, key, and root from the stack, verifies that the old
+///   value is stored under the key, and pushes the root of the tree with the new value onto
+///   the stack.
+pub fn parse_smt(program: &mut Vec<OpCode>, hints: &mut HintMap, op: &[&str], step: usize) -> Result<bool, AssemblyError> {
+    if op.len() < 3 {
+        return Err(AssemblyError::missing_param(op, step));
+    }
+    else if op.len() > 3 {
+        return Err(AssemblyError::extra_param(op, step));
+    }
+
+    let n = match op[2].parse::<u32>() {
+        Ok(i) => i,
+        Err(_) => return Err(AssemblyError::invalid_param(op, step))
+    };
+    if n < 2 || n > 128 {
+        return Err(AssemblyError::invalid_param_reason(op, step,
+            format!("parameter {} is invalid; value must be between 2 and 128", n)))
+    }
+
+    match op[1] {
+        "get" => {
+            append_pmpath(program, hints, n);
+            append_root_check(program);
+        },
+        "absent" => {
+            // an absent key is the same as a key with value [0, 0]
+            program.push(OpCode::Pad2);
+            append_pmpath(program, hints, n);
+            append_root_check(program);
+        },
+        "set" => {
+            // compute old and new roots; this leaves [new_r1, new_r0, old_r1, old_r0, r1, r0]
+            // at the top of the stack, and then make sure the old root is equal to r
+            append_mrupdate(program, hints, n);
+            program.extend_from_slice(&[
+                OpCode::Roll4, OpCode::Dup2,     OpCode::Roll8, OpCode::Dup2,
+                OpCode::AssertEq, OpCode::Drop4, OpCode::Swap2, OpCode::AssertEq,
+            ]);
+        },
+        _ => return Err(AssemblyError::invalid_param_reason(op, step,
+            format!("parameter {} is invalid; allowed values are: [get, absent, set]", op[1])))
+    }
+
+    return Ok(true);
+}
+
+/// Appends a sequence of operations to the program to verify a Lamport one-time signature of an
+/// n-bit message. The message is expected to be at the top of the stack followed by the public
+/// key commitment; the signature is expected to be provided via input tapes A and B.
+pub fn parse_lamport(program: &mut Vec<OpCode>, hints: &mut HintMap, op: &[&str], step: usize) -> Result<bool, AssemblyError> {
+    let n = read_param(op, step)?;
+    if n > 128 {
+        return Err(AssemblyError::invalid_param_reason(op, step,
+            format!("parameter {} is invalid; value must be between 1 and 128", n)))
+    }
+
+    // rearrange the stack into [0, 0, 1, 0, m, k1, k0] so that the public key commitment is
+    // initialized to [0, 0], binary multiplier is set to 1, and binary accumulator is set to 0
+    program.push(OpCode::Pad2);
+    append_push_op(program, hints, field::ONE);
+    program.extend_from_slice(&[OpCode::Swap, OpCode::Dup]);
+
+    // for each bit of the message:
+    // 1. read the next bit of the message (using binacc instruction)
+    // 2. read the revealed secret key element and compute its hash
+    // 3. read the hash of the other secret key element, and base on the bit value, swap the
+    //    hashes on the stack (using cswap2 instruction) and merge them into a public key element
+    // 4. merge the public key element into the public key commitment
+    for _ in 0..n {
+        program.extend_from_slice(&[
+            OpCode::Swap2, OpCode::Pad2,  OpCode::BinAcc, OpCode::Read2, OpCode::Swap4,
+            OpCode::Swap2, OpCode::Swap4, OpCode::Pad2,   OpCode::Pad2,
+        ]);
+        append_rescue_rounds(program);
+        program.extend_from_slice(&[OpCode::Read2, OpCode::CSwap2, OpCode::Pad2]);
+        append_rescue_rounds(program);
+        program.push(OpCode::Pad2);
+        append_rescue_rounds(program);
+    }
+
+    // make sure the accumulated value is equal to the message, and the computed commitment
+    // is equal to the public key commitment
+    program.extend_from_slice(&[OpCode::Swap2, OpCode::Drop, OpCode::Roll4, OpCode::AssertEq]);
+    append_root_check(program);
+
+    return Ok(true);
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Appends operations of pmpath.n macro to the program.
+fn append_pmpath(program: &mut Vec<OpCode>, hints: &mut HintMap, n: u32) {
     // add a hint indicating that pmpath macro is about to begin
     hints.insert(program.len(), OpHint::PmpathStart(n));
     
@@ -533,12 +705,70 @@ pub fn parse_pmpath(program: &mut Vec<OpCode>, hints: &mut HintMap, op: &[&str],
     // then make sure the accumulated value of index is indeed equal to the leaf index
     program.extend_from_slice(&SUB_CYCLE[..11]);
     program.extend_from_slice(&[OpCode::Swap2, OpCode::Drop, OpCode::Roll4, OpCode::AssertEq]);
+}
 
-    return Ok(true);
+/// Appends operations of mrupdate.n macro to the program.
+fn append_mrupdate(program: &mut Vec<OpCode>, hints: &mut HintMap, n: u32) {
+    // rearrange the stack into [0, 0, 1, 0, old1, old0, new1, new0, index] so that binary
+    // multiplier is set to 1 and binary accumulator is set to 0
+    program.extend_from_slice(&[OpCode::Swap2, OpCode::Pad2]);
+    append_push_op(program, hints, field::ONE);
+    program.extend_from_slice(&[OpCode::Swap, OpCode::Dup]);
+
+    // add a hint indicating that mrupdate macro is about to begin
+    hints.insert(program.len(), OpHint::MrupdateStart(n));
+
+    // for each node of the path, read the next bit of the index (using binacc instruction) and
+    // the next node of the path, duplicate both, and use them to arrange the stack so that
+    // the next old node can be hashed, while the next new node is prepared right below it
+    const PREP_CYCLE: [OpCode; 13] = [
+        OpCode::BinAcc, OpCode::Swap2, OpCode::Swap4,  OpCode::Roll8, OpCode::Roll8,
+        OpCode::Read2,  OpCode::Dup2,  OpCode::Swap4,  OpCode::Dup2,  OpCode::Swap4,
+        OpCode::Swap2,  OpCode::CSwap2, OpCode::Pad2,
+    ];
+
+    // after the old node is hashed, move it below the new node and prepare the new node for
+    // hashing; after the new node is hashed, the stack is [new, old, multiplier, accumulator]
+    const HASH_CYCLE: [OpCode; 4] = [OpCode::Swap2, OpCode::Swap4, OpCode::CSwap2, OpCode::Pad2];
+
+    // move binary multiplier and accumulator back to the top of the stack
+    const NEXT_CYCLE: [OpCode; 5] = [
+        OpCode::Swap2, OpCode::Pad2, OpCode::Roll8, OpCode::Roll8, OpCode::Swap2,
+    ];
+
+    for i in 0..(n - 1) {
+        if i > 0 {
+            program.extend_from_slice(&NEXT_CYCLE);
+        }
+        program.extend_from_slice(&PREP_CYCLE);
+        append_rescue_rounds(program);
+        program.extend_from_slice(&HASH_CYCLE);
+        append_rescue_rounds(program);
+    }
+
+    // make sure the accumulated value of index is equal to the leaf index, and drop all
+    // values used in computations except for the new and the old roots
+    program.extend_from_slice(&[
+        OpCode::Dup, OpCode::Roll8, OpCode::Roll8, OpCode::Roll8,
+        OpCode::Drop, OpCode::AssertEq, OpCode::Drop,
+    ]);
 }
 
-// HELPER FUNCTIONS
-// ================================================================================================
+/// Appends operations to the program to compute a hash of the top 6 items of the stack and
+/// truncate the result to 2 items. The hashing is aligned on a step which is a multiple of 16.
+fn append_rescue_rounds(program: &mut Vec<OpCode>) {
+    let alignment = program.len() % HASH_OP_ALIGNMENT;
+    let pad_length = (HASH_OP_ALIGNMENT - alignment) % HASH_OP_ALIGNMENT;
+    program.resize(program.len() + pad_length, OpCode::Noop);
+    program.resize(program.len() + 10, OpCode::RescR);
+    program.push(OpCode::Drop4);
+}
+
+/// Appends operations which pop two roots from the top of the stack and make sure they are
+/// equal; the stack is expected to be [a1, a0, b1, b0].
+fn append_root_check(program: &mut Vec<OpCode>) {
+    program.extend_from_slice(&[OpCode::Swap, OpCode::Roll4, OpCode::AssertEq, OpCode::AssertEq]);
+}
 
 fn read_param(op: &[&str], step: usize) -> Result<u32, AssemblyError> {
     if op.len() == 1 {
@@ -1,4 +1,5 @@
-use std::collections::HashMap;
+use std::{ collections::HashMap, convert::TryFrom };
+use serde::{ Serialize, Deserialize };
 use super::{ OpCode, OpHint, hash_seq, hash_op, BASE_CYCLE_LENGTH };
 
 #[cfg(test)]
@@ -26,7 +27,7 @@ const LOOP_BLOCK_SUFFIX: [u8; 16] = [
 // TYPES AND INTERFACES
 // ================================================================================================
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub enum ProgramBlock {
     Span(Span),
     Group(Group),
@@ -34,26 +35,31 @@ pub enum ProgramBlock {
     Loop(Loop),
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "SpanParts")]
 pub struct Span {
     op_codes    : Vec<OpCode>,
     op_hints    : HashMap<usize, OpHint>,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "GroupParts")]
 pub struct Group {
     body        : Vec<ProgramBlock>,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "SwitchParts")]
 pub struct Switch {
     t_branch    : Vec<ProgramBlock>,
     f_branch    : Vec<ProgramBlock>,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "LoopParts")]
 pub struct Loop {
     body        : Vec<ProgramBlock>,
+    #[serde(skip_serializing)]
     skip        : Vec<ProgramBlock>,
 }
 
@@ -88,29 +94,8 @@ impl std::fmt::Debug for ProgramBlock {
 impl Span {
 
     pub fn new(instructions: Vec<OpCode>, hints: HashMap<usize, OpHint>) -> Span {
-        let alignment = instructions.len() % BASE_CYCLE_LENGTH;
-        assert!(alignment == BASE_CYCLE_LENGTH - 1,
-            "invalid number of instructions: expected one less than a multiple of {}, but was {}",
-            BASE_CYCLE_LENGTH, instructions.len());
-
-        // make sure all instructions are valid
-        for i in 0..instructions.len() {
-            let op_code = instructions[i];
-            if op_code == OpCode::Push {
-                assert!(i % 8 == 0, "PUSH is not allowed on step {}, must be on step which is a multiple of 8", i);
-                let hint = hints.get(&i);
-                assert!(hint.is_some(), "invalid PUSH operation on step {}: operation value is missing", i);
-                match hint.unwrap() {
-                    OpHint::PushValue(_) => (),
-                    _ => panic!("invalid PUSH operation on step {}: operation value is of wrong type", i)
-                }
-            }
-        }
-
-        // make sure all hints are within bounds
-        for &step in hints.keys() {
-            assert!(step < instructions.len(), "hint out of bounds: step must be smaller than {} but is {}",
-                instructions.len(), step);
+        if let Err(msg) = validate_span(&instructions, &hints) {
+            panic!("{}", msg);
         }
 
         return Span {
@@ -196,7 +181,9 @@ impl std::fmt::Debug for Span {
 impl Group {
 
     pub fn new(body: Vec<ProgramBlock>) -> Group {
-        validate_block_list(&body, &[]);
+        if let Err(msg) = validate_block_list(&body, &[]) {
+            panic!("{}", msg);
+        }
         return Group { body };
     }
 
@@ -233,8 +220,9 @@ impl std::fmt::Debug for Group {
 impl Switch {
 
     pub fn new(true_branch: Vec<ProgramBlock>, false_branch: Vec<ProgramBlock>) -> Switch {
-        validate_block_list(&true_branch, &[OpCode::Assert]);
-        validate_block_list(&false_branch, &[OpCode::Not, OpCode::Assert]);
+        if let Err(msg) = validate_switch(&true_branch, &false_branch) {
+            panic!("{}", msg);
+        }
         return Switch {
             t_branch    : true_branch,
             f_branch    : false_branch
@@ -287,7 +275,9 @@ impl std::fmt::Debug for Switch {
 impl Loop {
 
     pub fn new(body: Vec<ProgramBlock>) -> Loop {
-        validate_block_list(&body, &[OpCode::Assert]);
+        if let Err(msg) = validate_block_list(&body, &[OpCode::Assert]) {
+            panic!("{}", msg);
+        }
 
         let skip_block = Span::from_instructions(LOOP_SKIP_BLOCK.to_vec());
         let skip = vec![ProgramBlock::Span(skip_block)];
@@ -338,20 +328,61 @@ impl std::fmt::Debug for Loop {
 
 // HELPER FUNCTIONS
 // ================================================================================================
-fn validate_block_list(blocks: &Vec<ProgramBlock>, starts_with: &[OpCode]) {
+fn validate_span(instructions: &[OpCode], hints: &HashMap<usize, OpHint>) -> Result<(), String> {
 
-    assert!(blocks.len() > 0, "a sequence of blocks must contain at least one block");
+    let alignment = instructions.len() % BASE_CYCLE_LENGTH;
+    if alignment != BASE_CYCLE_LENGTH - 1 {
+        return Err(format!("invalid number of instructions: expected one less than a multiple of {}, but was {}",
+            BASE_CYCLE_LENGTH, instructions.len()));
+    }
+
+    // make sure all instructions are valid
+    for i in 0..instructions.len() {
+        let op_code = instructions[i];
+        if op_code == OpCode::Push {
+            if i % 8 != 0 {
+                return Err(format!("PUSH is not allowed on step {}, must be on step which is a multiple of 8", i));
+            }
+            match hints.get(&i) {
+                Some(OpHint::PushValue(_)) => (),
+                Some(_) => return Err(format!("invalid PUSH operation on step {}: operation value is of wrong type", i)),
+                None => return Err(format!("invalid PUSH operation on step {}: operation value is missing", i)),
+            }
+        }
+    }
+
+    // make sure all hints are within bounds
+    for &step in hints.keys() {
+        if step >= instructions.len() {
+            return Err(format!("hint out of bounds: step must be smaller than {} but is {}",
+                instructions.len(), step));
+        }
+    }
+
+    return Ok(());
+}
+
+fn validate_switch(true_branch: &Vec<ProgramBlock>, false_branch: &Vec<ProgramBlock>) -> Result<(), String> {
+    validate_block_list(true_branch, &[OpCode::Assert])?;
+    validate_block_list(false_branch, &[OpCode::Not, OpCode::Assert])?;
+    return Ok(());
+}
+
+fn validate_block_list(blocks: &Vec<ProgramBlock>, starts_with: &[OpCode]) -> Result<(), String> {
+
+    if blocks.len() == 0 {
+        return Err(String::from("a sequence of blocks must contain at least one block"));
+    }
     
     // first block must be a span block
     match &blocks[0] {
         ProgramBlock::Span(block) => {
             // if the block must start with a specific sequence of instructions, make sure it does
-            if starts_with.len() > 0 {
-                assert!(block.starts_with(starts_with),
-                    "the first block does not start with a valid sequence of instructions");
+            if starts_with.len() > 0 && !block.starts_with(starts_with) {
+                return Err(String::from("the first block does not start with a valid sequence of instructions"));
             }
         },
-        _ => panic!("a sequence of blocks must start with a Span block"),
+        _ => return Err(String::from("a sequence of blocks must start with a Span block")),
     };
 
     // span block cannot be followed by another span block
@@ -359,9 +390,76 @@ fn validate_block_list(blocks: &Vec<ProgramBlock>, starts_with: &[OpCode]) {
     for i in 1..blocks.len() {
         match &blocks[i] {
             ProgramBlock::Span(_) => {
-                assert!(was_span == false, "a Span block cannot be followed by another Span block");
+                if was_span {
+                    return Err(String::from("a Span block cannot be followed by another Span block"));
+                }
             },
             _ => was_span = false,
         }
     }
-}
\ No newline at end of file
+
+    return Ok(());
+}
+
+// DESERIALIZATION
+// ================================================================================================
+// blocks are de-serialized via the same checks which are used to validate them when they are
+// built in code; invalid blocks are reported as de-serialization errors.
+
+#[derive(Deserialize)]
+struct SpanParts {
+    op_codes    : Vec<OpCode>,
+    op_hints    : HashMap<usize, OpHint>,
+}
+
+impl TryFrom<SpanParts> for Span {
+    type Error = String;
+
+    fn try_from(parts: SpanParts) -> Result<Span, String> {
+        validate_span(&parts.op_codes, &parts.op_hints)?;
+        return Ok(Span { op_codes: parts.op_codes, op_hints: parts.op_hints });
+    }
+}
+
+#[derive(Deserialize)]
+struct GroupParts {
+    body        : Vec<ProgramBlock>,
+}
+
+impl TryFrom<GroupParts> for Group {
+    type Error = String;
+
+    fn try_from(parts: GroupParts) -> Result<Group, String> {
+        validate_block_list(&parts.body, &[])?;
+        return Ok(Group { body: parts.body });
+    }
+}
+
+#[derive(Deserialize)]
+struct SwitchParts {
+    t_branch    : Vec<ProgramBlock>,
+    f_branch    : Vec<ProgramBlock>,
+}
+
+impl TryFrom<SwitchParts> for Switch {
+    type Error = String;
+
+    fn try_from(parts: SwitchParts) -> Result<Switch, String> {
+        validate_switch(&parts.t_branch, &parts.f_branch)?;
+        return Ok(Switch { t_branch: parts.t_branch, f_branch: parts.f_branch });
+    }
+}
+
+#[derive(Deserialize)]
+struct LoopParts {
+    body        : Vec<ProgramBlock>,
+}
+
+impl TryFrom<LoopParts> for Loop {
+    type Error = String;
+
+    fn try_from(parts: LoopParts) -> Result<Loop, String> {
+        validate_block_list(&parts.body, &[OpCode::Assert])?;
+        return Ok(Loop::new(parts.body));
+    }
+}
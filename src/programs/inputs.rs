@@ -1,11 +1,39 @@
-use crate::{ MAX_PUBLIC_INPUTS };
+use std::convert::TryFrom;
+use serde::{ Serialize, Deserialize };
+use crate::{ utils::serialization::field_elements, MAX_PUBLIC_INPUTS };
 
-#[derive(Clone, Debug)]
+/// Inputs of a program: public inputs which initialize the stack, and two secret input tapes.
+///
+/// Inputs can be serialized with serde. The serialized form is an object with `public`,
+/// `secret_a`, and `secret_b` fields, each of which is a list of field elements; all fields are
+/// optional when inputs are de-serialized. For example, in JSON:
+///
+/// ```json
+/// { "public": ["1", "0"], "secret_a": ["3", "4"], "secret_b": ["5"] }
+/// ```
+///
+/// In human-readable formats (e.g. JSON or TOML) field elements are written as decimal strings,
+/// but can be read either from strings (decimal or 0x-prefixed hexadecimal) or from integers
+/// which fit into 64 bits. De-serialized inputs are subject to the same checks as the inputs
+/// built via `ProgramInputs::new()`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(try_from = "InputsParts", into = "InputsParts")]
 pub struct ProgramInputs {
     public: Vec<u128>,
     secret: [Vec<u128>; 2],
 }
 
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct InputsParts {
+    #[serde(default, with = "field_elements")]
+    public      : Vec<u128>,
+    #[serde(default, with = "field_elements")]
+    secret_a    : Vec<u128>,
+    #[serde(default, with = "field_elements")]
+    secret_b    : Vec<u128>,
+}
+
 impl ProgramInputs {
 
     /// Returns `ProgramInputs` initialized with the provided public and secret inputs.
@@ -48,4 +76,59 @@ impl ProgramInputs {
     pub fn get_secret_inputs(&self) -> &[Vec<u128>; 2] {
         return &self.secret;
     }
-}
\ No newline at end of file
+}
+
+// SERIALIZATION
+// ================================================================================================
+
+impl TryFrom<InputsParts> for ProgramInputs {
+    type Error = String;
+
+    fn try_from(parts: InputsParts) -> Result<ProgramInputs, String> {
+        if parts.public.len() > MAX_PUBLIC_INPUTS {
+            return Err(format!("expected no more than {} public inputs, but received {}",
+                MAX_PUBLIC_INPUTS, parts.public.len()));
+        }
+        if parts.secret_a.len() < parts.secret_b.len() {
+            return Err(String::from(
+                "number of primary secret inputs cannot be smaller than the number of secondary secret inputs"));
+        }
+        return Ok(ProgramInputs::new(&parts.public, &parts.secret_a, &parts.secret_b));
+    }
+}
+
+impl From<ProgramInputs> for InputsParts {
+    fn from(inputs: ProgramInputs) -> InputsParts {
+        let [secret_a, secret_b] = inputs.secret;
+        return InputsParts { public: inputs.public, secret_a, secret_b };
+    }
+}
+
+// TESTS
+// ================================================================================================
+#[cfg(test)]
+mod tests {
+
+    use super::ProgramInputs;
+
+    #[test]
+    fn inputs_json_toml() {
+        let inputs = ProgramInputs::new(&[1, 2], &[3, u128::MAX >> 10], &[5]);
+
+        let json = serde_json::to_string(&inputs).unwrap();
+        let inputs2: ProgramInputs = serde_json::from_str(&json).unwrap();
+        assert_eq!(format!("{:?}", inputs), format!("{:?}", inputs2));
+
+        let toml = toml::to_string(&inputs).unwrap();
+        let inputs2: ProgramInputs = toml::from_str(&toml).unwrap();
+        assert_eq!(format!("{:?}", inputs), format!("{:?}", inputs2));
+
+        let inputs2: ProgramInputs = toml::from_str("public = [1, \"2\"]").unwrap();
+        assert_eq!(&[1, 2], inputs2.get_public_inputs());
+        assert!(inputs2.get_secret_inputs()[0].is_empty());
+
+        // secondary tape cannot be longer than the primary tape; unknown fields are rejected
+        assert!(serde_json::from_str::<ProgramInputs>(r#"{ "secret_b": [1] }"#).is_err());
+        assert!(serde_json::from_str::<ProgramInputs>(r#"{ "secret_c": [1] }"#).is_err());
+    }
+}
@@ -1,3 +1,5 @@
+use std::convert::TryFrom;
+use serde::{ Serialize, Deserialize };
 use crate::{
     math::field,
     processor::{ OpCode, OpHint },
@@ -13,6 +15,9 @@ use blocks::{ ProgramBlock, Span, Group, Switch, Loop };
 mod inputs;
 pub use inputs::{ ProgramInputs };
 
+mod statement;
+pub use statement::{ Statement };
+
 mod hashing;
 use hashing::{ hash_op, hash_acc, hash_seq };
 
@@ -21,26 +26,30 @@ mod tests;
 
 // TYPES AND INTERFACES
 // ================================================================================================
-#[derive(Clone)]
+/// A program which can be executed by Distaff VM. Programs can be serialized (e.g. with
+/// `bincode`); only the root block is serialized, and program hash is re-computed when a program
+/// is de-serialized.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "ProgramParts")]
 pub struct Program {
     root    : Group,
+    #[serde(skip_serializing)]
     hash    : [u8; 32],
 }
 
+#[derive(Deserialize)]
+struct ProgramParts {
+    root    : Group,
+}
+
 // PROGRAM IMPLEMENTATION
 // ================================================================================================
 impl Program {
 
     /// Constructs a new program from the specified root block.
     pub fn new(root: Group) -> Program {
-        
-        // make sure the root block starts with BEGIN operation
-        match &root.body()[0] {
-            ProgramBlock::Span(block) => {
-                let (op_code, _) = block.get_op(0);
-                assert!(op_code == OpCode::Begin, "a program must start with BEGIN operation");
-            },
-            _ => panic!("a program must start with a Span block")
+        if let Err(msg) = validate_root(&root) {
+            panic!("{}", msg);
         }
 
         // compute program hash
@@ -62,6 +71,15 @@ impl Program {
     }
 }
 
+impl TryFrom<ProgramParts> for Program {
+    type Error = String;
+
+    fn try_from(parts: ProgramParts) -> Result<Program, String> {
+        validate_root(&parts.root)?;
+        return Ok(Program::new(parts.root));
+    }
+}
+
 impl std::fmt::Debug for Program {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
     {
@@ -72,4 +90,21 @@ impl std::fmt::Debug for Program {
 
         return Ok(());
     }
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Makes sure the root block of a program starts with BEGIN operation.
+fn validate_root(root: &Group) -> Result<(), String> {
+    return match &root.body()[0] {
+        ProgramBlock::Span(block) => {
+            let (op_code, _) = block.get_op(0);
+            if op_code != OpCode::Begin {
+                return Err(String::from("a program must start with BEGIN operation"));
+            }
+            Ok(())
+        },
+        _ => Err(String::from("a program must start with a Span block")),
+    };
 }
\ No newline at end of file
@@ -0,0 +1,155 @@
+use std::convert::TryFrom;
+use serde::{ Serialize, Deserialize };
+use crate::{
+    crypto::Hasher,
+    stark::{ StarkProof, VerificationError },
+    utils::serialization::{ field_elements, digest },
+    MAX_PUBLIC_INPUTS, MAX_OUTPUTS,
+};
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// A claim that a program with the specified hash, executed with the specified public inputs
+/// (and some secret inputs), produces the specified outputs. A statement is what a `StarkProof`
+/// proves, and so it can be exchanged together with a serialized proof.
+///
+/// Statements can be serialized with serde. The serialized form is an object with
+/// `program_hash`, `public_inputs`, and `outputs` fields. In human-readable formats (e.g. JSON or
+/// TOML) program hash is written as a hex string, and field elements are written the same way as
+/// for `ProgramInputs`. For example, in JSON:
+///
+/// ```json
+/// {
+///     "program_hash": "d598c11aaf5b5382c531f06c3f859b90477e36836be4a3fd2e07b96e1e28962d",
+///     "public_inputs": ["1", "0"],
+///     "outputs": ["10946"]
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "StatementParts")]
+pub struct Statement {
+    #[serde(with = "digest")]
+    program_hash    : [u8; 32],
+    #[serde(with = "field_elements")]
+    public_inputs   : Vec<u128>,
+    #[serde(with = "field_elements")]
+    outputs         : Vec<u128>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct StatementParts {
+    #[serde(with = "digest")]
+    program_hash    : [u8; 32],
+    #[serde(default, with = "field_elements")]
+    public_inputs   : Vec<u128>,
+    #[serde(default, with = "field_elements")]
+    outputs         : Vec<u128>,
+}
+
+// STATEMENT IMPLEMENTATION
+// ================================================================================================
+impl Statement {
+
+    /// Returns a statement that a program with the specified hash, executed with the specified
+    /// public inputs, produces the specified outputs.
+    pub fn new(program_hash: &[u8; 32], public_inputs: &[u128], outputs: &[u128]) -> Statement {
+        assert!(public_inputs.len() <= MAX_PUBLIC_INPUTS,
+            "expected no more than {} public inputs, but received {}", MAX_PUBLIC_INPUTS, public_inputs.len());
+        assert!(outputs.len() <= MAX_OUTPUTS,
+            "expected no more than {} outputs, but received {}", MAX_OUTPUTS, outputs.len());
+
+        return Statement {
+            program_hash    : *program_hash,
+            public_inputs   : public_inputs.to_vec(),
+            outputs         : outputs.to_vec(),
+        };
+    }
+
+    pub fn program_hash(&self) -> &[u8; 32] {
+        return &self.program_hash;
+    }
+
+    pub fn public_inputs(&self) -> &[u128] {
+        return &self.public_inputs;
+    }
+
+    pub fn outputs(&self) -> &[u128] {
+        return &self.outputs;
+    }
+
+    /// Verifies that the `proof` proves this statement; this is the same as calling `verify()`
+    /// function with the values of this statement.
+    pub fn verify<H: Hasher>(&self, proof: &StarkProof<H>) -> Result<(), VerificationError> {
+        return crate::verify(&self.program_hash, &self.public_inputs, &self.outputs, proof);
+    }
+}
+
+// SERIALIZATION
+// ================================================================================================
+
+impl TryFrom<StatementParts> for Statement {
+    type Error = String;
+
+    fn try_from(parts: StatementParts) -> Result<Statement, String> {
+        if parts.public_inputs.len() > MAX_PUBLIC_INPUTS {
+            return Err(format!("expected no more than {} public inputs, but received {}",
+                MAX_PUBLIC_INPUTS, parts.public_inputs.len()));
+        }
+        if parts.outputs.len() > MAX_OUTPUTS {
+            return Err(format!("expected no more than {} outputs, but received {}",
+                MAX_OUTPUTS, parts.outputs.len()));
+        }
+        return Ok(Statement::new(&parts.program_hash, &parts.public_inputs, &parts.outputs));
+    }
+}
+
+// TESTS
+// ================================================================================================
+#[cfg(test)]
+mod tests {
+
+    use crate::{ ProgramInputs, ProofOptions, StarkProof, assembly };
+    use super::Statement;
+
+    #[test]
+    fn statement_json() {
+        let statement = Statement::new(&[7; 32], &[1, 0], &[u128::MAX >> 10]);
+        let json = serde_json::to_string(&statement).unwrap();
+        assert_eq!(statement, serde_json::from_str(&json).unwrap());
+
+        let json = r#"{ "program_hash": "0x0707070707070707070707070707070707070707070707070707070707070707",
+            "public_inputs": [1, "0x0"], "outputs": ["332306998946228968225951765070086143"] }"#;
+        assert_eq!(statement, serde_json::from_str(json).unwrap());
+
+        // invalid field elements and too many public inputs are rejected
+        let hash = "0707070707070707070707070707070707070707070707070707070707070707";
+        let json = format!(r#"{{ "program_hash": "{}", "outputs": ["{}"] }}"#, hash, u128::MAX);
+        assert!(serde_json::from_str::<Statement>(&json).is_err());
+        let json = format!(r#"{{ "program_hash": "{}", "outputs": [-1] }}"#, hash);
+        assert!(serde_json::from_str::<Statement>(&json).is_err());
+        let json = format!(r#"{{ "program_hash": "{}", "public_inputs": [1,2,3,4,5,6,7,8,9] }}"#, hash);
+        assert!(serde_json::from_str::<Statement>(&json).is_err());
+        assert!(serde_json::from_str::<Statement>(r#"{ "program_hash": "0707" }"#).is_err());
+    }
+
+    #[test]
+    fn verify_statement() {
+        let program = assembly::compile("begin push.3 read.a add end").unwrap();
+        let inputs: ProgramInputs = serde_json::from_str(r#"{ "public": [1], "secret_a": ["4"] }"#).unwrap();
+        let (outputs, proof) = crate::execute(&program, &inputs, 2, &ProofOptions::default());
+
+        let statement = Statement::new(program.hash(), inputs.get_public_inputs(), &outputs);
+        let proof_bytes = bincode::serialize(&proof).unwrap();
+        let statement_toml = toml::to_string(&statement).unwrap();
+
+        // a verifier gets the statement and the proof from the prover
+        let statement: Statement = toml::from_str(&statement_toml).unwrap();
+        let proof: StarkProof = bincode::deserialize(&proof_bytes).unwrap();
+        assert_eq!(Ok(()), statement.verify(&proof));
+
+        let statement = Statement::new(program.hash(), &[1], &[8, 1]);
+        assert!(statement.verify(&proof).is_err());
+    }
+}
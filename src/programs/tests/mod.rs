@@ -145,6 +145,31 @@ fn simple_loop() {
     assert_eq!(111, step);
 }
 
+#[test]
+fn serialization() {
+    let source = "begin push.3 read.a if.true add else mul end while.true dup drop read.a end end";
+    let program = crate::programs::assembly::compile(source).unwrap();
+
+    let bytes = bincode::serialize(&program).unwrap();
+    let program2 = bincode::deserialize::<Program>(&bytes).unwrap();
+    assert_eq!(program.hash(), program2.hash());
+    assert_eq!(format!("{:?}", program), format!("{:?}", program2));
+}
+
+#[test]
+fn deserialize_invalid_blocks() {
+    // serialized span with a PUSH operation but without a value for it
+    let op_hints = std::collections::HashMap::<usize, crate::processor::OpHint>::new();
+    let bytes = bincode::serialize(&(vec![OpCode::Push; 15], op_hints)).unwrap();
+    assert!(bincode::deserialize::<Span>(&bytes).is_err());
+
+    // serialized program which does not start with BEGIN operation
+    let root = Group::new(vec![Span::new_block(vec![OpCode::Noop; 15])]);
+    let bytes = bincode::serialize(&root).unwrap();
+    assert!(bincode::deserialize::<Group>(&bytes).is_ok());
+    assert!(bincode::deserialize::<Program>(&bytes).is_err());
+}
+
 // HELPER FUNCTIONS
 // ================================================================================================
 fn build_first_block(op_code: OpCode, length: usize) -> ProgramBlock {
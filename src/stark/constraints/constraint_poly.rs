@@ -1,52 +1,61 @@
-use crate::math::{ field, polynom, parallel };
-use crate::stark::{ MAX_CONSTRAINT_DEGREE, utils::CompositionCoefficients };
+use crate::math::{ field, polynom, polynom::Polynomial, fft::FftPlan, parallel };
+use crate::stark::{ MAX_CONSTRAINT_DEGREE, DOMAIN_OFFSET, utils::{ self, CompositionCoefficients } };
 
 // TYPES AND INTERFACES
 // ================================================================================================
 pub struct ConstraintPoly {
-    poly: Vec<u128>
+    poly        : Vec<u128>,
+    trace_length: usize,
 }
 
 // CONSTRAINT POLY IMPLEMENTATION
 // ================================================================================================
 impl ConstraintPoly {
-    pub fn new(poly: Vec<u128>) -> ConstraintPoly {
+    pub fn new(poly: Vec<u128>, trace_length: usize) -> ConstraintPoly {
 
         assert!(poly.len().is_power_of_two(), "poly length must be a power of two");
-        debug_assert!(get_expected_degree(&poly) == polynom::degree_of(&poly),
+        debug_assert!(get_expected_degree(&poly, trace_length) == polynom::degree_of(&poly),
             "expected polynomial of degree {} but received degree {}",
-            get_expected_degree(&poly),
+            get_expected_degree(&poly, trace_length),
             polynom::degree_of(&poly));
 
-        return ConstraintPoly { poly };
+        return ConstraintPoly { poly, trace_length };
     }
 
     pub fn degree(&self) -> usize {
-        return get_expected_degree(&self.poly);
+        return get_expected_degree(&self.poly, self.trace_length);
     }
 
-    pub fn eval(&self, twiddles: &[u128]) -> Vec<u128> {
-        let domain_size = twiddles.len() * 2;
+    /// Adds the `other` polynomial into this polynomial; both polynomials must describe
+    /// constraints of traces of the same length.
+    pub fn add(&mut self, other: &ConstraintPoly) {
+        assert!(self.trace_length == other.trace_length, "trace lengths of constraint polynomials must match");
+        assert!(self.poly.len() == other.poly.len(), "constraint polynomials must have the same length");
+        parallel::add_in_place(&mut self.poly, &other.poly, 1);
+    }
+
+    /// Evaluates the polynomial over the domain of `domain_size` elements shifted by DOMAIN_OFFSET.
+    pub fn eval(&self, fft_plan: &FftPlan<u128>, domain_size: usize) -> Vec<u128> {
         assert!(domain_size > self.poly.len(), "domain size must be greater than poly length");
 
         let mut evaluations = vec![field::ZERO; domain_size];
         evaluations[..self.poly.len()].copy_from_slice(&self.poly);
-        polynom::eval_fft_twiddles(&mut evaluations, twiddles, true);
+        fft_plan.evaluate(&mut evaluations, DOMAIN_OFFSET);
 
         return evaluations;
     }
 
-    pub fn merge_into(mut self, result: &mut Vec<u128>, z: u128, cc: &CompositionCoefficients) -> u128 {
+    pub fn merge_into(self, result: &mut Polynomial<u128>, z: u128, cc: &CompositionCoefficients) -> u128 {
 
         // evaluate the polynomial at point z
-        let z_value = polynom::eval(&self.poly, z);
+        let poly = Polynomial::new(self.poly);
+        let z_value = poly.eval(z);
 
         // compute C(x) = (P(x) - P(z)) / (x - z)
-        self.poly[0] = field::sub(self.poly[0], z_value);
-        polynom::syn_div_in_place(&mut self.poly, z);
+        let poly = (poly - z_value) / Polynomial::linear(z);
 
         // add C(x) * cc into the result
-        parallel::mul_acc(result, &self.poly, cc.constraints, 1);
+        *result += &(poly * cc.constraints);
 
         return z_value;
     }
@@ -55,7 +64,7 @@ impl ConstraintPoly {
 
 // HELPER FUNCTIONS
 // ================================================================================================
-fn get_expected_degree(poly: &[u128]) -> usize {
-    let trace_length = poly.len() / MAX_CONSTRAINT_DEGREE;
-    return poly.len() - trace_length;
+fn get_expected_degree(poly: &[u128], trace_length: usize) -> usize {
+    let poly_length = poly.len() / MAX_CONSTRAINT_DEGREE;
+    return utils::get_composition_degree(trace_length, poly_length) + 1;
 }
\ No newline at end of file
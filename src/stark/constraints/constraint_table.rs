@@ -1,5 +1,5 @@
-use crate::math::{ field, parallel, fft, polynom };
-use crate::stark::{ TraceTable, TraceState };
+use crate::math::{ field, fft, fft::FftPlan };
+use crate::stark::{ TraceTable, TraceState, DOMAIN_OFFSET };
 use crate::utils::{ uninit_vector };
 use super::{ ConstraintEvaluator, ConstraintPoly };
 
@@ -31,7 +31,7 @@ impl ConstraintTable {
         return self.evaluator.constraint_count();
     }
 
-    /// Returns the size of the evaluation domain = trace_length * MAX_CONSTRAINT_DEGREE
+    /// Returns the size of the evaluation domain = poly_length * MAX_CONSTRAINT_DEGREE
     pub fn evaluation_domain_size(&self) -> usize {
         return self.evaluator.domain_size();
     }
@@ -49,47 +49,56 @@ impl ConstraintTable {
         self.t_evaluations[step] = self.evaluator.evaluate_transition(current, next, x, step);
     }
 
-    /// Interpolates all constraint evaluations into polynomials and combines all these 
-    /// polynomials into a single polynomial using pseudo-random linear combination.
-    pub fn combine_polys(mut self) -> ConstraintPoly
+    /// Divides all constraint evaluations by their respective divisors, adds the results together,
+    /// and interpolates the sum into a single polynomial. Constraints are evaluated over a coset
+    /// of the evaluation domain; so, none of the divisors is zero at any of the evaluation points.
+    /// `fft_plan` must support domains of at least `evaluation_domain_size()` elements.
+    pub fn combine_polys(self, fft_plan: &FftPlan<u128>) -> ConstraintPoly
     {
-        let combination_root = field::get_root_of_unity(self.evaluation_domain_size());
-        let inv_twiddles = fft::get_inv_twiddles(combination_root, self.evaluation_domain_size());
-     
+        let domain_size = self.evaluation_domain_size();
+        let root = field::get_root_of_unity(domain_size);
+        let domain = fft::get_coset_domain(root, DOMAIN_OFFSET, domain_size);
+
         #[cfg(debug_assertions)]
         self.validate_transition_degrees();
-        
-        let mut combined_poly = uninit_vector(self.evaluation_domain_size());
-        
-        // 1 ----- boundary constraints for the initial step --------------------------------------
-        // interpolate initial step boundary constraint combination into a polynomial, divide the 
-        // polynomial by Z(x) = (x - 1), and add it to the result
-        polynom::interpolate_fft_twiddles(&mut self.i_evaluations, &inv_twiddles, true);
-        polynom::syn_div_in_place(&mut self.i_evaluations, field::ONE);
-        combined_poly.copy_from_slice(&self.i_evaluations);
-
-        // 2 ----- boundary constraints for the final step ----------------------------------------
-        // interpolate final step boundary constraint combination into a polynomial, divide the 
-        // polynomial by Z(x) = (x - x_at_last_step), and add it to the result
-        polynom::interpolate_fft_twiddles(&mut self.f_evaluations, &inv_twiddles, true);
+
+        // 1 ----- boundary constraint divisors ---------------------------------------------------
+        // Z(x) = (x - 1) for the initial step and Z(x) = (x - x_at_last_step) for the final step
         let x_at_last_step = self.evaluator.get_x_at_last_step();
-        polynom::syn_div_in_place(&mut self.f_evaluations, x_at_last_step);
-        parallel::add_in_place(&mut combined_poly, &self.f_evaluations, 1);
+        let i_divisors = domain.iter().map(|&x| field::sub(x, field::ONE)).collect::<Vec<u128>>();
+        let f_divisors = domain.iter().map(|&x| field::sub(x, x_at_last_step)).collect::<Vec<u128>>();
+        let i_inv_divisors = field::inv_many(&i_divisors);
+        let f_inv_divisors = field::inv_many(&f_divisors);
 
-        // 3 ----- transition constraints ---------------------------------------------------------
-        // interpolate transition constraint combination into a polynomial, divide the polynomial
-        // by Z(x) = (x^steps - 1) / (x - x_at_last_step), and add it to the result
+        // 2 ----- transition constraint divisors -------------------------------------------------
+        // Z(x) = (x^steps - 1) / (x - x_at_last_step); x^steps takes only domain_size / steps
+        // distinct values over the domain, so only these values need to be inverted
         let trace_length = self.trace_length();
-        polynom::interpolate_fft_twiddles(&mut self.t_evaluations, &inv_twiddles, true);
-        polynom::syn_div_expanded_in_place(&mut self.t_evaluations, trace_length, &[x_at_last_step]);
-        parallel::add_in_place(&mut combined_poly, &self.t_evaluations, 1);
+        let cycle_length = domain_size / trace_length;
+        let z_values = (0..cycle_length)
+            .map(|i| field::sub(field::exp(domain[i], trace_length as u128), field::ONE))
+            .collect::<Vec<u128>>();
+        let z_inv_values = field::inv_many(&z_values);
+
+        // 3 ----- combine and interpolate --------------------------------------------------------
+        let mut combined_evaluations = uninit_vector(domain_size);
+        for i in 0..domain_size {
+            let mut value = field::mul(self.i_evaluations[i], i_inv_divisors[i]);
+            value = field::add(value, field::mul(self.f_evaluations[i], f_inv_divisors[i]));
+            let t_value = field::mul(self.t_evaluations[i], f_divisors[i]);
+            value = field::add(value, field::mul(t_value, z_inv_values[i % cycle_length]));
+            combined_evaluations[i] = value;
+        }
+
+        fft_plan.interpolate(&mut combined_evaluations, DOMAIN_OFFSET);
 
-        return ConstraintPoly::new(combined_poly);
+        return ConstraintPoly::new(combined_evaluations, trace_length);
     }
 
     #[cfg(debug_assertions)]
     fn validate_transition_degrees(&self) {
-        let trace_degree = self.evaluator.trace_length() - 1;
+        // evaluation domain is MAX_CONSTRAINT_DEGREE times larger than trace polynomials
+        let trace_degree = self.evaluation_domain_size() / crate::stark::MAX_CONSTRAINT_DEGREE - 1;
         let mut expected_degrees = self.evaluator.get_transition_degrees();
         for i in 0..expected_degrees.len() {
             expected_degrees[i] = expected_degrees[i] * trace_degree;
@@ -72,7 +72,7 @@ pub struct Decoder {
 // ================================================================================================
 impl Decoder {
 
-    pub fn new(trace_length: usize, extension_factor: usize, ctx_depth: usize, loop_depth: usize) -> Decoder 
+    pub fn new(trace_length: usize, extension_factor: usize, domain_offset: u128, ctx_depth: usize, loop_depth: usize) -> Decoder 
     {
         // build an array of constraint degrees for the decoder
         let mut degrees = Vec::from(&OP_CONSTRAINT_DEGREES[..]);
@@ -83,15 +83,19 @@ impl Decoder {
             + cmp::max(loop_depth, MIN_LOOP_DEPTH),
             STACK_CONSTRAINT_DEGREE);
 
-        // determine extended cycle length
+        // determine extended cycle length; constant cycles repeat every BASE_CYCLE_LENGTH steps,
+        // so the evaluation domain shifted by domain_offset maps to constant cycles shifted by
+        // domain_offset^num_cycles
         let cycle_length = BASE_CYCLE_LENGTH * extension_factor;
+        let num_cycles = (trace_length / BASE_CYCLE_LENGTH) as u128;
+        let cycle_offset = field::exp(domain_offset, num_cycles);
 
         // extend rounds constants by the specified extension factor
-        let (ark_polys, ark_evaluations) = extend_constants(&ARK, extension_factor);
+        let (ark_polys, ark_evaluations) = extend_constants(&ARK, extension_factor, cycle_offset);
         let ark_values = transpose_ark_constants(ark_evaluations, cycle_length);
 
         // extend mask constants by the specified extension factor
-        let (mask_polys, mask_evaluations) = extend_constants(&MASKS, extension_factor);
+        let (mask_polys, mask_evaluations) = extend_constants(&MASKS, extension_factor, cycle_offset);
         let mask_values = transpose_mask_constants(mask_evaluations, cycle_length);
 
         return Decoder {
@@ -103,12 +107,10 @@ impl Decoder {
         };
     }
 
-    #[cfg(test)]
     pub fn ctx_depth(&self) -> usize {
         return self.ctx_depth;
     }
 
-    #[cfg(test)]
     pub fn loop_depth(&self) -> usize {
         return self.loop_depth;
     }
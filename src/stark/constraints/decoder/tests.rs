@@ -1,3 +1,4 @@
+use crate::math::field;
 use crate::utils::sponge::{ apply_round as apply_hacc_round };
 use super::{ Decoder, TraceState, UserOps };
 
@@ -103,7 +104,8 @@ fn enforce_hacc() {
 // HELPER FUNCTIONS
 // ================================================================================================
 fn new_decoder(ctx_depth: usize, loop_depth: usize) -> Decoder {
-    return Decoder::new(TRACE_LENGTH, EXTENSION_FACTOR, ctx_depth, loop_depth);
+    // evaluate constraints over the unshifted domain so that steps map to trace steps
+    return Decoder::new(TRACE_LENGTH, EXTENSION_FACTOR, field::ONE, ctx_depth, loop_depth);
 }
 
 fn evaluate_transition(decoder: &Decoder, step: usize, state1: Vec<u128>, state2: Vec<u128>) -> Vec<u128>
@@ -1,16 +1,18 @@
+use std::sync::Arc;
 use crate::{
     math::field,
+    crypto::{ Hasher, Digest },
     utils::uninit_vector,
-    stark::{ StarkProof, TraceTable, TraceState, ConstraintCoefficients },
+    stark::{ StarkProof, TraceTable, TraceState, ConstraintCoefficients, utils },
     PROGRAM_DIGEST_SIZE,
 };
-use super::{ decoder::Decoder, stack::Stack, super::MAX_CONSTRAINT_DEGREE };
+use super::{ decoder::Decoder, stack::Stack, super::{ MAX_CONSTRAINT_DEGREE, DOMAIN_OFFSET } };
 
 // TYPES AND INTERFACES
 // ================================================================================================
 pub struct Evaluator {
-    decoder         : Decoder,
-    stack           : Stack,
+    decoder         : Arc<Decoder>,
+    stack           : Arc<Stack>,
 
     coefficients    : ConstraintCoefficients,
     domain_size     : usize,
@@ -28,6 +30,15 @@ pub struct Evaluator {
     b_degree_adj    : u128,
 }
 
+/// Decoder and stack constraint evaluators for executions of the same shape; these depend only
+/// on trace length, extension factor, and register depths, and so can be built once and shared
+/// by all such executions.
+#[derive(Clone)]
+pub struct EvaluatorTemplate {
+    decoder         : Arc<Decoder>,
+    stack           : Arc<Stack>,
+}
+
 // EVALUATOR IMPLEMENTATION
 // ================================================================================================
 impl Evaluator {
@@ -39,11 +50,15 @@ impl Evaluator {
         let loop_depth = trace.loop_depth();
         let stack_depth = trace.stack_depth();
         let trace_length = trace.unextended_length();
-        let extension_factor = MAX_CONSTRAINT_DEGREE;
+        let poly_length = trace.poly_length();
+
+        // constraints are evaluated over a domain large enough to hold the highest-degree
+        // constraint; for masked traces, this domain is larger than for unmasked ones
+        let extension_factor = MAX_CONSTRAINT_DEGREE * poly_length / trace_length;
 
         // instantiate decoder and stack constraint evaluators 
-        let decoder = Decoder::new(trace_length, extension_factor, ctx_depth, loop_depth);
-        let stack = Stack::new(trace_length, extension_factor, stack_depth);
+        let decoder = Decoder::new(trace_length, extension_factor, DOMAIN_OFFSET, ctx_depth, loop_depth);
+        let stack = Stack::new(trace_length, extension_factor, DOMAIN_OFFSET, stack_depth);
 
         // build a list of transition constraint degrees
         let t_constraint_degrees = [
@@ -61,55 +76,58 @@ impl Evaluator {
         };
 
         return Evaluator {
-            decoder         : decoder,
-            stack           : stack,
+            decoder         : Arc::new(decoder),
+            stack           : Arc::new(stack),
             coefficients    : ConstraintCoefficients::new(*trace_root, ctx_depth, loop_depth, stack_depth),
             domain_size     : domain_size,
             extension_factor: extension_factor,
             t_constraint_num: t_constraint_degrees.len(),
-            t_degree_groups : group_transition_constraints(t_constraint_degrees, trace_length),
+            t_degree_groups : group_transition_constraints(t_constraint_degrees, trace_length, poly_length),
             t_evaluations   : t_evaluations,
             b_constraint_num: get_boundary_constraint_num(&inputs, &outputs),
             program_hash    : last_state.program_hash().to_vec(),
             op_count        : last_state.op_counter(),
             inputs          : inputs.to_vec(),
             outputs         : outputs.to_vec(),
-            b_degree_adj    : get_boundary_constraint_adjustment_degree(trace_length),
+            b_degree_adj    : get_boundary_constraint_adjustment_degree(trace_length, poly_length),
         };
     }
 
-    pub fn from_proof(proof: &StarkProof, program_hash: &[u8; 32], inputs: &[u128], outputs: &[u128]) -> Evaluator
+    /// Builds a constraint evaluator for the execution at the specified `index` of the proof;
+    /// `template` must have been built for the shape of this execution.
+    pub fn from_proof<H: Hasher>(proof: &StarkProof<H>, index: usize, program_hash: &[u8; 32], inputs: &[u128], outputs: &[u128], template: &EvaluatorTemplate) -> Evaluator
     {
-        let ctx_depth = proof.ctx_depth();
-        let loop_depth = proof.loop_depth();
-        let stack_depth = proof.stack_depth();
+        let execution = &proof.executions()[index];
+        let ctx_depth = execution.ctx_depth();
+        let loop_depth = execution.loop_depth();
+        let stack_depth = execution.stack_depth();
+        let seed = utils::get_execution_seed(proof.options().hasher(), &proof.trace_root().as_bytes(), index);
         let trace_length = proof.trace_length();
+        let poly_length = utils::get_trace_poly_length(trace_length, proof.options().zero_knowledge());
         let extension_factor = proof.options().extension_factor();
-        
-        // instantiate decoder and stack constraint evaluators 
-        let decoder = Decoder::new(trace_length, extension_factor, ctx_depth, loop_depth);
-        let stack = Stack::new(trace_length, extension_factor, stack_depth);
+        debug_assert!(template.decoder.ctx_depth() == ctx_depth && template.decoder.loop_depth() == loop_depth,
+            "evaluator template does not match the shape of the execution");
 
         // build a list of transition constraint degrees
         let t_constraint_degrees = [
-            decoder.constraint_degrees(), stack.constraint_degrees()
+            template.decoder.constraint_degrees(), template.stack.constraint_degrees()
         ].concat();
 
         return Evaluator {
-            decoder         : decoder,
-            stack           : stack,
-            coefficients    : ConstraintCoefficients::new(*proof.trace_root(), ctx_depth, loop_depth, stack_depth),
+            decoder         : template.decoder.clone(),
+            stack           : template.stack.clone(),
+            coefficients    : ConstraintCoefficients::new(seed, ctx_depth, loop_depth, stack_depth),
             domain_size     : proof.domain_size(),
             extension_factor: extension_factor,
             t_constraint_num: t_constraint_degrees.len(),
-            t_degree_groups : group_transition_constraints(t_constraint_degrees, trace_length),
+            t_degree_groups : group_transition_constraints(t_constraint_degrees, trace_length, poly_length),
             t_evaluations   : Vec::new(),
             b_constraint_num: get_boundary_constraint_num(&inputs, &outputs),
             program_hash    : parse_program_hash(program_hash),
-            op_count        : proof.op_count(),
+            op_count        : execution.op_count(),
             inputs          : inputs.to_vec(),
             outputs         : outputs.to_vec(),
-            b_degree_adj    : get_boundary_constraint_adjustment_degree(trace_length),
+            b_degree_adj    : get_boundary_constraint_adjustment_degree(trace_length, poly_length),
         };
     }
 
@@ -147,16 +165,6 @@ impl Evaluator {
         #[cfg(debug_assertions)]
         self.save_transition_evaluations(&evaluations, step);
 
-        // if the constraints should evaluate to all zeros at this step,
-        // make sure they do, and return
-        if self.should_evaluate_to_zero_at(step) {
-            let step = step / self.extension_factor;
-            for i in 0..evaluations.len() {
-                assert!(evaluations[i] == field::ZERO, "transition constraint at step {} were not satisfied", step);
-            }
-            return field::ZERO;
-        }
-
         // compute a pseudo-random linear combination of all transition constraints
         return self.combine_transition_constraints(&evaluations, x);
     }
@@ -327,11 +335,6 @@ impl Evaluator {
 
     // HELPER METHODS
     // -------------------------------------------------------------------------------------------
-    fn should_evaluate_to_zero_at(&self, step: usize) -> bool {
-        return (step & (self.extension_factor - 1) == 0) // same as: step % extension_factor == 0
-            && (step != self.domain_size - self.extension_factor);
-    }
-
     fn combine_transition_constraints(&self, evaluations: &Vec<u128>, x: u128) -> u128 {
         let cc = &self.coefficients.transition;
         let mut result = field::ZERO;
@@ -357,6 +360,32 @@ impl Evaluator {
         return result;
     }
 
+    /// Makes sure that transition constraints evaluate to zero at every step of the `trace`
+    /// except the last one; the trace must not be extended yet. Constraints are evaluated over
+    /// a coset of the evaluation domain, which contains none of the trace steps; so, in debug
+    /// mode, this check makes the prover fail at the offending step instead of producing a
+    /// proof which does not verify.
+    #[cfg(debug_assertions)]
+    pub fn validate_transitions(trace: &TraceTable) {
+        assert!(!trace.is_extended(), "trace table has already been extended");
+        let trace_length = trace.unextended_length();
+        let decoder = Decoder::new(trace_length, 1, field::ONE, trace.ctx_depth(), trace.loop_depth());
+        let stack = Stack::new(trace_length, 1, field::ONE, trace.stack_depth());
+
+        let mut current = TraceState::new(trace.ctx_depth(), trace.loop_depth(), trace.stack_depth());
+        let mut next = TraceState::new(trace.ctx_depth(), trace.loop_depth(), trace.stack_depth());
+        let mut evaluations = vec![field::ZERO; decoder.constraint_count() + stack.constraint_degrees().len()];
+        for step in 0..(trace_length - 1) {
+            trace.fill_state(&mut current, step);
+            trace.fill_state(&mut next, step + 1);
+            decoder.evaluate(&current, &next, step, &mut evaluations);
+            stack.evaluate(&current, &next, step, &mut evaluations[decoder.constraint_count()..]);
+            for i in 0..evaluations.len() {
+                debug_assert!(evaluations[i] == field::ZERO, "transition constraint at step {} were not satisfied", step);
+            }
+        }
+    }
+
     #[cfg(debug_assertions)]
     fn save_transition_evaluations(&self, evaluations: &[u128], step: usize) {
         unsafe {
@@ -380,9 +409,21 @@ impl Evaluator {
     }
 }
 
+// EVALUATOR TEMPLATE IMPLEMENTATION
+// ================================================================================================
+impl EvaluatorTemplate {
+
+    pub fn new(trace_length: usize, extension_factor: usize, ctx_depth: usize, loop_depth: usize, stack_depth: usize) -> EvaluatorTemplate {
+        return EvaluatorTemplate {
+            decoder : Arc::new(Decoder::new(trace_length, extension_factor, DOMAIN_OFFSET, ctx_depth, loop_depth)),
+            stack   : Arc::new(Stack::new(trace_length, extension_factor, DOMAIN_OFFSET, stack_depth)),
+        };
+    }
+}
+
 // HELPER FUNCTIONS
 // ================================================================================================
-fn group_transition_constraints(degrees: Vec<usize>, trace_length: usize) -> Vec<(u128, Vec<usize>)> {
+fn group_transition_constraints(degrees: Vec<usize>, trace_length: usize, poly_length: usize) -> Vec<(u128, Vec<usize>)> {
     let mut groups = [
         Vec::new(), Vec::new(), Vec::new(), Vec::new(), Vec::new(),
         Vec::new(), Vec::new(), Vec::new(), Vec::new(),
@@ -392,12 +433,12 @@ fn group_transition_constraints(degrees: Vec<usize>, trace_length: usize) -> Vec
         groups[degree].push(i);
     }
 
-    let target_degree = get_transition_constraint_target_degree(trace_length);
+    let target_degree = get_transition_constraint_target_degree(trace_length, poly_length);
 
     let mut result = Vec::new();
     for (degree, constraints) in groups.iter().enumerate() {
         if constraints.len() == 0 { continue; }
-        let constraint_degree = (trace_length - 1) * degree;    
+        let constraint_degree = (poly_length - 1) * degree;    
         let incremental_degree = (target_degree - constraint_degree) as u128;
         result.push((incremental_degree, constraints.clone()));
     }
@@ -405,17 +446,17 @@ fn group_transition_constraints(degrees: Vec<usize>, trace_length: usize) -> Vec
     return result;
 }
 
-fn get_boundary_constraint_adjustment_degree(trace_length: usize) -> u128 {
-    let target_degree = get_boundary_constraint_target_degree(trace_length);
-    let boundary_constraint_degree = trace_length - 1;
+fn get_boundary_constraint_adjustment_degree(trace_length: usize, poly_length: usize) -> u128 {
+    let target_degree = get_boundary_constraint_target_degree(trace_length, poly_length);
+    let boundary_constraint_degree = poly_length - 1;
     return (target_degree - boundary_constraint_degree) as u128;
 }
 
 /// target degree for boundary constraints is set so that when divided by boundary
 /// constraint divisor (degree 1 polynomial), the degree will be equal to
 /// deg(combination domain) - deg(trace)
-fn get_boundary_constraint_target_degree(trace_length: usize) -> usize {
-    let combination_degree = (MAX_CONSTRAINT_DEGREE - 1) * trace_length;
+fn get_boundary_constraint_target_degree(trace_length: usize, poly_length: usize) -> usize {
+    let combination_degree = get_combination_degree(trace_length, poly_length);
     let divisor_degree = 1;
     return combination_degree + divisor_degree;
 }
@@ -423,12 +464,20 @@ fn get_boundary_constraint_target_degree(trace_length: usize) -> usize {
 /// target degree for transition constraints is set so when divided transition 
 /// constraint divisor (deg(trace) - 1 polynomial), the degree will be equal to
 /// deg(combination domain) - deg(trace)
-fn get_transition_constraint_target_degree(trace_length: usize) -> usize {
-    let combination_degree = (MAX_CONSTRAINT_DEGREE - 1) * trace_length;
+fn get_transition_constraint_target_degree(trace_length: usize, poly_length: usize) -> usize {
+    let combination_degree = get_combination_degree(trace_length, poly_length);
     let divisor_degree = trace_length - 1;
     return combination_degree + divisor_degree;
 }
 
+/// degree of the combination is set so that the highest-degree transition constraints fit
+/// into it after being divided by transition constraint divisor; for unmasked traces, this
+/// is (MAX_CONSTRAINT_DEGREE - 1) * trace_length, but masking raises the degree of trace
+/// polynomials without raising the degree of the divisor
+fn get_combination_degree(trace_length: usize, poly_length: usize) -> usize {
+    return MAX_CONSTRAINT_DEGREE * poly_length - trace_length;
+}
+
 fn parse_program_hash(program_hash: &[u8; 32]) -> Vec<u128> {
     return vec![
         field::from_bytes(&program_hash[..16]),
@@ -7,6 +7,6 @@ mod utils;
 
 pub use decoder::{ NUM_STATIC_DECODER_CONSTRAINTS };
 pub use stack::{ NUM_AUX_CONSTRAINTS as NUM_AUX_STACK_CONSTRAINTS };
-pub use evaluator::{ Evaluator as ConstraintEvaluator};
+pub use evaluator::{ Evaluator as ConstraintEvaluator, EvaluatorTemplate };
 pub use constraint_table::{ ConstraintTable };
 pub use constraint_poly::{ ConstraintPoly };
\ No newline at end of file
@@ -1,4 +1,5 @@
 use crate::utils::hasher::{ apply_sbox, apply_mds, apply_inv_mds };
+use crate::utils::poseidon;
 use super::{
     field, are_equal, EvaluationResult, enforce_stack_copy,
     HASH_STATE_WIDTH
@@ -33,4 +34,43 @@ pub fn enforce_rescr(result: &mut [u128], old_stack: &[u128], new_stack: &[u128]
 
     // make sure the rest of the stack didn't change
     enforce_stack_copy(result, old_stack, new_stack, HASH_STATE_WIDTH, op_flag);
-}
\ No newline at end of file
+}
+
+/// Evaluates constraints for a single round of Poseidon hash function. The top 6 registers of
+/// user stack are assumed to contain a one-hot cycle selector, and the next 6 registers are
+/// assumed to contain hash state; the rest of the stack does not change. `full` specifies
+/// whether S-BOX is applied to the entire state (POSEF) or just to one element (POSEP).
+pub fn enforce_poser(result: &mut [u128], old_stack: &[u128], new_stack: &[u128], ark: &[u128], full: bool, op_flag: u128)
+{
+    let (old_selector, old_state) = old_stack.split_at(poseidon::NUM_CYCLES);
+    let (new_selector, new_state) = new_stack.split_at(poseidon::NUM_CYCLES);
+
+    // apply the round to the old state and add round constants selected by the cycle selector;
+    // constants are added after the S-BOX, and so the degree of the constraints is not affected
+    let mut state = [field::ZERO; HASH_STATE_WIDTH];
+    state.copy_from_slice(&old_state[..HASH_STATE_WIDTH]);
+    poseidon::apply_round(&mut state, full);
+    for i in 0..poseidon::NUM_CYCLES {
+        for j in 0..HASH_STATE_WIDTH {
+            let constant = field::mul(old_selector[i], ark[i * HASH_STATE_WIDTH + j]);
+            state[j] = field::add(state[j], constant);
+        }
+    }
+
+    for i in 0..HASH_STATE_WIDTH {
+        let register = poseidon::NUM_CYCLES + i;
+        result.agg_constraint(register, op_flag, are_equal(new_state[i], state[i]));
+    }
+
+    // on the last step of a cycle, the selector is rotated by one position; on all other
+    // steps it doesn't change
+    let cycle_end = ark[poseidon::ARK_WIDTH - 1];
+    for i in 0..poseidon::NUM_CYCLES {
+        let prev = old_selector[(i + poseidon::NUM_CYCLES - 1) % poseidon::NUM_CYCLES];
+        let expected = field::add(old_selector[i], field::mul(cycle_end, field::sub(prev, old_selector[i])));
+        result.agg_constraint(i, op_flag, are_equal(new_selector[i], expected));
+    }
+
+    // make sure the rest of the stack didn't change
+    enforce_stack_copy(result, old_stack, new_stack, poseidon::STACK_WIDTH, op_flag);
+}
@@ -2,7 +2,7 @@ use crate::{
     math::{ field, polynom },
     processor::OpCode,
     stark::TraceState,
-    utils::hasher::ARK,
+    utils::{ hasher::ARK, poseidon },
     BASE_CYCLE_LENGTH, HASH_STATE_WIDTH
 };
 use super::utils::{
@@ -32,13 +32,14 @@ mod conditional;
 use conditional::{ enforce_choose, enforce_choose2, enforce_cswap2 };
 
 mod hash;
-use hash::{ enforce_rescr };
+use hash::{ enforce_rescr, enforce_poser };
 
 // CONSTANTS
 // ================================================================================================
 pub const NUM_AUX_CONSTRAINTS: usize = 2;
 const AUX_CONSTRAINT_DEGREES: [usize; NUM_AUX_CONSTRAINTS] = [7, 7];
 const STACK_TRANSITION_DEGREE: usize = 7; // degree for all stack register transition constraints
+const POSEIDON_STATE_DEGREE: usize = 8;   // degree for registers holding Poseidon hash state
 
 // TYPES AND INTERFACES
 // ================================================================================================
@@ -47,6 +48,8 @@ pub struct Stack {
     cycle_length        : usize,
     ark_values          : Vec<[u128; 2 * HASH_STATE_WIDTH]>,
     ark_polys           : Vec<Vec<u128>>,
+    pos_ark_values      : Vec<[u128; poseidon::ARK_WIDTH]>,
+    pos_ark_polys       : Vec<Vec<u128>>,
     constraint_degrees  : Vec<usize>,
 }
 
@@ -54,22 +57,37 @@ pub struct Stack {
 // ================================================================================================
 impl Stack {
 
-    pub fn new(trace_length: usize, extension_factor: usize, stack_depth: usize) -> Stack 
+    pub fn new(trace_length: usize, extension_factor: usize, domain_offset: u128, stack_depth: usize) -> Stack 
     {
         // build an array of constraint degrees for the stack
         let mut degrees = Vec::from(&AUX_CONSTRAINT_DEGREES[..]);
         degrees.resize(stack_depth + NUM_AUX_CONSTRAINTS, STACK_TRANSITION_DEGREE);
 
-        // determine extended cycle length
+        // Poseidon rounds are low-degree operations, and so their flags have degree 5; together
+        // with the S-BOX, this raises the degree of hash state registers to 8
+        for i in poseidon::NUM_CYCLES..std::cmp::min(poseidon::STACK_WIDTH, stack_depth) {
+            degrees[NUM_AUX_CONSTRAINTS + i] = POSEIDON_STATE_DEGREE;
+        }
+
+        // determine extended cycle length; constant cycles repeat every BASE_CYCLE_LENGTH steps,
+        // so the evaluation domain shifted by domain_offset maps to constant cycles shifted by
+        // domain_offset^num_cycles
         let cycle_length = BASE_CYCLE_LENGTH * extension_factor;
+        let num_cycles = (trace_length / BASE_CYCLE_LENGTH) as u128;
+        let cycle_offset = field::exp(domain_offset, num_cycles);
 
         // extend rounds constants by the specified extension factor
-        let (ark_polys, ark_evaluations) = extend_constants(&ARK, extension_factor);
-        let ark_values = transpose_ark_constants(ark_evaluations, cycle_length);
+        let (ark_polys, ark_evaluations) = extend_constants(&ARK, extension_factor, cycle_offset);
+        let ark_values = transpose_constants(ark_evaluations, cycle_length);
+
+        // do the same for Poseidon round constants
+        let (pos_ark_polys, pos_ark_evaluations) = extend_constants(&poseidon::build_ark_cycles(), extension_factor, cycle_offset);
+        let pos_ark_values = transpose_constants(pos_ark_evaluations, cycle_length);
 
         return Stack {
             trace_length, cycle_length,
             ark_values, ark_polys,
+            pos_ark_values, pos_ark_polys,
             constraint_degrees: degrees,
         };
     }
@@ -87,9 +105,10 @@ impl Stack {
     {
         // determine round constants at the specified step
         let ark = self.ark_values[step % self.cycle_length];
+        let pos_ark = self.pos_ark_values[step % self.cycle_length];
 
         // evaluate transition constraints for the stack
-        enforce_constraints(current, next, &ark, result);
+        enforce_constraints(current, next, &ark, &pos_ark, result);
     }
 
     /// Evaluates stack transition constraints at the specified x coordinate and saves the
@@ -107,14 +126,19 @@ impl Stack {
             ark[i] = polynom::eval(&self.ark_polys[i], x);
         }
 
+        let mut pos_ark = [field::ZERO; poseidon::ARK_WIDTH];
+        for i in 0..pos_ark.len() {
+            pos_ark[i] = polynom::eval(&self.pos_ark_polys[i], x);
+        }
+
         // evaluate transition constraints for the stack
-        enforce_constraints(current, next, &ark, result);
+        enforce_constraints(current, next, &ark, &pos_ark, result);
     }
 }
 
 // HELPER FUNCTIONS
 // ================================================================================================
-fn enforce_constraints(current: &TraceState, next: &TraceState, ark: &[u128], result: &mut [u128])
+fn enforce_constraints(current: &TraceState, next: &TraceState, ark: &[u128], pos_ark: &[u128], result: &mut [u128])
 {
     // split constraint evaluation result into aux constraints and stack constraints
     let (aux, result) = result.split_at_mut(NUM_AUX_CONSTRAINTS);
@@ -173,6 +197,19 @@ fn enforce_constraints(current: &TraceState, next: &TraceState, ark: &[u128], re
     enforce_choose2 (&mut evaluations, aux, old_stack, new_stack, ld_flags[OpCode::Choose2.ld_index()]);
     enforce_cswap2  (&mut evaluations, aux, old_stack, new_stack, ld_flags[OpCode::CSwap2.ld_index()]);
 
+    // Poseidon rounds; these can be executed only if the stack is deep enough to hold the
+    // cycle selector and the hash state. For shallower stacks the state is not in the trace,
+    // and so the rounds cannot be constrained; instead, the flags of both operations must be 0
+    let posef_flag = ld_flags[OpCode::PoseF.ld_index()];
+    let posep_flag = ld_flags[OpCode::PoseP.ld_index()];
+    if old_stack.len() >= poseidon::STACK_WIDTH {
+        enforce_poser(&mut evaluations, old_stack, new_stack, pos_ark, true,  posef_flag);
+        enforce_poser(&mut evaluations, old_stack, new_stack, pos_ark, false, posep_flag);
+    }
+    else {
+        evaluations[0] = field::add(evaluations[0], field::add(posef_flag, posep_flag));
+    }
+
     // 2 ----- enforce constraints for high-degree operations --------------------------------------
     let hd_flags = current.hd_op_flags();
 
@@ -194,12 +231,12 @@ fn enforce_constraints(current: &TraceState, next: &TraceState, ark: &[u128], re
     result.copy_from_slice(&evaluations[..result.len()]);
 }
 
-fn transpose_ark_constants(constants: Vec<Vec<u128>>, cycle_length: usize) -> Vec<[u128; 2 * HASH_STATE_WIDTH]>
+fn transpose_constants<const N: usize>(constants: Vec<Vec<u128>>, cycle_length: usize) -> Vec<[u128; N]>
 {
     let mut values = Vec::new();
     for i in 0..cycle_length {
-        values.push([field::ZERO; 2 * HASH_STATE_WIDTH]);
-        for j in 0..(2 * HASH_STATE_WIDTH) {
+        values.push([field::ZERO; N]);
+        for j in 0..N {
             values[i][j] = constants[j][i];
         }
     }
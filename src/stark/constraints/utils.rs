@@ -84,7 +84,9 @@ impl EvaluationResult for Vec<u128> {
 
 // CONSTANT INTERPOLATION AND EXTENSIONS
 // ================================================================================================
-pub fn extend_constants(constants: &[[u128; BASE_CYCLE_LENGTH]], extension_factor: usize) -> (Vec<Vec<u128>>, Vec<Vec<u128>>)
+/// Interpolates cyclic `constants` into polynomials and evaluates these polynomials over the
+/// domain extended by `extension_factor` and shifted by `domain_offset`.
+pub fn extend_constants(constants: &[[u128; BASE_CYCLE_LENGTH]], extension_factor: usize, domain_offset: u128) -> (Vec<Vec<u128>>, Vec<Vec<u128>>)
 {
     let root = field::get_root_of_unity(BASE_CYCLE_LENGTH);
     let inv_twiddles = fft::get_inv_twiddles(root, BASE_CYCLE_LENGTH);
@@ -104,7 +106,7 @@ pub fn extend_constants(constants: &[[u128; BASE_CYCLE_LENGTH]], extension_facto
         polys.push(extended_constant.clone());
 
         unsafe { extended_constant.set_len(extended_constant.capacity()); }
-        polynom::eval_fft_twiddles(&mut extended_constant, &twiddles, true);
+        polynom::eval_fft_coset(&mut extended_constant, domain_offset, &twiddles, true);
 
         evaluations.push(extended_constant);
     }
@@ -0,0 +1,100 @@
+use crate::fri::{ FriError };
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Describes the reason for which a proof failed verification.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VerificationError {
+
+    // VERIFIER POLICY
+    // --------------------------------------------------------------------------------------------
+
+    /// Proof was generated with an extension factor not allowed by the policy.
+    ExtensionFactorNotAllowed(usize),
+    /// Security level of the proof is below the minimum required by the policy.
+    InsufficientSecurityLevel { actual: u32, required: u32 },
+    /// Execution trace is longer than the maximum allowed by the policy.
+    TraceTooLong { max: usize },
+    /// Execution trace is shorter than the minimum trace length.
+    TraceTooShort { min: usize },
+
+    // STATEMENT
+    // --------------------------------------------------------------------------------------------
+
+    /// Number of sets of public inputs does not match the number of sets of outputs.
+    InputOutputCountMismatch { inputs: usize, outputs: usize },
+    /// Number of public inputs of an execution exceeds `MAX_PUBLIC_INPUTS`.
+    TooManyInputs { max: usize, actual: usize },
+    /// Number of outputs of an execution exceeds `MAX_OUTPUTS`.
+    TooManyOutputs { max: usize, actual: usize },
+
+    // STARK PROOF
+    // --------------------------------------------------------------------------------------------
+
+    /// Proof-of-work nonce does not satisfy the grinding factor of the proof.
+    InsufficientPow,
+    /// Number of program executions covered by the proof does not match the number of provided
+    /// input/output pairs.
+    ExecutionCountMismatch { expected: usize, actual: usize },
+    /// Shape of an execution trace described in the proof is invalid, or does not match the
+    /// number of trace values in the proof.
+    InvalidTraceShape,
+    /// Number of executed operations is smaller than the minimum trace length.
+    InvalidOpCount,
+    /// Trace evaluations are inconsistent with the trace commitment.
+    TraceMerkleMismatch,
+    /// Constraint evaluations are inconsistent with the constraint commitment.
+    ConstraintMerkleMismatch,
+    /// Evaluations of the DEEP composition polynomial computed by the verifier do not match
+    /// the values committed to in the low-degree proof.
+    DeepCompositionMismatch,
+
+    // FRI PROOF
+    // --------------------------------------------------------------------------------------------
+
+    /// Low-degree proof of the composition polynomial is invalid.
+    InvalidFriProof(FriError),
+}
+
+// COMMON TRAIT IMPLEMENTATIONS
+// ================================================================================================
+
+impl std::fmt::Display for VerificationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VerificationError::ExtensionFactorNotAllowed(factor) =>
+                write!(f, "extension factor {} is not allowed", factor),
+            VerificationError::InsufficientSecurityLevel { actual, required } =>
+                write!(f, "proof security level of {} bits is below the minimum of {} bits", actual, required),
+            VerificationError::TraceTooLong { max } =>
+                write!(f, "trace length exceeds the maximum of {} steps", max),
+            VerificationError::TraceTooShort { min } =>
+                write!(f, "trace length is below the minimum of {} steps", min),
+            VerificationError::InputOutputCountMismatch { inputs, outputs } =>
+                write!(f, "received {} sets of public inputs, but {} sets of outputs", inputs, outputs),
+            VerificationError::TooManyInputs { max, actual } =>
+                write!(f, "expected no more than {} public inputs, but received {}", max, actual),
+            VerificationError::TooManyOutputs { max, actual } =>
+                write!(f, "expected no more than {} outputs, but received {}", max, actual),
+            VerificationError::InsufficientPow =>
+                write!(f, "seed proof-of-work verification failed"),
+            VerificationError::ExecutionCountMismatch { expected, actual } =>
+                write!(f, "expected proof of {} executions, but proof covers {}", expected, actual),
+            VerificationError::InvalidTraceShape =>
+                write!(f, "execution trace shape is inconsistent with trace values"),
+            VerificationError::InvalidOpCount =>
+                write!(f, "verification of minimum operation count failed"),
+            VerificationError::TraceMerkleMismatch =>
+                write!(f, "verification of trace Merkle proof failed"),
+            VerificationError::ConstraintMerkleMismatch =>
+                write!(f, "verification of constraint Merkle proof failed"),
+            VerificationError::DeepCompositionMismatch =>
+                write!(f, "DEEP composition evaluations did not match low-degree proof values"),
+            VerificationError::InvalidFriProof(error) =>
+                write!(f, "verification of low-degree proof failed: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for VerificationError {}
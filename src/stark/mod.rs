@@ -4,13 +4,15 @@ mod options;
 mod prover;
 mod verifier;
 mod proof;
-mod fri;
+mod policy;
+mod errors;
 mod utils;
 
 pub use trace::{ TraceTable, TraceState };
 
 pub use constraints::{
     ConstraintEvaluator,
+    EvaluatorTemplate,
     ConstraintTable,
     ConstraintPoly };
 
@@ -19,8 +21,14 @@ pub use utils::{
     CompositionCoefficients };
 
 pub use options::ProofOptions;
-pub use proof::{ StarkProof, DeepValues };
+pub use policy::VerifierPolicy;
+pub use errors::VerificationError;
+pub use proof::{ StarkProof, DeepValues, ExecutionInfo };
 pub use prover::{ prove };
-pub use verifier::{ verify };
+pub use verifier::{ verify, verify_many };
 
-const MAX_CONSTRAINT_DEGREE : usize = 8;
\ No newline at end of file
+const MAX_CONSTRAINT_DEGREE : usize = 8;
+
+// trace polynomials and constraints are evaluated over a coset of the LDE domain shifted by this
+// value; this way, no point of the evaluation domain coincides with a step of the execution trace
+const DOMAIN_OFFSET         : u128 = crate::math::field::GENERATOR;
\ No newline at end of file
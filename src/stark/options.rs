@@ -1,52 +1,100 @@
+use std::{ convert::TryFrom, marker::PhantomData };
 use serde::{ Serialize, Deserialize };
-use crate::crypto::{ HashFunction, hash };
+use crate::crypto::{ Hasher, hash::Blake3 };
+use crate::fri::{ FriOptions };
 use super::MAX_CONSTRAINT_DEGREE;
 
 // CONSTANTS
 // ================================================================================================
-const DEFAULT_EXTENSION_FACTOR: u8 = (MAX_CONSTRAINT_DEGREE * 4) as u8;
-const DEFAULT_NUM_QUERIES     : u8 = 50;
-const DEFAULT_GRINDING_FACTOR : u8 = 20;
+const DEFAULT_EXTENSION_FACTOR    : u8 = (MAX_CONSTRAINT_DEGREE * 4) as u8;
+const DEFAULT_NUM_QUERIES         : u8 = 50;
+const DEFAULT_GRINDING_FACTOR     : u8 = 20;
+const DEFAULT_FOLDING_FACTOR      : u8 = 4;
+const DEFAULT_MAX_REMAINDER_DEGREE: u8 = 63;
 
 // TYPES AND INTERFACES
 // ================================================================================================
 
-// TODO: validate field values on de-serialization
+/// Options are validated on de-serialization; so, options which could not have been built via
+/// `ProofOptions::new()` (e.g. options received as a part of a malformed proof) are rejected.
 #[derive(Clone, Serialize, Deserialize)]
-pub struct ProofOptions {
+#[serde(bound = "", try_from = "OptionsParts<H>")]
+pub struct ProofOptions<H: Hasher = Blake3> {
     extension_factor    : u8,   // stored as power of 2
     num_queries         : u8,
     grinding_factor     : u8,
+    zero_knowledge      : bool,
+    folding_factor      : u8,
+    max_remainder_degree: u8,
 
-    #[serde(with = "hash_fn_serialization")]
-    hash_fn: HashFunction,
+    #[serde(serialize_with = "hash_fn_serialization::serialize")]
+    hasher: H,
+}
+
+#[derive(Deserialize)]
+#[serde(bound = "")]
+struct OptionsParts<H: Hasher> {
+    extension_factor    : u8,
+    num_queries         : u8,
+    grinding_factor     : u8,
+    zero_knowledge      : bool,
+    folding_factor      : u8,
+    max_remainder_degree: u8,
+
+    #[serde(deserialize_with = "hash_fn_serialization::deserialize")]
+    hash_fn: PhantomData<H>,
 }
 
 // PROOF OPTIONS IMPLEMENTATION
 // ================================================================================================
-impl ProofOptions {
+impl <H: Hasher> ProofOptions<H> {
 
+    /// Returns a new set of proof options; hash function used in the proof is specified by
+    /// the `H` type parameter. When `zero_knowledge` is set to true, the prover masks trace
+    /// polynomials and the composition polynomial with random values so that the proof does
+    /// not reveal anything about secret inputs.
+    /// 
+    /// `folding_factor` specifies by how much the degree of the composition polynomial is reduced
+    /// at every FRI layer, and `max_remainder_degree` specifies the degree at which FRI reduction
+    /// stops; larger values of both result in smaller proofs but slower proof generation.
     pub fn new(
-        extension_factor : usize,
-        num_queries      : usize,
-        grinding_factor  : u32,
-        hash_fn          : HashFunction) -> ProofOptions
+        extension_factor    : usize,
+        num_queries         : usize,
+        grinding_factor     : u32,
+        zero_knowledge      : bool,
+        folding_factor      : usize,
+        max_remainder_degree: usize) -> ProofOptions<H>
     {
-        assert!(extension_factor.is_power_of_two(), "extension_factor must be a power of 2");
-        assert!(extension_factor >= 16, "extension_factor cannot be smaller than 16");
-        assert!(extension_factor <= 256, "extension_factor cannot be greater than 256");
-
-        assert!(num_queries > 0, "num_queries must be greater than 0");
-        assert!(num_queries <= 128, "num_queries cannot be greater than 128");
+        return match ProofOptions::try_new(extension_factor, num_queries, grinding_factor,
+            zero_knowledge, folding_factor, max_remainder_degree)
+        {
+            Ok(options) => options,
+            Err(msg) => panic!("{}", msg),
+        };
+    }
 
-        assert!(grinding_factor <= 32, "grinding factor cannot be greater than 32");
+    /// Same as `new()`, but returns an error instead of panicking if any of the options are
+    /// invalid; this is useful when options are provided by a user.
+    pub fn try_new(
+        extension_factor    : usize,
+        num_queries         : usize,
+        grinding_factor     : u32,
+        zero_knowledge      : bool,
+        folding_factor      : usize,
+        max_remainder_degree: usize) -> Result<ProofOptions<H>, String>
+    {
+        validate(extension_factor, num_queries, grinding_factor, zero_knowledge,
+            folding_factor, max_remainder_degree)?;
 
-        return ProofOptions {
+        return Ok(ProofOptions {
             extension_factor    : extension_factor.trailing_zeros() as u8,
             num_queries         : num_queries as u8,
             grinding_factor     : grinding_factor as u8,
-            hash_fn
-        };
+            zero_knowledge      : zero_knowledge,
+            folding_factor      : folding_factor as u8,
+            max_remainder_degree: max_remainder_degree as u8,
+            hasher              : H::default(),
+        });
     }
 
     pub fn extension_factor(&self) -> usize {
@@ -61,12 +109,50 @@ impl ProofOptions {
         return self.grinding_factor as u32;
     }
 
-    pub fn hash_fn(&self) -> HashFunction {
-        return self.hash_fn;
+    /// Returns true if trace and composition polynomials are masked with random values.
+    pub fn zero_knowledge(&self) -> bool {
+        return self.zero_knowledge;
+    }
+
+    /// Returns the factor by which the degree of the composition polynomial is reduced at
+    /// every FRI layer.
+    pub fn folding_factor(&self) -> usize {
+        return self.folding_factor as usize;
+    }
+
+    /// Returns the maximum degree of the polynomial remaining after the last FRI layer.
+    pub fn max_remainder_degree(&self) -> usize {
+        return self.max_remainder_degree as usize;
+    }
+
+    /// Returns options for the FRI proof of the composition polynomial.
+    pub fn fri_options(&self) -> FriOptions {
+        return FriOptions::new(self.folding_factor(), self.max_remainder_degree(), self.num_queries());
+    }
+
+    /// Returns these options with the hash function set to the provided `hasher` instance; this
+    /// is needed only for hash functions which carry state, such as keyed hash functions.
+    pub fn with_hasher(mut self, hasher: H) -> ProofOptions<H> {
+        self.hasher = hasher;
+        return self;
+    }
+
+    /// Returns the instance of the hash function used in the proof. The state of the instance
+    /// is not serialized, and so for de-serialized options this is the default instance.
+    pub fn hasher(&self) -> &H {
+        return &self.hasher;
+    }
+
+    /// Returns identifier of the hash function used in the proof.
+    pub fn hash_fn_id(&self) -> u8 {
+        return H::ID;
     }
 
     pub fn security_level(&self, optimistic: bool) -> u32 {
-        let one_over_rho = (self.extension_factor() / MAX_CONSTRAINT_DEGREE) as u32;
+        // masked trace polynomials have twice the degree of unmasked ones, and so the degree
+        // of the composition polynomial roughly doubles as well
+        let composition_factor = if self.zero_knowledge { 2 * MAX_CONSTRAINT_DEGREE } else { MAX_CONSTRAINT_DEGREE };
+        let one_over_rho = (self.extension_factor() / composition_factor) as u32;
         let security_factor = 31 - one_over_rho.leading_zeros(); // same as log2(one_over_rho)
         let num_queries = if optimistic == true { self.num_queries } else { self.num_queries / 2 };
 
@@ -79,43 +165,123 @@ impl ProofOptions {
     }
 }
 
-impl Default for ProofOptions {
+impl Default for ProofOptions<Blake3> {
 
-    fn default() -> ProofOptions {
+    fn default() -> ProofOptions<Blake3> {
         return ProofOptions {
-            extension_factor: DEFAULT_EXTENSION_FACTOR.trailing_zeros() as u8,
-            num_queries     : DEFAULT_NUM_QUERIES,
-            grinding_factor : DEFAULT_GRINDING_FACTOR,
-            hash_fn         : hash::blake3,
+            extension_factor    : DEFAULT_EXTENSION_FACTOR.trailing_zeros() as u8,
+            num_queries         : DEFAULT_NUM_QUERIES,
+            grinding_factor     : DEFAULT_GRINDING_FACTOR,
+            zero_knowledge      : false,
+            folding_factor      : DEFAULT_FOLDING_FACTOR,
+            max_remainder_degree: DEFAULT_MAX_REMAINDER_DEGREE,
+            hasher              : Blake3,
         };
     }
 
 }
 
+// DE-SERIALIZATION
+// ================================================================================================
+impl <H: Hasher> TryFrom<OptionsParts<H>> for ProofOptions<H> {
+    type Error = String;
+
+    fn try_from(parts: OptionsParts<H>) -> Result<ProofOptions<H>, String> {
+        // extension factor is stored as a power of 2; make sure it does not overflow
+        if parts.extension_factor > 8 {
+            return Err(String::from("extension_factor cannot be greater than 256"));
+        }
+
+        validate(
+            1 << parts.extension_factor,
+            parts.num_queries as usize,
+            parts.grinding_factor as u32,
+            parts.zero_knowledge,
+            parts.folding_factor as usize,
+            parts.max_remainder_degree as usize)?;
+
+        return Ok(ProofOptions {
+            extension_factor    : parts.extension_factor,
+            num_queries         : parts.num_queries,
+            grinding_factor     : parts.grinding_factor,
+            zero_knowledge      : parts.zero_knowledge,
+            folding_factor      : parts.folding_factor,
+            max_remainder_degree: parts.max_remainder_degree,
+            hasher              : H::default(),
+        });
+    }
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+fn validate(
+    extension_factor    : usize,
+    num_queries         : usize,
+    grinding_factor     : u32,
+    zero_knowledge      : bool,
+    folding_factor      : usize,
+    max_remainder_degree: usize) -> Result<(), String>
+{
+    if !extension_factor.is_power_of_two() {
+        return Err(String::from("extension_factor must be a power of 2"));
+    }
+    if extension_factor < 16 {
+        return Err(String::from("extension_factor cannot be smaller than 16"));
+    }
+    if extension_factor > 256 {
+        return Err(String::from("extension_factor cannot be greater than 256"));
+    }
+    if zero_knowledge && extension_factor < 32 {
+        return Err(String::from("extension_factor cannot be smaller than 32 for zero-knowledge proofs"));
+    }
+
+    if num_queries == 0 {
+        return Err(String::from("num_queries must be greater than 0"));
+    }
+    if num_queries > 128 {
+        return Err(String::from("num_queries cannot be greater than 128"));
+    }
+
+    if grinding_factor > 32 {
+        return Err(String::from("grinding factor cannot be greater than 32"));
+    }
+
+    if folding_factor != 2 && folding_factor != 4 && folding_factor != 8 && folding_factor != 16 {
+        return Err(String::from("folding_factor must be 2, 4, 8, or 16"));
+    }
+    if max_remainder_degree > 255 {
+        return Err(String::from("max_remainder_degree cannot be greater than 255"));
+    }
+
+    return Ok(());
+}
+
 // HASH FUNCTION SERIALIZATION / DE-SERIALIZATION
 // ================================================================================================
 mod hash_fn_serialization {
 
-    use serde::{ Serializer, Deserializer, Deserialize, ser, de };
-    use crate::crypto::{ HashFunction, hash };
+    use std::marker::PhantomData;
+    use serde::{ Serializer, Deserializer, Deserialize, de };
+    use crate::crypto::{ Hasher };
 
-    pub fn serialize<S>(hf: &HashFunction, s: S) -> Result<S::Ok, S::Error>
+    pub fn serialize<S, H>(_hasher: &H, s: S) -> Result<S::Ok, S::Error>
     where
-        S: Serializer
+        S: Serializer,
+        H: Hasher
     {
-        match *hf as usize {
-            f if f == hash::blake3 as usize => s.serialize_u8(0),
-            _ => Err(ser::Error::custom("unsupported hash function"))?
-        }
+        s.serialize_u8(H::ID)
     }
     
-    pub fn deserialize<'de, D>(deserializer: D) -> Result<HashFunction, D::Error>
+    pub fn deserialize<'de, D, H>(deserializer: D) -> Result<PhantomData<H>, D::Error>
     where
-        D: Deserializer<'de>
+        D: Deserializer<'de>,
+        H: Hasher
     {
-        match Deserialize::deserialize(deserializer)? {
-            0u8 => Ok(hash::blake3),
-            _ => Err(de::Error::custom("unsupported hash function"))
+        let id: u8 = Deserialize::deserialize(deserializer)?;
+        if id != H::ID {
+            return Err(de::Error::custom(format!(
+                "expected hash function with ID {}, but received {}", H::ID, id)));
         }
+        Ok(PhantomData)
     }
 }
\ No newline at end of file
@@ -0,0 +1,111 @@
+use std::cmp;
+use crate::{ math::field, crypto::Hasher, MIN_TRACE_LENGTH };
+use super::{ ProofOptions, StarkProof, VerificationError };
+
+// CONSTANTS
+// ================================================================================================
+const DEFAULT_MIN_SECURITY_LEVEL: u32 = 80;
+const DEFAULT_MAX_TRACE_LENGTH  : usize = 1 << 24;
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Defines the minimum requirements a proof must satisfy before the verifier will attempt to
+/// verify it. Since proof options are chosen by the prover and are embedded in the proof, the
+/// policy is the only way for the verifier to reject proofs which are too weak to be meaningful.
+/// 
+/// The hash function is not part of the policy: it is fixed by the type parameter of the
+/// verification functions, and a proof generated with a different hash function fails to
+/// deserialize into `StarkProof<H>`.
+#[derive(Clone)]
+pub struct VerifierPolicy {
+    min_security_level  : u32,
+    extension_factors   : Vec<usize>,
+    max_trace_length    : usize,
+}
+
+// VERIFIER POLICY IMPLEMENTATION
+// ================================================================================================
+impl VerifierPolicy {
+
+    pub fn new(
+        min_security_level  : u32,
+        extension_factors   : &[usize],
+        max_trace_length    : usize) -> VerifierPolicy
+    {
+        assert!(extension_factors.len() > 0, "at least one extension factor must be allowed");
+        for &extension_factor in extension_factors.iter() {
+            assert!(extension_factor.is_power_of_two(), "extension factor {} is not a power of 2", extension_factor);
+        }
+        assert!(max_trace_length.is_power_of_two(), "max_trace_length must be a power of 2");
+
+        return VerifierPolicy {
+            min_security_level  : min_security_level,
+            extension_factors   : extension_factors.to_vec(),
+            max_trace_length    : max_trace_length,
+        };
+    }
+
+    pub fn min_security_level(&self) -> u32 {
+        return self.min_security_level;
+    }
+
+    pub fn extension_factors(&self) -> &[usize] {
+        return &self.extension_factors;
+    }
+
+    pub fn max_trace_length(&self) -> usize {
+        return self.max_trace_length;
+    }
+
+    /// Checks whether the options and trace dimensions of the specified proof satisfy this
+    /// policy; security level is computed using the optimistic estimate.
+    pub fn check<H: Hasher>(&self, proof: &StarkProof<H>) -> Result<(), VerificationError> {
+        let options = proof.options();
+        self.check_options(options)?;
+
+        // compare depths rather than lengths to avoid overflow on malicious domain depths; the
+        // domain can never be larger than the largest multiplicative subgroup of the field
+        let extension_depth = options.extension_factor().trailing_zeros();
+        let max_trace_depth = cmp::min(self.max_trace_length.trailing_zeros(), field::TWO_ADICITY - extension_depth);
+        if proof.domain_depth() > (max_trace_depth + extension_depth) as usize {
+            return Err(VerificationError::TraceTooLong { max: 1 << max_trace_depth });
+        }
+
+        let min_domain_depth = MIN_TRACE_LENGTH.trailing_zeros() + options.extension_factor().trailing_zeros();
+        if proof.domain_depth() < min_domain_depth as usize {
+            return Err(VerificationError::TraceTooShort { min: MIN_TRACE_LENGTH });
+        }
+
+        return Ok(());
+    }
+
+    /// Checks whether the specified proof options satisfy this policy; this can be used by a
+    /// prover to make sure a proof will not be rejected before spending time generating it.
+    pub fn check_options<H: Hasher>(&self, options: &ProofOptions<H>) -> Result<(), VerificationError> {
+        if !self.extension_factors.contains(&options.extension_factor()) {
+            return Err(VerificationError::ExtensionFactorNotAllowed(options.extension_factor()));
+        }
+
+        let security_level = options.security_level(true);
+        if security_level < self.min_security_level {
+            return Err(VerificationError::InsufficientSecurityLevel {
+                actual  : security_level,
+                required: self.min_security_level
+            });
+        }
+
+        return Ok(());
+    }
+}
+
+impl Default for VerifierPolicy {
+
+    fn default() -> VerifierPolicy {
+        return VerifierPolicy {
+            min_security_level  : DEFAULT_MIN_SECURITY_LEVEL,
+            extension_factors   : vec![16, 32, 64, 128, 256],
+            max_trace_length    : DEFAULT_MAX_TRACE_LENGTH,
+        };
+    }
+}
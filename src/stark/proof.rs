@@ -1,24 +1,31 @@
+use std::ops::Range;
+use std::convert::TryInto;
 use serde::{ Serialize, Deserialize };
-use crate::crypto::{ BatchMerkleProof };
-use crate::stark::{ fri::FriProof, TraceState, ProofOptions };
-use crate::utils::{ uninit_vector, as_bytes };
+use crate::crypto::{ Hasher, Digest, BatchMerkleProof, hash::Blake3 };
+use crate::fri::{ FriProof };
+use crate::stark::{ TraceState, ProofOptions, utils };
+use crate::utils::{ as_bytes };
+use crate::{ MAX_CONTEXT_DEPTH, MAX_LOOP_DEPTH, MAX_STACK_DEPTH, MAX_REGISTER_COUNT };
 
 // TYPES AND INTERFACES
 // ================================================================================================
 
 // TODO: custom serialization should reduce size by 5% - 10%
 #[derive(Clone, Serialize, Deserialize)]
-pub struct StarkProof {
-    trace_root          : [u8; 32],
+#[serde(bound = "")]
+pub struct StarkProof<H: Hasher = Blake3> {
+    trace_root          : H::Digest,
     trace_info          : TraceInfo,
-    trace_nodes         : Vec<Vec<[u8; 32]>>,
+    trace_nodes         : Vec<Vec<H::Digest>>,
     trace_evaluations   : Vec<Vec<u128>>,
-    constraint_root     : [u8; 32],
-    constraint_proof    : BatchMerkleProof,
+    constraint_root     : H::Digest,
+    constraint_nodes    : Vec<Vec<H::Digest>>,
+    constraint_evaluations: Vec<[u128; 2]>,
+    randomizer_evaluations: Vec<[u128; 2]>,
     deep_values         : DeepValues,
-    degree_proof        : FriProof,
+    degree_proof        : FriProof<H>,
     pow_nonce           : u64,
-    options             : ProofOptions
+    options             : ProofOptions<H>
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -30,6 +37,13 @@ pub struct DeepValues {
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct TraceInfo {
     pub domain_depth    : u8,
+    pub executions      : Vec<ExecutionInfo>,
+}
+
+/// Describes the shape of a single execution trace; a proof contains one such description for
+/// every program execution covered by the proof.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ExecutionInfo {
     pub ctx_depth       : u8,
     pub loop_depth      : u8,
     pub stack_depth     : u8,
@@ -38,28 +52,24 @@ pub struct TraceInfo {
 
 // STARK PROOF IMPLEMENTATION
 // ================================================================================================
-impl StarkProof {
+impl <H: Hasher> StarkProof<H> {
     pub fn new(
-        trace_root          : &[u8; 32],
-        trace_proof         : BatchMerkleProof,
+        trace_root          : &H::Digest,
+        trace_proof         : BatchMerkleProof<H>,
         trace_evaluations   : Vec<Vec<u128>>,
-        constraint_root     : &[u8; 32],
-        constraint_proof    : BatchMerkleProof,
+        constraint_root     : &H::Digest,
+        constraint_proof    : BatchMerkleProof<H>,
+        constraint_evaluations: Vec<[u128; 2]>,
+        randomizer_evaluations: Vec<[u128; 2]>,
         deep_values         : DeepValues,
-        degree_proof        : FriProof,
+        degree_proof        : FriProof<H>,
         pow_nonce           : u64,
-        op_count            : u128,
-        ctx_depth           : usize,
-        loop_depth          : usize,
-        stack_depth         : usize,
-        options             : &ProofOptions ) -> StarkProof
+        executions          : Vec<ExecutionInfo>,
+        options             : &ProofOptions<H> ) -> StarkProof<H>
     {
         let trace_info = TraceInfo {
             domain_depth        : trace_proof.depth,
-            ctx_depth           : ctx_depth as u8,
-            loop_depth          : loop_depth as u8,
-            stack_depth         : stack_depth as u8,
-            op_count            : op_count as u32,
+            executions          : executions,
         };
 
         return StarkProof {
@@ -68,7 +78,9 @@ impl StarkProof {
             trace_nodes         : trace_proof.nodes,
             trace_evaluations   : trace_evaluations,
             constraint_root     : *constraint_root,
-            constraint_proof    : constraint_proof,
+            constraint_nodes    : constraint_proof.nodes,
+            constraint_evaluations: constraint_evaluations,
+            randomizer_evaluations: randomizer_evaluations,
             deep_values         : deep_values,
             degree_proof        : degree_proof,
             pow_nonce           : pow_nonce,
@@ -76,24 +88,34 @@ impl StarkProof {
         };
     }
 
-    pub fn trace_root(&self) -> &[u8; 32] {
+    pub fn trace_root(&self) -> &H::Digest {
         return &self.trace_root;
     }
 
-    pub fn options(&self) -> &ProofOptions {
+    pub fn options(&self) -> &ProofOptions<H> {
         return &self.options;
     }
 
+    /// Sets the instance of the hash function with which the proof is verified; the state of
+    /// the hasher is not serialized, and so a de-serialized proof uses the default instance
+    /// of `H` until the instance used by the prover is set.
+    pub fn set_hasher(&mut self, hasher: H) {
+        self.options = self.options.clone().with_hasher(hasher);
+    }
+
+    pub fn domain_depth(&self) -> usize {
+        return self.trace_info.domain_depth as usize;
+    }
+
     pub fn domain_size(&self) -> usize {
         return usize::pow(2, self.trace_info.domain_depth as u32);
     }
 
-    pub fn trace_proof(&self) -> BatchMerkleProof {
+    pub fn trace_proof(&self) -> BatchMerkleProof<H> {
 
-        let hash = self.options.hash_fn();
-        let mut hashed_states = uninit_vector::<[u8; 32]>(self.trace_evaluations.len());
+        let mut hashed_states = Vec::with_capacity(self.trace_evaluations.len());
         for i in 0..self.trace_evaluations.len() {
-            hash(as_bytes(&self.trace_evaluations[i]), &mut hashed_states[i]);
+            hashed_states.push(self.options.hasher().hash(as_bytes(&self.trace_evaluations[i])));
         }
 
         return BatchMerkleProof {
@@ -103,15 +125,36 @@ impl StarkProof {
          };
     }
 
-    pub fn constraint_root(&self) -> &[u8; 32] {
+    pub fn constraint_root(&self) -> &H::Digest {
         return &self.constraint_root;
     }
 
-    pub fn constraint_proof(&self) -> BatchMerkleProof {
-        return self.constraint_proof.clone();
+    pub fn constraint_proof(&self) -> BatchMerkleProof<H> {
+
+        let hashed_evaluations = utils::hash_constraint_evaluations(self.options.hasher(),
+            &self.constraint_evaluations, &self.randomizer_evaluations);
+
+        return BatchMerkleProof {
+            nodes   : self.constraint_nodes.clone(),
+            values  : hashed_evaluations,
+            depth   : self.trace_info.domain_depth - 1,
+        };
+    }
+
+    /// Returns constraint evaluations at the queried positions; every pair of evaluations
+    /// forms a single leaf of the constraint Merkle tree.
+    pub fn constraint_evaluations(&self) -> &[[u128; 2]] {
+        return &self.constraint_evaluations;
     }
 
-    pub fn degree_proof(&self) -> &FriProof {
+    /// Returns evaluations of the randomizer polynomial at the queried positions; these are
+    /// stored in the same leaves as constraint evaluations, and are empty when the proof is
+    /// not zero-knowledge.
+    pub fn randomizer_evaluations(&self) -> &[[u128; 2]] {
+        return &self.randomizer_evaluations;
+    }
+
+    pub fn degree_proof(&self) -> &FriProof<H> {
         return &self.degree_proof;
     }
 
@@ -129,37 +172,176 @@ impl StarkProof {
         return self.domain_size() / self.options.extension_factor();
     }
 
+    /// Returns the number of program executions covered by this proof.
+    pub fn execution_count(&self) -> usize {
+        return self.trace_info.executions.len();
+    }
+
+    /// Returns descriptions of execution traces for all program executions covered by this
+    /// proof; registers of all traces are stored side by side in the same order.
+    pub fn executions(&self) -> &[ExecutionInfo] {
+        return &self.trace_info.executions;
+    }
+
+    /// Returns the total number of registers in all execution traces.
+    pub fn register_count(&self) -> usize {
+        return self.trace_info.executions.iter().map(|e| e.width()).sum();
+    }
+
+    // DEEP VALUES
+    // -------------------------------------------------------------------------------------------
+
+    /// Returns the state of the execution trace at the specified `index` at point z.
+    pub fn get_state_at_z1(&self, index: usize) -> TraceState {
+        let execution = &self.trace_info.executions[index];
+        let range = self.get_register_range(index);
+        return execution.build_state(&self.deep_values.trace_at_z1[range]);
+    }
+
+    /// Returns the state of the execution trace at the specified `index` at point z * g.
+    pub fn get_state_at_z2(&self, index: usize) -> TraceState {
+        let execution = &self.trace_info.executions[index];
+        let range = self.get_register_range(index);
+        return execution.build_state(&self.deep_values.trace_at_z2[range]);
+    }
+
+    /// Returns the range of registers occupied by the execution trace at the specified `index`.
+    pub fn get_register_range(&self, index: usize) -> Range<usize> {
+        let start = self.trace_info.executions[..index].iter().map(|e| e.width()).sum();
+        return Range { start, end: start + self.trace_info.executions[index].width() };
+    }
+
+    /// Returns true if DEEP values and trace evaluations contain values for the expected number
+    /// of registers.
+    pub fn is_well_formed(&self) -> bool {
+        if !self.trace_info.executions.iter().all(|e| e.is_valid()) { return false; }
+        let register_count = self.register_count();
+        return self.deep_values.trace_at_z1.len() == register_count
+            && self.deep_values.trace_at_z2.len() == register_count
+            && self.trace_evaluations.iter().all(|row| row.len() == register_count);
+    }
+
+    // RECURSIVE VERIFICATION
+    // -------------------------------------------------------------------------------------------
+
+    /// Returns contents of secret input tapes A and B for a program which verifies this proof.
+    /// All values are laid out in pairs, with the first value of each pair on tape A and the
+    /// second on tape B, so that every pair can be read with a single READ2 operation:
+    /// 
+    /// 1. trace root and constraint root;
+    /// 2. proof-of-work nonce paired with the number of queries;
+    /// 3. DEEP values: for every register, its values at z and at z * g;
+    /// 4. trace evaluations at every queried position, followed by trace Merkle nodes;
+    /// 5. constraint and randomizer evaluations at every queried position, followed by
+    ///    constraint Merkle nodes;
+    /// 6. for every FRI layer, its root, evaluations at the queried positions and Merkle nodes,
+    ///    followed by the remainder.
+    /// 
+    /// Each digest is split into four 64-bit limbs and takes two pairs; each list of values is
+    /// padded with a ZERO to an even length; each list of Merkle nodes is preceded by a pair
+    /// holding the number of nodes and a ZERO. Thus, all values on the tapes are valid field
+    /// elements regardless of the hash function used by the proof.
+    pub fn verifier_tapes(&self) -> [Vec<u128>; 2] {
+        let mut tapes = [Vec::new(), Vec::new()];
+
+        push_digest(&mut tapes, &self.trace_root);
+        push_digest(&mut tapes, &self.constraint_root);
+        push_pair(&mut tapes, self.pow_nonce as u128, self.trace_evaluations.len() as u128);
+
+        for (&z1, &z2) in self.deep_values.trace_at_z1.iter().zip(self.deep_values.trace_at_z2.iter()) {
+            push_pair(&mut tapes, z1, z2);
+        }
+
+        self.trace_evaluations.iter().for_each(|row| push_values(&mut tapes, row));
+        self.trace_nodes.iter().for_each(|nodes| push_nodes(&mut tapes, nodes));
+
+        self.constraint_evaluations.iter().for_each(|&[a, b]| push_pair(&mut tapes, a, b));
+        self.randomizer_evaluations.iter().for_each(|&[a, b]| push_pair(&mut tapes, a, b));
+        self.constraint_nodes.iter().for_each(|nodes| push_nodes(&mut tapes, nodes));
+
+        for layer in self.degree_proof.layers.iter() {
+            push_digest(&mut tapes, &layer.root);
+            layer.values.iter().for_each(|values| push_values(&mut tapes, values));
+            layer.nodes.iter().for_each(|nodes| push_nodes(&mut tapes, nodes));
+        }
+        push_values(&mut tapes, &self.degree_proof.rem_values);
+
+        return tapes;
+    }
+}
+
+// EXECUTION INFO IMPLEMENTATION
+// ================================================================================================
+impl ExecutionInfo {
+
+    pub fn new(ctx_depth: usize, loop_depth: usize, stack_depth: usize, op_count: u128) -> ExecutionInfo {
+        return ExecutionInfo {
+            ctx_depth   : ctx_depth as u8,
+            loop_depth  : loop_depth as u8,
+            stack_depth : stack_depth as u8,
+            op_count    : op_count as u32,
+        };
+    }
+
     pub fn ctx_depth(&self) -> usize {
-        return self.trace_info.ctx_depth as usize;
+        return self.ctx_depth as usize;
     }
 
     pub fn loop_depth(&self) -> usize {
-        return self.trace_info.loop_depth as usize;
+        return self.loop_depth as usize;
     }
 
     pub fn stack_depth(&self) -> usize {
-        return self.trace_info.stack_depth as usize;
+        return self.stack_depth as usize;
     }
 
     pub fn op_count(&self) -> u128 {
-        return self.trace_info.op_count as u128;
+        return self.op_count as u128;
     }
 
-    // DEEP VALUES
-    // -------------------------------------------------------------------------------------------
-    pub fn get_state_at_z1(&self) -> TraceState {
-        return TraceState::from_vec(
-            self.ctx_depth(),
-            self.loop_depth(),
-            self.stack_depth(),
-            &self.deep_values.trace_at_z1);
-    }
-
-    pub fn get_state_at_z2(&self) -> TraceState {
-        return TraceState::from_vec(
-            self.ctx_depth(),
-            self.loop_depth(),
-            self.stack_depth(),
-            &self.deep_values.trace_at_z2);
-    }
-}
\ No newline at end of file
+    /// Returns the number of registers in the execution trace.
+    pub fn width(&self) -> usize {
+        return TraceState::compute_decoder_width(self.ctx_depth(), self.loop_depth()) + self.stack_depth();
+    }
+
+    /// Returns true if trace dimensions are within the limits supported by the VM.
+    pub fn is_valid(&self) -> bool {
+        return self.ctx_depth() <= MAX_CONTEXT_DEPTH
+            && self.loop_depth() <= MAX_LOOP_DEPTH
+            && self.stack_depth() > 0
+            && self.stack_depth() <= MAX_STACK_DEPTH
+            && self.width() < MAX_REGISTER_COUNT;
+    }
+
+    fn build_state(&self, values: &[u128]) -> TraceState {
+        return TraceState::from_vec(self.ctx_depth(), self.loop_depth(), self.stack_depth(), &values.to_vec());
+    }
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+fn push_pair(tapes: &mut [Vec<u128>; 2], a: u128, b: u128) {
+    tapes[0].push(a);
+    tapes[1].push(b);
+}
+
+/// Pushes values onto the tapes in pairs, padding the last pair with a ZERO if needed.
+fn push_values(tapes: &mut [Vec<u128>; 2], values: &[u128]) {
+    for pair in values.chunks(2) {
+        push_pair(tapes, pair[0], if pair.len() == 2 { pair[1] } else { 0 });
+    }
+}
+
+/// Pushes the digest onto the tapes as two pairs of 64-bit limbs, least significant limb first.
+fn push_digest<D: Digest>(tapes: &mut [Vec<u128>; 2], digest: &D) {
+    let bytes = digest.as_bytes();
+    let limbs = bytes.chunks(8).map(|limb| u64::from_le_bytes(limb.try_into().unwrap()) as u128).collect::<Vec<_>>();
+    push_values(tapes, &limbs);
+}
+
+/// Pushes the number of nodes onto the tapes followed by all of the nodes.
+fn push_nodes<D: Digest>(tapes: &mut [Vec<u128>; 2], nodes: &[D]) {
+    push_pair(tapes, nodes.len() as u128, 0);
+    nodes.iter().for_each(|node| push_digest(tapes, node));
+}
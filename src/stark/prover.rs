@@ -1,130 +1,151 @@
 use std::time::Instant;
 use log::debug;
 use crate::{
-    math::{ field, polynom, fft },
-    crypto::MerkleTree,
+    math::{ field, polynom, polynom::Polynomial, fft, fft::FftPlan },
+    crypto::{ Hasher, Digest, MerkleTree },
+    fri::{ self, FriTranscript },
 };
 use super::{
-    ProofOptions, StarkProof, CompositionCoefficients, DeepValues, fri, utils,
-    trace::{ TraceTable, TraceState },
+    ProofOptions, StarkProof, ExecutionInfo, CompositionCoefficients, DeepValues, DOMAIN_OFFSET, utils,
+    trace::{ self, TraceTable, TraceState },
     constraints::{ ConstraintTable, ConstraintPoly },
-    MAX_CONSTRAINT_DEGREE,
 };
 
 // PROVER FUNCTION
 // ================================================================================================
 
-pub fn prove(trace: &mut TraceTable, inputs: &[u128], outputs: &[u128], options: &ProofOptions) -> StarkProof {
-    // 1 ----- extend execution trace -------------------------------------------------------------
+pub fn prove<H: Hasher>(traces: &mut [TraceTable], inputs: &[Vec<u128>], outputs: &[Vec<u128>], options: &ProofOptions<H>) -> StarkProof<H> {
+    assert!(traces.len() > 0, "at least one execution trace must be provided");
+    assert!(traces.len() == inputs.len() && traces.len() == outputs.len(),
+        "number of inputs and outputs must match the number of execution traces");
+
+    // 1 ----- extend execution traces ------------------------------------------------------------
     let now = Instant::now();
 
-    // build LDE domain and LDE twiddles (for FFT evaluation over LDE domain)
-    let lde_root = field::get_root_of_unity(trace.domain_size());
-    let lde_domain = field::get_power_series(lde_root, trace.domain_size());
-    let lde_twiddles = twiddles_from_domain(&lde_domain);
+    // all traces must have the same length; so, pad shorter traces to the length of the longest
+    // one. Masking hides revealed trace values only if the trace is long enough; so, also pad
+    // short traces when generating zero-knowledge proofs
+    let mut trace_length = traces.iter().map(|t| t.unextended_length()).max().unwrap();
+    if options.zero_knowledge() {
+        trace_length = std::cmp::max(trace_length, utils::get_min_masked_trace_length(options.num_queries()));
+    }
+    traces.iter_mut().for_each(|trace| trace.pad(trace_length));
+
+    // in debug mode, make sure transition constraints hold at every step of the traces
+    #[cfg(debug_assertions)]
+    traces.iter().for_each(|trace| super::ConstraintEvaluator::validate_transitions(trace));
+
+    // build LDE domain and get an FFT plan which caches twiddles for all domains up to the size
+    // of the LDE domain; the plan is shared with all other proofs; the LDE domain is a coset of the multiplicative subgroup shifted by
+    // DOMAIN_OFFSET
+    let domain_size = traces[0].domain_size();
+    let lde_root = field::get_root_of_unity(domain_size);
+    let lde_domain = fft::get_coset_domain(lde_root, DOMAIN_OFFSET, domain_size);
+    let fft_plan = FftPlan::shared(domain_size);
 
     // extend the execution trace registers to LDE domain
-    trace.extend(&lde_twiddles);
-    debug!("Extended execution trace from {} to {} steps in {} ms",
-        trace.unextended_length(),
-        trace.domain_size(), 
+    traces.iter_mut().for_each(|trace| trace.extend(&fft_plan, options.zero_knowledge()));
+    debug!("Extended {} execution trace(s) from {} to {} steps in {} ms",
+        traces.len(),
+        trace_length,
+        domain_size, 
         now.elapsed().as_millis());
 
-    // 2 ----- build Merkle tree from the extended execution trace ------------------------------------
+    // 2 ----- build Merkle tree from the extended execution traces -------------------------------
     let now = Instant::now();
-    let trace_tree = trace.build_merkle_tree(options.hash_fn());
+    let hasher = options.hasher();
+    let trace_tree = trace::build_merkle_tree(hasher, traces);
     debug!("Built trace Merkle tree in {} ms", 
         now.elapsed().as_millis());
 
     // 3 ----- evaluate constraints ---------------------------------------------------------------
     let now = Instant::now();
-    
-    // initialize constraint evaluation table
-    let mut constraints = ConstraintTable::new(&trace, trace_tree.root(), inputs, outputs);
-    
-    // allocate space to hold current and next states for constraint evaluations
-    let mut current = TraceState::new(trace.ctx_depth(), trace.loop_depth(), trace.stack_depth());
-    let mut next = TraceState::new(trace.ctx_depth(), trace.loop_depth(), trace.stack_depth());
-
-    // we don't need to evaluate constraints over the entire extended execution trace; we need
-    // to evaluate them over the domain extended to match max constraint degree - thus, we can
-    // skip most trace states for the purposes of constraint evaluation.
-    let stride = trace.extension_factor() / MAX_CONSTRAINT_DEGREE;
-    for i in (0..trace.domain_size()).step_by(stride) {
-        // TODO: this loop should be parallelized and also potentially optimized to avoid copying
-        // next state from the trace table twice
 
-        // copy current and next states from the trace table; next state may wrap around the
-        // execution trace (close to the end of the trace)
-        trace.fill_state(&mut current, i);
-        trace.fill_state(&mut next, (i + trace.extension_factor()) % trace.domain_size());
-
-        // evaluate the constraints
-        constraints.evaluate(&current, &next, lde_domain[i], i / stride);
+    // constraints of each trace are combined using a distinct set of pseudo-random coefficients,
+    // and the resulting polynomials are added together into a single constraint polynomial
+    let trace_root = trace_tree.root().as_bytes();
+    let mut constraint_poly: Option<ConstraintPoly> = None;
+    let mut constraint_count = 0;
+    for (i, trace) in traces.iter().enumerate() {
+        let seed = utils::get_execution_seed(hasher, &trace_root, i);
+        let constraints = evaluate_constraints(trace, &seed, &inputs[i], &outputs[i], &lde_domain);
+        constraint_count += constraints.constraint_count();
+        let poly = constraints.combine_polys(&fft_plan);
+        match constraint_poly.as_mut() {
+            Some(result) => result.add(&poly),
+            None => constraint_poly = Some(poly),
+        }
     }
+    let constraint_poly = constraint_poly.unwrap();
 
-    debug!("Evaluated {} constraints over domain of {} elements in {} ms",
-        constraints.constraint_count(),
-        constraints.evaluation_domain_size(),
-        now.elapsed().as_millis());
-
-    // 4 ----- convert constraint evaluations into a polynomial -----------------------------------
-    let now = Instant::now();
-    let constraint_poly = constraints.combine_polys();
-    debug!("Converted constraint evaluations into a single polynomial of degree {} in {} ms",
+    debug!("Evaluated {} constraints of {} execution trace(s) and combined them into a single polynomial of degree {} in {} ms",
+        constraint_count,
+        traces.len(),
         constraint_poly.degree(),
         now.elapsed().as_millis());
 
-    // 5 ----- build Merkle tree from constraint polynomial evaluations ---------------------------
+    // 4 ----- build Merkle tree from constraint polynomial evaluations ---------------------------
     let now = Instant::now();
     
     // evaluate constraint polynomial over the evaluation domain
-    let constraint_evaluations = constraint_poly.eval(&lde_twiddles);
-
-    // put evaluations into a Merkle tree; 4 evaluations per leaf
-    let constraint_evaluations = evaluations_to_leaves(constraint_evaluations);
-    let constraint_tree = MerkleTree::new(constraint_evaluations, options.hash_fn());
+    let constraint_evaluations = constraint_poly.eval(&fft_plan, domain_size);
+
+    // when building zero-knowledge proofs, generate a random polynomial of the same degree as
+    // the composition polynomial; this polynomial will be added to the composition polynomial
+    // to make sure FRI layers don't reveal anything about the trace
+    let (randomizer_poly, randomizer_evaluations) = if options.zero_knowledge() {
+        let composition_degree = utils::get_composition_degree(trace_length, traces[0].poly_length());
+        let randomizer_poly = field::rand_vector(composition_degree + 1);
+        let mut randomizer_evaluations = vec![field::ZERO; lde_domain.len()];
+        randomizer_evaluations[..randomizer_poly.len()].copy_from_slice(&randomizer_poly);
+        fft_plan.evaluate(&mut randomizer_evaluations, DOMAIN_OFFSET);
+        (randomizer_poly, group_evaluations(&randomizer_evaluations))
+    }
+    else {
+        (Vec::new(), Vec::new())
+    };
+
+    // put evaluations into a Merkle tree; 2 evaluations per leaf
+    let constraint_evaluations = group_evaluations(&constraint_evaluations);
+    let constraint_tree = MerkleTree::with_hasher(
+        utils::hash_constraint_evaluations(hasher, &constraint_evaluations, &randomizer_evaluations), hasher.clone());
     debug!("Evaluated constraint polynomial and built constraint Merkle tree in {} ms",
         now.elapsed().as_millis());
 
-    // 6 ----- build and evaluate deep composition polynomial -------------------------------------
+    // 5 ----- build and evaluate deep composition polynomial -------------------------------------
     let now = Instant::now();
 
     // combine trace and constraint polynomials into the final deep composition polynomial
-    let seed = constraint_tree.root();
-    let (composition_poly, deep_values) = build_composition_poly(&trace, constraint_poly, seed);
+    let seed = constraint_tree.root().as_bytes();
+    let (composition_poly, deep_values) = build_composition_poly(hasher, traces, constraint_poly, &randomizer_poly, &seed);
 
     // evaluate the composition polynomial over LDE domain
-    let mut composed_evaluations = composition_poly;
-    debug_assert!(composed_evaluations.capacity() == lde_domain.len(), "invalid composition polynomial capacity");
-    unsafe { composed_evaluations.set_len(composed_evaluations.capacity()); }
-    polynom::eval_fft_twiddles(&mut composed_evaluations, &lde_twiddles, true);
+    let mut composed_evaluations = composition_poly.into_coefficients();
+    composed_evaluations.resize(lde_domain.len(), field::ZERO);
+    fft_plan.evaluate(&mut composed_evaluations, DOMAIN_OFFSET);
 
     debug!("Built composition polynomial and evaluated it over domain of {} elements in {} ms",
         composed_evaluations.len(),
         now.elapsed().as_millis());
 
-    // 7 ----- compute FRI layers for the composition polynomial ----------------------------------
+    // 6 ----- compute FRI layers for the composition polynomial ----------------------------------
     let now = Instant::now();
-    let composition_degree = utils::get_composition_degree(trace.unextended_length());
+    let composition_degree = utils::get_composition_degree(trace_length, traces[0].poly_length());
     debug_assert!(composition_degree == polynom::infer_degree(&composed_evaluations));
-    let (fri_trees, fri_values) = fri::reduce(&composed_evaluations, &lde_domain, options);
+    let fri_options = options.fri_options();
+    let fri_context = utils::get_fri_context(&trace_root, &constraint_tree.root().as_bytes());
+    let mut fri_transcript = FriTranscript::new(hasher, &fri_context, lde_domain.len(), composition_degree, &fri_options);
+    let (fri_trees, fri_values, fri_remainder) = fri::reduce(&composed_evaluations, &lde_domain, composition_degree, &fri_options, &mut fri_transcript);
     debug!("Computed {} FRI layers from composition polynomial evaluations in {} ms",
     fri_trees.len(),
         now.elapsed().as_millis());
 
-    // 8 ----- determine query positions -----------------------------------------------------------
+    // 7 ----- determine query positions -----------------------------------------------------------
     let now = Instant::now();
 
-    // combine all FRI layer roots into a single vector
-    let mut fri_roots: Vec<u8> = Vec::new();
-    for tree in fri_trees.iter() {
-        tree.root().iter().for_each(|&v| fri_roots.push(v));
-    }
-
-    // derive a seed from the combined roots
-    let mut seed = [0u8; 32];
-    options.hash_fn()(&fri_roots, &mut seed);
+    // derive a seed from the FRI transcript, which has absorbed all layer roots, and the
+    // remainder commitment
+    let seed = fri_transcript.commit_remainder(&fri_remainder);
 
     // apply proof-of-work to get a new seed
     let (seed, pow_nonce) = utils::find_pow_nonce(seed, &options);
@@ -136,17 +157,39 @@ pub fn prove(trace: &mut TraceTable, inputs: &[u128], outputs: &[u128], options:
         hex::encode(seed),
         now.elapsed().as_millis());
 
-    // 9 ----- build proof object -----------------------------------------------------------------
+    // 8 ----- build proof object -----------------------------------------------------------------
     let now = Instant::now();
 
     // generate FRI proof
-    let fri_proof = fri::build_proof(fri_trees, fri_values, &positions);
+    let fri_proof = fri::build_proof(fri_trees, fri_values, fri_remainder, &positions, &fri_options);
+
+    // built a list of trace evaluations at queried positions; values of all traces at a given
+    // position are concatenated together
+    let mut trace_evaluations = vec![Vec::new(); positions.len()];
+    for trace in traces.iter() {
+        for (row, values) in trace_evaluations.iter_mut().zip(trace.get_register_values_at(&positions)) {
+            row.extend_from_slice(&values);
+        }
+    }
 
-    // built a list of trace evaluations at queried positions
-    let trace_evaluations = trace.get_register_values_at(&positions);
+    // describe the shape of each execution trace
+    let executions = traces.iter().map(|trace| ExecutionInfo::new(
+        trace.ctx_depth(),
+        trace.loop_depth(),
+        trace.stack_depth(),
+        trace.get_last_state().op_counter())).collect();
 
-    // build a list of constraint positions
+    // build a list of constraint positions and constraint evaluations at these positions
     let constraint_positions = utils::map_trace_to_constraint_positions(&positions);
+    let queried_constraint_evaluations = constraint_positions.iter()
+        .map(|&p| constraint_evaluations[p])
+        .collect::<Vec<[u128; 2]>>();
+    let queried_randomizer_evaluations = if options.zero_knowledge() {
+        constraint_positions.iter().map(|&p| randomizer_evaluations[p]).collect::<Vec<[u128; 2]>>()
+    }
+    else {
+        Vec::new()
+    };
 
     // build the proof object
     let proof = StarkProof::new(
@@ -155,13 +198,12 @@ pub fn prove(trace: &mut TraceTable, inputs: &[u128], outputs: &[u128], options:
         trace_evaluations,
         constraint_tree.root(),
         constraint_tree.prove_batch(&constraint_positions),
+        queried_constraint_evaluations,
+        queried_randomizer_evaluations,
         deep_values,
         fri_proof,
         pow_nonce,
-        trace.get_last_state().op_counter(),
-        trace.ctx_depth(),
-        trace.loop_depth(),
-        trace.stack_depth(),
+        executions,
         &options);
 
     debug!("Built proof object in {} ms", now.elapsed().as_millis());
@@ -170,32 +212,65 @@ pub fn prove(trace: &mut TraceTable, inputs: &[u128], outputs: &[u128], options:
 
 // HELPER FUNCTIONS
 // ================================================================================================
-fn twiddles_from_domain(domain: &[u128]) -> Vec<u128> {
-    let mut twiddles = domain[..(domain.len() / 2)].to_vec();
-    fft::permute(&mut twiddles);
-    return twiddles;
+/// Groups consecutive pairs of evaluations together; each pair becomes a single leaf in
+/// the constraint Merkle tree.
+fn group_evaluations(evaluations: &[u128]) -> Vec<[u128; 2]> {
+    assert!(evaluations.len() % 2 == 0, "number of values must be divisible by 2");
+    return evaluations.chunks(2).map(|pair| [pair[0], pair[1]]).collect();
 }
 
-/// Re-interpret vector of 16-byte values as a vector of 32-byte arrays
-fn evaluations_to_leaves(evaluations: Vec<u128>) -> Vec<[u8; 32]> {
-    assert!(evaluations.len() % 2 == 0, "number of values must be divisible by 2");
-    let mut v = std::mem::ManuallyDrop::new(evaluations);
-    let p = v.as_mut_ptr();
-    let len = v.len() / 2;
-    let cap = v.capacity() / 2;
-    return unsafe { Vec::from_raw_parts(p as *mut [u8; 32], len, cap) };
+/// Evaluates constraints of the specified execution trace over the constraint evaluation domain.
+fn evaluate_constraints(trace: &TraceTable, seed: &[u8; 32], inputs: &[u128], outputs: &[u128], lde_domain: &[u128]) -> ConstraintTable {
+    // initialize constraint evaluation table
+    let mut constraints = ConstraintTable::new(trace, seed, inputs, outputs);
+
+    // allocate space to hold current and next states for constraint evaluations
+    let mut current = TraceState::new(trace.ctx_depth(), trace.loop_depth(), trace.stack_depth());
+    let mut next = TraceState::new(trace.ctx_depth(), trace.loop_depth(), trace.stack_depth());
+
+    // we don't need to evaluate constraints over the entire extended execution trace; we need
+    // to evaluate them over the domain extended to match max constraint degree - thus, we can
+    // skip most trace states for the purposes of constraint evaluation.
+    let stride = trace.domain_size() / constraints.evaluation_domain_size();
+    for i in (0..trace.domain_size()).step_by(stride) {
+        // TODO: this loop should be parallelized and also potentially optimized to avoid copying
+        // next state from the trace table twice
+
+        // copy current and next states from the trace table; next state may wrap around the
+        // execution trace (close to the end of the trace)
+        trace.fill_state(&mut current, i);
+        trace.fill_state(&mut next, (i + trace.extension_factor()) % trace.domain_size());
+
+        // evaluate the constraints
+        constraints.evaluate(&current, &next, lde_domain[i], i / stride);
+    }
+
+    return constraints;
 }
 
-fn build_composition_poly(trace: &TraceTable, constraint_poly: ConstraintPoly, seed: &[u8; 32]) -> (Vec<u128>, DeepValues) {
+fn build_composition_poly<H: Hasher>(hasher: &H, traces: &[TraceTable], constraint_poly: ConstraintPoly, randomizer_poly: &[u128], seed: &[u8; 32]) -> (Polynomial<u128>, DeepValues) {
     // pseudo-randomly selection deep point z and coefficients for the composition
     let z = field::prng(*seed);
     let coefficients = CompositionCoefficients::new(*seed);
 
-    // divide out deep point from trace polynomials and merge them into a single polynomial
-    let (mut result, s1, s2) = trace.get_composition_poly(z, &coefficients);
+    // divide out deep point from trace polynomials and merge them into a single polynomial;
+    // registers of each trace are combined using a distinct set of coefficients
+    let (mut result, mut s1, mut s2) = traces[0].get_composition_poly(z, &coefficients);
+    for i in 1..traces.len() {
+        let seed = utils::get_execution_seed(hasher, seed, i);
+        let (poly, t1, t2) = traces[i].get_composition_poly(z, &CompositionCoefficients::new(seed));
+        result += &poly;
+        s1.extend_from_slice(&t1);
+        s2.extend_from_slice(&t2);
+    }
 
     // divide out deep point from constraint polynomial and merge it into the result
     constraint_poly.merge_into(&mut result, z, &coefficients);
 
+    // add the randomizer polynomial (if any) into the result
+    if randomizer_poly.len() > 0 {
+        result += &(Polynomial::from(randomizer_poly) * coefficients.randomizer);
+    }
+
     return (result, DeepValues { trace_at_z1: s1, trace_at_z2: s2 });
 }
\ No newline at end of file
@@ -2,4 +2,4 @@ mod trace_state;
 mod trace_table;
 
 pub use trace_state::TraceState;
-pub use trace_table::TraceTable;
\ No newline at end of file
+pub use trace_table::{ TraceTable, build_merkle_tree };
\ No newline at end of file
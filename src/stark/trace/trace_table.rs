@@ -1,14 +1,14 @@
-use crate::math::{ field, fft, polynom, parallel };
-use crate::crypto::{ MerkleTree, HashFunction };
-use crate::stark::{ CompositionCoefficients, utils };
-use crate::utils::{ uninit_vector, filled_vector, as_bytes };
+use crate::math::{ field, polynom::Polynomial, fft::FftPlan };
+use crate::crypto::{ Hasher, MerkleTree };
+use crate::stark::{ CompositionCoefficients, DOMAIN_OFFSET, utils };
+use crate::utils::{ as_bytes };
 use super::{ TraceState };
 
 // TYPES AND INTERFACES
 // ================================================================================================
 pub struct TraceTable {
     registers       : Vec<Vec<u128>>,
-    polys           : Vec<Vec<u128>>,
+    polys           : Vec<Polynomial<u128>>,
     ctx_depth       : usize,
     loop_depth      : usize,
     stack_depth     : usize,
@@ -64,15 +64,16 @@ impl TraceTable {
         return result;
     }
 
-    /// Returns state of the trace table at the last step.
+    /// Returns state of the trace table at the last step. Once the trace table has been
+    /// extended, none of the stored states corresponds to a step of the trace; so, the last
+    /// state is computed by evaluating register polynomials at the last step.
     pub fn get_last_state(&self) -> TraceState {
-        let last_step = if self.is_extended() {
-            self.domain_size() - self.extension_factor()
+        if self.is_extended() {
+            let g = field::get_root_of_unity(self.trace_length);
+            let x = field::exp(g, (self.trace_length - 1) as u128);
+            return TraceState::from_vec(self.ctx_depth, self.loop_depth, self.stack_depth, &self.eval_polys_at(x));
         }
-        else {
-            self.unextended_length() - 1
-        };
-        return self.get_state(last_step);
+        return self.get_state(self.unextended_length() - 1);
     }
 
     /// Copies trace table state at the specified `step` to the passed in `state` object.
@@ -85,6 +86,14 @@ impl TraceTable {
         return self.trace_length;
     }
 
+    /// Returns the number of coefficients in register polynomials; can be called only after
+    /// the trace table has been extended. For masked trace tables, this is twice the number
+    /// of states in the un-extended trace table.
+    pub fn poly_length(&self) -> usize {
+        assert!(self.is_extended(), "trace table has not been extended yet");
+        return self.polys[0].len();
+    }
+
     /// Returns the number of states in the extended trace table.
     pub fn domain_size(&self) -> usize {
         return self.trace_length * self.extension_factor;
@@ -120,7 +129,7 @@ impl TraceTable {
     #[cfg(test)]
     pub fn get_register_poly(&self, index: usize) -> &[u128] {
         assert!(self.is_extended(), "trace table has not been extended yet");
-        return &self.polys[index];
+        return self.polys[index].coefficients();
     }
 
     /// Returns values of all registers at the specified `positions`.
@@ -138,50 +147,69 @@ impl TraceTable {
         return self.registers[0].len() > self.trace_length;
     }
 
-    /// Extends all registers of the trace table by the `extension_factor` specified during
-    /// trace table construction. A trace table can be extended only once.
-    pub fn extend(&mut self, twiddles: &[u128]) {
+    /// Pads the trace table with copies of its last state until the table has `length` steps.
+    /// The last state of an execution trace is always a NOOP state, and thus, the copies do
+    /// not break transition constraints. A trace table can be padded only before it has been
+    /// extended.
+    pub fn pad(&mut self, length: usize) {
         assert!(!self.is_extended(), "trace table has already been extended");
-        assert!(twiddles.len() * 2 == self.domain_size(), "invalid number of twiddles");
+        assert!(length.is_power_of_two(), "trace length must be a power of 2");
+        if length <= self.trace_length { return; }
 
-        // build inverse twiddles needed for FFT interpolation
-        let root = field::get_root_of_unity(self.unextended_length());
-        let inv_twiddles = fft::get_inv_twiddles(root, self.unextended_length());
-        
-        // move register traces into polys
-        std::mem::swap(&mut self.registers, &mut self.polys);
+        for register in self.registers.iter_mut() {
+            let last_value = register[self.trace_length - 1];
+            register.resize(length, last_value);
+        }
+        self.trace_length = length;
+    }
 
-        // extend all registers
-        let domain_size = self.domain_size();
-        for poly in self.polys.iter_mut() {
+    /// Extends all registers of the trace table by the `extension_factor` specified during
+    /// trace table construction; registers are evaluated over the coset of the extended domain
+    /// shifted by DOMAIN_OFFSET. A trace table can be extended only once. If `masked` is
+    /// set to true, a random multiple of x^n - 1 is added to each register polynomial; this
+    /// leaves register values at all steps of the trace unchanged, but makes register values
+    /// at all other points of the domain random.
+    pub fn extend(&mut self, fft_plan: &FftPlan<u128>, masked: bool) {
+        assert!(!self.is_extended(), "trace table has already been extended");
+        assert!(fft_plan.max_size() >= self.domain_size(), "FFT plan does not support domain of {} elements", self.domain_size());
+
+        // move register traces out of the table and interpolate them into polynomials
+        let mut polys = std::mem::replace(&mut self.registers, Vec::new());
+        fft_plan.interpolate_batch(&mut polys, field::ONE);
+
+        // compute T(x) + (x^n - 1) * R(x) for a random polynomial R(x) of degree n - 1
+        if masked {
+            let trace_length = self.unextended_length();
+            for poly in polys.iter_mut() {
+                let mask = field::rand_vector(trace_length);
+                poly.resize(utils::get_trace_poly_length(trace_length, true), field::ZERO);
+                for i in 0..trace_length {
+                    poly[i] = field::sub(poly[i], mask[i]);
+                    poly[trace_length + i] = mask[i];
+                }
+            }
+        }
 
-            // interpolate register trace into a polynomial
-            polynom::interpolate_fft_twiddles(poly, &inv_twiddles, true);
-            
-            // allocate space to hold extended evaluations and copy the polynomial into it
+        // allocate space to hold extended evaluations, copy the polynomials into it, and
+        // evaluate all polynomials over the extended domain
+        let domain_size = self.domain_size();
+        let mut registers = polys.iter().map(|poly| {
             let mut register = vec![field::ZERO; domain_size];
             register[..poly.len()].copy_from_slice(&poly);
-            
-            // evaluate the polynomial over extended domain
-            polynom::eval_fft_twiddles(&mut register, &twiddles, true);
-            self.registers.push(register);
-        }
+            register
+        }).collect::<Vec<_>>();
+        fft_plan.evaluate_batch(&mut registers, DOMAIN_OFFSET);
+
+        self.registers = registers;
+        self.polys = polys.into_iter().map(Polynomial::new).collect();
     }
 
     /// Puts the trace table into a Merkle tree such that each state of the table becomes
     /// a distinct leaf in the tree; all registers at a given step are hashed together to
     /// form a single leaf value.
-    pub fn build_merkle_tree(&self, hash: HashFunction) -> MerkleTree {
-        let mut trace_state = vec![field::ZERO; self.register_count()];
-        let mut hashed_states = uninit_vector::<[u8; 32]>(self.domain_size());
-        // TODO: this loop should be parallelized
-        for i in 0..self.domain_size() {
-            for j in 0..trace_state.len() {
-                trace_state[j] = self.registers[j][i];
-            }
-            hash(as_bytes(&trace_state), &mut hashed_states[i]);
-        }
-        return MerkleTree::new(hashed_states, hash);
+    #[cfg(test)]
+    pub fn build_merkle_tree<H: Hasher>(&self, hasher: &H) -> MerkleTree<H> {
+        return build_merkle_tree(hasher, std::slice::from_ref(self));
     }
 
     /// Evaluates trace polynomials at the specified point `z`; can be called only after
@@ -191,7 +219,7 @@ impl TraceTable {
 
         let mut result = Vec::new();
         for poly in self.polys.iter() {
-            result.push(polynom::eval(poly, z));
+            result.push(poly.eval(z));
         }
         return result;
     }
@@ -203,7 +231,7 @@ impl TraceTable {
     /// T2_i(x) = (T_i(x) - T_i(z * g)) / (x - z * g) are computed for all i and combined
     /// together into a single polynomial using a pseudo-random linear combination;
     /// 3. Then the degree of the polynomial is adjusted to match the specified degree
-    pub fn get_composition_poly(&self, z: u128, cc: &CompositionCoefficients) -> (Vec<u128>, Vec<u128>, Vec<u128>) {
+    pub fn get_composition_poly(&self, z: u128, cc: &CompositionCoefficients) -> (Polynomial<u128>, Vec<u128>, Vec<u128>) {
 
         let trace_length = self.unextended_length();
         assert!(self.is_extended(), "trace table has not been extended yet");
@@ -215,52 +243,62 @@ impl TraceTable {
         let trace_state1 = self.eval_polys_at(z);
         let trace_state2 = self.eval_polys_at(next_z);
 
-        let mut t1_composition = vec![field::ZERO; trace_length];
-        let mut t2_composition = vec![field::ZERO; trace_length];
-
         // combine trace polynomials into 2 composition polynomials T1(x) and T2(x)
-        for i in 0..self.polys.len() {
+        let mut t1_composition = Polynomial::zero();
+        let mut t2_composition = Polynomial::zero();
+        for (i, poly) in self.polys.iter().enumerate() {
             // compute T1(x) = (T(x) - T(z)), multiply it by a pseudo-random coefficient,
             // and add the result into composition polynomial
-            parallel::mul_acc(&mut t1_composition, &self.polys[i], cc.trace1[i], 1);
-            let adjusted_tz = field::mul(trace_state1[i], cc.trace1[i]);
-            t1_composition[0] = field::sub(t1_composition[0], adjusted_tz);
+            t1_composition += &((poly - trace_state1[i]) * cc.trace1[i]);
 
             // compute T2(x) = (T(x) - T(z * g)), multiply it by a pseudo-random
             // coefficient, and add the result into composition polynomial
-            parallel::mul_acc(&mut t2_composition, &self.polys[i], cc.trace2[i], 1);
-            let adjusted_tz = field::mul(trace_state2[i], cc.trace2[i]);
-            t2_composition[0] = field::sub(t2_composition[0], adjusted_tz);
+            t2_composition += &((poly - trace_state2[i]) * cc.trace2[i]);
         }
 
         // divide the two composition polynomials by (x - z) and (x - z * g)
         // respectively and add the resulting polynomials together
-        polynom::syn_div_in_place(&mut t1_composition, z);
-        polynom::syn_div_in_place(&mut t2_composition, next_z);
-        parallel::add_in_place(&mut t1_composition, &t2_composition, 1);
+        let t_composition = t1_composition / Polynomial::linear(z)
+            + t2_composition / Polynomial::linear(next_z);
 
         // adjust the degree of the polynomial to match the degree parameter by computing
         // C(x) = T(x) * k_1 + T(x) * x^incremental_degree * k_2
-        let poly_size = utils::get_composition_degree(trace_length).next_power_of_two();
-        let mut composition_poly = filled_vector(poly_size, self.domain_size(), field::ZERO);
-        let incremental_degree = utils::get_incremental_trace_degree(trace_length);
-        // this is equivalent to T(x) * k_1
-        parallel::mul_acc(
-            &mut composition_poly[..trace_length],
-            &t1_composition,
-            cc.t1_degree,
-            1);
-        // this is equivalent to T(x) * x^incremental_degree * k_2
-        parallel::mul_acc(
-            &mut composition_poly[incremental_degree..(incremental_degree + trace_length)],
-            &t1_composition,
-            cc.t2_degree,
-            1);
+        let incremental_degree = utils::get_incremental_trace_degree(trace_length, self.poly_length());
+        let composition_poly = &t_composition * cc.t1_degree
+            + t_composition.mul_by_x_pow(incremental_degree) * cc.t2_degree;
         
         return (composition_poly, trace_state1, trace_state2);
     }
 }
 
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Puts several trace tables into a single Merkle tree such that states of all tables at a given
+/// step are hashed together to form a single leaf; all tables must have been extended to the
+/// same domain. Leaves and nodes of the tree are computed with the provided `hasher`.
+pub fn build_merkle_tree<H: Hasher>(hasher: &H, traces: &[TraceTable]) -> MerkleTree<H> {
+    let domain_size = traces[0].domain_size();
+    assert!(traces.iter().all(|t| t.domain_size() == domain_size && t.is_extended()),
+        "all trace tables must be extended to the same domain");
+
+    let register_count = traces.iter().map(|t| t.register_count()).sum();
+    let mut trace_state = vec![field::ZERO; register_count];
+    let mut hashed_states = Vec::with_capacity(domain_size);
+    // TODO: this loop should be parallelized
+    for i in 0..domain_size {
+        let mut j = 0;
+        for trace in traces.iter() {
+            for register in trace.registers.iter() {
+                trace_state[j] = register[i];
+                j += 1;
+            }
+        }
+        hashed_states.push(hasher.hash(as_bytes(&trace_state)));
+    }
+    return MerkleTree::with_hasher(hashed_states, hasher.clone());
+}
+
 // TESTS
 // ================================================================================================
 
@@ -269,11 +307,11 @@ mod tests {
 
     use std::collections::HashMap;
     use crate::{
-        math::{ field, polynom, parallel, fft },
-        crypto::hash::blake3,
+        math::{ field, polynom, parallel, fft::FftPlan },
+        crypto::{ Digest, hash::Blake3 },
         programs::{ Program, ProgramInputs, blocks::{ ProgramBlock, Span, Group } },
         processor::{ execute, OpCode },
-        stark::{ TraceTable, CompositionCoefficients, utils::get_composition_degree }
+        stark::{ TraceTable, CompositionCoefficients, DOMAIN_OFFSET, utils::get_composition_degree }
     };
     
     const EXT_FACTOR: usize = 32;
@@ -281,38 +319,67 @@ mod tests {
     #[test]
     fn eval_polys_at() {
         let mut trace = build_trace_table();
+        let s1 = trace.get_state(1);
+        let s2 = trace.get_state(2);
+        let last_state = trace.get_last_state();
+
         let lde_root = field::get_root_of_unity(trace.domain_size());
-        trace.extend(&fft::get_twiddles(lde_root, trace.domain_size()));
+        trace.extend(&FftPlan::new(trace.domain_size()), false);
 
         let g = field::get_root_of_unity(trace.unextended_length());
 
         let v1 = trace.eval_polys_at(g);
-        let s1 = trace.get_state(1 * EXT_FACTOR);
         assert_eq!(v1, s1.to_vec());
 
         let v2 = trace.eval_polys_at(field::exp(g, 2));
-        let s2 = trace.get_state(2 * EXT_FACTOR);
         assert_eq!(v2, s2.to_vec());
+
+        // last state should be computed from polynomials once the trace has been extended
+        assert_eq!(last_state.to_vec(), trace.get_last_state().to_vec());
+
+        // extended trace should contain evaluations over the shifted domain
+        let x = field::mul(DOMAIN_OFFSET, lde_root);
+        assert_eq!(trace.eval_polys_at(x), trace.get_state(1).to_vec());
+    }
+
+    #[test]
+    fn extend_masked() {
+        let mut trace = build_trace_table();
+        let trace_length = trace.unextended_length();
+        let states = (0..trace_length).map(|i| trace.get_state(i).to_vec()).collect::<Vec<_>>();
+
+        trace.extend(&FftPlan::new(trace.domain_size()), true);
+        assert_eq!(trace_length * 2, trace.poly_length());
+
+        // masked polynomials should still evaluate to trace states at all steps of the trace
+        let g = field::get_root_of_unity(trace_length);
+        for i in 0..trace_length {
+            assert_eq!(states[i], trace.eval_polys_at(field::exp(g, i as u128)));
+        }
+
+        // but should have degree greater than trace length
+        let poly = trace.get_register_poly(0);
+        assert!(polynom::degree_of(poly) >= trace_length);
     }
 
     #[test]
     fn get_composition_poly() {
 
         let mut trace = build_trace_table();
-        let lde_root = field::get_root_of_unity(trace.domain_size());
-        trace.extend(&fft::get_twiddles(lde_root, trace.domain_size()));
+        trace.extend(&FftPlan::new(trace.domain_size()), false);
 
         // compute trace composition polynomial
-        let t_tree = trace.build_merkle_tree(blake3);
-        let z = field::prng(*t_tree.root());
-        let cc = CompositionCoefficients::new(*t_tree.root());
-        let target_degree =  get_composition_degree(trace.unextended_length());
+        let t_tree = trace.build_merkle_tree(&Blake3);
+        let z = field::prng(t_tree.root().as_bytes());
+        let cc = CompositionCoefficients::new(t_tree.root().as_bytes());
+        let target_degree =  get_composition_degree(trace.unextended_length(), trace.poly_length());
 
         let g = field::get_root_of_unity(trace.unextended_length());
         let zg = field::mul(z, g);
 
         let (composition_poly, ..) = trace.get_composition_poly(z, &cc);
-        let mut actual_evaluations = composition_poly.clone();
+        let mut actual_evaluations = composition_poly.into_coefficients();
+        actual_evaluations.resize(target_degree.next_power_of_two(), field::ZERO);
         polynom::eval_fft(&mut actual_evaluations, true);
         assert_eq!(target_degree, polynom::infer_degree(&actual_evaluations));
 
@@ -362,6 +429,21 @@ mod tests {
         assert_eq!(expected_evaluations, actual_evaluations);
     }
 
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "transition constraint at step 5 were not satisfied")]
+    fn validate_transitions() {
+        use crate::stark::{ TraceState, ConstraintEvaluator };
+
+        let mut trace = build_trace_table();
+        ConstraintEvaluator::validate_transitions(&trace);
+
+        // change the top of the user stack at step 6
+        let stack_offset = TraceState::compute_decoder_width(trace.ctx_depth(), trace.loop_depth());
+        trace.registers[stack_offset][6] = field::add(trace.registers[stack_offset][6], field::ONE);
+        ConstraintEvaluator::validate_transitions(&trace);
+    }
+
     fn build_trace_table() -> TraceTable {
         let instructions = vec![
             OpCode::Begin, OpCode::Swap, OpCode::Dup2, OpCode::Drop,
@@ -373,7 +455,7 @@ mod tests {
             ProgramBlock::Span(Span::new(instructions, HashMap::new()))
         ]));
         let inputs = ProgramInputs::from_public(&[1, 0]);
-        let (trace, ctx_depth, loop_depth) = execute(&program, &inputs);
+        let (trace, ctx_depth, loop_depth) = execute(&program, &inputs).unwrap();
         return TraceTable::new(trace, ctx_depth, loop_depth, EXT_FACTOR);
     }
 }
\ No newline at end of file
@@ -55,6 +55,7 @@ pub struct CompositionCoefficients {
     pub t1_degree   : u128,
     pub t2_degree   : u128,
     pub constraints : u128,
+    pub randomizer  : u128,
 }
 
 // IMPLEMENTATIONS
@@ -79,7 +80,7 @@ impl ConstraintCoefficients {
 impl CompositionCoefficients {
     pub fn new(seed: [u8; 32]) -> CompositionCoefficients {
         // generate a pseudo-random list of coefficients
-        let coefficients = field::prng_vector(seed, 1 + 4 * MAX_REGISTER_COUNT + 3);
+        let coefficients = field::prng_vector(seed, 1 + 4 * MAX_REGISTER_COUNT + 4);
 
         // skip the first value because it is used up by deep point z
         let start_index = 1;
@@ -98,8 +99,9 @@ impl CompositionCoefficients {
         let t1_degree = coefficients[index];
         let t2_degree = coefficients[index + 1];
         let constraints = coefficients[index + 2];
+        let randomizer = coefficients[index + 3];
 
-        return CompositionCoefficients { trace1, trace2, t1_degree, t2_degree, constraints };
+        return CompositionCoefficients { trace1, trace2, t1_degree, t2_degree, constraints, randomizer };
     }
 }
 
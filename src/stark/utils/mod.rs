@@ -1,5 +1,7 @@
 use rand::prelude::*;
 use rand::distributions::Uniform;
+use crate::crypto::{ Hasher, Digest };
+use crate::utils::as_bytes;
 use super::{ ProofOptions, MAX_CONSTRAINT_DEGREE };
 
 // RE-EXPORTS
@@ -10,19 +12,69 @@ pub use coefficients::{ ConstraintCoefficients, CompositionCoefficients };
 mod proof_of_work;
 pub use proof_of_work::{ find_pow_nonce, verify_pow_nonce };
 
-pub fn get_composition_degree(trace_length: usize) -> usize {
-    return (MAX_CONSTRAINT_DEGREE - 1) * trace_length - 1;
+/// Returns the degree of the composition polynomial for a trace with the specified number of
+/// steps and trace polynomials with the specified number of coefficients; for unmasked traces,
+/// this is equal to (MAX_CONSTRAINT_DEGREE - 1) * trace_length - 1.
+pub fn get_composition_degree(trace_length: usize, poly_length: usize) -> usize {
+    return MAX_CONSTRAINT_DEGREE * poly_length - trace_length - 1;
 }
 
 // PUBLIC FUNCTIONS
 // ================================================================================================
 
-pub fn get_incremental_trace_degree(trace_length: usize) -> usize {
-    let composition_degree = get_composition_degree(trace_length);
-    return composition_degree - (trace_length - 2);
+pub fn get_incremental_trace_degree(trace_length: usize, poly_length: usize) -> usize {
+    let composition_degree = get_composition_degree(trace_length, poly_length);
+    return composition_degree - (poly_length - 2);
 }
 
-pub fn compute_query_positions(seed: &[u8; 32], domain_size: usize, options: &ProofOptions) -> Vec<usize> {
+/// Returns the number of coefficients in trace polynomials; when trace masking is enabled, each
+/// trace polynomial T(x) is replaced with T(x) + (x^n - 1) * R(x), where n is the trace length
+/// and R(x) is a random polynomial of degree n - 1.
+pub fn get_trace_poly_length(trace_length: usize, zero_knowledge: bool) -> usize {
+    return if zero_knowledge { trace_length * 2 } else { trace_length };
+}
+
+/// Returns the minimum trace length for which masked trace polynomials hide all revealed values.
+/// Every query reveals trace and constraint evaluations which depend on trace values at up to 4
+/// points (x, x * g, and the same for the other value in the constraint leaf); 2 more points are
+/// revealed at z and z * g. The masking polynomial has n random coefficients, so as long as the
+/// number of revealed points does not exceed n, the revealed values are uniformly random.
+pub fn get_min_masked_trace_length(num_queries: usize) -> usize {
+    return (4 * num_queries + 2).next_power_of_two();
+}
+
+/// Hashes constraint evaluations into leaves of the constraint Merkle tree; when trace masking
+/// is enabled, evaluations of the randomizer polynomial are hashed into the same leaves.
+pub fn hash_constraint_evaluations<H: Hasher>(hasher: &H, evaluations: &[[u128; 2]], randomizer: &[[u128; 2]]) -> Vec<H::Digest> {
+    if randomizer.len() == 0 {
+        return evaluations.iter().map(|pair| hasher.hash(as_bytes(pair))).collect();
+    }
+
+    assert!(evaluations.len() == randomizer.len(), "number of randomizer evaluations must match number of constraint evaluations");
+    return evaluations.iter().zip(randomizer).map(|(pair, r_pair)| {
+        let leaf = [pair[0], pair[1], r_pair[0], r_pair[1]];
+        hasher.hash(as_bytes(&leaf))
+    }).collect();
+}
+
+/// Returns the context of the FRI transcript for the composition polynomial; the context binds
+/// folding challenges and query positions to the trace and constraint commitments.
+pub fn get_fri_context(trace_root: &[u8; 32], constraint_root: &[u8; 32]) -> Vec<u8> {
+    let mut result = trace_root.to_vec();
+    result.extend_from_slice(constraint_root);
+    return result;
+}
+
+/// Returns a seed for pseudo-random coefficients of the execution at the specified `index` when
+/// a proof covers several program executions; the first execution uses the `seed` itself.
+pub fn get_execution_seed<H: Hasher>(hasher: &H, seed: &[u8; 32], index: usize) -> [u8; 32] {
+    if index == 0 { return *seed; }
+    let mut buf = seed.to_vec();
+    buf.extend_from_slice(&(index as u64).to_le_bytes());
+    return hasher.hash(&buf).as_bytes();
+}
+
+pub fn compute_query_positions<H: Hasher>(seed: &[u8; 32], domain_size: usize, options: &ProofOptions<H>) -> Vec<usize> {
     let range = Uniform::from(0..domain_size);
     let mut index_iter = StdRng::from_seed(*seed).sample_iter(range);
     let num_queries = options.num_queries();
@@ -1,81 +1,247 @@
+use std::collections::HashMap;
+use crossbeam_utils::thread;
 use crate::{
-    math::field,
-    crypto::{ MerkleTree },
-    MIN_TRACE_LENGTH
+    math::{ field, fft::FftPlan },
+    crypto::{ Hasher, Digest, MerkleTree },
+    fri::{ self, FriTranscript, FriError },
+    MIN_TRACE_LENGTH, MAX_PUBLIC_INPUTS, MAX_OUTPUTS
 };
-use super::{ StarkProof, TraceState, ConstraintEvaluator, CompositionCoefficients, fri, utils };
+use super::{
+    StarkProof, ExecutionInfo, VerifierPolicy, VerificationError, TraceState, ConstraintEvaluator, EvaluatorTemplate,
+    CompositionCoefficients, DOMAIN_OFFSET, utils
+};
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Values which depend only on proof options and on dimensions of the execution trace; these are
+/// computed once and shared by all proofs with the same options and dimensions.
+struct VerifierContext {
+    lde_root        : u128,
+    trace_root      : u128,
+    remainder_plan  : Option<FftPlan<u128>>,
+    templates       : HashMap<ExecutionShape, EvaluatorTemplate>,
+}
 
-// VERIFIER FUNCTION
+/// Domain size, trace length, zero-knowledge flag, FRI folding factor, and max remainder degree.
+type ContextKey = (usize, usize, bool, usize, usize);
+
+/// Context, loop, and stack depths of a program execution.
+type ExecutionShape = (usize, usize, usize);
+
+// VERIFIER FUNCTIONS
 // ================================================================================================
 
-pub fn verify(program_hash: &[u8; 32], inputs: &[u128], outputs: &[u128], proof: &StarkProof) -> Result<bool, String>
+/// Verifies the `proof` against public inputs and outputs of all program executions covered
+/// by the proof; `inputs` and `outputs` must contain one entry per execution.
+pub fn verify<H: Hasher>(program_hash: &[u8; 32], inputs: &[Vec<u128>], outputs: &[Vec<u128>], proof: &StarkProof<H>, policy: &VerifierPolicy) -> Result<(), VerificationError>
+{
+    // make sure proof options satisfy verifier policy, and the statement and the proof are
+    // well formed; after this, verification cannot panic on attacker-supplied values
+    policy.check(proof)?;
+    check_statement(inputs, outputs)?;
+    check_proof_shape(proof, inputs.len())?;
+
+    let mut context = VerifierContext::new(proof);
+    context.add_templates(proof);
+    return verify_proof(program_hash, inputs, outputs, proof, &context);
+}
+
+/// Verifies a set of independent proofs, each against its own program hash, public inputs, and
+/// outputs, and returns the result for every proof in the same order. Proofs are checked against
+/// the `policy` first, and proofs which satisfy it are verified in `num_threads` threads; values
+/// which depend only on proof options and trace dimensions are computed once for all proofs.
+pub fn verify_many<H: Hasher>(
+    statements  : &[(&[u8; 32], &[u128], &[u128], &StarkProof<H>)],
+    policy      : &VerifierPolicy,
+    num_threads : usize) -> Vec<Result<(), VerificationError>>
+{
+    assert!(num_threads > 0, "number of threads must be greater than 0");
+    if statements.len() == 0 { return Vec::new(); }
+
+    // check all proofs against the policy, and build verifier contexts once for every distinct
+    // set of options and trace dimensions among the proofs which passed the check
+    let mut contexts = HashMap::new();
+    let mut results = Vec::with_capacity(statements.len());
+    for &(_, inputs, outputs, proof) in statements.iter() {
+        let result = policy.check(proof)
+            .and_then(|_| check_statement(&[inputs.to_vec()], &[outputs.to_vec()]))
+            .and_then(|_| check_proof_shape(proof, 1));
+        if result.is_ok() {
+            contexts.entry(get_context_key(proof))
+                .or_insert_with(|| VerifierContext::new(proof))
+                .add_templates(proof);
+        }
+        results.push(result);
+    }
+
+    // verify proofs in batches distributed across multiple threads
+    let batch_size = (statements.len() + num_threads - 1) / num_threads;
+    let contexts = &contexts;
+    thread::scope(|s| {
+        for (statements, results) in statements.chunks(batch_size).zip(results.chunks_mut(batch_size)) {
+            s.spawn(move |_| {
+                for (&(program_hash, inputs, outputs, proof), result) in statements.iter().zip(results) {
+                    if result.is_err() { continue; }
+                    let context = &contexts[&get_context_key(proof)];
+                    *result = verify_proof(program_hash, &[inputs.to_vec()], &[outputs.to_vec()], proof, context);
+                }
+            });
+        }
+    }).expect("verifier threads should not panic");
+
+    return results;
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Verifies the `proof` assuming that it has already been checked against the verifier policy,
+/// and that the shapes of the statement and the proof have been checked as well.
+fn verify_proof<H: Hasher>(program_hash: &[u8; 32], inputs: &[Vec<u128>], outputs: &[Vec<u128>], proof: &StarkProof<H>, context: &VerifierContext) -> Result<(), VerificationError>
 {
     let options = proof.options();
-    let hash_fn = options.hash_fn();
+    let hasher = options.hasher();
 
     // 1 ----- Verify proof of work and determine query positions ---------------------------------
+    // replay the FRI transcript to get folding challenges and a seed bound to all commitments
     let degree_proof = proof.degree_proof();
-    let mut fri_roots: Vec<u8> = Vec::new();
-    for layer in degree_proof.layers.iter() {
-        layer.root.iter().for_each(|&v| fri_roots.push(v));
-    }
-    degree_proof.rem_root.iter().for_each(|&v| fri_roots.push(v));
-
-    let mut seed = [0u8; 32];
-    hash_fn(&fri_roots, &mut seed);
-    let seed = match utils::verify_pow_nonce(seed, proof.pow_nonce(), &options) {
-        Ok(seed) => seed,
-        Err(msg) => return Err(msg)
-    };
+    let poly_length = utils::get_trace_poly_length(proof.trace_length(), options.zero_knowledge());
+    let max_degree = utils::get_composition_degree(proof.trace_length(), poly_length);
+    let fri_options = options.fri_options();
+    let fri_context = utils::get_fri_context(&proof.trace_root().as_bytes(), &proof.constraint_root().as_bytes());
+    let mut fri_transcript = FriTranscript::new(hasher, &fri_context, proof.domain_size(), max_degree, &fri_options);
+    let (fri_challenges, seed) = fri_transcript.replay(degree_proof);
+    let seed = utils::verify_pow_nonce(seed, proof.pow_nonce(), &options)?;
 
     let t_positions = utils::compute_query_positions(&seed, proof.domain_size(), options);
     let c_positions = utils::map_trace_to_constraint_positions(&t_positions);
 
     // 2 ----- Verify number of operations in the program -----------------------------------------
-    if proof.op_count() < MIN_TRACE_LENGTH as u128 {
-        return Err(String::from("Verification of minimum operation count failed"));
+    if proof.executions().iter().any(|execution| execution.op_count() < MIN_TRACE_LENGTH as u128) {
+        return Err(VerificationError::InvalidOpCount);
     }
 
     // 3 ----- Verify trace and constraint Merkle proofs ------------------------------------------
-    if !MerkleTree::verify_batch(proof.trace_root(), &t_positions, &proof.trace_proof(), hash_fn) {
-        return Err(String::from("verification of trace Merkle proof failed"));
+    if MerkleTree::verify_batch(hasher, proof.trace_root(), &t_positions, &proof.trace_proof()).is_err() {
+        return Err(VerificationError::TraceMerkleMismatch);
+    }
+
+    // randomizer evaluations must be present if and only if the proof is zero-knowledge
+    let num_randomizer_leaves = if options.zero_knowledge() { proof.constraint_evaluations().len() } else { 0 };
+    if proof.randomizer_evaluations().len() != num_randomizer_leaves {
+        return Err(VerificationError::ConstraintMerkleMismatch);
     }
 
-    if !MerkleTree::verify_batch(proof.constraint_root(), &c_positions, &proof.constraint_proof(), hash_fn) {
-        return Err(String::from("verification of constraint Merkle proof failed"));
+    if MerkleTree::verify_batch(hasher, proof.constraint_root(), &c_positions, &proof.constraint_proof()).is_err() {
+        return Err(VerificationError::ConstraintMerkleMismatch);
     }
 
     // 4 ----- Compute constraint evaluations at DEEP point z -------------------------------------
     // derive DEEP point z from the root of the constraint tree
-    let z = field::prng(*proof.constraint_root());
+    let z = field::prng(proof.constraint_root().as_bytes());
 
-    // evaluate constraints at z
-    let constraint_evaluation_at_z = evaluate_constraints(
-        ConstraintEvaluator::from_proof(proof, program_hash, inputs, outputs),
-        proof.get_state_at_z1(),
-        proof.get_state_at_z2(),
-        z
-    );
+    // evaluate constraints of all executions at z and add the results together
+    let mut constraint_evaluation_at_z = field::ZERO;
+    for i in 0..proof.execution_count() {
+        let template = &context.templates[&get_execution_shape(&proof.executions()[i])];
+        let evaluation = evaluate_constraints(
+            ConstraintEvaluator::from_proof(proof, i, program_hash, &inputs[i], &outputs[i], template),
+            proof.get_state_at_z1(i),
+            proof.get_state_at_z2(i),
+            z
+        );
+        constraint_evaluation_at_z = field::add(constraint_evaluation_at_z, evaluation);
+    }
 
     // 5 ----- Compute composition polynomial evaluations -----------------------------------------
     // derive coefficient for linear combination from the root of constraint tree
-    let coefficients = CompositionCoefficients::new(*proof.constraint_root());
+    let coefficients = CompositionCoefficients::new(proof.constraint_root().as_bytes());
+
+    // compute x coordinates of queried positions once for all compositions
+    let xs = t_positions.iter()
+        .map(|&p| field::mul(DOMAIN_OFFSET, field::exp(context.lde_root, p as u128)))
+        .collect::<Vec<u128>>();
+    let next_z = field::mul(z, context.trace_root);
 
-    // compute composition values separately for trace and constraints, and then add them together
-    let t_composition = compose_registers(&proof, &t_positions, z, &coefficients);
-    let c_composition = compose_constraints(&proof, &t_positions, &c_positions, z, constraint_evaluation_at_z, &coefficients);
-    let evaluations = t_composition.iter().zip(c_composition).map(|(&t, c)| field::add(t, c)).collect::<Vec<u128>>();
+    // compute composition values separately for trace and constraints, and then add them together;
+    // registers of each execution are combined using a distinct set of coefficients
+    let mut t_composition = compose_registers(&proof, 0, &xs, z, next_z, &coefficients);
+    for i in 1..proof.execution_count() {
+        let seed = utils::get_execution_seed(hasher, &proof.constraint_root().as_bytes(), i);
+        let composition = compose_registers(&proof, i, &xs, z, next_z, &CompositionCoefficients::new(seed));
+        t_composition.iter_mut().zip(composition).for_each(|(t, c)| *t = field::add(*t, c));
+    }
+
+    let c_composition = compose_constraints(&proof, &t_positions, &c_positions, &xs, z, constraint_evaluation_at_z, &coefficients);
+    let mut evaluations = t_composition.iter().zip(c_composition).map(|(&t, c)| field::add(t, c)).collect::<Vec<u128>>();
+
+    // for zero-knowledge proofs, add randomizer values to the composition values
+    if options.zero_knowledge() {
+        let r_composition = compose_randomizer(&proof, &t_positions, &c_positions, &coefficients);
+        for (evaluation, r) in evaluations.iter_mut().zip(r_composition) {
+            *evaluation = field::add(*evaluation, r);
+        }
+    }
     
     // 6 ----- Verify low-degree proof -------------------------------------------------------------
-    let max_degree = utils::get_composition_degree(proof.trace_length());
-    return match fri::verify(&degree_proof, &evaluations, &t_positions, max_degree, options) {
-        Ok(result) => Ok(result),
-        Err(msg) => Err(format!("verification of low-degree proof failed: {}", msg))
+    let result = match &context.remainder_plan {
+        Some(plan) => fri::verify_layers_with_plan(hasher, &degree_proof, &evaluations, &t_positions, &fri_challenges, proof.domain_size(), DOMAIN_OFFSET, max_degree, &fri_options, plan),
+        None => fri::verify_layers(hasher, &degree_proof, &evaluations, &t_positions, &fri_challenges, proof.domain_size(), DOMAIN_OFFSET, max_degree, &fri_options),
+    };
+    return match result {
+        // if composition evaluations don't match the first FRI layer, the DEEP composition
+        // polynomial computed by the verifier is different from the one committed to by the prover
+        Err(FriError::LayerInconsistent { layer: 0 }) => Err(VerificationError::DeepCompositionMismatch),
+        Err(error) => Err(VerificationError::InvalidFriProof(error)),
+        Ok(()) => Ok(())
     }
 }
 
-// HELPER FUNCTIONS
-// ================================================================================================
+fn get_context_key<H: Hasher>(proof: &StarkProof<H>) -> ContextKey {
+    let options = proof.options();
+    return (proof.domain_size(), proof.trace_length(), options.zero_knowledge(),
+        options.folding_factor(), options.max_remainder_degree());
+}
+
+fn get_execution_shape(execution: &ExecutionInfo) -> ExecutionShape {
+    return (execution.ctx_depth(), execution.loop_depth(), execution.stack_depth());
+}
+
+/// Makes sure that there is a set of outputs for every set of public inputs, and that the
+/// number of inputs and outputs of every execution is within the limits supported by the VM.
+fn check_statement(inputs: &[Vec<u128>], outputs: &[Vec<u128>]) -> Result<(), VerificationError> {
+    if inputs.len() != outputs.len() {
+        return Err(VerificationError::InputOutputCountMismatch { inputs: inputs.len(), outputs: outputs.len() });
+    }
+
+    for (inputs, outputs) in inputs.iter().zip(outputs) {
+        if inputs.len() > MAX_PUBLIC_INPUTS {
+            return Err(VerificationError::TooManyInputs { max: MAX_PUBLIC_INPUTS, actual: inputs.len() });
+        }
+        if outputs.len() > MAX_OUTPUTS {
+            return Err(VerificationError::TooManyOutputs { max: MAX_OUTPUTS, actual: outputs.len() });
+        }
+    }
+
+    return Ok(());
+}
+
+/// Makes sure that the `proof` covers the expected number of executions, and that execution
+/// trace shapes described in the proof are consistent with the number of trace values in it.
+/// Shapes of Merkle proofs and of the low-degree proof are checked when they are verified.
+fn check_proof_shape<H: Hasher>(proof: &StarkProof<H>, execution_count: usize) -> Result<(), VerificationError> {
+    if proof.execution_count() != execution_count {
+        return Err(VerificationError::ExecutionCountMismatch { expected: execution_count, actual: proof.execution_count() });
+    }
+
+    if !proof.is_well_formed() {
+        return Err(VerificationError::InvalidTraceShape);
+    }
+
+    return Ok(());
+}
+
 fn evaluate_constraints(evaluator: ConstraintEvaluator, state1: TraceState, state2: TraceState, x: u128) -> u128 {
     let (i_value, f_value) = evaluator.evaluate_boundaries(&state1, x);
     let t_value = evaluator.evaluate_transition_at(&state1, &state2, x);
@@ -95,22 +261,19 @@ fn evaluate_constraints(evaluator: ConstraintEvaluator, state1: TraceState, stat
     return result;
 }
 
-fn compose_registers(proof: &StarkProof, positions: &[usize], z: u128, cc: &CompositionCoefficients) -> Vec<u128>
+/// Computes DEEP composition values of the registers of the execution at the specified `index`.
+fn compose_registers<H: Hasher>(proof: &StarkProof<H>, index: usize, xs: &[u128], z: u128, next_z: u128, cc: &CompositionCoefficients) -> Vec<u128>
 {    
-    let lde_root = field::get_root_of_unity(proof.domain_size());
-    let trace_root = field::get_root_of_unity(proof.trace_length());
-    let next_z = field::mul(z, trace_root);
+    let trace_at_z1 = proof.get_state_at_z1(index).to_vec();
+    let trace_at_z2 = proof.get_state_at_z2(index).to_vec();
+    let register_range = proof.get_register_range(index);
+    let evaluations = proof.trace_evaluations().iter().map(|row| &row[register_range.clone()]);
 
-    let trace_at_z1 = proof.get_state_at_z1().to_vec();
-    let trace_at_z2 = proof.get_state_at_z2().to_vec();
-    let evaluations = proof.trace_evaluations();
-
-    let incremental_degree = utils::get_incremental_trace_degree(proof.trace_length()) as u128;
+    let poly_length = utils::get_trace_poly_length(proof.trace_length(), proof.options().zero_knowledge());
+    let incremental_degree = utils::get_incremental_trace_degree(proof.trace_length(), poly_length) as u128;
 
     let mut result = Vec::with_capacity(evaluations.len());
-    for (registers, &position) in evaluations.into_iter().zip(positions) {
-        let x = field::exp(lde_root, position as u128);
-        
+    for (registers, &x) in evaluations.zip(xs) {
         let mut composition = field::ZERO;
         for (i, &value) in registers.iter().enumerate() {
             // compute T1(x) = (T(x) - T(z)) / (x - z)
@@ -135,24 +298,18 @@ fn compose_registers(proof: &StarkProof, positions: &[usize], z: u128, cc: &Comp
     return result;
 }
 
-fn compose_constraints(proof: &StarkProof, t_positions: &[usize], c_positions: &[usize], z: u128, evaluation_at_z: u128, cc: &CompositionCoefficients) -> Vec<u128> {
+fn compose_constraints<H: Hasher>(proof: &StarkProof<H>, t_positions: &[usize], c_positions: &[usize], xs: &[u128], z: u128, evaluation_at_z: u128, cc: &CompositionCoefficients) -> Vec<u128> {
     // build constraint evaluation values from the leaves of constraint Merkle proof
     let mut evaluations: Vec<u128> = Vec::with_capacity(t_positions.len());
-    let leaves = proof.constraint_proof().values;
+    let leaves = proof.constraint_evaluations();
     for &position in t_positions.iter() {
         let leaf_idx = c_positions.iter().position(|&v| v == position / 2).unwrap();
-        let element_start = (position % 2) * 16;
-        let element_bytes = &leaves[leaf_idx][element_start..(element_start + 16)];
-        evaluations.push(field::from_bytes(element_bytes));
+        evaluations.push(leaves[leaf_idx][position % 2]);
     }
 
-    let lde_root = field::get_root_of_unity(proof.domain_size());
-
     // divide out deep point from the evaluations
     let mut result = Vec::with_capacity(evaluations.len());
-    for (evaluation, &position) in evaluations.into_iter().zip(t_positions) {
-        let x = field::exp(lde_root, position as u128);
-
+    for (evaluation, &x) in evaluations.into_iter().zip(xs) {
         // compute C(x) = (P(x) - P(z)) / (x - z)
         let composition = field::div(field::sub(evaluation, evaluation_at_z), field::sub(x, z));
         // multiply by pseudo-random coefficient for linear combination
@@ -160,4 +317,59 @@ fn compose_constraints(proof: &StarkProof, t_positions: &[usize], c_positions: &
     }
 
     return result;
-}
\ No newline at end of file
+}
+
+fn compose_randomizer<H: Hasher>(proof: &StarkProof<H>, t_positions: &[usize], c_positions: &[usize], cc: &CompositionCoefficients) -> Vec<u128> {
+    // randomizer values are stored in the same leaves as constraint evaluations
+    let leaves = proof.randomizer_evaluations();
+    let mut result = Vec::with_capacity(t_positions.len());
+    for &position in t_positions.iter() {
+        let leaf_idx = c_positions.iter().position(|&v| v == position / 2).unwrap();
+        result.push(field::mul(leaves[leaf_idx][position % 2], cc.randomizer));
+    }
+    return result;
+}
+
+// VERIFIER CONTEXT IMPLEMENTATION
+// ================================================================================================
+impl VerifierContext {
+
+    fn new<H: Hasher>(proof: &StarkProof<H>) -> VerifierContext {
+        let domain_size = proof.domain_size();
+        let trace_length = proof.trace_length();
+
+        // the remainder is interpolated once per proof, so twiddles for it are computed up front
+        let options = proof.options();
+        let fri_options = options.fri_options();
+        let poly_length = utils::get_trace_poly_length(trace_length, options.zero_knowledge());
+        let max_degree = utils::get_composition_degree(trace_length, poly_length);
+        let remainder_size = fri::get_remainder_size(domain_size, max_degree,
+            fri_options.folding_factor(), fri_options.max_remainder_degree());
+        let remainder_plan = if remainder_size > 1 && remainder_size.is_power_of_two() {
+            Some(FftPlan::with_threads(remainder_size, 1))
+        }
+        else {
+            None
+        };
+
+        return VerifierContext {
+            lde_root    : field::get_root_of_unity(domain_size),
+            trace_root  : field::get_root_of_unity(trace_length),
+            remainder_plan,
+            templates   : HashMap::new(),
+        };
+    }
+
+    /// Builds constraint evaluator templates for all executions covered by the `proof` which are
+    /// not yet in the context; the shape of the proof must have been checked already.
+    fn add_templates<H: Hasher>(&mut self, proof: &StarkProof<H>) {
+        let trace_length = proof.trace_length();
+        let extension_factor = proof.options().extension_factor();
+        for execution in proof.executions() {
+            let (ctx_depth, loop_depth, stack_depth) = get_execution_shape(execution);
+            self.templates.entry((ctx_depth, loop_depth, stack_depth)).or_insert_with(|| {
+                return EvaluatorTemplate::new(trace_length, extension_factor, ctx_depth, loop_depth, stack_depth);
+            });
+        }
+    }
+}
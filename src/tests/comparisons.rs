@@ -24,7 +24,7 @@ fn eq_operations() {
     assert_eq!(expected_result, outputs);
 
     let result = verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
-    assert_eq!(Ok(true), result);
+    assert_eq!(Ok(()), result);
 }
 
 #[test]
@@ -63,7 +63,7 @@ fn cmp_operation() {
 
     // verify execution proof
     let result = verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
-    assert_eq!(Ok(true), result);
+    assert_eq!(Ok(()), result);
 }
 
 #[test]
@@ -101,7 +101,7 @@ fn binacc_operation() {
 
     // verify execution proof
     let result = verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
-    assert_eq!(Ok(true), result);
+    assert_eq!(Ok(()), result);
 }
 
 // HELPER FUNCTIONS
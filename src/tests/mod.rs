@@ -1,8 +1,10 @@
 use std::collections::HashMap;
 use crate::{
-    ProofOptions, Program, ProgramInputs, OpCode, OpHint,
+    ProofOptions, VerifierPolicy, VerificationError, StarkProof, Program, ProgramInputs, OpCode, OpHint, ExecutionError, assembly,
+    processor, stark, MIN_STACK_DEPTH,
     blocks::{ ProgramBlock, Span, Group },
-    math::field, utils::hasher
+    math::field, utils::hasher,
+    crypto::{ Hasher, hash::{ Blake3, Sha3, Poseidon, Rescue, GMiMC } },
 };
 
 mod branches;
@@ -25,7 +27,10 @@ fn execute_verify() {
     assert_eq!(outputs, [3]);
 
     let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
-    assert_eq!(Ok(true), result);
+    assert_eq!(Ok(()), result);
+
+    // running the program without generating a proof produces the same outputs
+    assert_eq!(Ok(outputs), super::run(&program, &inputs, num_outputs));
 }
 
 #[test]
@@ -46,20 +51,363 @@ fn execute_verify_fail() {
 
     // wrong inputs
     let result = super::verify(program.hash(), &[1, 1], &outputs, &proof);
-    let err_msg = format!("verification of low-degree proof failed: evaluations did not match column value at depth 0");
-    assert_eq!(Err(err_msg), result);
+    assert_eq!(Err(VerificationError::DeepCompositionMismatch), result);
 
     // wrong outputs
     let result = super::verify(program.hash(), inputs.get_public_inputs(), &[5], &proof);
-    let err_msg = format!("verification of low-degree proof failed: evaluations did not match column value at depth 0");
-    assert_eq!(Err(err_msg), result);
+    assert_eq!(Err(VerificationError::DeepCompositionMismatch), result);
 
     // wrong program hash
     let mut program_hash2 = program.hash().clone();
     program_hash2[0] = 1;
     let result = super::verify(&program_hash2, inputs.get_public_inputs(), &outputs, &proof);
-    let err_msg = format!("verification of low-degree proof failed: evaluations did not match column value at depth 0");
-    assert_eq!(Err(err_msg), result);
+    assert_eq!(Err(VerificationError::DeepCompositionMismatch), result);
+}
+
+#[test]
+fn execute_verify_policy() {
+    let program = build_program(vec![
+        OpCode::Begin, OpCode::Swap, OpCode::Dup2, OpCode::Drop,
+        OpCode::Add,   OpCode::Swap, OpCode::Dup2, OpCode::Drop,
+        OpCode::Add,   OpCode::Swap, OpCode::Dup2, OpCode::Drop,
+        OpCode::Add,   OpCode::Noop, OpCode::Noop,
+    ], &[]);
+
+    // weak proof options should be rejected by the default policy
+    let options = ProofOptions::<Blake3>::new(16, 4, 0, false, 4, 63);
+    let inputs = ProgramInputs::from_public(&[1, 0]);
+    let num_outputs = 1;
+
+    let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options);
+    assert_eq!(outputs, [3]);
+
+    let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+    assert_eq!(Err(VerificationError::InsufficientSecurityLevel { actual: 4, required: 80 }), result);
+
+    // but should pass a policy which allows them
+    let policy = VerifierPolicy::new(4, &[16], 1024);
+    let result = super::verify_with_policy(program.hash(), inputs.get_public_inputs(), &outputs, &proof, &policy);
+    assert_eq!(Ok(()), result);
+
+    // policy with disallowed extension factor or trace length should fail
+    let policy = VerifierPolicy::new(4, &[32], 1024);
+    let result = super::verify_with_policy(program.hash(), inputs.get_public_inputs(), &outputs, &proof, &policy);
+    assert_eq!(Err(VerificationError::ExtensionFactorNotAllowed(16)), result);
+
+    let policy = VerifierPolicy::new(4, &[16], 8);
+    let result = super::verify_with_policy(program.hash(), inputs.get_public_inputs(), &outputs, &proof, &policy);
+    assert_eq!(Err(VerificationError::TraceTooLong { max: 8 }), result);
+}
+
+#[test]
+fn execute_verify_hash_fns() {
+    let program = build_program(vec![
+        OpCode::Begin, OpCode::Swap, OpCode::Dup2, OpCode::Drop,
+        OpCode::Add,   OpCode::Swap, OpCode::Dup2, OpCode::Drop,
+        OpCode::Add,   OpCode::Swap, OpCode::Dup2, OpCode::Drop,
+        OpCode::Add,   OpCode::Noop, OpCode::Noop,
+    ], &[]);
+
+    execute_verify_with::<Blake3>(&program);
+    execute_verify_with::<Sha3>(&program);
+    execute_verify_with::<Poseidon>(&program);
+    execute_verify_with::<Rescue>(&program);
+    execute_verify_with::<GMiMC>(&program);
+
+    fn execute_verify_with<H: Hasher>(program: &Program) {
+        let options = ProofOptions::<H>::new(32, 50, 4, false, 4, 63);
+        let inputs = ProgramInputs::from_public(&[1, 0]);
+        let num_outputs = 1;
+
+        let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options);
+        assert_eq!(outputs, [3]);
+
+        // make sure the proof survives serialization round trip
+        let proof_bytes = bincode::serialize(&proof).unwrap();
+        let proof = bincode::deserialize::<StarkProof<H>>(&proof_bytes).unwrap();
+        assert_eq!(H::ID, proof.options().hash_fn_id());
+
+        // proof generated with one hash function cannot be deserialized as a proof for another
+        if H::ID != Blake3::ID {
+            assert!(bincode::deserialize::<StarkProof<Blake3>>(&proof_bytes).is_err());
+        }
+
+        let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+        assert_eq!(Ok(()), result);
+    }
+}
+
+#[test]
+fn execute_verify_keyed_hasher() {
+    let program = build_program(vec![
+        OpCode::Begin, OpCode::Swap, OpCode::Dup2, OpCode::Drop,
+        OpCode::Add,   OpCode::Swap, OpCode::Dup2, OpCode::Drop,
+        OpCode::Add,   OpCode::Swap, OpCode::Dup2, OpCode::Drop,
+        OpCode::Add,   OpCode::Noop, OpCode::Noop,
+    ], &[]);
+
+    let hasher = KeyedHasher::new([1; 32]);
+    let options = ProofOptions::<KeyedHasher>::new(32, 50, 4, false, 4, 63).with_hasher(hasher.clone());
+    let inputs = ProgramInputs::from_public(&[1, 0]);
+
+    let (outputs, proof) = super::execute(&program, &inputs, 1, &options);
+    let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+    assert_eq!(Ok(()), result);
+
+    // the key is not serialized, and so it must be set by the verifier
+    let proof_bytes = bincode::serialize(&proof).unwrap();
+    let mut proof = bincode::deserialize::<StarkProof<KeyedHasher>>(&proof_bytes).unwrap();
+    assert!(super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof).is_err());
+
+    proof.set_hasher(KeyedHasher::new([2; 32]));
+    assert!(super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof).is_err());
+
+    proof.set_hasher(hasher);
+    let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+    assert_eq!(Ok(()), result);
+}
+
+#[test]
+fn execute_verify_fri_options() {
+    let program = build_program(vec![
+        OpCode::Begin, OpCode::Swap, OpCode::Dup2, OpCode::Drop,
+        OpCode::Add,   OpCode::Swap, OpCode::Dup2, OpCode::Drop,
+        OpCode::Add,   OpCode::Swap, OpCode::Dup2, OpCode::Drop,
+        OpCode::Add,   OpCode::Noop, OpCode::Noop,
+    ], &[]);
+    let inputs = ProgramInputs::from_public(&[1, 0]);
+    let num_outputs = 1;
+
+    for &(folding_factor, max_remainder_degree) in [(2, 0), (8, 31), (16, 255)].iter() {
+        let options = ProofOptions::<Blake3>::new(32, 50, 4, false, folding_factor, max_remainder_degree);
+        let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options);
+        assert_eq!(outputs, [3]);
+
+        let proof_bytes = bincode::serialize(&proof).unwrap();
+        let proof = bincode::deserialize::<StarkProof>(&proof_bytes).unwrap();
+        assert_eq!(folding_factor, proof.options().folding_factor());
+        assert_eq!(max_remainder_degree, proof.options().max_remainder_degree());
+
+        let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+        assert_eq!(Ok(()), result);
+    }
+}
+
+#[test]
+fn deserialize_tampered_options() {
+    let program = build_program(vec![
+        OpCode::Begin, OpCode::Swap, OpCode::Dup2, OpCode::Drop,
+        OpCode::Add,   OpCode::Swap, OpCode::Dup2, OpCode::Drop,
+        OpCode::Add,   OpCode::Swap, OpCode::Dup2, OpCode::Drop,
+        OpCode::Add,   OpCode::Noop, OpCode::Noop,
+    ], &[]);
+    let inputs = ProgramInputs::from_public(&[1, 0]);
+    let (_, proof) = super::execute(&program, &inputs, 1, &ProofOptions::default());
+    let proof_bytes = bincode::serialize(&proof).unwrap();
+
+    // options are at the end of the proof: extension factor, number of queries, grinding
+    // factor, zero-knowledge flag, folding factor, max remainder degree, and hash function ID
+    let folding_factor_idx = proof_bytes.len() - 3;
+    assert_eq!(4, proof_bytes[folding_factor_idx]);
+    for &folding_factor in [0, 3, 5, 32, 255].iter() {
+        let mut tampered = proof_bytes.clone();
+        tampered[folding_factor_idx] = folding_factor;
+        assert!(bincode::deserialize::<StarkProof>(&tampered).is_err());
+    }
+
+    let mut tampered = proof_bytes.clone();
+    tampered[folding_factor_idx - 4] = 9; // extension factor of 512
+    assert!(bincode::deserialize::<StarkProof>(&tampered).is_err());
+
+    let mut tampered = proof_bytes.clone();
+    tampered[folding_factor_idx - 3] = 0; // no queries
+    assert!(bincode::deserialize::<StarkProof>(&tampered).is_err());
+
+    // a valid change of options is accepted by the de-serializer, and rejected by the verifier
+    let mut tampered = proof_bytes.clone();
+    tampered[folding_factor_idx] = 8;
+    let proof = bincode::deserialize::<StarkProof>(&tampered).unwrap();
+    assert!(super::verify(program.hash(), inputs.get_public_inputs(), &[3], &proof).is_err());
+}
+
+#[test]
+fn proof_options_try_new() {
+    assert!(ProofOptions::<Blake3>::try_new(32, 50, 4, false, 4, 63).is_ok());
+    assert_eq!(Err(String::from("folding_factor must be 2, 4, 8, or 16")),
+        ProofOptions::<Blake3>::try_new(32, 50, 4, false, 3, 63).map(|_| ()));
+    assert_eq!(Err(String::from("extension_factor cannot be smaller than 32 for zero-knowledge proofs")),
+        ProofOptions::<Blake3>::try_new(16, 50, 4, true, 4, 63).map(|_| ()));
+}
+
+#[test]
+fn execute_verify_zk() {
+    let program = build_program(vec![
+        OpCode::Begin, OpCode::Swap, OpCode::Dup2, OpCode::Drop,
+        OpCode::Add,   OpCode::Swap, OpCode::Dup2, OpCode::Drop,
+        OpCode::Add,   OpCode::Swap, OpCode::Dup2, OpCode::Drop,
+        OpCode::Add,   OpCode::Noop, OpCode::Noop,
+    ], &[]);
+
+    let options = ProofOptions::<Blake3>::new(64, 50, 4, true, 4, 63);
+    assert_eq!(100 + 4, options.security_level(true));
+    let inputs = ProgramInputs::from_public(&[1, 0]);
+    let num_outputs = 1;
+
+    let (outputs1, proof1) = super::execute(&program, &inputs, num_outputs, &options);
+    let (outputs2, proof2) = super::execute(&program, &inputs, num_outputs, &options);
+    assert_eq!(outputs1, [3]);
+    assert_eq!(outputs1, outputs2);
+
+    // short traces are padded so that masking can hide all revealed values
+    assert_eq!(256, proof1.trace_length());
+
+    // the same execution should result in different values at DEEP points
+    assert_ne!(proof1.get_state_at_z1(0).to_vec(), proof2.get_state_at_z1(0).to_vec());
+
+    // both proofs should survive serialization and pass verification
+    let proof_bytes = bincode::serialize(&proof1).unwrap();
+    let proof1 = bincode::deserialize::<StarkProof>(&proof_bytes).unwrap();
+    assert!(proof1.options().zero_knowledge());
+
+    let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs1, &proof1);
+    assert_eq!(Ok(()), result);
+
+    let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs2, &proof2);
+    assert_eq!(Ok(()), result);
+
+    // wrong outputs
+    let result = super::verify(program.hash(), inputs.get_public_inputs(), &[5], &proof1);
+    assert_eq!(Err(VerificationError::DeepCompositionMismatch), result);
+}
+
+#[test]
+fn execute_verify_batch() {
+    // the number of loop iterations is determined by secret inputs; so, traces of different
+    // executions have different lengths
+    let program = assembly::compile("begin read.a while.true push.1 add read.a end end").unwrap();
+    let options = ProofOptions::default();
+    let inputs = vec![
+        ProgramInputs::new(&[0], &[1, 1, 0], &[]),
+        ProgramInputs::new(&[5], &[0], &[]),
+        ProgramInputs::new(&[7], &[1; 20].iter().chain(&[0]).cloned().collect::<Vec<u128>>(), &[]),
+    ];
+    let public_inputs = inputs.iter().map(|i| i.get_public_inputs().to_vec()).collect::<Vec<_>>();
+
+    let (outputs, proof) = super::execute_batch(&program, &inputs, 1, &options);
+    assert_eq!(vec![vec![2], vec![5], vec![27]], outputs);
+    assert_eq!(3, proof.execution_count());
+
+    let proof_bytes = bincode::serialize(&proof).unwrap();
+    let proof = bincode::deserialize::<StarkProof>(&proof_bytes).unwrap();
+
+    let result = super::verify_batch(program.hash(), &public_inputs, &outputs, &proof);
+    assert_eq!(Ok(()), result);
+
+    // wrong outputs for one of the executions
+    let mut outputs2 = outputs.clone();
+    outputs2[1] = vec![6];
+    let result = super::verify_batch(program.hash(), &public_inputs, &outputs2, &proof);
+    assert_eq!(Err(VerificationError::DeepCompositionMismatch), result);
+
+    // swapped executions
+    let mut public_inputs2 = public_inputs.clone();
+    public_inputs2.swap(0, 2);
+    outputs2 = outputs.clone();
+    outputs2.swap(0, 2);
+    let result = super::verify_batch(program.hash(), &public_inputs2, &outputs2, &proof);
+    assert_eq!(Err(VerificationError::DeepCompositionMismatch), result);
+
+    // wrong number of executions
+    let result = super::verify_batch(program.hash(), &public_inputs[..2], &outputs[..2], &proof);
+    assert_eq!(Err(VerificationError::ExecutionCountMismatch { expected: 2, actual: 3 }), result);
+
+    let result = super::verify(program.hash(), &public_inputs[0], &outputs[0], &proof);
+    assert_eq!(Err(VerificationError::ExecutionCountMismatch { expected: 1, actual: 3 }), result);
+
+    // malformed statements are rejected rather than causing the verifier to panic
+    let result = super::verify_batch(program.hash(), &public_inputs, &outputs[..2], &proof);
+    assert_eq!(Err(VerificationError::InputOutputCountMismatch { inputs: 3, outputs: 2 }), result);
+
+    let result = super::verify(program.hash(), &[0; 9], &outputs[0], &proof);
+    assert_eq!(Err(VerificationError::TooManyInputs { max: 8, actual: 9 }), result);
+
+    let result = super::verify(program.hash(), &public_inputs[0], &[0; 9], &proof);
+    assert_eq!(Err(VerificationError::TooManyOutputs { max: 8, actual: 9 }), result);
+}
+
+#[test]
+fn execute_verify_batch_zk() {
+    let program = assembly::compile("begin read.a while.true push.1 add read.a end end").unwrap();
+    let options = ProofOptions::<Sha3>::new(64, 50, 4, true, 4, 63);
+    let inputs = vec![
+        ProgramInputs::new(&[1], &[1, 1, 1, 0], &[]),
+        ProgramInputs::new(&[2], &[1, 0], &[]),
+    ];
+    let public_inputs = inputs.iter().map(|i| i.get_public_inputs().to_vec()).collect::<Vec<_>>();
+
+    let (outputs, proof) = super::execute_batch(&program, &inputs, 1, &options);
+    assert_eq!(vec![vec![4], vec![3]], outputs);
+
+    let result = super::verify_batch(program.hash(), &public_inputs, &outputs, &proof);
+    assert_eq!(Ok(()), result);
+
+    let result = super::verify_batch(program.hash(), &public_inputs, &[vec![4], vec![4]], &proof);
+    assert_eq!(Err(VerificationError::DeepCompositionMismatch), result);
+}
+
+#[test]
+fn verify_many() {
+    let program = assembly::compile("begin read.a while.true push.1 add read.a end end").unwrap();
+    let inputs1 = ProgramInputs::new(&[0], &[1, 1, 0], &[]);
+    let inputs2 = ProgramInputs::new(&[3], &[1; 20].iter().chain(&[0]).cloned().collect::<Vec<u128>>(), &[]);
+
+    let (outputs1, proof1) = super::execute(&program, &inputs1, 1, &ProofOptions::default());
+    let (outputs2, proof2) = super::execute(&program, &inputs2, 1, &ProofOptions::default());
+    let (outputs3, proof3) = super::execute(&program, &inputs1, 1, &ProofOptions::<Blake3>::new(16, 4, 0, false, 4, 63));
+    assert_ne!(proof1.trace_length(), proof2.trace_length());
+
+    let statements = vec![
+        (program.hash(), inputs1.get_public_inputs(), &outputs1[..], &proof1),
+        (program.hash(), inputs2.get_public_inputs(), &outputs2[..], &proof2),
+        (program.hash(), inputs1.get_public_inputs(), &outputs2[..], &proof1),
+        (program.hash(), inputs1.get_public_inputs(), &outputs3[..], &proof3),
+        (program.hash(), inputs2.get_public_inputs(), &outputs2[..], &proof2),
+    ];
+
+    let results = super::verify_many(&statements);
+    assert_eq!(vec![
+        Ok(()),
+        Ok(()),
+        Err(VerificationError::DeepCompositionMismatch),
+        Err(VerificationError::InsufficientSecurityLevel { actual: 4, required: 80 }),
+        Ok(()),
+    ], results);
+
+    // results should be the same as when proofs are verified one by one
+    for (&(program_hash, inputs, outputs, proof), result) in statements.iter().zip(results) {
+        assert_eq!(super::verify(program_hash, inputs, outputs, proof), result);
+    }
+
+    // policy is applied to all proofs
+    let policy = VerifierPolicy::new(4, &[16, 32], 1024);
+    let results = super::verify_many_with_policy(&statements[2..4], &policy);
+    assert_eq!(vec![Err(VerificationError::DeepCompositionMismatch), Ok(())], results);
+
+    assert_eq!(0, super::verify_many::<Blake3>(&[]).len());
+
+    // a malformed proof fails on its own without affecting other proofs in the batch
+    let mut proof_bytes = bincode::serialize(&proof1).unwrap();
+    let folding_factor_idx = proof_bytes.len() - 3;
+    proof_bytes[folding_factor_idx] = 8;
+    let tampered: StarkProof = bincode::deserialize(&proof_bytes).unwrap();
+    let statements = vec![
+        (program.hash(), inputs1.get_public_inputs(), &outputs1[..], &tampered),
+        (program.hash(), inputs1.get_public_inputs(), &outputs1[..], &proof1),
+        (program.hash(), inputs2.get_public_inputs(), &outputs2[..], &proof2),
+    ];
+    let results = super::verify_many(&statements);
+    assert!(results[0].is_err());
+    assert_eq!(vec![Ok(()), Ok(())], results[1..].to_vec());
 }
 
 #[test]
@@ -83,7 +431,7 @@ fn stack_manipulation() {
     assert_eq!(outputs, [46, 19, 4, 11, 0, 11, 0, 6]);
 
     let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
-    assert_eq!(Ok(true), result);
+    assert_eq!(Ok(()), result);
 }
 
 #[test]
@@ -104,7 +452,7 @@ fn conditional_operations() {
     assert_eq!(outputs, [5, 6, 7, 8, 0, 0, 0, 0]);
 
     let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
-    assert_eq!(Ok(true), result);
+    assert_eq!(Ok(()), result);
 
     // CHOOSE2
     let program = build_program(vec![
@@ -126,7 +474,7 @@ fn conditional_operations() {
     assert_eq!(outputs, [7, 8, 0, 0, 0, 0, 0, 0]);
 
     let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
-    assert_eq!(Ok(true), result);
+    assert_eq!(Ok(()), result);
 
     // CSWAP2
     let program = build_program(vec![
@@ -144,12 +492,11 @@ fn conditional_operations() {
     assert_eq!(outputs, [3, 4, 5, 6, 1, 2, 0, 0]);
 
     let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
-    assert_eq!(Ok(true), result);
+    assert_eq!(Ok(()), result);
 }
 
 #[test]
-#[should_panic(expected = "CHOOSE on a non-binary condition at step 2")]
-fn selection_operations_panic() {
+fn selection_operations_fail() {
     let program = build_program(vec![
         OpCode::Begin, OpCode::Choose, OpCode::Choose, OpCode::Noop,
         OpCode::Noop,  OpCode::Noop,   OpCode::Noop,   OpCode::Noop,
@@ -161,7 +508,8 @@ fn selection_operations_panic() {
     let inputs = ProgramInputs::from_public(&[3, 4, 2, 5, 0, 6, 7, 8]);
     let num_outputs = 8;
 
-    super::execute(&program, &inputs, num_outputs, &options);
+    let result = super::try_execute(&program, &inputs, num_outputs, &options);
+    assert_eq!(Err(ExecutionError::NonBinaryValue { op: OpCode::Choose, step: 2 }), result.map(|_| ()));
 }
 
 #[test]
@@ -183,7 +531,7 @@ fn math_operations() {
     assert_eq!(expected_result, outputs);
 
     let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
-    assert_eq!(Ok(true), result);
+    assert_eq!(Ok(()), result);
 }
 
 #[test]
@@ -205,7 +553,7 @@ fn bool_operations() {
     assert_eq!(expected_result, outputs);
 
     let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
-    assert_eq!(Ok(true), result);
+    assert_eq!(Ok(()), result);
 }
 
 #[test]
@@ -234,7 +582,7 @@ fn hash_operations() {
     assert_eq!(expected_hash, outputs);
 
     let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
-    assert_eq!(Ok(true), result);
+    assert_eq!(Ok(()), result);
 
     // double hash
     let program = build_program(vec![
@@ -265,7 +613,63 @@ fn hash_operations() {
     assert_eq!(expected_hash, outputs);
 
     let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
-    assert_eq!(Ok(true), result);
+    assert_eq!(Ok(()), result);
+}
+
+#[test]
+fn poseidon_operations() {
+    // hashes are computed inside a loop, and so they start on steps which are multiples of 16,
+    // but are not aligned on the number of steps needed for all Poseidon rounds
+    let program = assembly::compile("begin read.a while.true poseidon.2 read.a end end").unwrap();
+
+    let value = [1, 2];
+    let mut expected_hash = value;
+    for _ in 0..3 {
+        expected_hash = crate::crypto::poseidon::hash(&expected_hash);
+    }
+
+    let options = ProofOptions::default();
+    let inputs = ProgramInputs::new(&[2, 1], &[1, 1, 1, 0], &[]);
+    let num_outputs = 2;
+
+    let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options);
+    assert_eq!(vec![expected_hash[1], expected_hash[0]], outputs);
+
+    let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+    assert_eq!(Ok(()), result);
+
+    // wrong outputs
+    let result = super::verify(program.hash(), inputs.get_public_inputs(), &[1, 2], &proof);
+    assert!(result.is_err());
+}
+
+#[test]
+#[cfg_attr(debug_assertions, should_panic(expected = "transition constraint at step"))]
+fn poseidon_operations_shallow_stack() {
+    // push 12 zeros, and then execute a cycle of Poseidon rounds starting on step 16
+    let mut instructions = vec![OpCode::Begin];
+    instructions.resize(7, OpCode::Pad2);
+    instructions.resize(16, OpCode::Noop);
+    instructions.resize(24, OpCode::PoseF);
+    instructions.resize(31, OpCode::Noop);
+    let program = build_program(instructions, &[]);
+    let inputs = ProgramInputs::none();
+    let options = ProofOptions::default();
+
+    // a prover which declares a stack too shallow for the hash state leaves the state out of
+    // the trace, and then replaces the result of the last round with an arbitrary value
+    let (mut registers, ctx_depth, loop_depth) = processor::execute(&program, &inputs).unwrap();
+    let stack_offset = stark::TraceState::compute_decoder_width(ctx_depth, loop_depth);
+    registers.truncate(stack_offset + MIN_STACK_DEPTH);
+    registers[stack_offset][24..].iter_mut().for_each(|value| *value = 42);
+    let outputs = vec![42];
+
+    let mut traces = vec![stark::TraceTable::new(registers, ctx_depth, loop_depth, options.extension_factor())];
+    let proof = stark::prove(&mut traces, &[vec![]], &[outputs.clone()], &options);
+    assert_eq!(MIN_STACK_DEPTH, proof.executions()[0].stack_depth());
+
+    let result = super::verify(program.hash(), &[], &outputs, &proof);
+    assert!(result.is_err());
 }
 
 #[test]
@@ -285,7 +689,7 @@ fn read_operations() {
     assert_eq!(vec![5, 4, 3, 2, 1], outputs);
 
     let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
-    assert_eq!(Ok(true), result);
+    assert_eq!(Ok(()), result);
 }
 
 #[test]
@@ -307,7 +711,32 @@ fn assert_operations() {
     assert_eq!(expected_result, outputs);
 
     let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
-    assert_eq!(Ok(true), result);
+    assert_eq!(Ok(()), result);
+}
+
+#[test]
+fn verifier_tapes() {
+    let program = assembly::compile("begin push.3 push.5 add end").unwrap();
+    let options = ProofOptions::default();
+    let (_, proof) = super::execute(&program, &ProgramInputs::none(), 1, &options);
+
+    let [a, b] = proof.verifier_tapes();
+    assert_eq!(a.len(), b.len());
+    assert!(a.iter().chain(b.iter()).all(|&value| value < field::MODULUS));
+
+    // DEEP values for all registers follow the two roots and the nonce
+    let deep_start = 5;
+    for i in 0..proof.register_count() {
+        assert_eq!(proof.get_state_at_z1(0).to_vec()[i], a[deep_start + i]);
+        assert_eq!(proof.get_state_at_z2(0).to_vec()[i], b[deep_start + i]);
+    }
+    assert_eq!([proof.pow_nonce() as u128, proof.trace_evaluations().len() as u128], [a[4], b[4]]);
+
+    // a program can read the trace root from the tapes; limbs come out in reverse order
+    let inputs = ProgramInputs::new(&[], &a, &b);
+    let limbs = run_source("begin read.ab read.ab end", &inputs, 4).unwrap();
+    let root = limbs.iter().rev().flat_map(|&limb| (limb as u64).to_le_bytes().to_vec()).collect::<Vec<u8>>();
+    assert_eq!(&proof.trace_root()[..], &root[..]);
 }
 
 // TODO: add more tests
@@ -333,4 +762,34 @@ fn build_program(instructions: Vec<OpCode>, push_values: &[u128]) -> Program {
 
     let root = vec![ProgramBlock::Span(Span::new(instructions, hints))];
     return Program::new(Group::new(root));
-}
\ No newline at end of file
+}
+
+/// Compiles the `source` program, runs it against the specified `inputs`, and returns
+/// `num_outputs` items from the top of the stack; used by tests of the crypto gadgets.
+pub fn run_source(source: &str, inputs: &ProgramInputs, num_outputs: usize) -> Result<Vec<u128>, ExecutionError> {
+    let program = assembly::compile(source).unwrap();
+    return super::run(&program, inputs, num_outputs);
+}
+
+/// Hash function keyed by a secret; used by tests of hash functions which carry state.
+#[derive(Clone, Debug, Default)]
+pub struct KeyedHasher {
+    key: [u8; 32],
+}
+
+impl KeyedHasher {
+    pub fn new(key: [u8; 32]) -> KeyedHasher {
+        return KeyedHasher { key };
+    }
+}
+
+impl Hasher for KeyedHasher {
+    type Digest = [u8; 32];
+    const ID: u8 = 255;
+
+    fn hash(&self, values: &[u8]) -> [u8; 32] {
+        let mut buf = self.key.to_vec();
+        buf.extend_from_slice(values);
+        return Blake3.hash(&buf);
+    }
+}
@@ -9,6 +9,9 @@ use crate::{
 
 // HASHER FUNCTIONS
 // ================================================================================================
+
+/// Hashes up to 4 values using the same function as `hash.n` instruction; for a stable API with
+/// a documented input and output layout, use `crypto::rescue::hash()`.
 pub fn digest(values: &[u128]) -> Vec<u128> {
     assert!(values.len() <= STATE_RATE,
         "expected no more than {}, but received {}", STATE_RATE, values.len());
@@ -3,7 +3,9 @@ use std::{ mem, slice, ops::Range };
 // RE-EXPORTS
 // ================================================================================================
 pub mod hasher;
+pub mod poseidon;
 pub mod sponge;
+pub(crate) mod serialization;
 
 // VECTOR FUNCTIONS
 // ================================================================================================
@@ -0,0 +1,95 @@
+use crate::{
+    math::field,
+    crypto::hash::{
+        POSEIDON_NUM_ROUNDS,
+        poseidon_round_constants, apply_poseidon_round, is_poseidon_full_round,
+    },
+    HASH_STATE_WIDTH as STATE_WIDTH,
+    BASE_CYCLE_LENGTH as CYCLE_LENGTH,
+};
+
+// CONSTANTS
+// ================================================================================================
+
+/// Number of rounds in Poseidon permutation; each round is executed by a single operation.
+pub const NUM_ROUNDS: usize = POSEIDON_NUM_ROUNDS;
+
+/// Number of 16-step cycles spanned by the rounds of Poseidon permutation.
+pub const NUM_CYCLES: usize = (NUM_ROUNDS + CYCLE_LENGTH - 1) / CYCLE_LENGTH;
+
+/// Number of stack registers used by POSEF and POSEP operations: the top NUM_CYCLES registers
+/// hold a one-hot cycle selector, and the next 6 registers hold the hash state.
+pub const STACK_WIDTH: usize = NUM_CYCLES + STATE_WIDTH;
+
+/// Number of periodic values used by POSEF and POSEP operations: round constants for every
+/// cycle followed by a mask which is set to 1 on the last step of a cycle.
+pub const ARK_WIDTH: usize = NUM_CYCLES * STATE_WIDTH + 1;
+
+// ROUND FUNCTIONS
+// ================================================================================================
+//
+// In the VM, hash state is stored on the stack in reverse order (i.e. the last element of the
+// state is at the top of the stack), and round constants of the next round are added to the
+// state at the end of each round, rather than at the start. This way, round constants are never
+// passed through the S-BOX, and transition constraints for the rounds remain low-degree.
+
+/// Returns true if the specified round of the permutation is a full round, and thus, should be
+/// executed via POSEF operation; all other rounds should be executed via POSEP operation.
+pub fn is_full_round(round: usize) -> bool {
+    return is_poseidon_full_round(round);
+}
+
+/// Returns round constants which need to be added to the hash state before the first round of
+/// the permutation; the constants are listed in the order of stack registers.
+pub fn get_initial_constants() -> [u128; STATE_WIDTH] {
+    return to_stack_order(poseidon_round_constants(0));
+}
+
+/// Returns round constants added to the hash state at the specified step of the execution
+/// trace. `selector` is the one-hot cycle selector, and so the result is a combination of
+/// constants for all cycles weighted by the values of the selector.
+pub fn get_round_constants(selector: &[u128], step: usize) -> [u128; STATE_WIDTH] {
+    let mut result = [field::ZERO; STATE_WIDTH];
+    for i in 0..NUM_CYCLES {
+        let ark = to_stack_order(poseidon_round_constants(i * CYCLE_LENGTH + step % CYCLE_LENGTH + 1));
+        for j in 0..STATE_WIDTH {
+            result[j] = field::add(result[j], field::mul(selector[i], ark[j]));
+        }
+    }
+    return result;
+}
+
+/// Applies S-BOX and MDS steps of a single Poseidon round to the state stored in stack order.
+pub fn apply_round(state: &mut [u128], full: bool) {
+    let mut host_state = [field::ZERO; STATE_WIDTH];
+    for i in 0..STATE_WIDTH {
+        host_state[i] = state[STATE_WIDTH - i - 1];
+    }
+
+    apply_poseidon_round(&mut host_state, full);
+    state[..STATE_WIDTH].copy_from_slice(&to_stack_order(host_state));
+}
+
+/// Builds periodic values for POSEF and POSEP operations: for every cycle and every element of
+/// the state, the constants added to the state at each step of the cycle, followed by the mask
+/// which marks the last step of a cycle.
+pub fn build_ark_cycles() -> Vec<[u128; CYCLE_LENGTH]> {
+    let mut result = vec![[field::ZERO; CYCLE_LENGTH]; ARK_WIDTH];
+    for i in 0..NUM_CYCLES {
+        for step in 0..CYCLE_LENGTH {
+            let ark = to_stack_order(poseidon_round_constants(i * CYCLE_LENGTH + step + 1));
+            for j in 0..STATE_WIDTH {
+                result[i * STATE_WIDTH + j][step] = ark[j];
+            }
+        }
+    }
+    result[ARK_WIDTH - 1][CYCLE_LENGTH - 1] = field::ONE;
+    return result;
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+fn to_stack_order(mut values: [u128; STATE_WIDTH]) -> [u128; STATE_WIDTH] {
+    values.reverse();
+    return values;
+}
@@ -0,0 +1,122 @@
+use std::fmt;
+use serde::{ Serialize, Serializer, Deserialize, Deserializer, de };
+use crate::math::field;
+
+// FIELD ELEMENTS
+// ================================================================================================
+
+/// Serialization for lists of field elements, to be used as `#[serde(with = "...")]`.
+///
+/// In human-readable formats (e.g. JSON or TOML) elements are written as decimal strings because
+/// many languages and formats cannot represent 128-bit integers exactly. When reading, elements
+/// can be given either as strings (decimal or 0x-prefixed hexadecimal) or as integers which fit
+/// into 64 bits. In binary formats (e.g. bincode) elements are written as 128-bit integers.
+pub mod field_elements {
+
+    use super::*;
+
+    pub fn serialize<S: Serializer>(values: &Vec<u128>, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            return serializer.collect_seq(values.iter().map(|v| v.to_string()));
+        }
+        return serializer.collect_seq(values.iter());
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u128>, D::Error> {
+        let elements: Vec<FieldElement> = Deserialize::deserialize(deserializer)?;
+        return Ok(elements.into_iter().map(|e| e.0).collect());
+    }
+}
+
+struct FieldElement(u128);
+
+impl<'de> Deserialize<'de> for FieldElement {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<FieldElement, D::Error> {
+        let value = if deserializer.is_human_readable() {
+            deserializer.deserialize_any(FieldElementVisitor)?
+        }
+        else {
+            u128::deserialize(deserializer)?
+        };
+
+        if value >= field::MODULUS {
+            return Err(de::Error::custom(format!("{} is not a valid field element", value)));
+        }
+        return Ok(FieldElement(value));
+    }
+}
+
+struct FieldElementVisitor;
+
+impl<'de> de::Visitor<'de> for FieldElementVisitor {
+    type Value = u128;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a field element as a string or as a 64-bit unsigned integer")
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<u128, E> {
+        return Ok(value as u128);
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<u128, E> {
+        if value < 0 {
+            return Err(E::custom(format!("{} is not a valid field element", value)));
+        }
+        return Ok(value as u128);
+    }
+
+    fn visit_u128<E: de::Error>(self, value: u128) -> Result<u128, E> {
+        return Ok(value);
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<u128, E> {
+        return Err(E::custom(format!(
+            "{} is not an integer or is too large to be read exactly; use a string instead", value)));
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<u128, E> {
+        let result = if value.starts_with("0x") {
+            u128::from_str_radix(&value[2..], 16)
+        }
+        else {
+            value.parse::<u128>()
+        };
+        return result.map_err(|_| E::custom(format!("'{}' is not a valid field element", value)));
+    }
+}
+
+// DIGESTS
+// ================================================================================================
+
+/// Serialization for 32-byte digests (e.g. program hashes), to be used as
+/// `#[serde(with = "...")]`. In human-readable formats digests are written as hex strings.
+pub mod digest {
+
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &[u8; 32], serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            return serializer.serialize_str(&hex::encode(value));
+        }
+        return value.serialize(serializer);
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[u8; 32], D::Error> {
+        if !deserializer.is_human_readable() {
+            return <[u8; 32]>::deserialize(deserializer);
+        }
+
+        let value = String::deserialize(deserializer)?;
+        let bytes = hex::decode(value.trim_start_matches("0x"))
+            .map_err(|err| de::Error::custom(format!("'{}' is not a valid digest: {}", value, err)))?;
+        if bytes.len() != 32 {
+            return Err(de::Error::custom(format!(
+                "'{}' is not a valid digest: expected 32 bytes, but was {}", value, bytes.len())));
+        }
+
+        let mut result = [0u8; 32];
+        result.copy_from_slice(&bytes);
+        return Ok(result);
+    }
+}